@@ -0,0 +1,307 @@
+//! Validation of the parsed items and generation of the Rust code.
+
+use std::{collections::HashMap, fmt::Write};
+
+use crate::{Error, Result, parse::{Bits, Endianness, Item, Name, Type}};
+
+/// The primitive types.
+const PRIMITIVES: &[&str] = &["u8", "u16", "u32", "u64", "i8", "i16", "i32", "i64", "f32", "f64", "bool", "char"];
+
+/// Rust keywords that have to be emitted as raw identifiers.
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "dyn", "else", "enum",
+    "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match",
+    "mod", "move", "mut", "pub", "ref", "return", "static", "struct", "trait",
+    "true", "type", "unsafe", "use", "where", "while", "abstract", "become",
+    "box", "do", "final", "gen", "macro", "override", "priv", "try", "typeof",
+    "unsized", "virtual", "yield",
+];
+
+fn error<T>(name: &Name, message: impl Into<String>) -> Result<T> {
+    Err(Error::Parse { line: name.position.line, column: name.position.column, message: message.into() })
+}
+
+/// The number of bits of an unsigned integer type.
+fn unsigned_bits(name: &str) -> Option<u32> {
+    match name {
+        "u8" => Some(8),
+        "u16" => Some(16),
+        "u32" => Some(32),
+        "u64" => Some(64),
+        _ => None,
+    }
+}
+
+/// The range of an integer type.
+fn integer_range(name: &str) -> Option<(i128, i128)> {
+    match name {
+        "u8" => Some((0, u8::MAX.into())),
+        "u16" => Some((0, u16::MAX.into())),
+        "u32" => Some((0, u32::MAX.into())),
+        "u64" => Some((0, u64::MAX.into())),
+        "i8" => Some((i8::MIN.into(), i8::MAX.into())),
+        "i16" => Some((i16::MIN.into(), i16::MAX.into())),
+        "i32" => Some((i32::MIN.into(), i32::MAX.into())),
+        "i64" => Some((i64::MIN.into(), i64::MAX.into())),
+        _ => None,
+    }
+}
+
+/// Checks names, references, enum values and bitfield widths.
+pub fn validate(items: &[Item]) -> Result<()> {
+    let mut declared: HashMap<&str, &Item> = HashMap::new();
+    for item in items {
+        let name = item_name(item);
+        if PRIMITIVES.contains(&name.value.as_str()) || name.value == "le" || name.value == "be" {
+            return error(name, format!("{} is a reserved type name", name.value));
+        }
+        if declared.insert(&name.value, item).is_some() {
+            return error(name, format!("{} is declared multiple times", name.value));
+        }
+    }
+
+    for item in items {
+        match item {
+            Item::Struct { fields, .. } => {
+                let mut names = Vec::new();
+                for field in fields {
+                    if names.contains(&&field.name.value) {
+                        return error(&field.name, format!("field {} is declared multiple times", field.name.value));
+                    }
+                    names.push(&field.name.value);
+                    check_type(&field.ty, &declared)?;
+                }
+            },
+            Item::Enum { repr, variants, .. } => {
+                let (min, max) = match integer_range(&repr.value) {
+                    Some(range) => range,
+                    None => return error(repr, "enums must be represented by an integer type"),
+                };
+                for (i, variant) in variants.iter().enumerate() {
+                    if variant.value < min || variant.value > max {
+                        return error(&variant.name, format!("{} does not fit into {}", variant.value, repr.value));
+                    }
+                    if let Some(other) = variants[..i].iter().find(|v| v.name.value == variant.name.value || v.value == variant.value) {
+                        return error(&variant.name, format!("variant {} conflicts with {}", variant.name.value, other.name.value));
+                    }
+                }
+            },
+            Item::Bitfield { repr, fields, .. } => {
+                let bits = match unsigned_bits(&repr.value) {
+                    Some(bits) => bits,
+                    None => return error(repr, "bitfields must be represented by an unsigned integer type"),
+                };
+                let mut total = 0;
+                for (i, field) in fields.iter().enumerate() {
+                    total += field.width;
+                    if total > bits {
+                        return error(&field.name, format!("the fields exceed the {} bits of {}", bits, repr.value));
+                    }
+                    if field.name.value != "_" && fields[..i].iter().any(|f| f.name.value == field.name.value) {
+                        return error(&field.name, format!("field {} is declared multiple times", field.name.value));
+                    }
+                }
+            },
+        }
+    }
+
+    // Structs that (transitively) contain themselves would have an infinite size
+    for item in items {
+        if let Item::Struct { name, .. } = item {
+            if contains(item, &name.value, &declared, &mut Vec::new()) {
+                return error(name, format!("{} contains itself", name.value));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn item_name(item: &Item) -> &Name {
+    match item {
+        Item::Struct { name, .. } | Item::Enum { name, .. } | Item::Bitfield { name, .. } => name,
+    }
+}
+
+fn check_type(ty: &Type, declared: &HashMap<&str, &Item>) -> Result<()> {
+    match ty {
+        Type::Named(name) => {
+            if PRIMITIVES.contains(&name.value.as_str()) || declared.contains_key(name.value.as_str()) {
+                Ok(())
+            } else {
+                error(name, format!("unknown type {}", name.value))
+            }
+        },
+        Type::Array(element, _) | Type::Endian(_, element) => check_type(element, declared),
+    }
+}
+
+/// Whether the item refers to the struct with the given name.
+fn contains<'a>(item: &'a Item, target: &str, declared: &HashMap<&str, &'a Item>, visited: &mut Vec<&'a str>) -> bool {
+    fn named(ty: &Type) -> &Name {
+        match ty {
+            Type::Named(name) => name,
+            Type::Array(element, _) | Type::Endian(_, element) => named(element),
+        }
+    }
+
+    if let Item::Struct { fields, .. } = item {
+        for field in fields {
+            let name = &named(&field.ty).value;
+            if name == target {
+                return true;
+            }
+            if let Some(inner) = declared.get(name.as_str()) {
+                if !visited.contains(&name.as_str()) {
+                    visited.push(name);
+                    if contains(inner, target, declared, visited) {
+                        return true;
+                    }
+                }
+            }
+        }
+    }
+    false
+}
+
+/// Emits the Rust code for the (validated) items.
+pub fn generate(items: &[Item], derives: &[String]) -> String {
+    let mut out = String::new();
+    writeln!(out, "// Generated by lightpack-build. Do not edit.").unwrap();
+
+    for item in items {
+        writeln!(out).unwrap();
+        match item {
+            Item::Struct { docs, name, fields } => {
+                write_docs(&mut out, docs, "");
+                let floats = fields.iter().any(|f| contains_float(&f.ty, items));
+                let std_derives = if floats { "Debug, Clone, Copy, PartialEq" } else { "Debug, Clone, Copy, PartialEq, Eq, Hash" };
+                writeln!(out, "#[derive(::lightpack::Size, ::lightpack::Pack, ::lightpack::Unpack{}, {})]", extra_derives(derives), std_derives).unwrap();
+                writeln!(out, "pub struct {} {{", name.value).unwrap();
+                for field in fields {
+                    write_docs(&mut out, &field.docs, "    ");
+                    writeln!(out, "    pub {}: {},", identifier(&field.name.value), rust_type(&field.ty)).unwrap();
+                }
+                writeln!(out, "}}").unwrap();
+            },
+            Item::Enum { docs, name, repr, variants } => {
+                write_docs(&mut out, docs, "");
+                writeln!(out, "#[derive(::lightpack::Size, ::lightpack::Pack, ::lightpack::Unpack{}, Debug, Clone, Copy, PartialEq, Eq, Hash)]", extra_derives(derives)).unwrap();
+                writeln!(out, "#[repr({})]", repr.value).unwrap();
+                writeln!(out, "pub enum {} {{", name.value).unwrap();
+                for variant in variants {
+                    write_docs(&mut out, &variant.docs, "    ");
+                    writeln!(out, "    {} = {},", identifier(&variant.name.value), variant.value).unwrap();
+                }
+                writeln!(out, "}}").unwrap();
+            },
+            Item::Bitfield { docs, name, repr, fields } => {
+                write_docs(&mut out, docs, "");
+                writeln!(out, "#[derive(::lightpack::Size, ::lightpack::Pack, ::lightpack::Unpack{}, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]", extra_derives(derives)).unwrap();
+                writeln!(out, "pub struct {}(pub {});", name.value, repr.value).unwrap();
+                writeln!(out).unwrap();
+                writeln!(out, "impl {} {{", name.value).unwrap();
+                write_bits(&mut out, &repr.value, fields);
+                writeln!(out, "}}").unwrap();
+            },
+        }
+    }
+
+    out
+}
+
+fn extra_derives(derives: &[String]) -> String {
+    derives.iter().map(|d| format!(", ::lightpack::{}", d)).collect()
+}
+
+/// Emits the getters and setters of a bitfield's fields (starting at the
+/// least significant bit).
+fn write_bits(out: &mut String, repr: &str, fields: &[Bits]) {
+    let mut shift = 0;
+    let mut first = true;
+    for field in fields {
+        let name = &field.name.value;
+        if name != "_" {
+            if !first {
+                writeln!(out).unwrap();
+            }
+            first = false;
+
+            let value_type = bits_type(field.width);
+            let mask = format!("{:#x}", if field.width == 64 { u64::MAX } else { (1u64 << field.width) - 1 });
+            let shifted = if shift == 0 { "self.0".to_owned() } else { format!("(self.0 >> {})", shift) };
+            let positioned_mask = if shift == 0 { mask.clone() } else { format!("({} << {})", mask, shift) };
+            let value = match value_type {
+                "bool" => format!("(value as {})", repr),
+                _ if value_type == repr => format!("(value & {})", mask),
+                _ => format!("(value as {} & {})", repr, mask),
+            };
+            let positioned_value = if shift == 0 { value } else { format!("({} << {})", value, shift) };
+
+            if field.docs.is_empty() {
+                writeln!(out, "    /// The `{}` bits.", name).unwrap();
+            }
+            write_docs(out, &field.docs, "    ");
+            writeln!(out, "    pub const fn {}(&self) -> {} {{", identifier(name), value_type).unwrap();
+            match value_type {
+                "bool" => writeln!(out, "        {} & 0x1 != 0", shifted).unwrap(),
+                _ if value_type == repr => writeln!(out, "        {} & {}", shifted, mask).unwrap(),
+                _ => writeln!(out, "        ({} & {}) as {}", shifted, mask, value_type).unwrap(),
+            }
+            writeln!(out, "    }}").unwrap();
+            writeln!(out).unwrap();
+            writeln!(out, "    /// Sets the `{}` bits (excess bits of the value are ignored).", name).unwrap();
+            writeln!(out, "    pub fn set_{}(&mut self, value: {}) {{", name, value_type).unwrap();
+            writeln!(out, "        self.0 = (self.0 & !{}) | {};", positioned_mask, positioned_value).unwrap();
+            writeln!(out, "    }}").unwrap();
+        }
+        shift += field.width;
+    }
+}
+
+/// The smallest type holding a bitfield field of the given width.
+fn bits_type(width: u32) -> &'static str {
+    match width {
+        1 => "bool",
+        2..=8 => "u8",
+        9..=16 => "u16",
+        17..=32 => "u32",
+        _ => "u64",
+    }
+}
+
+/// Whether the type (transitively) contains floats, which prevents deriving `Eq` and `Hash`.
+fn contains_float(ty: &Type, items: &[Item]) -> bool {
+    match ty {
+        Type::Named(name) if name.value == "f32" || name.value == "f64" => true,
+        Type::Named(name) => items.iter().any(|item| match item {
+            Item::Struct { name: n, fields, .. } if n.value == name.value => fields.iter().any(|f| contains_float(&f.ty, items)),
+            _ => false,
+        }),
+        Type::Array(element, _) | Type::Endian(_, element) => contains_float(element, items),
+    }
+}
+
+fn rust_type(ty: &Type) -> String {
+    match ty {
+        Type::Named(name) => name.value.clone(),
+        Type::Array(element, len) => format!("[{}; {}]", rust_type(element), len),
+        Type::Endian(Endianness::Big, ty) => format!("::lightpack::extra::BE<{}>", rust_type(ty)),
+        Type::Endian(Endianness::Little, ty) => format!("::lightpack::extra::LE<{}>", rust_type(ty)),
+    }
+}
+
+fn write_docs(out: &mut String, docs: &[String], indent: &str) {
+    for doc in docs {
+        writeln!(out, "{}/// {}", indent, doc).unwrap();
+    }
+}
+
+/// Escapes Rust keywords as raw identifiers.
+fn identifier(name: &str) -> String {
+    if KEYWORDS.contains(&name) {
+        format!("r#{}", name)
+    } else {
+        name.to_owned()
+    }
+}
@@ -0,0 +1,143 @@
+//! Generates Lightpack types from a small IDL, intended to be used from
+//! build scripts:
+//!
+//! ```ignore
+//! // build.rs
+//! fn main() {
+//!     lightpack_build::compile("protocol.lp").unwrap();
+//! }
+//!
+//! // src/lib.rs
+//! include!(concat!(env!("OUT_DIR"), "/protocol.rs"));
+//! ```
+//!
+//! The IDL declares structs, enums with a `#[repr]` and explicit values and
+//! bitfields over an unsigned integer. Field types are primitives (`u8` to
+//! `u64`, `i8` to `i64`, `f32`, `f64`, `bool` and `char`), declared types,
+//! arrays (`[T; N]`) and values with a fixed byte order (`le T` or `be T`).
+//! `//` starts a comment and `///` a doc comment, which is copied to the
+//! generated code:
+//!
+//! ```text
+//! /// The operating mode.
+//! enum Mode : u8 {
+//!     Off = 0,
+//!     On = 1,
+//!     Auto = 0x10,
+//! }
+//!
+//! /// Status flags, starting at the least significant bit.
+//! bitfield Flags : u8 {
+//!     enabled: 1,
+//!     _: 3, // unused
+//!     level: 4,
+//! }
+//!
+//! struct Frame {
+//!     seq: u32,
+//!     mode: Mode,
+//!     flags: Flags,
+//!     samples: [le i16; 4],
+//! }
+//! ```
+//!
+//! Every type derives `Size`, `Pack` and `Unpack` (as well as `Debug`,
+//! `Clone`, `Copy` and `PartialEq`). Bitfields are generated as newtypes of
+//! their integer with a getter and a setter for each field, fields of width
+//! 1 are represented as `bool`.
+
+mod generate;
+mod parse;
+
+use std::{env, fmt, fs, io, path::{Path, PathBuf}};
+
+/// An error while generating code from an IDL file.
+#[derive(Debug)]
+pub enum Error {
+    /// The IDL file could not be read or the output could not be written.
+    Io(io::Error),
+    /// The IDL is malformed or invalid.
+    Parse { line: usize, column: usize, message: String },
+}
+
+/// The result type for generated code.
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Parse { line, column, message } => write!(f, "{}:{}: {}", line, column, message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            Error::Parse { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+/// A generator for Rust code from IDL sources.
+#[derive(Debug, Clone, Default)]
+pub struct Generator {
+    derives: Vec<String>,
+}
+
+impl Generator {
+    /// Creates a generator.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Additionally derives the given Lightpack trait (e.g. `Schema` or
+    /// `View`) for all generated types.
+    pub fn derive(mut self, derive: &str) -> Self {
+        self.derives.push(derive.to_owned());
+        self
+    }
+
+    /// Generates Rust code from the given IDL source.
+    pub fn generate(&self, source: &str) -> Result<String> {
+        let items = parse::parse(source)?;
+        generate::validate(&items)?;
+        Ok(generate::generate(&items, &self.derives))
+    }
+
+    /// Generates Rust code from the given IDL file and writes it to
+    /// `$OUT_DIR/<file stem>.rs`, returning the path of the generated file.
+    /// Also instructs Cargo to rerun the build script if the IDL file changes.
+    pub fn compile(&self, path: impl AsRef<Path>) -> Result<PathBuf> {
+        let path = path.as_ref();
+        println!("cargo:rerun-if-changed={}", path.display());
+
+        let source = fs::read_to_string(path)?;
+        let code = self.generate(&source).map_err(|e| match e {
+            Error::Parse { line, column, message } => Error::Parse { line, column, message: format!("{} (in {})", message, path.display()) },
+            e => e,
+        })?;
+
+        let out_dir = env::var_os("OUT_DIR")
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "OUT_DIR is not set, compile should be called from a build script"))?;
+        let stem = path.file_stem()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "the IDL path has no file name"))?;
+        let out_path = Path::new(&out_dir).join(stem).with_extension("rs");
+        fs::write(&out_path, code)?;
+        Ok(out_path)
+    }
+}
+
+/// Generates Rust code from the given IDL file with the default settings,
+/// see [`Generator::compile`].
+pub fn compile(path: impl AsRef<Path>) -> Result<PathBuf> {
+    Generator::new().compile(path)
+}
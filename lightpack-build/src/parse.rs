@@ -0,0 +1,340 @@
+//! The IDL's syntax tree and parser.
+
+use crate::{Error, Result};
+
+/// A position in the source.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// A top-level declaration.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Item {
+    Struct { docs: Vec<String>, name: Name, fields: Vec<Field> },
+    Enum { docs: Vec<String>, name: Name, repr: Name, variants: Vec<Variant> },
+    Bitfield { docs: Vec<String>, name: Name, repr: Name, fields: Vec<Bits> },
+}
+
+/// An identifier with its position.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Name {
+    pub value: String,
+    pub position: Position,
+}
+
+/// A field of a struct.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Field {
+    pub docs: Vec<String>,
+    pub name: Name,
+    pub ty: Type,
+}
+
+/// A variant of an enum.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Variant {
+    pub docs: Vec<String>,
+    pub name: Name,
+    pub value: i128,
+}
+
+/// A named range of bits in a bitfield (`_` for unused bits).
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Bits {
+    pub docs: Vec<String>,
+    pub name: Name,
+    pub width: u32,
+}
+
+/// The type of a field.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Type {
+    /// A primitive or a declared type.
+    Named(Name),
+    /// A fixed-size array.
+    Array(Box<Type>, usize),
+    /// A value with a fixed byte order (`le` or `be`).
+    Endian(Endianness, Box<Type>),
+}
+
+/// A byte order.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Endianness {
+    Big,
+    Little,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+enum Token {
+    Ident(String),
+    Number(i128),
+    Doc(String),
+    Punct(char),
+}
+
+/// Splits the source into tokens, skipping whitespace and (non-doc) comments.
+fn tokenize(source: &str) -> Result<Vec<(Token, Position)>> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+    let mut position = Position { line: 1, column: 1 };
+
+    fn advance(chars: &mut std::iter::Peekable<std::str::Chars>, position: &mut Position) -> Option<char> {
+        let c = chars.next()?;
+        if c == '\n' {
+            position.line += 1;
+            position.column = 1;
+        } else {
+            position.column += 1;
+        }
+        Some(c)
+    }
+
+    while let Some(&c) = chars.peek() {
+        let start = position;
+        if c.is_whitespace() {
+            advance(&mut chars, &mut position);
+        } else if c == '/' {
+            advance(&mut chars, &mut position);
+            if chars.peek() != Some(&'/') {
+                return Err(Error::Parse { line: start.line, column: start.column, message: "expected a comment".to_owned() });
+            }
+            let mut comment = String::new();
+            while let Some(&c) = chars.peek() {
+                if c == '\n' {
+                    break;
+                }
+                comment.push(c);
+                advance(&mut chars, &mut position);
+            }
+            // `comment` starts after the first slash, i.e. doc comments start with `//`
+            if let Some(doc) = comment.strip_prefix("//").filter(|doc| !doc.starts_with('/')) {
+                tokens.push((Token::Doc(doc.strip_prefix(' ').unwrap_or(doc).to_owned()), start));
+            }
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let mut ident = String::new();
+            while let Some(&c) = chars.peek() {
+                if !(c.is_ascii_alphanumeric() || c == '_') {
+                    break;
+                }
+                ident.push(c);
+                advance(&mut chars, &mut position);
+            }
+            tokens.push((Token::Ident(ident), start));
+        } else if c.is_ascii_digit() || c == '-' {
+            let mut literal = String::new();
+            while let Some(&c) = chars.peek() {
+                if !(c.is_ascii_alphanumeric() || c == '_' || (c == '-' && literal.is_empty())) {
+                    break;
+                }
+                literal.push(c);
+                advance(&mut chars, &mut position);
+            }
+            let value = parse_number(&literal)
+                .ok_or_else(|| Error::Parse { line: start.line, column: start.column, message: format!("invalid number {}", literal) })?;
+            tokens.push((Token::Number(value), start));
+        } else if "{}[]:;,=".contains(c) {
+            advance(&mut chars, &mut position);
+            tokens.push((Token::Punct(c), start));
+        } else {
+            return Err(Error::Parse { line: start.line, column: start.column, message: format!("unexpected character {:?}", c) });
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Parses a decimal, hexadecimal (`0x`) or binary (`0b`) integer.
+fn parse_number(literal: &str) -> Option<i128> {
+    let (negative, literal) = match literal.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, literal),
+    };
+    let digits = literal.replace('_', "");
+    let magnitude = if let Some(hex) = digits.strip_prefix("0x") {
+        i128::from_str_radix(hex, 16).ok()?
+    } else if let Some(binary) = digits.strip_prefix("0b") {
+        i128::from_str_radix(binary, 2).ok()?
+    } else {
+        digits.parse().ok()?
+    };
+    Some(if negative { -magnitude } else { magnitude })
+}
+
+/// Parses the items of an IDL source.
+pub fn parse(source: &str) -> Result<Vec<Item>> {
+    let tokens = tokenize(source)?;
+    let end = source.lines().count().max(1);
+    let mut parser = Parser { tokens, index: 0, end: Position { line: end, column: source.lines().last().map_or(1, |l| l.chars().count() + 1) } };
+    let mut items = Vec::new();
+    while !parser.at_end() {
+        items.push(parser.item()?);
+    }
+    Ok(items)
+}
+
+struct Parser {
+    tokens: Vec<(Token, Position)>,
+    index: usize,
+    end: Position,
+}
+
+impl Parser {
+    fn at_end(&self) -> bool {
+        self.index == self.tokens.len()
+    }
+
+    fn position(&self) -> Position {
+        self.tokens.get(self.index).map_or(self.end, |(_, p)| *p)
+    }
+
+    fn error<T>(&self, message: impl Into<String>) -> Result<T> {
+        let position = self.position();
+        Err(Error::Parse { line: position.line, column: position.column, message: message.into() })
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.index).map(|(t, _)| t)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.index).map(|(t, _)| t.clone());
+        self.index += 1;
+        token
+    }
+
+    fn docs(&mut self) -> Vec<String> {
+        let mut docs = Vec::new();
+        while let Some(Token::Doc(doc)) = self.peek() {
+            docs.push(doc.clone());
+            self.index += 1;
+        }
+        docs
+    }
+
+    fn punct(&mut self, expected: char) -> Result<()> {
+        match self.peek() {
+            Some(Token::Punct(c)) if *c == expected => {
+                self.index += 1;
+                Ok(())
+            },
+            _ => self.error(format!("expected `{}`", expected)),
+        }
+    }
+
+    fn name(&mut self) -> Result<Name> {
+        let position = self.position();
+        match self.peek() {
+            Some(Token::Ident(ident)) => {
+                let value = ident.clone();
+                self.index += 1;
+                Ok(Name { value, position })
+            },
+            _ => self.error("expected an identifier"),
+        }
+    }
+
+    fn number(&mut self) -> Result<i128> {
+        match self.peek() {
+            Some(Token::Number(n)) => {
+                let n = *n;
+                self.index += 1;
+                Ok(n)
+            },
+            _ => self.error("expected a number"),
+        }
+    }
+
+    /// Parses comma-separated members until the closing brace.
+    fn members<T>(&mut self, mut member: impl FnMut(&mut Self, Vec<String>) -> Result<T>) -> Result<Vec<T>> {
+        self.punct('{')?;
+        let mut members = Vec::new();
+        loop {
+            let docs = self.docs();
+            if self.peek() == Some(&Token::Punct('}')) {
+                self.index += 1;
+                return Ok(members);
+            }
+            members.push(member(self, docs)?);
+            if self.peek() != Some(&Token::Punct('}')) {
+                self.punct(',')?;
+            }
+        }
+    }
+
+    fn item(&mut self) -> Result<Item> {
+        let docs = self.docs();
+        let keyword = self.name()?;
+        match keyword.value.as_str() {
+            "struct" => {
+                let name = self.name()?;
+                let fields = self.members(|p, docs| {
+                    let name = p.name()?;
+                    p.punct(':')?;
+                    Ok(Field { docs, name, ty: p.ty()? })
+                })?;
+                Ok(Item::Struct { docs, name, fields })
+            },
+            "enum" => {
+                let name = self.name()?;
+                self.punct(':')?;
+                let repr = self.name()?;
+                let variants = self.members(|p, docs| {
+                    let name = p.name()?;
+                    p.punct('=')?;
+                    Ok(Variant { docs, name, value: p.number()? })
+                })?;
+                Ok(Item::Enum { docs, name, repr, variants })
+            },
+            "bitfield" => {
+                let name = self.name()?;
+                self.punct(':')?;
+                let repr = self.name()?;
+                let fields = self.members(|p, docs| {
+                    let name = p.name()?;
+                    p.punct(':')?;
+                    let width = p.number()?;
+                    match u32::try_from(width) {
+                        Ok(width) if width > 0 => Ok(Bits { docs, name, width }),
+                        _ => {
+                            p.index -= 1;
+                            p.error("expected a positive width")
+                        },
+                    }
+                })?;
+                Ok(Item::Bitfield { docs, name, repr, fields })
+            },
+            _ => {
+                self.index -= 1;
+                self.error("expected `struct`, `enum` or `bitfield`")
+            },
+        }
+    }
+
+    fn ty(&mut self) -> Result<Type> {
+        match self.peek() {
+            Some(Token::Punct('[')) => {
+                self.index += 1;
+                let element = self.ty()?;
+                self.punct(';')?;
+                let len = self.number()?;
+                let len = match usize::try_from(len) {
+                    Ok(len) => len,
+                    Err(_) => {
+                        self.index -= 1;
+                        return self.error("expected a non-negative length");
+                    },
+                };
+                self.punct(']')?;
+                Ok(Type::Array(Box::new(element), len))
+            },
+            Some(Token::Ident(ident)) if ident == "le" || ident == "be" => {
+                let order = if ident == "le" { Endianness::Little } else { Endianness::Big };
+                self.next();
+                Ok(Type::Endian(order, Box::new(self.ty()?)))
+            },
+            _ => Ok(Type::Named(self.name()?)),
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use std::{env, fs, path::PathBuf};
+
+use lightpack_build::{Error, Generator};
+
+/// Compares the output with the snapshot in `tests/snapshots`, or updates
+/// the snapshot if `UPDATE_SNAPSHOTS` is set.
+fn assert_snapshot(name: &str, actual: &str) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("snapshots").join(name);
+    if env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::write(&path, actual).unwrap();
+    }
+    let expected = fs::read_to_string(&path).unwrap();
+    assert_eq!(actual, expected, "Output differs from snapshot {}, set UPDATE_SNAPSHOTS=1 to update it", name);
+}
+
+fn parse_error(source: &str) -> (usize, usize, String) {
+    match Generator::new().generate(source) {
+        Err(Error::Parse { line, column, message }) => (line, column, message),
+        result => panic!("Expected a parse error, got {:?}", result),
+    }
+}
+
+#[test]
+fn snapshot() {
+    let source = fs::read_to_string(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("protocol.lp")).unwrap();
+    let code = Generator::new().generate(&source).unwrap();
+
+    assert_snapshot("protocol.rs", &code);
+}
+
+#[test]
+fn derives() {
+    let code = Generator::new().derive("Schema").generate("enum Mode : u8 { Off = 0 }").unwrap();
+
+    assert!(code.contains("#[derive(::lightpack::Size, ::lightpack::Pack, ::lightpack::Unpack, ::lightpack::Schema, Debug, Clone, Copy, PartialEq, Eq, Hash)]"));
+}
+
+#[test]
+fn syntax_errors() {
+    assert_eq!(parse_error("struct A {\n    x u8,\n}"), (2, 7, "expected `:`".to_owned()));
+    assert_eq!(parse_error("union A {}"), (1, 1, "expected `struct`, `enum` or `bitfield`".to_owned()));
+    assert_eq!(parse_error("struct A { x: [u8; -1] }"), (1, 20, "expected a non-negative length".to_owned()));
+    assert_eq!(parse_error("struct A { x: u8 y: u8 }"), (1, 18, "expected `,`".to_owned()));
+    assert_eq!(parse_error("struct A {"), (1, 11, "expected an identifier".to_owned()));
+    assert_eq!(parse_error("struct A { x: u8 } /* */"), (1, 20, "expected a comment".to_owned()));
+}
+
+#[test]
+fn validation_errors() {
+    assert_eq!(parse_error("struct A { x: B }"), (1, 15, "unknown type B".to_owned()));
+    assert_eq!(parse_error("struct A {}\nenum A : u8 {}"), (2, 6, "A is declared multiple times".to_owned()));
+    assert_eq!(parse_error("struct A { x: u8, x: u16 }"), (1, 19, "field x is declared multiple times".to_owned()));
+    assert_eq!(parse_error("struct A { b: [B; 2] }\nstruct B { a: A }"), (1, 8, "A contains itself".to_owned()));
+    assert_eq!(parse_error("enum A : u8 { X = 256 }"), (1, 15, "256 does not fit into u8".to_owned()));
+    assert_eq!(parse_error("enum A : i8 { X = 1, Y = 1 }"), (1, 22, "variant Y conflicts with X".to_owned()));
+    assert_eq!(parse_error("enum A : f32 { X = 1 }"), (1, 10, "enums must be represented by an integer type".to_owned()));
+    assert_eq!(parse_error("bitfield A : i8 { x: 1 }"), (1, 14, "bitfields must be represented by an unsigned integer type".to_owned()));
+    assert_eq!(parse_error("bitfield A : u8 { x: 4, y: 5 }"), (1, 25, "the fields exceed the 8 bits of u8".to_owned()));
+    assert_eq!(parse_error("bitfield A : u8 { x: 0 }"), (1, 22, "expected a positive width".to_owned()));
+}
+
+#[test]
+fn compile_requires_out_dir() {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("protocol.lp");
+
+    assert!(matches!(lightpack_build::compile(path), Err(Error::Io(_))));
+}
@@ -0,0 +1,50 @@
+use lightpack::{Pack, Size, Unpack, byteorder::BigEndian, extra::{BE, LE}};
+
+include!("snapshots/protocol.rs");
+
+#[test]
+fn sizes() {
+    assert_eq!(Mode::SIZE, 1);
+    assert_eq!(Level::SIZE, 2);
+    assert_eq!(Flags::SIZE, 2);
+    assert_eq!(Frame::SIZE, 4 + 1 + 2 + 2 + 8 + 12 + 6 + 8 + 4);
+}
+
+#[test]
+fn bitfields() {
+    let mut flags = Flags::default();
+    flags.set_enabled(true);
+    flags.set_level(0x1f);
+    flags.set_channel(0xab);
+
+    assert_eq!(flags, Flags(0xabf1));
+    assert!(flags.enabled());
+    assert_eq!(flags.level(), 0xf);
+    assert_eq!(flags.channel(), 0xab);
+
+    flags.set_enabled(false);
+    flags.set_level(2);
+    assert_eq!(flags, Flags(0xab20));
+}
+
+#[test]
+fn roundtrip() {
+    let frame = Frame {
+        seq: 7,
+        mode: Mode::Auto,
+        level: Level::Low,
+        flags: Flags(0x0102),
+        samples: [LE(1), LE(-2), LE(3), LE(-4)],
+        readings: [Reading { id: 1, value: 0.5 }, Reading { id: 2, value: -1.0 }],
+        grid: [[1, 2], [3, 4], [5, 6]],
+        raw: BE(0x0102030405060708),
+        r#type: 'x',
+    };
+
+    let mut buffer = [0u8; Frame::SIZE];
+    frame.pack::<BigEndian>(&mut buffer);
+
+    assert_eq!(&buffer[..9], &[0, 0, 0, 7, 0x10, 0xff, 0x9c, 0x01, 0x02]);
+    assert_eq!(&buffer[9..11], &[1, 0]);
+    assert_eq!(Frame::unpack::<BigEndian>(&buffer).unwrap(), frame);
+}
@@ -0,0 +1,75 @@
+// Generated by lightpack-build. Do not edit.
+
+/// The operating mode.
+#[derive(::lightpack::Size, ::lightpack::Pack, ::lightpack::Unpack, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum Mode {
+    Off = 0,
+    On = 1,
+    /// Switches automatically.
+    Auto = 16,
+}
+
+#[derive(::lightpack::Size, ::lightpack::Pack, ::lightpack::Unpack, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(i16)]
+pub enum Level {
+    Low = -100,
+    High = 1000,
+}
+
+/// Status flags, starting at the least significant bit.
+#[derive(::lightpack::Size, ::lightpack::Pack, ::lightpack::Unpack, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Flags(pub u16);
+
+impl Flags {
+    /// The `enabled` bits.
+    pub const fn enabled(&self) -> bool {
+        self.0 & 0x1 != 0
+    }
+
+    /// Sets the `enabled` bits (excess bits of the value are ignored).
+    pub fn set_enabled(&mut self, value: bool) {
+        self.0 = (self.0 & !0x1) | (value as u16);
+    }
+
+    /// The `level` bits.
+    pub const fn level(&self) -> u8 {
+        ((self.0 >> 4) & 0xf) as u8
+    }
+
+    /// Sets the `level` bits (excess bits of the value are ignored).
+    pub fn set_level(&mut self, value: u8) {
+        self.0 = (self.0 & !(0xf << 4)) | ((value as u16 & 0xf) << 4);
+    }
+
+    /// The `channel` bits.
+    pub const fn channel(&self) -> u8 {
+        ((self.0 >> 8) & 0xff) as u8
+    }
+
+    /// Sets the `channel` bits (excess bits of the value are ignored).
+    pub fn set_channel(&mut self, value: u8) {
+        self.0 = (self.0 & !(0xff << 8)) | ((value as u16 & 0xff) << 8);
+    }
+}
+
+#[derive(::lightpack::Size, ::lightpack::Pack, ::lightpack::Unpack, Debug, Clone, Copy, PartialEq)]
+pub struct Reading {
+    pub id: u16,
+    pub value: f32,
+}
+
+/// A frame sent by a sensor.
+#[derive(::lightpack::Size, ::lightpack::Pack, ::lightpack::Unpack, Debug, Clone, Copy, PartialEq)]
+pub struct Frame {
+    /// The sequence number.
+    pub seq: u32,
+    pub mode: Mode,
+    pub level: Level,
+    pub flags: Flags,
+    pub samples: [::lightpack::extra::LE<i16>; 4],
+    pub readings: [Reading; 2],
+    pub grid: [[u8; 2]; 3],
+    pub raw: ::lightpack::extra::BE<u64>,
+    pub r#type: char,
+}
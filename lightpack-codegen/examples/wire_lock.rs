@@ -0,0 +1,31 @@
+//! Writes the lock file for the types below and prints the changes:
+//!
+//! ```text
+//! $ cargo run -p lightpack-codegen --example wire_lock -- wire.lock
+//! ```
+
+use std::{env, process::ExitCode};
+
+use lightpack::{Schema, Size};
+use lightpack_codegen::lock::Lock;
+
+#[derive(Size, Schema)]
+#[repr(u8)]
+#[allow(dead_code)]
+enum Mode {
+    Off = 0,
+    On = 1,
+}
+
+#[derive(Size, Schema)]
+#[allow(dead_code)]
+struct Frame {
+    seq: u32,
+    mode: Mode,
+    origin: Option<(i8, i8)>,
+}
+
+fn main() -> ExitCode {
+    let path = env::args().nth(1).unwrap_or_else(|| "wire.lock".to_owned());
+    Lock::new().add::<Frame>().catch_all::<Mode>().run(path)
+}
@@ -0,0 +1,550 @@
+//! C header generation.
+//!
+//! The generated header is self-contained and defines, for every struct and
+//! enum reachable from the added types:
+//!
+//! - a `typedef` (enums are represented by their `#[repr]` integer type),
+//! - `<NAME>_SIZE` and `<NAME>_OFFSET_<FIELD>` defines,
+//! - `<NAME>_SCALE_<FIELD>` and `<NAME>_VALUE_OFFSET_<FIELD>` defines for
+//!   scaled fields, whose members hold the encoded value,
+//! - `<NAME>_<VARIANT>` constants for enums,
+//! - `pack_<name>` and `unpack_<name>` functions that produce and consume
+//!   exactly the bytes of `Pack::pack` and `Unpack::unpack` with the
+//!   configured byte order.
+//!
+//! Reserved bytes have no member, they are zeroed by `pack_<name>` and
+//! skipped by `unpack_<name>`. BCD values are represented by their decimal
+//! value and reduced-precision floats as `float`. `unpack_<name>` returns
+//! `LIGHTPACK_OK` on success or a negative `LIGHTPACK_ERR_*` code for invalid
+//! enum values, chars and BCD digits.
+
+use std::fmt::Write;
+
+use lightpack::{Schema, schema::{Endianness, Primitive, Type}};
+
+use crate::{Offset, Result, fixed_size_types, member_name, scaled_value, snake_case, upper_snake_case};
+
+/// Helpers shared by all generated headers.
+const HELPERS: &str = r#"#ifndef LIGHTPACK_HELPERS
+#define LIGHTPACK_HELPERS
+
+#define LIGHTPACK_OK 0
+#define LIGHTPACK_ERR_INVALID_ENUM (-1)
+#define LIGHTPACK_ERR_INVALID_CHAR (-2)
+#define LIGHTPACK_ERR_INVALID_BCD (-3)
+
+#define LIGHTPACK_TRY(expr) do { int lp_err_ = (expr); if (lp_err_ != LIGHTPACK_OK) return lp_err_; } while (0)
+
+static inline void lp_write_u8(uint8_t *p, uint8_t v) { p[0] = v; }
+static inline void lp_write_i8(uint8_t *p, int8_t v) { p[0] = (uint8_t)v; }
+static inline void lp_write_bool(uint8_t *p, bool v) { p[0] = v ? 1 : 0; }
+static inline void lp_write_u16_be(uint8_t *p, uint16_t v) { p[0] = (uint8_t)(v >> 8); p[1] = (uint8_t)v; }
+static inline void lp_write_u16_le(uint8_t *p, uint16_t v) { p[0] = (uint8_t)v; p[1] = (uint8_t)(v >> 8); }
+static inline void lp_write_u32_be(uint8_t *p, uint32_t v) { lp_write_u16_be(p, (uint16_t)(v >> 16)); lp_write_u16_be(p + 2, (uint16_t)v); }
+static inline void lp_write_u32_le(uint8_t *p, uint32_t v) { lp_write_u16_le(p, (uint16_t)v); lp_write_u16_le(p + 2, (uint16_t)(v >> 16)); }
+static inline void lp_write_u64_be(uint8_t *p, uint64_t v) { lp_write_u32_be(p, (uint32_t)(v >> 32)); lp_write_u32_be(p + 4, (uint32_t)v); }
+static inline void lp_write_u64_le(uint8_t *p, uint64_t v) { lp_write_u32_le(p, (uint32_t)v); lp_write_u32_le(p + 4, (uint32_t)(v >> 32)); }
+static inline void lp_write_i16_be(uint8_t *p, int16_t v) { lp_write_u16_be(p, (uint16_t)v); }
+static inline void lp_write_i16_le(uint8_t *p, int16_t v) { lp_write_u16_le(p, (uint16_t)v); }
+static inline void lp_write_i32_be(uint8_t *p, int32_t v) { lp_write_u32_be(p, (uint32_t)v); }
+static inline void lp_write_i32_le(uint8_t *p, int32_t v) { lp_write_u32_le(p, (uint32_t)v); }
+static inline void lp_write_i64_be(uint8_t *p, int64_t v) { lp_write_u64_be(p, (uint64_t)v); }
+static inline void lp_write_i64_le(uint8_t *p, int64_t v) { lp_write_u64_le(p, (uint64_t)v); }
+static inline void lp_write_f32_be(uint8_t *p, float v) { uint32_t bits; memcpy(&bits, &v, 4); lp_write_u32_be(p, bits); }
+static inline void lp_write_f32_le(uint8_t *p, float v) { uint32_t bits; memcpy(&bits, &v, 4); lp_write_u32_le(p, bits); }
+static inline void lp_write_f64_be(uint8_t *p, double v) { uint64_t bits; memcpy(&bits, &v, 8); lp_write_u64_be(p, bits); }
+static inline void lp_write_f64_le(uint8_t *p, double v) { uint64_t bits; memcpy(&bits, &v, 8); lp_write_u64_le(p, bits); }
+
+static inline uint8_t lp_read_u8(const uint8_t *p) { return p[0]; }
+static inline int8_t lp_read_i8(const uint8_t *p) { return (int8_t)p[0]; }
+static inline bool lp_read_bool(const uint8_t *p) { return p[0] != 0; }
+static inline uint16_t lp_read_u16_be(const uint8_t *p) { return (uint16_t)((p[0] << 8) | p[1]); }
+static inline uint16_t lp_read_u16_le(const uint8_t *p) { return (uint16_t)((p[1] << 8) | p[0]); }
+static inline uint32_t lp_read_u32_be(const uint8_t *p) { return ((uint32_t)lp_read_u16_be(p) << 16) | lp_read_u16_be(p + 2); }
+static inline uint32_t lp_read_u32_le(const uint8_t *p) { return ((uint32_t)lp_read_u16_le(p + 2) << 16) | lp_read_u16_le(p); }
+static inline uint64_t lp_read_u64_be(const uint8_t *p) { return ((uint64_t)lp_read_u32_be(p) << 32) | lp_read_u32_be(p + 4); }
+static inline uint64_t lp_read_u64_le(const uint8_t *p) { return ((uint64_t)lp_read_u32_le(p + 4) << 32) | lp_read_u32_le(p); }
+static inline int16_t lp_read_i16_be(const uint8_t *p) { return (int16_t)lp_read_u16_be(p); }
+static inline int16_t lp_read_i16_le(const uint8_t *p) { return (int16_t)lp_read_u16_le(p); }
+static inline int32_t lp_read_i32_be(const uint8_t *p) { return (int32_t)lp_read_u32_be(p); }
+static inline int32_t lp_read_i32_le(const uint8_t *p) { return (int32_t)lp_read_u32_le(p); }
+static inline int64_t lp_read_i64_be(const uint8_t *p) { return (int64_t)lp_read_u64_be(p); }
+static inline int64_t lp_read_i64_le(const uint8_t *p) { return (int64_t)lp_read_u64_le(p); }
+static inline float lp_read_f32_be(const uint8_t *p) { uint32_t bits = lp_read_u32_be(p); float v; memcpy(&v, &bits, 4); return v; }
+static inline float lp_read_f32_le(const uint8_t *p) { uint32_t bits = lp_read_u32_le(p); float v; memcpy(&v, &bits, 4); return v; }
+static inline double lp_read_f64_be(const uint8_t *p) { uint64_t bits = lp_read_u64_be(p); double v; memcpy(&v, &bits, 8); return v; }
+static inline double lp_read_f64_le(const uint8_t *p) { uint64_t bits = lp_read_u64_le(p); double v; memcpy(&v, &bits, 8); return v; }
+
+static inline void lp_write_uint_be(uint8_t *p, uint64_t v, size_t n) { for (size_t i = 0; i < n; i++) p[i] = (uint8_t)(v >> (8 * (n - 1 - i))); }
+static inline void lp_write_uint_le(uint8_t *p, uint64_t v, size_t n) { for (size_t i = 0; i < n; i++) p[i] = (uint8_t)(v >> (8 * i)); }
+static inline uint64_t lp_read_uint_be(const uint8_t *p, size_t n) { uint64_t v = 0; for (size_t i = 0; i < n; i++) v = (v << 8) | p[i]; return v; }
+static inline uint64_t lp_read_uint_le(const uint8_t *p, size_t n) { uint64_t v = 0; for (size_t i = n; i > 0; i--) v = (v << 8) | p[i - 1]; return v; }
+static inline uint64_t lp_zero_extend(uint64_t v, size_t n) { (void)n; return v; }
+static inline int64_t lp_sign_extend(uint64_t v, size_t n) { uint64_t sign = (uint64_t)1 << (8 * n - 1); return (int64_t)((v ^ sign) - sign); }
+
+#define LP_ODD_INT(name, type, n, extend) \
+    static inline void lp_write_##name##_be(uint8_t *p, type v) { lp_write_uint_be(p, (uint64_t)v, n); } \
+    static inline void lp_write_##name##_le(uint8_t *p, type v) { lp_write_uint_le(p, (uint64_t)v, n); } \
+    static inline type lp_read_##name##_be(const uint8_t *p) { return (type)extend(lp_read_uint_be(p, n), n); } \
+    static inline type lp_read_##name##_le(const uint8_t *p) { return (type)extend(lp_read_uint_le(p, n), n); }
+LP_ODD_INT(u24, uint32_t, 3, lp_zero_extend)
+LP_ODD_INT(i24, int32_t, 3, lp_sign_extend)
+LP_ODD_INT(u40, uint64_t, 5, lp_zero_extend)
+LP_ODD_INT(u48, uint64_t, 6, lp_zero_extend)
+LP_ODD_INT(i48, int64_t, 6, lp_sign_extend)
+
+static inline uint32_t lp_bcd_encode(uint32_t v) { uint32_t bits = 0; for (unsigned shift = 0; v != 0; shift += 4) { bits |= (v % 10) << shift; v /= 10; } return bits; }
+static inline uint32_t lp_bcd_decode(uint32_t bits) { uint32_t v = 0; for (uint32_t factor = 1; bits != 0; factor *= 10) { v += (bits & 0xf) * factor; bits >>= 4; } return v; }
+static inline bool lp_bcd_is_valid(uint32_t bits) { for (; bits != 0; bits >>= 4) if ((bits & 0xf) > 9) return false; return true; }
+
+static inline uint32_t lp_shift_right_even(uint32_t v, unsigned shift) {
+    if (shift == 0) return v;
+    if (shift >= 32) return 0;
+    uint32_t truncated = v >> shift, rest = v & ((1u << shift) - 1), half = 1u << (shift - 1);
+    return rest > half || (rest == half && (truncated & 1)) ? truncated + 1 : truncated;
+}
+static inline uint32_t lp_float_encode(float v, unsigned exp_bits, unsigned mantissa_bits) {
+    uint32_t bits; memcpy(&bits, &v, 4);
+    uint32_t sign = (bits >> 31) << (exp_bits + mantissa_bits), exp_mask = (1u << exp_bits) - 1, raw_mantissa = bits & 0x7fffff;
+    int raw_exp = (int)((bits >> 23) & 0xff), bias = (1 << (exp_bits - 1)) - 1;
+    if (raw_exp == 0xff) return sign | (exp_mask << mantissa_bits) | (raw_mantissa != 0 ? (raw_mantissa >> (23 - mantissa_bits)) | (1u << (mantissa_bits - 1)) : 0);
+    if (raw_exp == 0 && raw_mantissa == 0) return sign;
+    uint32_t significand = raw_exp != 0 ? raw_mantissa | 0x800000 : raw_mantissa;
+    int exp = raw_exp != 0 ? raw_exp - 150 : -149, magnitude = exp;
+    for (uint32_t rest = significand >> 1; rest != 0; rest >>= 1) magnitude++;
+    if (magnitude > bias) return sign | (exp_mask << mantissa_bits);
+    int unit = (magnitude > 1 - bias ? magnitude : 1 - bias) - (int)mantissa_bits;
+    uint32_t mantissa = exp >= unit ? significand << (exp - unit) : lp_shift_right_even(significand, (unsigned)(unit - exp));
+    if ((mantissa >> (mantissa_bits + 1)) != 0) { mantissa >>= 1; unit++; }
+    if ((mantissa >> mantissa_bits) == 0) return sign | mantissa;
+    int biased = unit + (int)mantissa_bits + bias;
+    if (biased >= (int)exp_mask) return sign | (exp_mask << mantissa_bits);
+    return sign | ((uint32_t)biased << mantissa_bits) | (mantissa & ((1u << mantissa_bits) - 1));
+}
+static inline float lp_float_decode(uint32_t bits, unsigned exp_bits, unsigned mantissa_bits) {
+    uint32_t exp_mask = (1u << exp_bits) - 1, raw_exp = (bits >> mantissa_bits) & exp_mask, mantissa = bits & ((1u << mantissa_bits) - 1);
+    int bias = (1 << (exp_bits - 1)) - 1, exp = (int)raw_exp - bias;
+    uint32_t out = ((bits >> (exp_bits + mantissa_bits)) & 1) << 31;
+    if (raw_exp == exp_mask) {
+        out |= 0x7f800000 | (mantissa << (23 - mantissa_bits));
+    } else if (raw_exp != 0) {
+        out |= ((uint32_t)(exp + 127) << 23) | (mantissa << (23 - mantissa_bits));
+    } else if (mantissa != 0 && bias == 127) {
+        out |= mantissa << (23 - mantissa_bits);
+    } else if (mantissa != 0) {
+        /* Subnormals of the narrower formats are normal floats */
+        for (exp = 1 - bias; (mantissa >> mantissa_bits) == 0; exp--) mantissa <<= 1;
+        out |= ((uint32_t)(exp + 127) << 23) | ((mantissa & ((1u << mantissa_bits) - 1)) << (23 - mantissa_bits));
+    }
+    float v; memcpy(&v, &out, 4); return v;
+}
+
+static inline bool lp_char_is_valid(uint32_t v) { return v <= 0x10FFFF && (v < 0xD800 || v > 0xDFFF); }
+
+#endif /* LIGHTPACK_HELPERS */
+"#;
+
+/// A generator for a C header.
+#[derive(Debug, Clone)]
+pub struct Generator {
+    name: String,
+    byte_order: Endianness,
+    roots: Vec<&'static Type>,
+}
+
+impl Generator {
+    /// Creates a generator for a header with the given name, which is used
+    /// for the include guard. The byte order defaults to big endian.
+    pub fn new(name: &str) -> Self {
+        Self { name: name.to_owned(), byte_order: Endianness::Big, roots: Vec::new() }
+    }
+
+    /// Sets the byte order used by the generated functions (except for
+    /// values with fixed endianness).
+    pub fn byte_order(mut self, byte_order: Endianness) -> Self {
+        self.byte_order = byte_order;
+        self
+    }
+
+    /// Adds a type (and all types it refers to) to the header.
+    pub fn add<T>(mut self) -> Self where T: Schema {
+        self.roots.push(&T::SCHEMA);
+        self
+    }
+
+    /// Generates the header.
+    pub fn generate(&self) -> Result<String> {
+        let guard = format!("{}_H", upper_snake_case(&self.name).replace(|c: char| !c.is_ascii_alphanumeric(), "_"));
+        let mut out = String::new();
+
+        writeln!(out, "/* Generated by lightpack-codegen. Do not edit. */").unwrap();
+        writeln!(out, "#ifndef {}", guard).unwrap();
+        writeln!(out, "#define {}", guard).unwrap();
+        writeln!(out).unwrap();
+        writeln!(out, "#include <stdbool.h>").unwrap();
+        writeln!(out, "#include <stddef.h>").unwrap();
+        writeln!(out, "#include <stdint.h>").unwrap();
+        writeln!(out, "#include <string.h>").unwrap();
+        writeln!(out).unwrap();
+        out.push_str(HELPERS);
+
+        for ty in fixed_size_types(&self.roots)? {
+            writeln!(out).unwrap();
+            match ty {
+                Type::Struct { name, fields } => {
+                    let upper = upper_snake_case(name);
+                    let mut members = String::new();
+                    for field in fields.iter() {
+                        if let Some(decl) = declaration(field.ty, &member_name(field.name), 1)? {
+                            match scaled_value(field, &member_name(field.name)) {
+                                Some(value) => writeln!(members, "    {}; /* value = {} */", decl, value).unwrap(),
+                                None => writeln!(members, "    {};", decl).unwrap(),
+                            }
+                        }
+                    }
+                    if members.is_empty() {
+                        members.push_str("    uint8_t _unused;\n");
+                    }
+                    writeln!(out, "typedef struct {{\n{}}} {};", members, name).unwrap();
+                    writeln!(out, "#define {}_SIZE {}", upper, ty.size()).unwrap();
+                    let mut offset = 0;
+                    for field in fields.iter() {
+                        writeln!(out, "#define {}_OFFSET_{} {}", upper, field.name.to_uppercase(), offset).unwrap();
+                        offset += field.ty.size();
+                    }
+                    for field in fields.iter().filter(|f| f.is_scaled()) {
+                        writeln!(out, "#define {}_SCALE_{} {}", upper, field.name.to_uppercase(), float_literal(field.scale)).unwrap();
+                        writeln!(out, "#define {}_VALUE_OFFSET_{} {}", upper, field.name.to_uppercase(), float_literal(field.offset)).unwrap();
+                    }
+                },
+                Type::Enum { name, repr, variants } => {
+                    let upper = upper_snake_case(name);
+                    writeln!(out, "typedef {} {};", primitive_type(*repr), name).unwrap();
+                    writeln!(out, "#define {}_SIZE {}", upper, ty.size()).unwrap();
+                    for variant in variants.iter() {
+                        writeln!(out, "#define {}_{} (({}){})", upper, upper_snake_case(variant.name), name, literal(variant.value, *repr)).unwrap();
+                    }
+                    writeln!(out).unwrap();
+                    writeln!(out, "static inline bool {}_is_valid({} value) {{", snake_case(name), name).unwrap();
+                    writeln!(out, "    switch (value) {{").unwrap();
+                    for variant in variants.iter() {
+                        writeln!(out, "    case {}:", literal(variant.value, *repr)).unwrap();
+                    }
+                    writeln!(out, "        return true;").unwrap();
+                    writeln!(out, "    default:").unwrap();
+                    writeln!(out, "        return false;").unwrap();
+                    writeln!(out, "    }}").unwrap();
+                    writeln!(out, "}}").unwrap();
+                },
+                _ => unreachable!("Only named types are generated"),
+            }
+
+            let name = crate::type_name(ty).unwrap();
+            let snake = snake_case(name);
+
+            let mut emitter = Emitter::new(self.byte_order);
+            emitter.pack_named(ty, "(*value)", &Offset::default(), self.byte_order)?;
+            writeln!(out).unwrap();
+            writeln!(out, "static inline void pack_{}(const {} *value, uint8_t *buf) {{", snake, name).unwrap();
+            writeln!(out, "{}}}", emitter.finish(ty.size() == 0)).unwrap();
+
+            let mut emitter = Emitter::new(self.byte_order);
+            emitter.unpack_named(ty, "(*out)", &Offset::default(), self.byte_order)?;
+            emitter.line("return LIGHTPACK_OK;".to_owned());
+            writeln!(out).unwrap();
+            writeln!(out, "static inline int unpack_{}({} *out, const uint8_t *buf) {{", snake, name).unwrap();
+            writeln!(out, "{}}}", emitter.finish(ty.size() == 0)).unwrap();
+        }
+
+        writeln!(out).unwrap();
+        writeln!(out, "#endif /* {} */", guard).unwrap();
+        Ok(out)
+    }
+}
+
+/// Emits the statements of a function body.
+struct Emitter {
+    byte_order: Endianness,
+    body: String,
+    indent: usize,
+    loop_depth: usize,
+}
+
+impl Emitter {
+    fn new(byte_order: Endianness) -> Self {
+        Self { byte_order, body: String::new(), indent: 1, loop_depth: 0 }
+    }
+
+    fn line(&mut self, line: String) {
+        for _ in 0..self.indent {
+            self.body.push_str("    ");
+        }
+        self.body.push_str(&line);
+        self.body.push('\n');
+    }
+
+    /// Returns the function body, dereferencing the `value`/`out` parameters.
+    fn finish(self, unused: bool) -> String {
+        let body = self.body
+            .replace("(*value).", "value->")
+            .replace("(*out).", "out->")
+            .replace("(*value)", "*value")
+            .replace("(*out)", "*out");
+        if unused {
+            format!("    (void)buf;\n{}", body)
+        } else {
+            body
+        }
+    }
+
+    /// Emits the fields of a named type (rather than a call to its function).
+    fn pack_named(&mut self, ty: &Type, value: &str, offset: &Offset, order: Endianness) -> Result<()> {
+        match ty {
+            Type::Struct { fields, .. } => {
+                let mut offset = offset.clone();
+                for field in fields.iter() {
+                    self.pack(field.ty, &format!("{}.{}", value, member_name(field.name)), &offset, order)?;
+                    offset = offset.plus(field.ty.size());
+                }
+                Ok(())
+            },
+            Type::Enum { repr, .. } => self.pack(&Type::Primitive(*repr), value, offset, order),
+            _ => unreachable!("Only named types are generated"),
+        }
+    }
+
+    fn unpack_named(&mut self, ty: &Type, out: &str, offset: &Offset, order: Endianness) -> Result<()> {
+        match ty {
+            Type::Struct { fields, .. } => {
+                let mut offset = offset.clone();
+                for field in fields.iter() {
+                    self.unpack(field.ty, &format!("{}.{}", out, member_name(field.name)), &offset, order)?;
+                    offset = offset.plus(field.ty.size());
+                }
+                Ok(())
+            },
+            Type::Enum { name, repr, .. } => {
+                self.unpack(&Type::Primitive(*repr), out, offset, order)?;
+                self.line(format!("if (!{}_is_valid({})) return LIGHTPACK_ERR_INVALID_ENUM;", snake_case(name), out));
+                Ok(())
+            },
+            _ => unreachable!("Only named types are generated"),
+        }
+    }
+
+    fn pack(&mut self, ty: &Type, value: &str, offset: &Offset, order: Endianness) -> Result<()> {
+        if ty.size() == 0 {
+            return Ok(());
+        }
+        match ty {
+            Type::Primitive(p) => {
+                let value = if *p == Primitive::Char { format!("(uint32_t){}", value) } else { value.to_owned() };
+                self.line(format!("lp_write_{}({}, {});", primitive_suffix(*p, order), offset.render("buf"), value));
+            },
+            Type::Tuple(tys) => {
+                let mut offset = offset.clone();
+                for (i, ty) in tys.iter().enumerate() {
+                    self.pack(ty, &format!("{}._{}", value, i), &offset, order)?;
+                    offset = offset.plus(ty.size());
+                }
+            },
+            Type::Array { element, len } => {
+                let var = self.begin_loop(*len);
+                self.pack(element, &format!("{}[{}]", value, var), &offset.with_term(&var, element.size()), order)?;
+                self.end_loop();
+            },
+            Type::Option(ty) => {
+                self.line(format!("lp_write_bool({}, {}.present);", offset.render("buf"), value));
+                self.line(format!("if ({}.present) {{", value));
+                self.indent += 1;
+                self.pack(ty, &format!("{}.value", value), &offset.plus(1), order)?;
+                self.indent -= 1;
+                self.line("}".to_owned());
+            },
+            Type::Bcd(p) => {
+                self.line(format!("lp_write_{}({}, ({})lp_bcd_encode({}));", primitive_suffix(*p, order), offset.render("buf"), primitive_type(*p), value));
+            },
+            Type::Float { repr, exp_bits, mantissa_bits } => {
+                let bits = format!("({})lp_float_encode({}, {}, {})", primitive_type(*repr), value, exp_bits, mantissa_bits);
+                self.line(format!("lp_write_{}({}, {});", primitive_suffix(*repr, order), offset.render("buf"), bits));
+            },
+            Type::Endian(order, ty) => self.pack(ty, value, offset, *order)?,
+            Type::Varint(_) => unreachable!("varints are rejected by fixed_size_types"),
+            Type::Reserved(len) => self.line(format!("memset({}, 0, {});", offset.render("buf"), len)),
+            Type::Struct { name, .. } | Type::Enum { name, .. } => if order == self.byte_order {
+                self.line(format!("pack_{}(&{}, {});", snake_case(name), value, offset.render("buf")));
+            } else {
+                self.pack_named(ty, value, offset, order)?;
+            },
+        }
+        Ok(())
+    }
+
+    fn unpack(&mut self, ty: &Type, out: &str, offset: &Offset, order: Endianness) -> Result<()> {
+        if ty.size() == 0 {
+            return Ok(());
+        }
+        match ty {
+            Type::Primitive(p) => {
+                self.line(format!("{} = lp_read_{}({});", out, primitive_suffix(*p, order), offset.render("buf")));
+                if *p == Primitive::Char {
+                    self.line(format!("if (!lp_char_is_valid({})) return LIGHTPACK_ERR_INVALID_CHAR;", out));
+                }
+            },
+            Type::Tuple(tys) => {
+                let mut offset = offset.clone();
+                for (i, ty) in tys.iter().enumerate() {
+                    self.unpack(ty, &format!("{}._{}", out, i), &offset, order)?;
+                    offset = offset.plus(ty.size());
+                }
+            },
+            Type::Array { element, len } => {
+                let var = self.begin_loop(*len);
+                self.unpack(element, &format!("{}[{}]", out, var), &offset.with_term(&var, element.size()), order)?;
+                self.end_loop();
+            },
+            Type::Option(ty) => {
+                self.line(format!("{}.present = lp_read_bool({});", out, offset.render("buf")));
+                self.line(format!("if ({}.present) {{", out));
+                self.indent += 1;
+                self.unpack(ty, &format!("{}.value", out), &offset.plus(1), order)?;
+                self.indent -= 1;
+                if declaration(ty, "value", 0)?.is_some() {
+                    self.line("} else {".to_owned());
+                    self.line(format!("    memset(&{0}.value, 0, sizeof({0}.value));", out));
+                }
+                self.line("}".to_owned());
+            },
+            Type::Bcd(p) => {
+                let bits = format!("lp_read_{}({})", primitive_suffix(*p, order), offset.render("buf"));
+                self.line(format!("if (!lp_bcd_is_valid({})) return LIGHTPACK_ERR_INVALID_BCD;", bits));
+                self.line(format!("{} = ({})lp_bcd_decode({});", out, primitive_type(*p), bits));
+            },
+            Type::Float { repr, exp_bits, mantissa_bits } => {
+                let bits = format!("lp_read_{}({})", primitive_suffix(*repr, order), offset.render("buf"));
+                self.line(format!("{} = lp_float_decode({}, {}, {});", out, bits, exp_bits, mantissa_bits));
+            },
+            Type::Endian(order, ty) => self.unpack(ty, out, offset, *order)?,
+            Type::Varint(_) => unreachable!("varints are rejected by fixed_size_types"),
+            Type::Reserved(_) => {},
+            Type::Struct { name, .. } | Type::Enum { name, .. } => if order == self.byte_order {
+                self.line(format!("LIGHTPACK_TRY(unpack_{}(&{}, {}));", snake_case(name), out, offset.render("buf")));
+            } else {
+                self.unpack_named(ty, out, offset, order)?;
+            },
+        }
+        Ok(())
+    }
+
+    fn begin_loop(&mut self, len: usize) -> String {
+        let var = format!("i{}", self.loop_depth);
+        self.line(format!("for (size_t {0} = 0; {0} < {1}; {0}++) {{", var, len));
+        self.indent += 1;
+        self.loop_depth += 1;
+        var
+    }
+
+    fn end_loop(&mut self) {
+        self.loop_depth -= 1;
+        self.indent -= 1;
+        self.line("}".to_owned());
+    }
+}
+
+/// The C declaration of a value of the given type, or `None` for zero-sized types.
+fn declaration(ty: &Type, declarator: &str, indent: usize) -> Result<Option<String>> {
+    if ty.size() == 0 {
+        return Ok(None);
+    }
+    let padding = "    ".repeat(indent);
+    Ok(Some(match ty {
+        Type::Primitive(p) | Type::Bcd(p) => format!("{} {}", primitive_type(*p), declarator),
+        Type::Float { .. } => format!("float {}", declarator),
+        Type::Tuple(tys) => {
+            let mut members = String::new();
+            for (i, ty) in tys.iter().enumerate() {
+                if let Some(decl) = declaration(ty, &format!("_{}", i), indent + 1)? {
+                    writeln!(members, "{}    {};", padding, decl).unwrap();
+                }
+            }
+            format!("struct {{\n{}{}}} {}", members, padding, declarator)
+        },
+        Type::Array { element, len } => return declaration(element, &format!("{}[{}]", declarator, len), indent),
+        Type::Option(ty) => match declaration(ty, "value", indent + 1)? {
+            Some(value) => format!("struct {{\n{0}    bool present;\n{0}    {1};\n{0}}} {2}", padding, value, declarator),
+            None => format!("struct {{\n{0}    bool present;\n{0}}} {1}", padding, declarator),
+        },
+        Type::Endian(_, ty) => return declaration(ty, declarator, indent),
+        Type::Struct { name, .. } | Type::Enum { name, .. } => format!("{} {}", name, declarator),
+        Type::Varint(_) => unreachable!("varints are rejected by fixed_size_types"),
+        Type::Reserved(_) => return Ok(None),
+    }))
+}
+
+fn primitive_type(p: Primitive) -> &'static str {
+    match p {
+        Primitive::U8 => "uint8_t",
+        Primitive::U16 => "uint16_t",
+        Primitive::U32 => "uint32_t",
+        Primitive::U64 => "uint64_t",
+        Primitive::I8 => "int8_t",
+        Primitive::I16 => "int16_t",
+        Primitive::I32 => "int32_t",
+        Primitive::I64 => "int64_t",
+        Primitive::F32 => "float",
+        Primitive::F64 => "double",
+        Primitive::Bool => "bool",
+        Primitive::Char => "uint32_t",
+        Primitive::U24 => "uint32_t",
+        Primitive::I24 => "int32_t",
+        Primitive::U40 | Primitive::U48 => "uint64_t",
+        Primitive::I48 => "int64_t",
+    }
+}
+
+/// The suffix of the `lp_write_*`/`lp_read_*` helpers for a primitive.
+fn primitive_suffix(p: Primitive, order: Endianness) -> String {
+    let base = match p {
+        Primitive::U8 => return "u8".to_owned(),
+        Primitive::I8 => return "i8".to_owned(),
+        Primitive::Bool => return "bool".to_owned(),
+        Primitive::U16 => "u16",
+        Primitive::U32 | Primitive::Char => "u32",
+        Primitive::U64 => "u64",
+        Primitive::I16 => "i16",
+        Primitive::I32 => "i32",
+        Primitive::I64 => "i64",
+        Primitive::F32 => "f32",
+        Primitive::F64 => "f64",
+        Primitive::U24 => "u24",
+        Primitive::I24 => "i24",
+        Primitive::U40 => "u40",
+        Primitive::U48 => "u48",
+        Primitive::I48 => "i48",
+    };
+    match order {
+        Endianness::Big => format!("{}_be", base),
+        Endianness::Little => format!("{}_le", base),
+    }
+}
+
+/// An integer literal of the given type.
+fn literal(value: i128, repr: Primitive) -> String {
+    match repr {
+        Primitive::U32 => format!("{}u", value),
+        Primitive::U64 => format!("UINT64_C({})", value),
+        Primitive::I32 if value == i32::MIN as i128 => "INT32_MIN".to_owned(),
+        Primitive::I64 if value == i64::MIN as i128 => "INT64_MIN".to_owned(),
+        Primitive::I64 => format!("INT64_C({})", value),
+        _ => value.to_string(),
+    }
+}
+
+/// A double literal, parenthesized if negative.
+fn float_literal(value: f64) -> String {
+    if value < 0.0 {
+        format!("({:?})", value)
+    } else {
+        format!("{:?}", value)
+    }
+}
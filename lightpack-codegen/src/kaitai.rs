@@ -0,0 +1,288 @@
+//! Kaitai Struct (`.ksy`) export.
+//!
+//! The generated spec describes a single message type as the root type.
+//! Structs become types (named in `snake_case`), enums become enums with
+//! `snake_case` value names and arrays use `repeat: expr`. Options are
+//! described by a `<id>_present` flag, the value with an `if` condition and
+//! a `<id>_padding` attribute that skips the unused bytes if the flag is
+//! not set. Tuples and arrays of arrays or options are wrapped in synthetic
+//! types named after their position, e.g. `sensor_frame_origin`. Scaled
+//! fields get a `<id>_value` instance with the converted value.
+//!
+//! BCD values and reduced-precision floats are described by their raw bits.
+//! Unsigned 24-, 40- and 48-bit integers are described as bit-sized integers
+//! (e.g. `b24be`), signed ones are not supported.
+//!
+//! Identifiers that YAML 1.1 would read as booleans or null (e.g. `on` and
+//! `off`) are quoted.
+
+use std::fmt::Write;
+
+use lightpack::{Schema, schema::{Endianness, Primitive, Type}};
+
+use crate::{Error, Result, fixed_size_types, scaled_value, snake_case};
+
+/// A generator for a Kaitai Struct spec.
+#[derive(Debug, Clone)]
+pub struct Generator {
+    id: String,
+    byte_order: Endianness,
+    message: &'static Type,
+}
+
+impl Generator {
+    /// Creates a generator for a spec with the given (`snake_case`) id that
+    /// describes messages of type `T`. The byte order defaults to big endian.
+    pub fn new<T>(id: &str) -> Self where T: Schema {
+        Self { id: id.to_owned(), byte_order: Endianness::Big, message: &T::SCHEMA }
+    }
+
+    /// Sets the default byte order of the spec (values with fixed
+    /// endianness are described explicitly).
+    pub fn byte_order(mut self, byte_order: Endianness) -> Self {
+        self.byte_order = byte_order;
+        self
+    }
+
+    /// Generates the spec.
+    pub fn generate(&self) -> Result<String> {
+        let types = fixed_size_types(&[self.message])?;
+        let mut spec = Spec { byte_order: self.byte_order, types: Vec::new(), instances: Vec::new() };
+
+        let root = match self.message {
+            Type::Struct { name, fields } => spec.fields(fields, &snake_case(name))?,
+            Type::Enum { .. } => spec.attributes(self.message, "value", "", None)?,
+            _ => return Err(Error::Unnamed),
+        };
+        for ty in &types {
+            if let Type::Struct { name, fields } = ty {
+                if *ty != self.message {
+                    let seq = spec.fields(fields, &snake_case(name))?;
+                    spec.add_type(snake_case(name), seq)?;
+                }
+            }
+        }
+        spec.types.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let mut out = String::new();
+        writeln!(out, "# Generated by lightpack-codegen. Do not edit.").unwrap();
+        writeln!(out, "meta:").unwrap();
+        writeln!(out, "  id: {}", scalar(&self.id)).unwrap();
+        writeln!(out, "  endian: {}", endian_suffix(self.byte_order)).unwrap();
+        write_seq(&mut out, &root, 0);
+        if let Type::Struct { name, .. } = self.message {
+            write_instances(&mut out, &spec.instances, &snake_case(name), 0);
+        }
+
+        if !spec.types.is_empty() {
+            writeln!(out, "types:").unwrap();
+            for (name, seq) in &spec.types {
+                writeln!(out, "  {}:", scalar(name)).unwrap();
+                write_seq(&mut out, seq, 4);
+                write_instances(&mut out, &spec.instances, name, 4);
+            }
+        }
+
+        let enums: Vec<_> = types.iter().filter(|ty| matches!(ty, Type::Enum { .. })).collect();
+        if !enums.is_empty() {
+            writeln!(out, "enums:").unwrap();
+            for ty in enums {
+                if let Type::Enum { name, variants, .. } = ty {
+                    writeln!(out, "  {}:", scalar(&snake_case(name))).unwrap();
+                    for variant in variants.iter() {
+                        writeln!(out, "    {}: {}", variant.value, scalar(&snake_case(variant.name))).unwrap();
+                    }
+                }
+            }
+        }
+
+        Ok(out)
+    }
+}
+
+/// An attribute of a sequence as a list of keys and values.
+type Attribute = Vec<(&'static str, String)>;
+
+/// Collects the types of a spec.
+struct Spec {
+    byte_order: Endianness,
+    types: Vec<(String, Vec<Attribute>)>,
+    /// The value instances of scaled fields as owner, id and expression.
+    instances: Vec<(String, String, String)>,
+}
+
+impl Spec {
+    /// Adds a type, checking for name conflicts with existing ones.
+    fn add_type(&mut self, name: String, seq: Vec<Attribute>) -> Result<()> {
+        if self.types.iter().any(|(n, _)| *n == name) {
+            return Err(Error::Unsupported(format!("multiple different types named {}", name)));
+        }
+        self.types.push((name, seq));
+        Ok(())
+    }
+
+    /// The attributes of a struct's fields.
+    fn fields(&mut self, fields: &[lightpack::schema::Field], owner: &str) -> Result<Vec<Attribute>> {
+        let mut seq = Vec::new();
+        for field in fields {
+            let id = identifier(field.name);
+            if let Some(value) = scaled_value(field, &id) {
+                self.instances.push((owner.to_owned(), format!("{}_value", id), value));
+            }
+            seq.extend(self.attributes(field.ty, &id, owner, None)?);
+        }
+        Ok(seq)
+    }
+
+    /// The attributes describing a value of the given type. Anonymous compound
+    /// types are added as types named `<owner>_<id>`.
+    fn attributes(&mut self, ty: &Type, id: &str, owner: &str, order: Option<Endianness>) -> Result<Vec<Attribute>> {
+        let synthetic = if owner.is_empty() { id.to_owned() } else { format!("{}_{}", owner, id) };
+        let attribute = match ty {
+            Type::Primitive(p @ (Primitive::U24 | Primitive::U40 | Primitive::U48)) => {
+                // Bit-sized integers have their own byte order that defaults to big endian
+                let order = order.unwrap_or(self.byte_order);
+                vec![("id", id.to_owned()), ("type", format!("b{}{}", p.size() * 8, endian_suffix(order)))]
+            },
+            Type::Primitive(p @ (Primitive::I24 | Primitive::I48)) => {
+                return Err(Error::Unsupported(format!("signed {}-bit integers", p.size() * 8)));
+            },
+            Type::Primitive(p) => vec![("id", id.to_owned()), ("type", primitive_type(*p, order))],
+            // Kaitai has no built-in BCD type, so describe the raw bits
+            Type::Bcd(p) => vec![("id", id.to_owned()), ("type", primitive_type(*p, order)), ("doc", "packed BCD".to_owned())],
+            Type::Float { repr, exp_bits, mantissa_bits } => {
+                let doc = format!("float with {} exponent and {} mantissa bits", exp_bits, mantissa_bits);
+                vec![("id", id.to_owned()), ("type", primitive_type(*repr, order)), ("doc", doc)]
+            },
+            Type::Enum { name, repr, .. } => vec![("id", id.to_owned()), ("type", primitive_type(*repr, order)), ("enum", snake_case(name))],
+            Type::Struct { name, .. } => {
+                if order.is_some_and(|order| order != self.byte_order) {
+                    return Err(Error::Unsupported(format!("struct {} with fixed endianness", name)));
+                }
+                vec![("id", id.to_owned()), ("type", snake_case(name))]
+            },
+            Type::Tuple(tys) => {
+                let mut seq = Vec::new();
+                for (i, ty) in tys.iter().enumerate() {
+                    seq.extend(self.attributes(ty, &identifier(&i.to_string()), &synthetic, order)?);
+                }
+                self.add_type(synthetic.clone(), seq)?;
+                vec![("id", id.to_owned()), ("type", synthetic)]
+            },
+            Type::Array { element, len } => {
+                let mut attribute = if matches!(unwrap_endian(element), Type::Array { .. } | Type::Option(_)) {
+                    // Repeated attributes cannot be repeated again, so wrap them in a type
+                    let seq = self.attributes(element, "value", &synthetic, order)?;
+                    self.add_type(synthetic.clone(), seq)?;
+                    vec![("id", id.to_owned()), ("type", synthetic)]
+                } else {
+                    self.attributes(element, id, owner, order)?.remove(0)
+                };
+                attribute.push(("repeat", "expr".to_owned()));
+                attribute.push(("repeat-expr", len.to_string()));
+                attribute
+            },
+            Type::Option(inner) => {
+                let flag = format!("{}_present", id);
+                let mut value = if matches!(unwrap_endian(inner), Type::Option(_)) {
+                    // Conditional attributes cannot be made conditional again, so wrap them in a type
+                    let seq = self.attributes(inner, "value", &synthetic, order)?;
+                    self.add_type(synthetic.clone(), seq)?;
+                    vec![("id", id.to_owned()), ("type", synthetic)]
+                } else {
+                    self.attributes(inner, id, owner, order)?.remove(0)
+                };
+                value.push(("if", format!("{} != 0", flag)));
+                let padding = vec![("id", format!("{}_padding", id)), ("size", inner.size().to_string()), ("if", format!("{} == 0", flag))];
+                return Ok(vec![vec![("id", flag), ("type", "u1".to_owned())], value, padding]);
+            },
+            Type::Endian(order, ty) => return self.attributes(ty, id, owner, Some(*order)),
+            Type::Varint(_) => unreachable!("varints are rejected by fixed_size_types"),
+            Type::Reserved(len) => vec![("id", id.to_owned()), ("size", len.to_string())],
+        };
+        Ok(vec![attribute])
+    }
+}
+
+/// The type without fixed endianness.
+fn unwrap_endian(ty: &Type) -> &Type {
+    match ty {
+        Type::Endian(_, ty) => unwrap_endian(ty),
+        _ => ty,
+    }
+}
+
+fn write_seq(out: &mut String, seq: &[Attribute], indent: usize) {
+    let indent = " ".repeat(indent);
+    if seq.is_empty() {
+        writeln!(out, "{}seq: []", indent).unwrap();
+        return;
+    }
+    writeln!(out, "{}seq:", indent).unwrap();
+    for attribute in seq {
+        for (i, (key, value)) in attribute.iter().enumerate() {
+            let bullet = if i == 0 { "- " } else { "  " };
+            writeln!(out, "{}  {}{}: {}", indent, bullet, key, scalar(value)).unwrap();
+        }
+    }
+}
+
+fn write_instances(out: &mut String, instances: &[(String, String, String)], owner: &str, indent: usize) {
+    let indent = " ".repeat(indent);
+    let mut instances = instances.iter().filter(|(o, _, _)| o == owner).peekable();
+    if instances.peek().is_none() {
+        return;
+    }
+    writeln!(out, "{}instances:", indent).unwrap();
+    for (_, id, value) in instances {
+        writeln!(out, "{}  {}:", indent, scalar(id)).unwrap();
+        writeln!(out, "{}    value: {}", indent, scalar(value)).unwrap();
+    }
+}
+
+/// Quotes a plain scalar that YAML 1.1 would not read as a string.
+fn scalar(value: &str) -> String {
+    const RESERVED: &[&str] = &["y", "yes", "n", "no", "true", "false", "on", "off", "null", "~"];
+    if RESERVED.contains(&value.to_lowercase().as_str()) {
+        format!("'{}'", value)
+    } else {
+        value.to_owned()
+    }
+}
+
+/// The Kaitai type of a primitive, with an explicit byte order if given.
+fn primitive_type(p: Primitive, order: Option<Endianness>) -> String {
+    let name = match p {
+        Primitive::U8 | Primitive::Bool => "u1",
+        Primitive::U16 => "u2",
+        Primitive::U32 | Primitive::Char => "u4",
+        Primitive::U64 => "u8",
+        Primitive::I8 => "s1",
+        Primitive::I16 => "s2",
+        Primitive::I32 => "s4",
+        Primitive::I64 => "s8",
+        Primitive::F32 => "f4",
+        Primitive::F64 => "f8",
+        Primitive::U24 | Primitive::I24 | Primitive::U40 | Primitive::U48 | Primitive::I48 => unreachable!("odd-width integers are bit-sized"),
+    };
+    match order {
+        Some(order) if p.size() > 1 => format!("{}{}", name, endian_suffix(order)),
+        _ => name.to_owned(),
+    }
+}
+
+fn endian_suffix(order: Endianness) -> &'static str {
+    match order {
+        Endianness::Big => "be",
+        Endianness::Little => "le",
+    }
+}
+
+/// Converts a field name to a valid Kaitai identifier (tuple fields are numbered).
+fn identifier(name: &str) -> String {
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("field_{}", name)
+    } else {
+        snake_case(name)
+    }
+}
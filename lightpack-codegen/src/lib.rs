@@ -0,0 +1,208 @@
+//! Code generators that emit codecs for Lightpack's wire format in other
+//! languages, based on the static [`Schema`](lightpack::Schema) of derived types.
+//!
+//! The generators are intended to be run from build scripts or small helper
+//! binaries, e.g.:
+//!
+//! ```ignore
+//! let header = lightpack_codegen::c::Generator::new("protocol")
+//!     .add::<Point>()
+//!     .generate()?;
+//! std::fs::write(out_dir.join("protocol.h"), header)?;
+//! ```
+
+pub mod c;
+pub mod kaitai;
+pub mod lock;
+pub mod python;
+pub mod typescript;
+pub mod wireshark;
+
+use std::fmt;
+
+use lightpack::schema::{Field, Type};
+
+/// An error during code generation.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub enum Error {
+    /// A top-level type is neither a struct nor an enum and therefore has no name.
+    Unnamed,
+    /// Two different types share the same name (e.g. two instantiations of a generic struct).
+    Conflict(&'static str),
+    /// The type cannot be represented in the target language.
+    Unsupported(String),
+}
+
+/// The result type for generated code.
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Unnamed => write!(f, "only structs and enums can be generated as top-level types"),
+            Error::Conflict(name) => write!(f, "multiple different types are named {}", name),
+            Error::Unsupported(what) => write!(f, "unsupported type: {}", what),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// The name of a struct or enum.
+fn type_name(ty: &Type) -> Option<&'static str> {
+    match ty {
+        Type::Struct { name, .. } | Type::Enum { name, .. } => Some(name),
+        _ => None,
+    }
+}
+
+/// Collects the given types and all named types they (transitively) refer to,
+/// ordered such that every type comes after its dependencies.
+fn named_types(roots: &[&'static Type]) -> Result<Vec<&'static Type>> {
+    fn visit(ty: &'static Type, types: &mut Vec<&'static Type>) -> Result<()> {
+        match ty {
+            Type::Primitive(_) | Type::Reserved(_) | Type::Varint(_) | Type::Bcd(_) | Type::Float { .. } => {},
+            Type::Tuple(tys) => for ty in tys.iter() {
+                visit(ty, types)?;
+            },
+            Type::Array { element, .. } => visit(element, types)?,
+            Type::Option(ty) | Type::Endian(_, ty) => visit(ty, types)?,
+            Type::Struct { name, fields } => {
+                if register(name, ty, types)? {
+                    for field in fields.iter() {
+                        visit(field.ty, types)?;
+                    }
+                    types.push(ty);
+                }
+            },
+            Type::Enum { name, .. } => if register(name, ty, types)? {
+                types.push(ty);
+            },
+        }
+        Ok(())
+    }
+
+    /// Checks whether a type has to be added, i.e. wasn't visited yet.
+    fn register(name: &'static str, ty: &'static Type, types: &[&'static Type]) -> Result<bool> {
+        match types.iter().find(|t| type_name(t) == Some(name)) {
+            Some(existing) if *existing == ty => Ok(false),
+            Some(_) => Err(Error::Conflict(name)),
+            None => Ok(true),
+        }
+    }
+
+    let mut types = Vec::new();
+    for root in roots {
+        if type_name(root).is_none() {
+            return Err(Error::Unnamed);
+        }
+        visit(root, &mut types)?;
+    }
+    Ok(types)
+}
+
+/// Like [`named_types`], but rejects varints, since the generated codecs
+/// address values by fixed offsets.
+fn fixed_size_types(roots: &[&'static Type]) -> Result<Vec<&'static Type>> {
+    fn check(ty: &Type) -> Result<()> {
+        match ty {
+            Type::Varint(_) => Err(Error::Unsupported("varints".to_owned())),
+            Type::Tuple(tys) => tys.iter().try_for_each(check),
+            Type::Array { element: ty, .. } | Type::Option(ty) | Type::Endian(_, ty) => check(ty),
+            Type::Struct { fields, .. } => fields.iter().try_for_each(|field| check(field.ty)),
+            Type::Primitive(_) | Type::Bcd(_) | Type::Float { .. } | Type::Enum { .. } | Type::Reserved(_) => Ok(()),
+        }
+    }
+
+    let types = named_types(roots)?;
+    types.iter().try_for_each(|ty| check(ty))?;
+    Ok(types)
+}
+
+/// A position in the buffer, i.e. a constant offset plus loop variables
+/// multiplied by their strides.
+#[derive(Debug, Default, Clone)]
+struct Offset {
+    base: usize,
+    terms: Vec<(String, usize)>,
+}
+
+impl Offset {
+    fn plus(&self, delta: usize) -> Self {
+        Self { base: self.base + delta, terms: self.terms.clone() }
+    }
+
+    fn with_term(&self, var: &str, stride: usize) -> Self {
+        let mut terms = self.terms.clone();
+        terms.push((var.to_owned(), stride));
+        Self { base: self.base, terms }
+    }
+
+    /// Renders the offset as an expression relative to `start`.
+    fn render(&self, start: &str) -> String {
+        let mut result = start.to_owned();
+        if self.base > 0 {
+            result.push_str(&format!(" + {}", self.base));
+        }
+        for (var, stride) in &self.terms {
+            if *stride == 1 {
+                result.push_str(&format!(" + {}", var));
+            } else {
+                result.push_str(&format!(" + {} * {}", stride, var));
+            }
+        }
+        result
+    }
+}
+
+/// Converts a `CamelCase` name to `snake_case`.
+fn snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut result = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() {
+            let prev_lower = i > 0 && (chars[i - 1].is_lowercase() || chars[i - 1].is_ascii_digit());
+            let next_lower = chars.get(i + 1).is_some_and(|n| n.is_lowercase());
+            let prev_upper = i > 0 && chars[i - 1].is_uppercase();
+            if prev_lower || (prev_upper && next_lower) {
+                result.push('_');
+            }
+            result.extend(c.to_lowercase());
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+/// Converts a `CamelCase` name to `UPPER_SNAKE_CASE`.
+fn upper_snake_case(name: &str) -> String {
+    snake_case(name).to_uppercase()
+}
+
+/// Converts a field name to a valid identifier (tuple struct fields are numbered).
+fn member_name(name: &str) -> String {
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{}", name)
+    } else {
+        name.to_owned()
+    }
+}
+
+/// The expression converting the encoded value `raw` of a scaled field, e.g.
+/// `raw * 0.01 - 40.0`, or `None` if the field is not scaled.
+fn scaled_value(field: &Field, raw: &str) -> Option<String> {
+    if !field.is_scaled() {
+        return None;
+    }
+    let mut result = raw.to_owned();
+    if field.scale != 1.0 {
+        result.push_str(&format!(" * {:?}", field.scale));
+    }
+    if field.offset < 0.0 {
+        result.push_str(&format!(" - {:?}", -field.offset));
+    } else if field.offset != 0.0 {
+        result.push_str(&format!(" + {:?}", field.offset));
+    }
+    Some(result)
+}
@@ -0,0 +1,471 @@
+//! Wire-layout lock files.
+//!
+//! A lock file records the layout of selected types in a human-readable
+//! format that is meant to be checked into the repository:
+//!
+//! ```text
+//! struct Frame (7 bytes)
+//!     seq: u32
+//!     mode: Mode
+//!     origin: Option<(i8, i8)>
+//!
+//! enum Mode: u8 (catch-all)
+//!     Off = 0
+//!     On = 1
+//! ```
+//!
+//! [`Lock::check`] compares the current layout with the lock file and
+//! classifies every difference as compatible (fields appended to a struct
+//! that is not embedded in another one, new variants of enums with a
+//! catch-all, renames and new types) or breaking (everything else, e.g.
+//! reordered or removed fields, changed types, scales or offsets and removed
+//! variants). Scaled fields are recorded with their conversion, e.g.
+//! `i16 (scale 0.01, offset -40.0)`.
+//!
+//! The check is intended to be called from a test:
+//!
+//! ```ignore
+//! #[test]
+//! fn wire_layout() {
+//!     Lock::new().add::<Frame>().check("wire.lock").unwrap();
+//! }
+//! ```
+//!
+//! Any difference, including a missing lock file, fails the check until the
+//! lock file is updated by running the test with `LIGHTPACK_UPDATE_LOCK=1`,
+//! or `LIGHTPACK_UPDATE_LOCK=breaking` if there are breaking changes. Other
+//! values of the variable are ignored.
+//!
+//! Alternatively, [`Lock::run`] serves as the `main` function of a small
+//! binary that writes the lock file and prints the changes, e.g. in
+//! `src/bin/wire-lock.rs` (see `examples/wire_lock.rs`):
+//!
+//! ```ignore
+//! fn main() -> ExitCode {
+//!     Lock::new().add::<Frame>().run("wire.lock")
+//! }
+//! ```
+//!
+//! ```text
+//! $ cargo run --bin wire-lock
+//! wire.lock: Frame: appended field flags: u16 (compatible)
+//! ```
+
+use std::{env, fmt::{self, Write}, fs, io, path::{Path, PathBuf}, process::ExitCode};
+
+use lightpack::{Schema, schema::{Endianness, Field, Primitive, Type}};
+
+use crate::named_types;
+
+/// The environment variable that allows updating the lock file.
+pub const UPDATE_VAR: &str = "LIGHTPACK_UPDATE_LOCK";
+
+/// An error while checking a lock file.
+#[derive(Debug)]
+pub enum Error {
+    /// The locked types cannot be described.
+    Codegen(crate::Error),
+    /// The lock file could not be read or written.
+    Io(io::Error),
+    /// The lock file does not exist.
+    Missing(PathBuf),
+    /// The lock file could not be parsed.
+    Malformed { line: usize, message: String },
+    /// The layout differs from the lock file.
+    Outdated(Vec<Change>),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Codegen(e) => write!(f, "{}", e),
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Missing(path) => write!(f, "the lock file {} does not exist, rerun with {}=1 to create it", path.display(), UPDATE_VAR),
+            Error::Malformed { line, message } => write!(f, "malformed lock file in line {}: {}", line, message),
+            Error::Outdated(changes) => {
+                let breaking = changes.iter().any(|c| c.compatibility == Compatibility::Breaking);
+                writeln!(f, "the wire layout differs from the lock file:")?;
+                for change in changes {
+                    writeln!(f, "  {}", change)?;
+                }
+                write!(f, "rerun with {}={} to update the lock file", UPDATE_VAR, if breaking { "breaking" } else { "1" })
+            },
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<crate::Error> for Error {
+    fn from(e: crate::Error) -> Self {
+        Error::Codegen(e)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+/// Whether a change keeps existing peers working.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Compatibility {
+    Compatible,
+    Breaking,
+}
+
+/// A difference between the locked and the current layout of a type.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct Change {
+    /// The name of the changed type.
+    pub ty: String,
+    /// Whether the change is compatible.
+    pub compatibility: Compatibility,
+    /// A description of the change.
+    pub description: String,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let compatibility = match self.compatibility {
+            Compatibility::Compatible => "compatible",
+            Compatibility::Breaking => "breaking",
+        };
+        write!(f, "{}: {} ({})", self.ty, self.description, compatibility)
+    }
+}
+
+/// A set of types whose layout is locked.
+#[derive(Debug, Clone, Default)]
+pub struct Lock {
+    roots: Vec<&'static Type>,
+    catch_all: Vec<&'static str>,
+}
+
+impl Lock {
+    /// Creates an empty lock.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a type (and all types it refers to) to the lock.
+    pub fn add<T>(mut self) -> Self where T: Schema {
+        self.roots.push(&T::SCHEMA);
+        self
+    }
+
+    /// Declares that peers handle unknown values of the enum `T` (e.g. by
+    /// mapping them to a catch-all variant), which makes new variants compatible.
+    pub fn catch_all<T>(mut self) -> Self where T: Schema {
+        if let Some(name) = crate::type_name(&T::SCHEMA) {
+            self.catch_all.push(name);
+        }
+        self
+    }
+
+    /// Renders the lock file.
+    pub fn render(&self) -> Result<String, Error> {
+        let mut out = String::new();
+        writeln!(out, "# Generated by lightpack-codegen. Do not edit.").unwrap();
+        writeln!(out, "# Update by running the check with {}=1 (or =breaking for breaking changes).", UPDATE_VAR).unwrap();
+        for ty in self.locked()? {
+            writeln!(out).unwrap();
+            match ty {
+                Locked::Struct { name, size, fields } => {
+                    writeln!(out, "struct {} ({} bytes)", name, size).unwrap();
+                    for (field, ty) in fields {
+                        writeln!(out, "    {}: {}", field, ty).unwrap();
+                    }
+                },
+                Locked::Enum { name, repr, catch_all, variants } => {
+                    writeln!(out, "enum {}: {}{}", name, repr, if catch_all { " (catch-all)" } else { "" }).unwrap();
+                    for (variant, value) in variants {
+                        writeln!(out, "    {} = {}", variant, value).unwrap();
+                    }
+                },
+            }
+        }
+        Ok(out)
+    }
+
+    /// Compares the current layout with the contents of a lock file.
+    pub fn diff(&self, lock_file: &str) -> Result<Vec<Change>, Error> {
+        Ok(diff(&parse(lock_file)?, &self.locked()?))
+    }
+
+    /// Checks the current layout against the lock file at the given path,
+    /// creating or updating it as described in the [module docs](self).
+    /// Returns the accepted changes.
+    pub fn check(&self, path: impl AsRef<Path>) -> Result<Vec<Change>, Error> {
+        let path = path.as_ref();
+        let allowed = match env::var(UPDATE_VAR).as_deref() {
+            Ok("1") => Some(Compatibility::Compatible),
+            Ok("breaking") => Some(Compatibility::Breaking),
+            _ => None,
+        };
+        let lock_file = match fs::read_to_string(path) {
+            Ok(lock_file) => lock_file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound && allowed.is_some() => String::new(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Err(Error::Missing(path.to_owned())),
+            Err(e) => return Err(e.into()),
+        };
+
+        let changes = self.diff(&lock_file)?;
+        let breaking = changes.iter().any(|c| c.compatibility == Compatibility::Breaking);
+        let accepted = match allowed {
+            Some(Compatibility::Breaking) => true,
+            Some(Compatibility::Compatible) => !breaking,
+            None => changes.is_empty(),
+        };
+        if !accepted {
+            return Err(Error::Outdated(changes));
+        }
+        self.write(path, &lock_file)?;
+        Ok(changes)
+    }
+
+    /// Writes the lock file at the given path regardless of the changes and
+    /// returns them.
+    pub fn update(&self, path: impl AsRef<Path>) -> Result<Vec<Change>, Error> {
+        let path = path.as_ref();
+        let lock_file = match fs::read_to_string(path) {
+            Ok(lock_file) => lock_file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e.into()),
+        };
+        let changes = self.diff(&lock_file)?;
+        self.write(path, &lock_file)?;
+        Ok(changes)
+    }
+
+    /// Updates the lock file at the given path and prints the changes, for
+    /// use as the `main` function of a binary.
+    pub fn run(&self, path: impl AsRef<Path>) -> ExitCode {
+        let path = path.as_ref();
+        match self.update(path) {
+            Ok(changes) if changes.is_empty() => println!("{}: unchanged", path.display()),
+            Ok(changes) => for change in changes {
+                println!("{}: {}", path.display(), change);
+            },
+            Err(e) => {
+                eprintln!("{}: {}", path.display(), e);
+                return ExitCode::FAILURE;
+            },
+        }
+        ExitCode::SUCCESS
+    }
+
+    /// Writes the rendered lock file if it differs from the existing one.
+    fn write(&self, path: &Path, lock_file: &str) -> Result<(), Error> {
+        let rendered = self.render()?;
+        if lock_file != rendered {
+            fs::write(path, rendered)?;
+        }
+        Ok(())
+    }
+
+    fn locked(&self) -> Result<Vec<Locked>, Error> {
+        let mut locked = Vec::new();
+        for ty in named_types(&self.roots)? {
+            match ty {
+                Type::Struct { name, fields } => locked.push(Locked::Struct {
+                    name: name.to_string(),
+                    size: ty.size(),
+                    fields: fields.iter().map(|f| (f.name.to_owned(), field_expression(f))).collect(),
+                }),
+                Type::Enum { name, repr, variants } => locked.push(Locked::Enum {
+                    name: name.to_string(),
+                    repr: type_expression(&Type::Primitive(*repr)),
+                    catch_all: self.catch_all.contains(name),
+                    variants: variants.iter().map(|v| (v.name.to_owned(), v.value)).collect(),
+                }),
+                _ => unreachable!("Only named types are locked"),
+            }
+        }
+        Ok(locked)
+    }
+}
+
+/// The locked layout of a type, with field types rendered as Rust types.
+#[derive(Debug, PartialEq, Eq, Clone)]
+enum Locked {
+    Struct { name: String, size: usize, fields: Vec<(String, String)> },
+    Enum { name: String, repr: String, catch_all: bool, variants: Vec<(String, i128)> },
+}
+
+impl Locked {
+    fn name(&self) -> &str {
+        match self {
+            Locked::Struct { name, .. } | Locked::Enum { name, .. } => name,
+        }
+    }
+}
+
+/// Renders a type like the Rust type that describes it.
+fn type_expression(ty: &Type) -> String {
+    match ty {
+        Type::Primitive(p) => match p {
+            Primitive::U8 => "u8",
+            Primitive::U16 => "u16",
+            Primitive::U32 => "u32",
+            Primitive::U64 => "u64",
+            Primitive::I8 => "i8",
+            Primitive::I16 => "i16",
+            Primitive::I32 => "i32",
+            Primitive::I64 => "i64",
+            Primitive::F32 => "f32",
+            Primitive::F64 => "f64",
+            Primitive::Bool => "bool",
+            Primitive::Char => "char",
+            Primitive::U24 => "U24",
+            Primitive::I24 => "I24",
+            Primitive::U40 => "U40",
+            Primitive::U48 => "U48",
+            Primitive::I48 => "I48",
+        }.to_owned(),
+        Type::Tuple(tys) if tys.len() == 1 => format!("({},)", type_expression(&tys[0])),
+        Type::Tuple(tys) => format!("({})", tys.iter().map(type_expression).collect::<Vec<_>>().join(", ")),
+        Type::Array { element, len } => format!("[{}; {}]", type_expression(element), len),
+        Type::Option(ty) => format!("Option<{}>", type_expression(ty)),
+        Type::Bcd(p) => format!("Bcd<{}>", type_expression(&Type::Primitive(*p))),
+        Type::Float { repr: Primitive::U16, exp_bits: 5, mantissa_bits: 10 } => "F16".to_owned(),
+        Type::Float { repr: Primitive::U16, exp_bits: 8, mantissa_bits: 7 } => "BF16".to_owned(),
+        Type::Float { exp_bits, mantissa_bits, .. } => format!("Minifloat<{}, {}>", exp_bits, mantissa_bits),
+        Type::Endian(Endianness::Big, ty) => format!("BE<{}>", type_expression(ty)),
+        Type::Endian(Endianness::Little, ty) => format!("LE<{}>", type_expression(ty)),
+        Type::Struct { name, .. } | Type::Enum { name, .. } => name.to_string(),
+        Type::Reserved(len) => format!("Reserved<{}>", len),
+        Type::Varint(p) if p.is_signed() => format!("ZigZag<{}>", type_expression(&Type::Primitive(*p))),
+        Type::Varint(p) => format!("Varint<{}>", type_expression(&Type::Primitive(*p))),
+    }
+}
+
+/// Parses a lock file.
+fn parse(lock_file: &str) -> Result<Vec<Locked>, Error> {
+    let mut locked: Vec<Locked> = Vec::new();
+    for (i, line) in lock_file.lines().enumerate() {
+        let malformed = |message: &str| Error::Malformed { line: i + 1, message: message.to_owned() };
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(member) = line.strip_prefix("    ") {
+            match locked.last_mut() {
+                Some(Locked::Struct { fields, .. }) => {
+                    let (name, ty) = member.split_once(": ").ok_or_else(|| malformed("expected `<field>: <type>`"))?;
+                    fields.push((name.to_owned(), ty.to_owned()));
+                },
+                Some(Locked::Enum { variants, .. }) => {
+                    let (name, value) = member.split_once(" = ").ok_or_else(|| malformed("expected `<variant> = <value>`"))?;
+                    let value = value.parse().map_err(|_| malformed("invalid enum value"))?;
+                    variants.push((name.to_owned(), value));
+                },
+                None => return Err(malformed("expected a struct or enum")),
+            }
+        } else if let Some(header) = line.strip_prefix("struct ") {
+            let (name, size) = header.strip_suffix(" bytes)")
+                .and_then(|h| h.split_once(" ("))
+                .ok_or_else(|| malformed("expected `struct <name> (<size> bytes)`"))?;
+            let size = size.parse().map_err(|_| malformed("invalid size"))?;
+            locked.push(Locked::Struct { name: name.to_owned(), size, fields: Vec::new() });
+        } else if let Some(header) = line.strip_prefix("enum ") {
+            let (header, catch_all) = match header.strip_suffix(" (catch-all)") {
+                Some(header) => (header, true),
+                None => (header, false),
+            };
+            let (name, repr) = header.split_once(": ").ok_or_else(|| malformed("expected `enum <name>: <repr>`"))?;
+            locked.push(Locked::Enum { name: name.to_owned(), repr: repr.to_owned(), catch_all, variants: Vec::new() });
+        } else {
+            return Err(malformed("expected a struct or enum"));
+        }
+    }
+    Ok(locked)
+}
+
+/// Compares the locked types with the current ones.
+fn diff(old: &[Locked], new: &[Locked]) -> Vec<Change> {
+    let mut changes = Vec::new();
+    let mut change = |ty: &str, compatibility, description: String| {
+        changes.push(Change { ty: ty.to_owned(), compatibility, description });
+    };
+
+    for old_ty in old {
+        let name = old_ty.name();
+        let Some(new_ty) = new.iter().find(|t| t.name() == name) else {
+            change(name, Compatibility::Breaking, "type is no longer locked".to_owned());
+            continue;
+        };
+        match (old_ty, new_ty) {
+            (Locked::Struct { fields: old_fields, .. }, Locked::Struct { fields: new_fields, .. }) => {
+                for (i, (old_field, old_type)) in old_fields.iter().enumerate() {
+                    let moved = new_fields.iter().position(|(f, _)| f == old_field).is_some_and(|j| j != i)
+                        || new_fields.get(i).is_some_and(|(f, _)| f != old_field && old_fields.iter().any(|(o, _)| o == f));
+                    match new_fields.get(i) {
+                        _ if moved => change(name, Compatibility::Breaking, format!("moved field {}", old_field)),
+                        Some((new_field, new_type)) if new_type == old_type => if new_field != old_field {
+                            change(name, Compatibility::Compatible, format!("renamed field {} to {}", old_field, new_field));
+                        },
+                        Some((new_field, new_type)) if new_field == old_field => {
+                            change(name, Compatibility::Breaking, format!("changed type of field {} from {} to {}", old_field, old_type, new_type));
+                        },
+                        _ => change(name, Compatibility::Breaking, format!("removed field {}", old_field)),
+                    }
+                }
+                for (new_field, new_type) in new_fields.iter().skip(old_fields.len()) {
+                    // Appending changes the size, which shifts the values following an embedded struct
+                    let embedding = new.iter().find_map(|t| match t {
+                        Locked::Struct { name: outer, fields, .. } if fields.iter().any(|(_, ty)| mentions(ty, name)) => Some(outer),
+                        _ => None,
+                    });
+                    match embedding {
+                        Some(outer) => change(name, Compatibility::Breaking, format!("appended field {}: {}, which changes the layout of {}", new_field, new_type, outer)),
+                        None => change(name, Compatibility::Compatible, format!("appended field {}: {}", new_field, new_type)),
+                    }
+                }
+            },
+            (Locked::Enum { repr: old_repr, variants: old_variants, .. }, Locked::Enum { repr: new_repr, catch_all, variants: new_variants, .. }) => {
+                if old_repr != new_repr {
+                    change(name, Compatibility::Breaking, format!("changed representation from {} to {}", old_repr, new_repr));
+                }
+                for (old_variant, old_value) in old_variants {
+                    match new_variants.iter().find(|(_, v)| v == old_value) {
+                        Some((new_variant, _)) if new_variant != old_variant => {
+                            change(name, Compatibility::Compatible, format!("renamed variant {} to {}", old_variant, new_variant));
+                        },
+                        Some(_) => {},
+                        None => change(name, Compatibility::Breaking, format!("removed variant {} = {}", old_variant, old_value)),
+                    }
+                }
+                for (new_variant, new_value) in new_variants {
+                    if !old_variants.iter().any(|(_, v)| v == new_value) {
+                        let compatibility = if *catch_all { Compatibility::Compatible } else { Compatibility::Breaking };
+                        change(name, compatibility, format!("added variant {} = {}", new_variant, new_value));
+                    }
+                }
+            },
+            _ => change(name, Compatibility::Breaking, "changed between struct and enum".to_owned()),
+        }
+    }
+
+    for new_ty in new {
+        if !old.iter().any(|t| t.name() == new_ty.name()) {
+            change(new_ty.name(), Compatibility::Compatible, "added type".to_owned());
+        }
+    }
+
+    changes
+}
+
+/// The rendered type of a field, followed by its scale and offset if it is
+/// scaled (changing them changes the meaning of the encoded value).
+fn field_expression(field: &Field) -> String {
+    match field.is_scaled() {
+        true => format!("{} (scale {:?}, offset {:?})", type_expression(field.ty), field.scale, field.offset),
+        false => type_expression(field.ty),
+    }
+}
+
+/// Whether a rendered type refers to the named type.
+fn mentions(ty: &str, name: &str) -> bool {
+    ty.split(|c: char| !(c.is_alphanumeric() || c == '_')).any(|word| word == name)
+}
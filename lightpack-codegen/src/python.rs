@@ -0,0 +1,445 @@
+//! Python codec generation.
+//!
+//! The generated module only depends on the standard library and defines,
+//! for every struct and enum reachable from the added types:
+//!
+//! - a `@dataclass` (structs) or an `IntEnum` (enums),
+//! - a `<NAME>_SIZE` constant and, if the type can be described by a single
+//!   format string, the big-endian `<NAME>_FORMAT` for the `struct` module
+//!   (the same as the derived `STRUCT_FORMAT`),
+//! - `<NAME>_SCALE_<FIELD>` and `<NAME>_VALUE_OFFSET_<FIELD>` constants for
+//!   scaled fields, whose attributes hold the encoded value,
+//! - `pack(little_endian=False)` and `pack_into(buffer, offset=0, little_endian=False)`
+//!   methods as well as `unpack(data, little_endian=False)` and
+//!   `unpack_from(data, offset=0, little_endian=False)` class methods that
+//!   produce and consume exactly the bytes of `Pack::pack` and `Unpack::unpack`.
+//!
+//! Integers are represented as `int`, floats (including reduced-precision
+//! ones) as `float`, chars as single-character strings, tuples as tuples,
+//! arrays as lists, options as `Optional[T]` and BCD values by their decimal
+//! value. Reserved bytes are zeroed when packing and omitted from dataclasses
+//! (or `None` in tuples). Unpacking raises a `ValueError` for invalid enum
+//! values, chars and BCD digits.
+
+use std::fmt::Write;
+
+use lightpack::{Schema, schema::{Endianness, Primitive, StructFormat, Type}};
+
+use crate::{Offset, Result, fixed_size_types, member_name, scaled_value, upper_snake_case};
+
+/// Imports and helpers shared by the generated classes.
+const HELPERS: &str = r#"from __future__ import annotations
+
+import math
+import struct
+from dataclasses import dataclass
+from enum import IntEnum
+from typing import List, Optional, Tuple
+
+
+def _order(little_endian: bool) -> str:
+    return "<" if little_endian else ">"
+
+
+def _byteorder(little_endian: bool) -> str:
+    return "little" if little_endian else "big"
+
+
+def _encode_char(value: str) -> int:
+    if len(value) != 1:
+        raise ValueError(f"Not a single character: {value!r}")
+    return ord(value)
+
+
+def _decode_char(code_point: int) -> str:
+    if code_point > 0x10FFFF or 0xD800 <= code_point <= 0xDFFF:
+        raise ValueError(f"Invalid char: {code_point}")
+    return chr(code_point)
+
+
+def _encode_bcd(value: int) -> int:
+    return int(str(value), 16)
+
+
+def _decode_bcd(bits: int) -> int:
+    digits = f"{bits:x}"
+    if not digits.isdigit():
+        raise ValueError(f"Invalid BCD: {bits:#x}")
+    return int(digits)
+
+
+def _encode_float(value: float, exp_bits: int, mantissa_bits: int) -> int:
+    sign = 1 << (exp_bits + mantissa_bits) if math.copysign(1.0, value) < 0 else 0
+    exp_mask = (1 << exp_bits) - 1
+    bias = (1 << (exp_bits - 1)) - 1
+    if math.isnan(value):
+        return sign | exp_mask << mantissa_bits | 1 << (mantissa_bits - 1)
+    value = abs(value)
+    if value == 0:
+        return sign
+    magnitude = math.frexp(value)[1] - 1
+    if math.isinf(value) or magnitude > bias:
+        return sign | exp_mask << mantissa_bits
+    unit = max(magnitude, 1 - bias) - mantissa_bits
+    # round() rounds to the nearest integer with ties to even
+    mantissa = round(math.ldexp(value, -unit))
+    if mantissa >> (mantissa_bits + 1):
+        mantissa >>= 1
+        unit += 1
+    if not mantissa >> mantissa_bits:
+        return sign | mantissa
+    biased = unit + mantissa_bits + bias
+    if biased >= exp_mask:
+        return sign | exp_mask << mantissa_bits
+    return sign | biased << mantissa_bits | mantissa & ((1 << mantissa_bits) - 1)
+
+
+def _decode_float(bits: int, exp_bits: int, mantissa_bits: int) -> float:
+    sign = -1.0 if bits >> (exp_bits + mantissa_bits) & 1 else 1.0
+    exp_mask = (1 << exp_bits) - 1
+    bias = (1 << (exp_bits - 1)) - 1
+    exp = bits >> mantissa_bits & exp_mask
+    mantissa = bits & ((1 << mantissa_bits) - 1)
+    if exp == exp_mask:
+        return math.copysign(math.nan if mantissa else math.inf, sign)
+    if exp == 0:
+        return sign * math.ldexp(mantissa, 1 - bias - mantissa_bits)
+    return sign * math.ldexp(mantissa | 1 << mantissa_bits, exp - bias - mantissa_bits)
+"#;
+
+/// Python keywords and builtin constants that cannot be used as names.
+const KEYWORDS: &[&str] = &[
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break",
+    "class", "continue", "def", "del", "elif", "else", "except", "finally", "for",
+    "from", "global", "if", "import", "in", "is", "lambda", "nonlocal", "not",
+    "or", "pass", "raise", "return", "try", "while", "with", "yield",
+];
+
+/// A generator for a Python module.
+#[derive(Debug, Clone, Default)]
+pub struct Generator {
+    roots: Vec<&'static Type>,
+}
+
+impl Generator {
+    /// Creates a generator for a Python module.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a type (and all types it refers to) to the module.
+    pub fn add<T>(mut self) -> Self where T: Schema {
+        self.roots.push(&T::SCHEMA);
+        self
+    }
+
+    /// Generates the module.
+    pub fn generate(&self) -> Result<String> {
+        let mut out = String::new();
+        writeln!(out, "# Generated by lightpack-codegen. Do not edit.").unwrap();
+        writeln!(out).unwrap();
+        out.push_str(HELPERS);
+
+        for ty in fixed_size_types(&self.roots)? {
+            let name = crate::type_name(ty).unwrap();
+            writeln!(out).unwrap();
+            writeln!(out).unwrap();
+            writeln!(out, "{}_SIZE = {}", upper_snake_case(name), ty.size()).unwrap();
+            if let Some(format) = StructFormat::new(ty, Endianness::Big).as_str() {
+                writeln!(out, "{}_FORMAT = \"{}\"", upper_snake_case(name), format).unwrap();
+            }
+            if let Type::Struct { fields, .. } = ty {
+                for field in fields.iter().filter(|f| f.is_scaled()) {
+                    writeln!(out, "{}_SCALE_{} = {:?}", upper_snake_case(name), field.name.to_uppercase(), field.scale).unwrap();
+                    writeln!(out, "{}_VALUE_OFFSET_{} = {:?}", upper_snake_case(name), field.name.to_uppercase(), field.offset).unwrap();
+                }
+            }
+            writeln!(out).unwrap();
+            writeln!(out).unwrap();
+
+            let mut emitter = Emitter { body: String::new(), indent: 2, loop_depth: 0 };
+            match ty {
+                Type::Struct { fields, .. } => {
+                    writeln!(out, "@dataclass").unwrap();
+                    writeln!(out, "class {}:", name).unwrap();
+                    for field in fields.iter().filter(|field| !is_reserved(field.ty)) {
+                        let member = identifier(&member_name(field.name));
+                        match scaled_value(field, &member) {
+                            Some(value) => writeln!(out, "    {}: {}  # value = {}", member, type_annotation(field.ty), value).unwrap(),
+                            None => writeln!(out, "    {}: {}", member, type_annotation(field.ty)).unwrap(),
+                        }
+                    }
+                    let mut offset = Offset::default();
+                    for field in fields.iter() {
+                        emitter.encode(field.ty, &format!("self.{}", identifier(&member_name(field.name))), &offset, &Order::Inherited);
+                        offset = offset.plus(field.ty.size());
+                    }
+                },
+                Type::Enum { repr, variants, .. } => {
+                    writeln!(out, "class {}(IntEnum):", name).unwrap();
+                    for variant in variants.iter() {
+                        writeln!(out, "    {} = {}", identifier(variant.name), variant.value).unwrap();
+                    }
+                    emitter.encode(&Type::Primitive(*repr), "self", &Offset::default(), &Order::Inherited);
+                },
+                _ => unreachable!("Only named types are generated"),
+            }
+            if emitter.body.is_empty() {
+                emitter.line("pass".to_owned());
+            }
+
+            writeln!(out).unwrap();
+            writeln!(out, "    def pack(self, little_endian: bool = False) -> bytes:").unwrap();
+            writeln!(out, "        buffer = bytearray({}_SIZE)", upper_snake_case(name)).unwrap();
+            writeln!(out, "        self.pack_into(buffer, 0, little_endian)").unwrap();
+            writeln!(out, "        return bytes(buffer)").unwrap();
+            writeln!(out).unwrap();
+            writeln!(out, "    def pack_into(self, buffer: bytearray, offset: int = 0, little_endian: bool = False) -> None:").unwrap();
+            out.push_str(&emitter.body);
+            writeln!(out).unwrap();
+            writeln!(out, "    @classmethod").unwrap();
+            writeln!(out, "    def unpack(cls, data: bytes, little_endian: bool = False) -> {}:", name).unwrap();
+            writeln!(out, "        return cls.unpack_from(data, 0, little_endian)").unwrap();
+            writeln!(out).unwrap();
+            writeln!(out, "    @classmethod").unwrap();
+            writeln!(out, "    def unpack_from(cls, data: bytes, offset: int = 0, little_endian: bool = False) -> {}:", name).unwrap();
+            match ty {
+                Type::Struct { fields, .. } => {
+                    writeln!(out, "        return cls(").unwrap();
+                    let mut offset = Offset::default();
+                    for field in fields.iter() {
+                        if !is_reserved(field.ty) {
+                            writeln!(out, "            {}={},", identifier(&member_name(field.name)), decode(field.ty, &offset, &Order::Inherited, 0)).unwrap();
+                        }
+                        offset = offset.plus(field.ty.size());
+                    }
+                    writeln!(out, "        )").unwrap();
+                },
+                Type::Enum { repr, .. } => {
+                    writeln!(out, "        return cls({})", decode(&Type::Primitive(*repr), &Offset::default(), &Order::Inherited, 0)).unwrap();
+                },
+                _ => unreachable!("Only named types are generated"),
+            }
+        }
+
+        Ok(out)
+    }
+}
+
+/// The byte order of a value.
+enum Order {
+    /// The byte order passed to the surrounding function.
+    Inherited,
+    /// A fixed byte order.
+    Fixed(Endianness),
+}
+
+impl Order {
+    /// The expression for the `little_endian` flag.
+    fn flag(&self) -> &'static str {
+        match self {
+            Order::Inherited => "little_endian",
+            Order::Fixed(Endianness::Big) => "False",
+            Order::Fixed(Endianness::Little) => "True",
+        }
+    }
+
+    /// The expression for the `byteorder` argument of `int.to_bytes`.
+    fn byteorder(&self) -> &'static str {
+        match self {
+            Order::Inherited => "_byteorder(little_endian)",
+            Order::Fixed(Endianness::Big) => "\"big\"",
+            Order::Fixed(Endianness::Little) => "\"little\"",
+        }
+    }
+
+    /// The expression for a format string with the given codes.
+    fn format(&self, codes: &str) -> String {
+        match self {
+            Order::Inherited => format!("_order(little_endian) + \"{}\"", codes),
+            Order::Fixed(Endianness::Big) => format!("\">{}\"", codes),
+            Order::Fixed(Endianness::Little) => format!("\"<{}\"", codes),
+        }
+    }
+}
+
+/// Emits the statements of a `pack_into` method.
+struct Emitter {
+    body: String,
+    indent: usize,
+    loop_depth: usize,
+}
+
+impl Emitter {
+    fn line(&mut self, line: String) {
+        for _ in 0..self.indent {
+            self.body.push_str("    ");
+        }
+        self.body.push_str(&line);
+        self.body.push('\n');
+    }
+
+    fn encode(&mut self, ty: &Type, value: &str, offset: &Offset, order: &Order) {
+        let position = offset.render("offset");
+        match ty {
+            Type::Primitive(Primitive::Char) => {
+                self.line(format!("struct.pack_into({}, buffer, {}, _encode_char({}))", order.format("I"), position, value));
+            },
+            Type::Primitive(p) if is_odd_width(*p) => {
+                let end = offset.plus(p.size()).render("offset");
+                self.line(format!("buffer[{}:{}] = {}.to_bytes({}, {}, signed={})", position, end, value, p.size(), order.byteorder(), python_bool(p.is_signed())));
+            },
+            Type::Primitive(p) => {
+                self.line(format!("struct.pack_into({}, buffer, {}, {})", order.format(code(*p)), position, value));
+            },
+            Type::Tuple(tys) => {
+                let mut offset = offset.clone();
+                for (i, ty) in tys.iter().enumerate() {
+                    self.encode(ty, &format!("{}[{}]", value, i), &offset, order);
+                    offset = offset.plus(ty.size());
+                }
+            },
+            Type::Array { element: Type::Primitive(p), len } if *p != Primitive::Char && !is_odd_width(*p) => {
+                self.line(format!("struct.pack_into({}, buffer, {}, *{})", order.format(&format!("{}{}", len, code(*p))), position, value));
+            },
+            Type::Array { element, len } => {
+                let var = format!("i{}", self.loop_depth);
+                self.line(format!("for {} in range({}):", var, len));
+                self.indent += 1;
+                self.loop_depth += 1;
+                self.encode(element, &format!("{}[{}]", value, var), &offset.with_term(&var, element.size()), order);
+                self.loop_depth -= 1;
+                self.indent -= 1;
+            },
+            Type::Option(ty) => {
+                self.line(format!("struct.pack_into(\"?\", buffer, {}, {} is not None)", position, value));
+                self.line(format!("if {} is not None:", value));
+                self.indent += 1;
+                self.encode(ty, value, &offset.plus(1), order);
+                self.indent -= 1;
+            },
+            Type::Bcd(p) => {
+                self.line(format!("struct.pack_into({}, buffer, {}, _encode_bcd({}))", order.format(code(*p)), position, value));
+            },
+            Type::Float { repr, exp_bits, mantissa_bits } => {
+                let bits = format!("_encode_float({}, {}, {})", value, exp_bits, mantissa_bits);
+                self.line(format!("struct.pack_into({}, buffer, {}, {})", order.format(code(*repr)), position, bits));
+            },
+            Type::Endian(endianness, ty) => self.encode(ty, value, offset, &Order::Fixed(*endianness)),
+            Type::Struct { .. } => {
+                self.line(format!("{}.pack_into(buffer, {}, {})", value, position, order.flag()));
+            },
+            Type::Enum { name, .. } => {
+                self.line(format!("{}({}).pack_into(buffer, {}, {})", name, value, position, order.flag()));
+            },
+            Type::Varint(_) => unreachable!("varints are rejected by fixed_size_types"),
+            Type::Reserved(0) => {},
+            Type::Reserved(len) => {
+                self.line(format!("struct.pack_into(\"{}x\", buffer, {})", len, position));
+            },
+        }
+    }
+}
+
+/// The expression unpacking the given type.
+fn decode(ty: &Type, offset: &Offset, order: &Order, loop_depth: usize) -> String {
+    let position = offset.render("offset");
+    match ty {
+        Type::Primitive(Primitive::Char) => format!("_decode_char(struct.unpack_from({}, data, {})[0])", order.format("I"), position),
+        Type::Primitive(p) if is_odd_width(*p) => {
+            let end = offset.plus(p.size()).render("offset");
+            format!("int.from_bytes(data[{}:{}], {}, signed={})", position, end, order.byteorder(), python_bool(p.is_signed()))
+        },
+        Type::Primitive(p) => format!("struct.unpack_from({}, data, {})[0]", order.format(code(*p)), position),
+        Type::Tuple(tys) => {
+            let mut offset = offset.clone();
+            let mut elements = Vec::new();
+            for ty in tys.iter() {
+                elements.push(decode(ty, &offset, order, loop_depth));
+                offset = offset.plus(ty.size());
+            }
+            match elements.len() {
+                1 => format!("({},)", elements[0]),
+                _ => format!("({})", elements.join(", ")),
+            }
+        },
+        Type::Array { element: Type::Primitive(p), len } if *p != Primitive::Char && !is_odd_width(*p) => {
+            format!("list(struct.unpack_from({}, data, {}))", order.format(&format!("{}{}", len, code(*p))), position)
+        },
+        Type::Array { element, len } => {
+            let var = format!("i{}", loop_depth);
+            let element = decode(element, &offset.with_term(&var, element.size()), order, loop_depth + 1);
+            format!("[{} for {} in range({})]", element, var, len)
+        },
+        Type::Option(ty) => format!("({} if data[{}] != 0 else None)", decode(ty, &offset.plus(1), order, loop_depth), position),
+        Type::Bcd(p) => format!("_decode_bcd(struct.unpack_from({}, data, {})[0])", order.format(code(*p)), position),
+        Type::Float { repr, exp_bits, mantissa_bits } => {
+            format!("_decode_float(struct.unpack_from({}, data, {})[0], {}, {})", order.format(code(*repr)), position, exp_bits, mantissa_bits)
+        },
+        Type::Endian(endianness, ty) => decode(ty, offset, &Order::Fixed(*endianness), loop_depth),
+        Type::Struct { name, .. } | Type::Enum { name, .. } => format!("{}.unpack_from(data, {}, {})", name, position, order.flag()),
+        Type::Varint(_) => unreachable!("varints are rejected by fixed_size_types"),
+        Type::Reserved(_) => "None".to_owned(),
+    }
+}
+
+/// The Python type of a value of the given type.
+fn type_annotation(ty: &Type) -> String {
+    match ty {
+        Type::Primitive(Primitive::Bool) => "bool".to_owned(),
+        Type::Primitive(Primitive::Char) => "str".to_owned(),
+        Type::Primitive(p) if p.is_float() => "float".to_owned(),
+        Type::Float { .. } => "float".to_owned(),
+        Type::Primitive(_) | Type::Bcd(_) => "int".to_owned(),
+        Type::Tuple([]) => "Tuple[()]".to_owned(),
+        Type::Tuple(tys) => format!("Tuple[{}]", tys.iter().map(type_annotation).collect::<Vec<_>>().join(", ")),
+        Type::Array { element, .. } => format!("List[{}]", type_annotation(element)),
+        Type::Option(ty) => format!("Optional[{}]", type_annotation(ty)),
+        Type::Endian(_, ty) => type_annotation(ty),
+        Type::Struct { name, .. } | Type::Enum { name, .. } => name.to_string(),
+        Type::Varint(_) => unreachable!("varints are rejected by fixed_size_types"),
+        Type::Reserved(_) => "None".to_owned(),
+    }
+}
+
+/// Whether values of the given type only reserve space.
+fn is_reserved(ty: &Type) -> bool {
+    matches!(ty, Type::Reserved(_))
+}
+
+/// The `struct` format code of a primitive.
+fn code(p: Primitive) -> &'static str {
+    match p {
+        Primitive::U8 => "B",
+        Primitive::U16 => "H",
+        Primitive::U32 | Primitive::Char => "I",
+        Primitive::U64 => "Q",
+        Primitive::I8 => "b",
+        Primitive::I16 => "h",
+        Primitive::I32 => "i",
+        Primitive::I64 => "q",
+        Primitive::F32 => "f",
+        Primitive::F64 => "d",
+        Primitive::Bool => "?",
+        Primitive::U24 | Primitive::I24 | Primitive::U40 | Primitive::U48 | Primitive::I48 => unreachable!("odd-width integers have no format code"),
+    }
+}
+
+/// Whether the primitive is an integer without a `struct` format code,
+/// which is converted with `int.to_bytes` and `int.from_bytes` instead.
+fn is_odd_width(p: Primitive) -> bool {
+    matches!(p, Primitive::U24 | Primitive::I24 | Primitive::U40 | Primitive::U48 | Primitive::I48)
+}
+
+fn python_bool(value: bool) -> &'static str {
+    if value { "True" } else { "False" }
+}
+
+/// Appends an underscore to names that are reserved in Python.
+fn identifier(name: &str) -> String {
+    if KEYWORDS.contains(&name) {
+        format!("{}_", name)
+    } else {
+        name.to_owned()
+    }
+}
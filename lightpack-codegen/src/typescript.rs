@@ -0,0 +1,524 @@
+//! TypeScript codec generation.
+//!
+//! The generated module exports, for every struct and enum reachable from
+//! the added types:
+//!
+//! - an `interface` (structs) or a constant object with a union type (enums),
+//! - a `<NAME>_SIZE` constant,
+//! - `<NAME>_SCALE_<FIELD>` and `<NAME>_VALUE_OFFSET_<FIELD>` constants for
+//!   scaled fields, whose members hold the encoded value,
+//! - `encode<Name>(value, view, offset = 0, littleEndian = false)` and
+//!   `decode<Name>(view, offset = 0, littleEndian = false)` functions that
+//!   operate on a `DataView` and produce and consume exactly the bytes of
+//!   `Pack::pack` and `Unpack::unpack`.
+//!
+//! Integers up to 48 bits and floats (including reduced-precision ones) are
+//! represented as `number`, 64-bit integers as `bigint`, chars as
+//! single-character strings, tuples and arrays as arrays, options as
+//! `T | null` and BCD values by their decimal value. Reserved bytes are
+//! zeroed when encoding and omitted from interfaces (or `null` in tuples).
+//! Decoding throws a `RangeError` for invalid enum values, chars and BCD
+//! digits. Nested options cannot be represented as `T | null | null` and are
+//! therefore not supported.
+
+use std::fmt::Write;
+
+use lightpack::{Schema, schema::{Endianness, Primitive, Type}};
+
+use crate::{Error, Offset, Result, fixed_size_types, member_name, scaled_value, upper_snake_case};
+
+/// Helpers shared by the generated functions.
+const HELPERS: &str = r#"function lpEncodeChar(value: string): number {
+  const codePoint = value.codePointAt(0);
+  if (codePoint === undefined || String.fromCodePoint(codePoint) !== value) {
+    throw new RangeError(`Not a single character: ${value}`);
+  }
+  return codePoint;
+}
+
+function lpDecodeChar(codePoint: number): string {
+  if (codePoint > 0x10ffff || (codePoint >= 0xd800 && codePoint <= 0xdfff)) {
+    throw new RangeError(`Invalid char: ${codePoint}`);
+  }
+  return String.fromCodePoint(codePoint);
+}
+
+function lpEncodeBcd(value: number): number {
+  let bits = 0;
+  for (let factor = 1; value > 0; factor *= 16) {
+    bits += (value % 10) * factor;
+    value = Math.floor(value / 10);
+  }
+  return bits;
+}
+
+function lpDecodeBcd(bits: number): number {
+  let value = 0;
+  for (let rest = bits, factor = 1; rest > 0; factor *= 10) {
+    const digit = rest % 16;
+    if (digit > 9) {
+      throw new RangeError(`Invalid BCD: ${bits.toString(16)}`);
+    }
+    value += digit * factor;
+    rest = Math.floor(rest / 16);
+  }
+  return value;
+}
+
+function lpEncodeFloat(value: number, expBits: number, mantissaBits: number): number {
+  const sign = value < 0 || Object.is(value, -0) ? 2 ** (expBits + mantissaBits) : 0;
+  const expMask = 2 ** expBits - 1;
+  const bias = 2 ** (expBits - 1) - 1;
+  if (Number.isNaN(value)) {
+    return expMask * 2 ** mantissaBits + 2 ** (mantissaBits - 1);
+  }
+  const magnitude = Math.abs(value);
+  if (magnitude === 0) {
+    return sign;
+  }
+  // Math.log2 may be off by one next to powers of two
+  let exp = Math.floor(Math.log2(magnitude));
+  if (2 ** exp > magnitude) {
+    exp--;
+  } else if (2 ** (exp + 1) <= magnitude) {
+    exp++;
+  }
+  if (exp > bias) {
+    return sign + expMask * 2 ** mantissaBits;
+  }
+  let unit = Math.max(exp, 1 - bias) - mantissaBits;
+  const scaled = magnitude / 2 ** unit;
+  let mantissa = Math.floor(scaled);
+  const rest = scaled - mantissa;
+  if (rest > 0.5 || (rest === 0.5 && mantissa % 2 === 1)) {
+    mantissa++;
+  }
+  if (mantissa >= 2 ** (mantissaBits + 1)) {
+    mantissa /= 2;
+    unit++;
+  }
+  if (mantissa < 2 ** mantissaBits) {
+    return sign + mantissa;
+  }
+  const biased = unit + mantissaBits + bias;
+  if (biased >= expMask) {
+    return sign + expMask * 2 ** mantissaBits;
+  }
+  return sign + biased * 2 ** mantissaBits + mantissa - 2 ** mantissaBits;
+}
+
+function lpDecodeFloat(bits: number, expBits: number, mantissaBits: number): number {
+  const sign = bits >= 2 ** (expBits + mantissaBits) ? -1 : 1;
+  const exp = Math.floor(bits / 2 ** mantissaBits) % 2 ** expBits;
+  const mantissa = bits % 2 ** mantissaBits;
+  const bias = 2 ** (expBits - 1) - 1;
+  if (exp === 2 ** expBits - 1) {
+    return mantissa === 0 ? sign * Infinity : NaN;
+  }
+  if (exp === 0) {
+    return sign * mantissa * 2 ** (1 - bias - mantissaBits);
+  }
+  return sign * (mantissa + 2 ** mantissaBits) * 2 ** (exp - bias - mantissaBits);
+}
+
+function lpSetInt(view: DataView, offset: number, value: number, size: number, littleEndian: boolean): void {
+  let rest = value < 0 ? value + 2 ** (8 * size) : value;
+  for (let i = 0; i < size; i++) {
+    view.setUint8(littleEndian ? offset + i : offset + size - 1 - i, rest % 256);
+    rest = Math.floor(rest / 256);
+  }
+}
+
+function lpGetUint(view: DataView, offset: number, size: number, littleEndian: boolean): number {
+  let value = 0;
+  for (let i = 0; i < size; i++) {
+    value = value * 256 + view.getUint8(littleEndian ? offset + size - 1 - i : offset + i);
+  }
+  return value;
+}
+
+function lpGetInt(view: DataView, offset: number, size: number, littleEndian: boolean): number {
+  const value = lpGetUint(view, offset, size, littleEndian);
+  return value >= 2 ** (8 * size - 1) ? value - 2 ** (8 * size) : value;
+}
+"#;
+
+/// A generator for a TypeScript module.
+#[derive(Debug, Clone, Default)]
+pub struct Generator {
+    javascript: bool,
+    roots: Vec<&'static Type>,
+}
+
+impl Generator {
+    /// Creates a generator for a TypeScript module.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Emits a plain JavaScript module (without type declarations and
+    /// annotations) instead, e.g. for consumers without a TypeScript toolchain.
+    pub fn javascript(mut self) -> Self {
+        self.javascript = true;
+        self
+    }
+
+    /// Adds a type (and all types it refers to) to the module.
+    pub fn add<T>(mut self) -> Self where T: Schema {
+        self.roots.push(&T::SCHEMA);
+        self
+    }
+
+    /// Generates the module.
+    pub fn generate(&self) -> Result<String> {
+        let mut out = String::new();
+        writeln!(out, "// Generated by lightpack-codegen. Do not edit.").unwrap();
+        writeln!(out).unwrap();
+        out.push_str(&self.strip(HELPERS));
+
+        for ty in fixed_size_types(&self.roots)? {
+            writeln!(out).unwrap();
+            let name = crate::type_name(ty).unwrap();
+            match ty {
+                Type::Struct { fields, .. } => {
+                    for field in fields.iter() {
+                        check_options(field.ty)?;
+                    }
+                    if !self.javascript {
+                        writeln!(out, "export interface {} {{", name).unwrap();
+                        for field in fields.iter().filter(|field| !is_reserved(field.ty)) {
+                            if let Some(value) = scaled_value(field, &member_name(field.name)) {
+                                writeln!(out, "  /** The encoded value, the value is `{}`. */", value).unwrap();
+                            }
+                            writeln!(out, "  {}: {};", member_name(field.name), type_annotation(field.ty)).unwrap();
+                        }
+                        writeln!(out, "}}").unwrap();
+                        writeln!(out).unwrap();
+                    }
+                },
+                Type::Enum { repr, variants, .. } => {
+                    writeln!(out, "export const {} = {{", name).unwrap();
+                    for variant in variants.iter() {
+                        writeln!(out, "  {}: {},", variant.name, literal(variant.value, *repr)).unwrap();
+                    }
+                    writeln!(out, "}}{};", self.ts(" as const")).unwrap();
+                    if !self.javascript {
+                        writeln!(out).unwrap();
+                        writeln!(out, "export type {0} = typeof {0}[keyof typeof {0}];", name).unwrap();
+                    }
+                    writeln!(out).unwrap();
+                },
+                _ => unreachable!("Only named types are generated"),
+            }
+
+            writeln!(out, "export const {}_SIZE = {};", upper_snake_case(name), ty.size()).unwrap();
+            if let Type::Struct { fields, .. } = ty {
+                for field in fields.iter().filter(|f| f.is_scaled()) {
+                    writeln!(out, "export const {}_SCALE_{} = {:?};", upper_snake_case(name), field.name.to_uppercase(), field.scale).unwrap();
+                    writeln!(out, "export const {}_VALUE_OFFSET_{} = {:?};", upper_snake_case(name), field.name.to_uppercase(), field.offset).unwrap();
+                }
+            }
+            writeln!(out).unwrap();
+
+            let mut emitter = Emitter { body: String::new(), indent: 1, loop_depth: 0, locals: 0 };
+            let offset = Offset::default();
+            match ty {
+                Type::Struct { fields, .. } => {
+                    let mut offset = offset;
+                    for field in fields.iter() {
+                        emitter.encode(field.ty, &format!("value.{}", member_name(field.name)), &offset, "littleEndian");
+                        offset = offset.plus(field.ty.size());
+                    }
+                },
+                Type::Enum { name, repr, .. } => {
+                    emitter.line(format!("if (!Object.values({}).includes(value)) {{", name));
+                    emitter.line(format!("  throw new RangeError(`Invalid {} value: ${{value}}`);", name));
+                    emitter.line("}".to_owned());
+                    emitter.encode(&Type::Primitive(*repr), "value", &offset, "littleEndian");
+                },
+                _ => unreachable!("Only named types are generated"),
+            }
+            writeln!(
+                out,
+                "export function encode{0}(value{1}, view{2}, offset{3} = 0, littleEndian{4} = false){5} {{",
+                name,
+                self.ts(&format!(": {}", name)),
+                self.ts(": DataView"),
+                self.ts(": number"),
+                self.ts(": boolean"),
+                self.ts(": void"),
+            ).unwrap();
+            out.push_str(&emitter.body);
+            writeln!(out, "}}").unwrap();
+            writeln!(out).unwrap();
+
+            writeln!(
+                out,
+                "export function decode{0}(view{1}, offset{2} = 0, littleEndian{3} = false){4} {{",
+                name,
+                self.ts(": DataView"),
+                self.ts(": number"),
+                self.ts(": boolean"),
+                self.ts(&format!(": {}", name)),
+            ).unwrap();
+            match ty {
+                Type::Struct { fields, .. } => {
+                    writeln!(out, "  return {{").unwrap();
+                    let mut offset = Offset::default();
+                    for field in fields.iter() {
+                        if !is_reserved(field.ty) {
+                            writeln!(out, "    {}: {},", member_name(field.name), decode(field.ty, &offset, "littleEndian", 0)).unwrap();
+                        }
+                        offset = offset.plus(field.ty.size());
+                    }
+                    writeln!(out, "  }};").unwrap();
+                },
+                Type::Enum { name, repr, .. } => {
+                    let values = if self.javascript {
+                        format!("Object.values({})", name)
+                    } else {
+                        format!("(Object.values({}) as {}[])", name, type_annotation(&Type::Primitive(*repr)))
+                    };
+                    writeln!(out, "  const value = {};", decode(&Type::Primitive(*repr), &Offset::default(), "littleEndian", 0)).unwrap();
+                    writeln!(out, "  if (!{}.includes(value)) {{", values).unwrap();
+                    writeln!(out, "    throw new RangeError(`Invalid {} value: ${{value}}`);", name).unwrap();
+                    writeln!(out, "  }}").unwrap();
+                    writeln!(out, "  return value{};", self.ts(&format!(" as {}", name))).unwrap();
+                },
+                _ => unreachable!("Only named types are generated"),
+            }
+            writeln!(out, "}}").unwrap();
+        }
+
+        Ok(out)
+    }
+
+    /// Returns the given TypeScript-only syntax, or nothing for JavaScript.
+    fn ts<'a>(&self, syntax: &'a str) -> &'a str {
+        if self.javascript { "" } else { syntax }
+    }
+
+    /// Removes the type annotations from the helpers for JavaScript.
+    fn strip(&self, code: &str) -> String {
+        if self.javascript {
+            code.replace(": string", "").replace(": number", "").replace(": boolean", "").replace(": DataView", "").replace(": void", "")
+        } else {
+            code.to_owned()
+        }
+    }
+}
+
+/// Emits the statements of an encode function.
+struct Emitter {
+    body: String,
+    indent: usize,
+    loop_depth: usize,
+    locals: usize,
+}
+
+impl Emitter {
+    fn line(&mut self, line: String) {
+        for _ in 0..self.indent {
+            self.body.push_str("  ");
+        }
+        self.body.push_str(&line);
+        self.body.push('\n');
+    }
+
+    fn encode(&mut self, ty: &Type, value: &str, offset: &Offset, little_endian: &str) {
+        let position = offset.render("offset");
+        match ty {
+            Type::Primitive(p) if is_odd_width(*p) => {
+                self.line(format!("lpSetInt(view, {}, {}, {}, {});", position, value, p.size(), little_endian));
+            },
+            Type::Primitive(p) => {
+                let (setter, value) = match p {
+                    Primitive::Bool => ("setUint8".to_owned(), format!("{} ? 1 : 0", value)),
+                    Primitive::Char => ("setUint32".to_owned(), format!("lpEncodeChar({})", value)),
+                    _ => (format!("set{}", accessor(*p)), value.to_owned()),
+                };
+                if p.size() == 1 {
+                    self.line(format!("view.{}({}, {});", setter, position, value));
+                } else {
+                    self.line(format!("view.{}({}, {}, {});", setter, position, value, little_endian));
+                }
+            },
+            Type::Tuple(tys) => {
+                let mut offset = offset.clone();
+                for (i, ty) in tys.iter().enumerate() {
+                    self.encode(ty, &format!("{}[{}]", value, i), &offset, little_endian);
+                    offset = offset.plus(ty.size());
+                }
+            },
+            Type::Array { element, len } => {
+                let var = format!("i{}", self.loop_depth);
+                self.line(format!("for (let {0} = 0; {0} < {1}; {0}++) {{", var, len));
+                self.indent += 1;
+                self.loop_depth += 1;
+                self.encode(element, &format!("{}[{}]", value, var), &offset.with_term(&var, element.size()), little_endian);
+                self.loop_depth -= 1;
+                self.indent -= 1;
+                self.line("}".to_owned());
+            },
+            Type::Option(ty) => {
+                // Bind the value to a local, since TypeScript does not narrow indexed accesses
+                let local = format!("o{}", self.locals);
+                self.locals += 1;
+                self.line(format!("const {} = {};", local, value));
+                self.line(format!("view.setUint8({}, {} !== null ? 1 : 0);", position, local));
+                self.line(format!("if ({} !== null) {{", local));
+                self.indent += 1;
+                self.encode(ty, &local, &offset.plus(1), little_endian);
+                self.indent -= 1;
+                self.line("}".to_owned());
+            },
+            Type::Bcd(p) if p.size() == 1 => {
+                self.line(format!("view.set{}({}, lpEncodeBcd({}));", accessor(*p), position, value));
+            },
+            Type::Bcd(p) => {
+                self.line(format!("view.set{}({}, lpEncodeBcd({}), {});", accessor(*p), position, value, little_endian));
+            },
+            Type::Float { repr, exp_bits, mantissa_bits } => {
+                let bits = format!("lpEncodeFloat({}, {}, {})", value, exp_bits, mantissa_bits);
+                if repr.size() == 1 {
+                    self.line(format!("view.set{}({}, {});", accessor(*repr), position, bits));
+                } else {
+                    self.line(format!("view.set{}({}, {}, {});", accessor(*repr), position, bits, little_endian));
+                }
+            },
+            Type::Endian(order, ty) => self.encode(ty, value, offset, endianness_literal(*order)),
+            Type::Varint(_) => unreachable!("varints are rejected by fixed_size_types"),
+            Type::Reserved(len) => {
+                self.line(format!("new Uint8Array(view.buffer, view.byteOffset + {}, {}).fill(0);", position, len));
+            },
+            Type::Struct { name, .. } | Type::Enum { name, .. } => {
+                self.line(format!("encode{}({}, view, {}, {});", name, value, position, little_endian));
+            },
+        }
+    }
+}
+
+/// The expression decoding the given type.
+fn decode(ty: &Type, offset: &Offset, little_endian: &str, loop_depth: usize) -> String {
+    let position = offset.render("offset");
+    match ty {
+        Type::Primitive(Primitive::Bool) => format!("view.getUint8({}) !== 0", position),
+        Type::Primitive(Primitive::Char) => format!("lpDecodeChar(view.getUint32({}, {}))", position, little_endian),
+        Type::Primitive(p) if is_odd_width(*p) => {
+            let getter = if p.is_signed() { "lpGetInt" } else { "lpGetUint" };
+            format!("{}(view, {}, {}, {})", getter, position, p.size(), little_endian)
+        },
+        Type::Primitive(p) if p.size() == 1 => format!("view.get{}({})", accessor(*p), position),
+        Type::Primitive(p) => format!("view.get{}({}, {})", accessor(*p), position, little_endian),
+        Type::Tuple(tys) => {
+            let mut offset = offset.clone();
+            let mut elements = Vec::new();
+            for ty in tys.iter() {
+                elements.push(decode(ty, &offset, little_endian, loop_depth));
+                offset = offset.plus(ty.size());
+            }
+            format!("[{}]", elements.join(", "))
+        },
+        Type::Array { element, len } => {
+            let var = format!("i{}", loop_depth);
+            let element = decode(element, &offset.with_term(&var, element.size()), little_endian, loop_depth + 1);
+            format!("Array.from({{ length: {} }}, (_, {}) => {})", len, var, element)
+        },
+        Type::Option(ty) => format!("view.getUint8({}) !== 0 ? {} : null", position, decode(ty, &offset.plus(1), little_endian, loop_depth)),
+        Type::Bcd(p) if p.size() == 1 => format!("lpDecodeBcd(view.get{}({}))", accessor(*p), position),
+        Type::Bcd(p) => format!("lpDecodeBcd(view.get{}({}, {}))", accessor(*p), position, little_endian),
+        Type::Float { repr, exp_bits, mantissa_bits } if repr.size() == 1 => {
+            format!("lpDecodeFloat(view.get{}({}), {}, {})", accessor(*repr), position, exp_bits, mantissa_bits)
+        },
+        Type::Float { repr, exp_bits, mantissa_bits } => {
+            format!("lpDecodeFloat(view.get{}({}, {}), {}, {})", accessor(*repr), position, little_endian, exp_bits, mantissa_bits)
+        },
+        Type::Endian(order, ty) => decode(ty, offset, endianness_literal(*order), loop_depth),
+        Type::Varint(_) => unreachable!("varints are rejected by fixed_size_types"),
+        Type::Reserved(_) => "null".to_owned(),
+        Type::Struct { name, .. } | Type::Enum { name, .. } => format!("decode{}(view, {}, {})", name, position, little_endian),
+    }
+}
+
+/// Rejects nested options, since `null` cannot distinguish their levels.
+fn check_options(ty: &Type) -> Result<()> {
+    match ty {
+        Type::Varint(_) => unreachable!("varints are rejected by fixed_size_types"),
+        Type::Primitive(_) | Type::Bcd(_) | Type::Float { .. } | Type::Struct { .. } | Type::Enum { .. } | Type::Reserved(_) => Ok(()),
+        Type::Tuple(tys) => tys.iter().try_for_each(check_options),
+        Type::Array { element, .. } => check_options(element),
+        Type::Option(ty) => {
+            let mut inner = *ty;
+            while let Type::Endian(_, ty) = inner {
+                inner = ty;
+            }
+            if let Type::Option(_) = inner {
+                return Err(Error::Unsupported("nested options".to_owned()));
+            }
+            check_options(ty)
+        },
+        Type::Endian(_, ty) => check_options(ty),
+    }
+}
+
+/// The TypeScript type of a value of the given type.
+fn type_annotation(ty: &Type) -> String {
+    match ty {
+        Type::Primitive(Primitive::U64 | Primitive::I64) => "bigint".to_owned(),
+        Type::Primitive(Primitive::Bool) => "boolean".to_owned(),
+        Type::Primitive(Primitive::Char) => "string".to_owned(),
+        Type::Primitive(_) | Type::Bcd(_) | Type::Float { .. } => "number".to_owned(),
+        Type::Tuple(tys) => format!("[{}]", tys.iter().map(type_annotation).collect::<Vec<_>>().join(", ")),
+        Type::Array { element, .. } => match element {
+            Type::Option(_) => format!("({})[]", type_annotation(element)),
+            _ => format!("{}[]", type_annotation(element)),
+        },
+        Type::Option(ty) => format!("{} | null", type_annotation(ty)),
+        Type::Endian(_, ty) => type_annotation(ty),
+        Type::Struct { name, .. } | Type::Enum { name, .. } => name.to_string(),
+        Type::Varint(_) => unreachable!("varints are rejected by fixed_size_types"),
+        Type::Reserved(_) => "null".to_owned(),
+    }
+}
+
+/// Whether values of the given type only reserve space.
+fn is_reserved(ty: &Type) -> bool {
+    matches!(ty, Type::Reserved(_))
+}
+
+/// The name of the `DataView` accessor for a numeric primitive.
+fn accessor(p: Primitive) -> &'static str {
+    match p {
+        Primitive::U8 | Primitive::Bool => "Uint8",
+        Primitive::U16 => "Uint16",
+        Primitive::U32 | Primitive::Char => "Uint32",
+        Primitive::U64 => "BigUint64",
+        Primitive::I8 => "Int8",
+        Primitive::I16 => "Int16",
+        Primitive::I32 => "Int32",
+        Primitive::I64 => "BigInt64",
+        Primitive::F32 => "Float32",
+        Primitive::F64 => "Float64",
+        Primitive::U24 | Primitive::I24 | Primitive::U40 | Primitive::U48 | Primitive::I48 => unreachable!("odd-width integers have no accessor"),
+    }
+}
+
+/// Whether the primitive is an integer without a `DataView` accessor.
+fn is_odd_width(p: Primitive) -> bool {
+    matches!(p, Primitive::U24 | Primitive::I24 | Primitive::U40 | Primitive::U48 | Primitive::I48)
+}
+
+fn endianness_literal(order: Endianness) -> &'static str {
+    match order {
+        Endianness::Big => "false",
+        Endianness::Little => "true",
+    }
+}
+
+/// An integer literal of the given type.
+fn literal(value: i128, repr: Primitive) -> String {
+    match repr {
+        Primitive::U64 | Primitive::I64 => format!("{}n", value),
+        _ => value.to_string(),
+    }
+}
@@ -0,0 +1,355 @@
+//! Wireshark Lua dissector generation.
+//!
+//! The generated plugin registers a protocol that dissects packets as a
+//! sequence of messages of a single type. Every field becomes a filterable
+//! `ProtoField` named after its path (e.g. `sensor.readings.id`), structs,
+//! tuples and arrays are shown as subtrees and enum values are shown with
+//! their variant names. Options add a `<path>.present` flag and only show
+//! the value if it is set. BCD values are shown in hex, which reads as their
+//! decimal value. Reserved bytes are not shown, scaled fields show the
+//! converted value next to the encoded one and reduced-precision floats show
+//! their value next to their raw bits.
+//!
+//! The protocol is registered for "Decode As..." on UDP and TCP and can
+//! additionally be bound to fixed ports. Messages split across TCP segments
+//! are reassembled.
+
+use std::fmt::Write;
+
+use lightpack::{Schema, schema::{Endianness, Field, Primitive, Type}};
+
+use crate::{Error, Offset, Result, fixed_size_types, member_name, scaled_value, snake_case};
+
+/// A generator for a Wireshark Lua dissector.
+#[derive(Debug, Clone)]
+pub struct Generator {
+    name: String,
+    description: String,
+    byte_order: Endianness,
+    udp_ports: Vec<u16>,
+    tcp_ports: Vec<u16>,
+    message: &'static Type,
+}
+
+impl Generator {
+    /// Creates a generator for a protocol with the given (lowercase) name
+    /// whose packets contain messages of type `T`. The byte order defaults
+    /// to big endian.
+    pub fn new<T>(name: &str) -> Self where T: Schema {
+        Self {
+            name: name.to_owned(),
+            description: name.to_owned(),
+            byte_order: Endianness::Big,
+            udp_ports: Vec::new(),
+            tcp_ports: Vec::new(),
+            message: &T::SCHEMA,
+        }
+    }
+
+    /// Sets the protocol's description shown in the packet details.
+    pub fn description(mut self, description: &str) -> Self {
+        self.description = description.to_owned();
+        self
+    }
+
+    /// Sets the byte order of the messages (except for values with fixed
+    /// endianness).
+    pub fn byte_order(mut self, byte_order: Endianness) -> Self {
+        self.byte_order = byte_order;
+        self
+    }
+
+    /// Registers the dissector for the given UDP port.
+    pub fn udp_port(mut self, port: u16) -> Self {
+        self.udp_ports.push(port);
+        self
+    }
+
+    /// Registers the dissector for the given TCP port.
+    pub fn tcp_port(mut self, port: u16) -> Self {
+        self.tcp_ports.push(port);
+        self
+    }
+
+    /// Generates the dissector.
+    pub fn generate(&self) -> Result<String> {
+        let size = self.message.size();
+        if size == 0 {
+            return Err(Error::Unsupported("zero-sized messages".to_owned()));
+        }
+
+        let mut dissector = Dissector { proto: &self.name, fields: Vec::new(), body: String::new(), indent: 1, depth: 0, trees: 0, scaled: None, floats: false };
+        match self.message {
+            Type::Struct { fields, .. } => {
+                let mut offset = Offset::default();
+                for field in fields.iter() {
+                    dissector.scaled = Some(field).filter(|f| f.is_scaled());
+                    dissector.dissect(field.ty, &member_name(field.name), field.name, &offset, self.byte_order, "tree", None)?;
+                    offset = offset.plus(field.ty.size());
+                }
+            },
+            Type::Enum { name, .. } => {
+                dissector.dissect(self.message, &snake_case(name), name, &Offset::default(), self.byte_order, "tree", None)?;
+            },
+            _ => return Err(Error::Unnamed),
+        }
+
+        let mut out = String::new();
+        writeln!(out, "-- Generated by lightpack-codegen. Do not edit.").unwrap();
+        writeln!(out).unwrap();
+        writeln!(out, "local proto = Proto({}, {})", string(&self.name), string(&self.description)).unwrap();
+
+        for ty in fixed_size_types(&[self.message])? {
+            if let Type::Enum { name, variants, .. } = ty {
+                writeln!(out).unwrap();
+                writeln!(out, "local {}_values = {{", snake_case(name)).unwrap();
+                for variant in variants.iter() {
+                    writeln!(out, "  [{}] = {},", variant.value, string(variant.name)).unwrap();
+                }
+                writeln!(out, "}}").unwrap();
+            }
+        }
+
+        writeln!(out).unwrap();
+        writeln!(out, "local fields = {{").unwrap();
+        for (path, declaration) in &dissector.fields {
+            writeln!(out, "  [{}] = {},", string(path), declaration).unwrap();
+        }
+        writeln!(out, "}}").unwrap();
+        writeln!(out, "proto.fields = fields").unwrap();
+        writeln!(out).unwrap();
+        writeln!(out, "local MESSAGE_SIZE = {}", size).unwrap();
+        writeln!(out).unwrap();
+        if dissector.floats {
+            out.push_str(DECODE_FLOAT);
+            writeln!(out).unwrap();
+        }
+        writeln!(out, "local function dissect_message(tvb, tree, offset)").unwrap();
+        out.push_str(&dissector.body);
+        writeln!(out, "end").unwrap();
+        writeln!(out).unwrap();
+        out.push_str(DISSECTOR);
+
+        writeln!(out).unwrap();
+        for (table, ports) in [("udp.port", &self.udp_ports), ("tcp.port", &self.tcp_ports)] {
+            for port in ports {
+                writeln!(out, "DissectorTable.get(\"{}\"):add({}, proto)", table, port).unwrap();
+            }
+            writeln!(out, "DissectorTable.get(\"{}\"):add_for_decode_as(proto)", table).unwrap();
+        }
+
+        Ok(out)
+    }
+}
+
+/// Decodes the raw bits of a reduced-precision float.
+const DECODE_FLOAT: &str = r#"local function decode_float(bits, exp_bits, mantissa_bits)
+  local sign = bits >= 2 ^ (exp_bits + mantissa_bits) and -1 or 1
+  local exp = math.floor(bits / 2 ^ mantissa_bits) % 2 ^ exp_bits
+  local mantissa = bits % 2 ^ mantissa_bits
+  local bias = 2 ^ (exp_bits - 1) - 1
+  if exp == 2 ^ exp_bits - 1 then
+    return mantissa == 0 and sign * math.huge or 0 / 0
+  elseif exp == 0 then
+    return sign * mantissa * 2 ^ (1 - bias - mantissa_bits)
+  end
+  return sign * (mantissa + 2 ^ mantissa_bits) * 2 ^ (exp - bias - mantissa_bits)
+end
+"#;
+
+/// The dissector entry point, which splits packets into messages.
+const DISSECTOR: &str = r#"function proto.dissector(tvb, pinfo, tree)
+  pinfo.cols.protocol = proto.name
+  local offset = 0
+  while offset < tvb:len() do
+    if tvb:len() - offset < MESSAGE_SIZE then
+      pinfo.desegment_offset = offset
+      pinfo.desegment_len = MESSAGE_SIZE - (tvb:len() - offset)
+      return
+    end
+    dissect_message(tvb, tree:add(proto, tvb(offset, MESSAGE_SIZE)), offset)
+    offset = offset + MESSAGE_SIZE
+  end
+end
+"#;
+
+/// Collects the fields and emits the statements of the message dissector.
+struct Dissector<'a> {
+    proto: &'a str,
+    fields: Vec<(String, String)>,
+    body: String,
+    indent: usize,
+    depth: usize,
+    trees: usize,
+    /// The scaled field whose value is dissected next.
+    scaled: Option<&'static Field>,
+    /// Whether a reduced-precision float was dissected.
+    floats: bool,
+}
+
+impl Dissector<'_> {
+    fn line(&mut self, line: String) {
+        for _ in 0..self.indent {
+            self.body.push_str("  ");
+        }
+        self.body.push_str(&line);
+        self.body.push('\n');
+    }
+
+    /// Declares the field with the given path, unless it already exists.
+    fn field(&mut self, path: &str, constructor: &str, label: &str, args: &str) -> Result<()> {
+        let declaration = format!("ProtoField.{}({}, {}{})", constructor, string(&format!("{}.{}", self.proto, path)), string(label), args);
+        match self.fields.iter().find(|(p, _)| p == path) {
+            Some((_, existing)) if *existing == declaration => Ok(()),
+            Some(_) => Err(Error::Unsupported(format!("multiple different fields named {}", path))),
+            None => {
+                self.fields.push((path.to_owned(), declaration));
+                Ok(())
+            },
+        }
+    }
+
+    /// Adds a subtree to `tree` and returns its name.
+    fn subtree(&mut self, item: String, index: Option<&str>) -> String {
+        self.trees += 1;
+        let tree = format!("t{}", self.trees);
+        self.line(format!("local {} = {}", tree, item));
+        if let Some(index) = index {
+            self.line(format!("{}:append_text(\" [\" .. {} .. \"]\")", tree, index));
+        }
+        tree
+    }
+
+    /// Emits the statements adding a value of the given type to `tree`. The
+    /// index is set for elements of arrays.
+    #[allow(clippy::too_many_arguments)]
+    fn dissect(&mut self, ty: &Type, path: &str, label: &str, offset: &Offset, order: Endianness, tree: &str, index: Option<&str>) -> Result<()> {
+        let range = format!("tvb({}, {})", offset.render("offset"), ty.size());
+        let add = match order {
+            Endianness::Little if ty.size() > 1 => "add_le",
+            _ => "add",
+        };
+        match ty {
+            Type::Primitive(p) => {
+                let (constructor, args) = match p {
+                    Primitive::Bool => ("bool", ""),
+                    Primitive::Char => ("uint32", ", base.HEX"),
+                    Primitive::F32 => ("float", ""),
+                    Primitive::F64 => ("double", ""),
+                    _ => (integer_constructor(*p), ", base.DEC"),
+                };
+                self.field(path, constructor, label, args)?;
+                let item = format!("{}:{}(fields[{}], {})", tree, add, string(path), range);
+                match self.scaled.take().and_then(|field| scaled_value(field, &raw_value(*p, &range, order)?)) {
+                    // Show the converted value next to the encoded one
+                    Some(value) => self.line(format!("{}:append_text(string.format(\" (%g)\", {}))", item, value)),
+                    None => self.line(item),
+                }
+            },
+            Type::Bcd(p) => {
+                // Packed BCD reads as the decimal value in hex
+                self.scaled = None;
+                self.field(path, integer_constructor(*p), label, ", base.HEX")?;
+                self.line(format!("{}:{}(fields[{}], {})", tree, add, string(path), range));
+            },
+            Type::Float { repr, exp_bits, mantissa_bits } => {
+                // Show the decoded value next to the raw bits
+                self.scaled = None;
+                self.floats = true;
+                self.field(path, integer_constructor(*repr), label, ", base.HEX")?;
+                let bits = raw_value(*repr, &range, order).unwrap();
+                let item = format!("{}:{}(fields[{}], {})", tree, add, string(path), range);
+                self.line(format!("{}:append_text(string.format(\" (%g)\", decode_float({}, {}, {})))", item, bits, exp_bits, mantissa_bits));
+            },
+            Type::Enum { name, repr, .. } => {
+                self.scaled = None;
+                self.field(path, integer_constructor(*repr), label, &format!(", base.DEC, {}_values", snake_case(name)))?;
+                self.line(format!("{}:{}(fields[{}], {})", tree, add, string(path), range));
+            },
+            Type::Struct { fields, .. } => {
+                self.field(path, "none", label, "")?;
+                let subtree = self.subtree(format!("{}:add(fields[{}], {})", tree, string(path), range), index);
+                let mut offset = offset.clone();
+                for field in fields.iter() {
+                    self.scaled = Some(field).filter(|f| f.is_scaled());
+                    self.dissect(field.ty, &format!("{}.{}", path, member_name(field.name)), field.name, &offset, order, &subtree, None)?;
+                    offset = offset.plus(field.ty.size());
+                }
+            },
+            Type::Tuple(tys) => {
+                self.field(path, "none", label, "")?;
+                let subtree = self.subtree(format!("{}:add(fields[{}], {})", tree, string(path), range), index);
+                let mut offset = offset.clone();
+                for (i, ty) in tys.iter().enumerate() {
+                    self.dissect(ty, &format!("{}.{}", path, i), &i.to_string(), &offset, order, &subtree, None)?;
+                    offset = offset.plus(ty.size());
+                }
+            },
+            Type::Array { element, len } => {
+                // The elements share the array's path, so the array itself is a text item
+                let subtree = self.subtree(format!("{}:add({}, {})", tree, range, string(label)), index);
+                let var = format!("i{}", self.depth);
+                self.line(format!("for {0} = 0, {1} do", var, *len as i128 - 1));
+                self.indent += 1;
+                self.depth += 1;
+                self.dissect(element, path, label, &offset.with_term(&var, element.size()), order, &subtree, Some(&var))?;
+                self.depth -= 1;
+                self.indent -= 1;
+                self.line("end".to_owned());
+            },
+            Type::Option(ty) => {
+                let flag = format!("{}.present", path);
+                self.field(&flag, "bool", &format!("{} present", label), "")?;
+                let flag_range = format!("tvb({}, 1)", offset.render("offset"));
+                self.line(format!("{}:add(fields[{}], {})", tree, string(&flag), flag_range));
+                self.line(format!("if {}:uint() ~= 0 then", flag_range));
+                self.indent += 1;
+                self.dissect(ty, path, label, &offset.plus(1), order, tree, index)?;
+                self.indent -= 1;
+                self.line("end".to_owned());
+            },
+            Type::Endian(order, ty) => self.dissect(ty, path, label, offset, *order, tree, index)?,
+            // Reserved bytes carry no information
+            Type::Varint(_) => unreachable!("varints are rejected by fixed_size_types"),
+            Type::Reserved(_) => {},
+        }
+        Ok(())
+    }
+}
+
+/// The `ProtoField` constructor for an integer primitive.
+fn integer_constructor(p: Primitive) -> &'static str {
+    match p {
+        Primitive::U8 | Primitive::Bool => "uint8",
+        Primitive::U16 => "uint16",
+        Primitive::U24 => "uint24",
+        Primitive::U32 | Primitive::Char => "uint32",
+        // There are no 40- and 48-bit fields, wider ones accept shorter ranges
+        Primitive::U40 | Primitive::U48 | Primitive::U64 => "uint64",
+        Primitive::I8 => "int8",
+        Primitive::I16 => "int16",
+        Primitive::I24 => "int24",
+        Primitive::I32 => "int32",
+        Primitive::I48 | Primitive::I64 => "int64",
+        Primitive::F32 | Primitive::F64 => unreachable!("Floats are not integers"),
+    }
+}
+
+/// The Lua expression reading a numeric primitive from a `TvbRange`.
+fn raw_value(p: Primitive, range: &str, order: Endianness) -> Option<String> {
+    let method = match p {
+        Primitive::U8 | Primitive::U16 | Primitive::U24 | Primitive::U32 => "uint()",
+        Primitive::I8 | Primitive::I16 | Primitive::I24 | Primitive::I32 => "int()",
+        Primitive::U40 | Primitive::U48 | Primitive::U64 => "uint64():tonumber()",
+        Primitive::I48 | Primitive::I64 => "int64():tonumber()",
+        Primitive::F32 | Primitive::F64 => "float()",
+        Primitive::Bool | Primitive::Char => return None,
+    };
+    let prefix = if order == Endianness::Little && p.size() > 1 { "le_" } else { "" };
+    Some(format!("{}:{}{}", range, prefix, method))
+}
+
+/// A Lua string literal.
+fn string(value: &str) -> String {
+    format!("{:?}", value)
+}
@@ -0,0 +1,324 @@
+use std::{fs, path::{Path, PathBuf}, process::Command};
+
+use lightpack::{Schema, Size, byteorder::{BigEndian, ByteOrder, LittleEndian}, extra::Reserved, schema::Endianness};
+use lightpack_codegen::{Error, c::Generator};
+
+mod common;
+
+use common::{Climate, Clock, Halves, Registers, SensorFrame, byte_list, clock, frame, halves, pack, registers};
+
+/// The C statements that construct the same value as [`frame`].
+const C_FRAME: &str = r#"
+    SensorFrame frame;
+    memset(&frame, 0, sizeof(frame));
+    frame.seq = 0xdeadbeef;
+    frame.mode = MODE_AUTO;
+    frame.readings[0].id = 1;
+    frame.readings[0].value = 1.5f;
+    frame.readings[0].temperature = -20;
+    frame.readings[0].valid = true;
+    frame.readings[1].id = 515;
+    frame.readings[1].value = -0.25f;
+    frame.readings[1].temperature = 35;
+    frame.readings[1].valid = false;
+    frame.origin.present = true;
+    frame.origin.value._0 = -3;
+    frame.origin.value._1 = 1000;
+    frame.raw = UINT64_C(0x0102030405060708);
+    frame.initial = 0x3bb;
+    frame.stamp.present = false;
+    frame.ratio = -1e10;
+    for (size_t i = 0; i < 6; i++) frame.grid[i / 2][i % 2] = (uint8_t)(i + 1);
+    frame.slots[0].present = false;
+    frame.slots[1].present = true;
+    frame.slots[1].value.id = 9;
+    frame.slots[1].value.value = 2.0f;
+    frame.slots[1].value.temperature = 0;
+    frame.slots[1].value.valid = true;
+"#;
+
+fn c_array(bytes: &[u8]) -> String {
+    format!("{{ {} }}", byte_list(bytes))
+}
+
+/// Compiles and runs the given C program, returning its stdout or `None` if no compiler is available.
+fn run_c(dir: &Path, header: &str, main: &str) -> Option<String> {
+    fs::create_dir_all(dir).unwrap();
+    fs::write(dir.join("protocol.h"), header).unwrap();
+    fs::write(dir.join("main.c"), main).unwrap();
+
+    let compiler = std::env::var("CC").unwrap_or_else(|_| "cc".to_owned());
+    let binary = dir.join("main");
+    let status = match Command::new(&compiler)
+        .args(["-std=c99", "-Wall", "-Wextra", "-Werror", "-o"])
+        .arg(&binary)
+        .arg(dir.join("main.c"))
+        .status() {
+        Ok(status) => status,
+        Err(e) => {
+            eprintln!("Skipping C test, could not run {}: {}", compiler, e);
+            return None;
+        },
+    };
+    assert!(status.success(), "Compiling the generated header failed");
+
+    let output = Command::new(&binary).output().unwrap();
+    assert!(output.status.success(), "Running the C program failed: {}", String::from_utf8_lossy(&output.stdout));
+    Some(String::from_utf8(output.stdout).unwrap())
+}
+
+fn check_roundtrip<B>(byte_order: Endianness, dir: &str) where B: ByteOrder {
+    let header = Generator::new("protocol")
+        .byte_order(byte_order)
+        .add::<SensorFrame>()
+        .generate()
+        .unwrap();
+
+    let expected = pack::<B, _>(&frame());
+    let mut invalid_mode = expected.clone();
+    invalid_mode[SensorFrame::OFFSET_MODE] = 3;
+    let mut invalid_char = expected.clone();
+    B::write_u32(&mut invalid_char[SensorFrame::OFFSET_INITIAL..], 0xd800);
+
+    let main = format!(r#"#include <stdio.h>
+#include "protocol.h"
+
+static const uint8_t EXPECTED[SENSOR_FRAME_SIZE] = {expected};
+static const uint8_t INVALID_MODE[SENSOR_FRAME_SIZE] = {invalid_mode};
+static const uint8_t INVALID_CHAR[SENSOR_FRAME_SIZE] = {invalid_char};
+
+int main(void) {{
+{c_frame}
+    uint8_t buf[SENSOR_FRAME_SIZE] = {{ 0 }};
+    pack_sensor_frame(&frame, buf);
+    if (memcmp(buf, EXPECTED, SENSOR_FRAME_SIZE) != 0) return 1;
+
+    SensorFrame decoded;
+    if (unpack_sensor_frame(&decoded, EXPECTED) != LIGHTPACK_OK) return 2;
+    if (decoded.seq != frame.seq || decoded.readings[1].value != -0.25f || decoded.origin.value._1 != 1000) return 3;
+    if (decoded.raw != frame.raw || decoded.initial != frame.initial || decoded.stamp.present || decoded.grid[2][1] != 6) return 4;
+
+    uint8_t repacked[SENSOR_FRAME_SIZE] = {{ 0 }};
+    pack_sensor_frame(&decoded, repacked);
+    if (memcmp(repacked, EXPECTED, SENSOR_FRAME_SIZE) != 0) return 5;
+
+    if (unpack_sensor_frame(&decoded, INVALID_MODE) != LIGHTPACK_ERR_INVALID_ENUM) return 6;
+    if (unpack_sensor_frame(&decoded, INVALID_CHAR) != LIGHTPACK_ERR_INVALID_CHAR) return 7;
+
+    printf("%d %d %d\n", SENSOR_FRAME_SIZE, SENSOR_FRAME_OFFSET_READINGS, SENSOR_FRAME_OFFSET_GRID);
+    return 0;
+}}
+"#,
+        expected = c_array(&expected),
+        invalid_mode = c_array(&invalid_mode),
+        invalid_char = c_array(&invalid_char),
+        c_frame = C_FRAME,
+    );
+
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(dir);
+    if let Some(stdout) = run_c(&dir, &header, &main) {
+        assert_eq!(stdout.trim(), format!("{} {} {}", SensorFrame::SIZE, SensorFrame::OFFSET_READINGS, SensorFrame::OFFSET_GRID));
+    }
+}
+
+#[test]
+fn big_endian_roundtrip() {
+    check_roundtrip::<BigEndian>(Endianness::Big, "c_big_endian");
+}
+
+#[test]
+fn little_endian_roundtrip() {
+    check_roundtrip::<LittleEndian>(Endianness::Little, "c_little_endian");
+}
+
+#[test]
+fn declarations() {
+    let header = Generator::new("protocol").add::<SensorFrame>().generate().unwrap();
+
+    assert!(header.contains("#ifndef PROTOCOL_H"));
+    assert!(header.contains("typedef int16_t Mode;"));
+    assert!(header.contains("#define MODE_OFF ((Mode)-1)"));
+    assert!(header.contains("typedef struct {\n    uint16_t id;\n    float value;\n    int8_t temperature;\n    bool valid;\n} Reading;"));
+    assert!(header.contains("    Reading readings[2];\n"));
+    assert!(header.contains("    uint8_t grid[3][2];\n"));
+    assert!(header.contains("#define SENSOR_FRAME_OFFSET_MODE 4"));
+    assert!(header.contains("static inline int unpack_sensor_frame(SensorFrame *out, const uint8_t *buf) {"));
+}
+
+#[test]
+fn name_conflicts() {
+    #[derive(Size, Schema)]
+    #[allow(dead_code)]
+    struct Wrap<T>(T);
+
+    #[derive(Size, Schema)]
+    #[allow(dead_code)]
+    struct Both(Wrap<u8>, Wrap<u16>);
+
+    assert_eq!(Generator::new("protocol").add::<Both>().generate(), Err(Error::Conflict("Wrap")));
+    assert_eq!(Generator::new("protocol").add::<(u8, u8)>().generate(), Err(Error::Unnamed));
+}
+
+#[test]
+fn reserved() {
+    #[derive(Size, Schema)]
+    #[allow(dead_code)]
+    struct Padded {
+        id: u8,
+        _reserved: Reserved<3>,
+        value: u16,
+    }
+
+    let header = Generator::new("protocol").add::<Padded>().generate().unwrap();
+    assert!(header.contains("typedef struct {\n    uint8_t id;\n    uint16_t value;\n} Padded;"));
+    assert!(header.contains("#define PADDED_OFFSET__RESERVED 1\n#define PADDED_OFFSET_VALUE 4\n"));
+
+    let main = r#"#include <stdio.h>
+#include "protocol.h"
+
+int main(void) {
+    uint8_t buf[PADDED_SIZE];
+    memset(buf, 0xff, sizeof(buf));
+    Padded padded = { 7, 0x0102 };
+    pack_padded(&padded, buf);
+    for (size_t i = 0; i < sizeof(buf); i++) printf("%d ", buf[i]);
+    buf[2] = 9;
+    Padded unpacked;
+    if (unpack_padded(&unpacked, buf) != LIGHTPACK_OK || unpacked.id != 7 || unpacked.value != 0x0102) return 1;
+    return 0;
+}
+"#;
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("c_reserved");
+    if let Some(output) = run_c(&dir, &header, main) {
+        assert_eq!(output.trim(), "7 0 0 0 1 2");
+    }
+}
+
+#[test]
+fn scaled_fields() {
+    let header = Generator::new("protocol").add::<Climate>().generate().unwrap();
+    assert!(header.contains("    int16_t temperature; /* value = temperature * 0.01 - 40.0 */\n    uint8_t humidity; /* value = humidity * 0.5 */\n"));
+    assert!(header.contains("#define CLIMATE_SCALE_TEMPERATURE 0.01\n#define CLIMATE_VALUE_OFFSET_TEMPERATURE (-40.0)\n"));
+
+    let main = r#"#include <stdio.h>
+#include "protocol.h"
+
+int main(void) {
+    uint8_t buf[CLIMATE_SIZE] = { 0x18, 0x06, 90 };
+    Climate climate;
+    if (unpack_climate(&climate, buf) != LIGHTPACK_OK) return 1;
+    printf("%.2f %.1f", climate.temperature * CLIMATE_SCALE_TEMPERATURE + CLIMATE_VALUE_OFFSET_TEMPERATURE, climate.humidity * CLIMATE_SCALE_HUMIDITY + CLIMATE_VALUE_OFFSET_HUMIDITY);
+    return 0;
+}
+"#;
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("c_scaled_fields");
+    if let Some(output) = run_c(&dir, &header, main) {
+        assert_eq!(output.trim(), "21.50 45.0");
+    }
+}
+
+#[test]
+fn odd_width_integers() {
+    for (byte_order, expected, dir) in [
+        (Endianness::Big, pack::<BigEndian, _>(&registers()), "c_odd_width_big_endian"),
+        (Endianness::Little, pack::<LittleEndian, _>(&registers()), "c_odd_width_little_endian"),
+    ] {
+        let header = Generator::new("protocol").byte_order(byte_order).add::<Registers>().generate().unwrap();
+        assert!(header.contains("    uint32_t status;\n    int32_t offset;\n    uint64_t uptime;\n    uint64_t serial;\n    int64_t drift;\n"));
+
+        let main = format!(r#"#include <stdio.h>
+#include "protocol.h"
+
+static const uint8_t EXPECTED[REGISTERS_SIZE] = {expected};
+
+int main(void) {{
+    Registers registers = {{ 0x010203, -2, UINT64_C(0x0102030405), UINT64_C(0xfedcba987654), -300 }};
+    uint8_t buf[REGISTERS_SIZE] = {{ 0 }};
+    pack_registers(&registers, buf);
+    if (memcmp(buf, EXPECTED, REGISTERS_SIZE) != 0) return 1;
+
+    Registers decoded;
+    if (unpack_registers(&decoded, EXPECTED) != LIGHTPACK_OK) return 2;
+    if (memcmp(&decoded, &registers, sizeof(Registers)) != 0) return 3;
+    printf("%d", REGISTERS_SIZE);
+    return 0;
+}}
+"#,
+            expected = c_array(&expected),
+        );
+        let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(dir);
+        if let Some(output) = run_c(&dir, &header, &main) {
+            assert_eq!(output.trim(), Registers::SIZE.to_string());
+        }
+    }
+}
+
+#[test]
+fn bcd() {
+    let header = Generator::new("protocol").byte_order(Endianness::Little).add::<Clock>().generate().unwrap();
+    assert!(header.contains("typedef struct {\n    uint8_t seconds;\n"));
+
+    let expected = pack::<LittleEndian, _>(&clock());
+    let mut invalid = expected.clone();
+    invalid[Clock::SIZE - 1] = 0x1a;
+    let main = format!(r#"#include <stdio.h>
+#include "protocol.h"
+
+static const uint8_t EXPECTED[CLOCK_SIZE] = {expected};
+static const uint8_t INVALID[CLOCK_SIZE] = {invalid};
+
+int main(void) {{
+    Clock clock = {{ {{ 30, 37, 13, 2, 19, 10, 26 }}, 12345678 }};
+    uint8_t buf[CLOCK_SIZE] = {{ 0 }};
+    pack_clock(&clock, buf);
+    if (memcmp(buf, EXPECTED, CLOCK_SIZE) != 0) return 1;
+
+    Clock decoded;
+    if (unpack_clock(&decoded, EXPECTED) != LIGHTPACK_OK) return 2;
+    if (decoded.time.hours != 13 || decoded.time.year != 26 || decoded.counter != 12345678) return 3;
+    if (unpack_clock(&decoded, INVALID) != LIGHTPACK_ERR_INVALID_BCD) return 4;
+    printf("%d", CLOCK_SIZE);
+    return 0;
+}}
+"#,
+        expected = c_array(&expected),
+        invalid = c_array(&invalid),
+    );
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("c_bcd");
+    if let Some(output) = run_c(&dir, &header, &main) {
+        assert_eq!(output.trim(), Clock::SIZE.to_string());
+    }
+}
+
+#[test]
+fn floats() {
+    let header = Generator::new("protocol").add::<Halves>().generate().unwrap();
+    assert!(header.contains("typedef struct {\n    float half;\n"));
+
+    let expected = pack::<BigEndian, _>(&halves());
+    let main = format!(r#"#include <stdio.h>
+#include "protocol.h"
+
+static const uint8_t EXPECTED[HALVES_SIZE] = {expected};
+
+int main(void) {{
+    Halves halves = {{ 1.00048828125f, -2.7f, 1e-6f, 0.3f, -1500.0f }};
+    uint8_t buf[HALVES_SIZE] = {{ 0 }};
+    pack_halves(&halves, buf);
+    if (memcmp(buf, EXPECTED, HALVES_SIZE) != 0) return 1;
+
+    Halves decoded;
+    if (unpack_halves(&decoded, EXPECTED) != LIGHTPACK_OK) return 2;
+    if (decoded.half != 1.0f || decoded.brain != -2.703125f || decoded.tiny != 17.0f / 16777216.0f) return 3;
+    if (decoded.e4m3 != 0.3125f || decoded.e5m2 != -1536.0f) return 4;
+    printf("%d", HALVES_SIZE);
+    return 0;
+}}
+"#,
+        expected = c_array(&expected),
+    );
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("c_floats");
+    if let Some(output) = run_c(&dir, &header, &main) {
+        assert_eq!(output.trim(), Halves::SIZE.to_string());
+    }
+}
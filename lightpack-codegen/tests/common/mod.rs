@@ -0,0 +1,142 @@
+//! The fixture shared by the generator tests.
+
+#![allow(dead_code)]
+
+use std::{env, fs, path::PathBuf};
+
+use lightpack::{Pack, Schema, Size, Unpack, bcd::{Bcd, DateTime}, byteorder::ByteOrder, extra::{BE, LE}, float::{BF16, F16, Minifloat}, int::{I24, I48, U24, U40, U48}};
+
+#[derive(Size, Pack, Unpack, Schema, Clone, Copy, Debug, PartialEq)]
+#[repr(i16)]
+pub enum Mode {
+    Off = -1,
+    On = 1,
+    Auto = 700,
+}
+
+#[derive(Size, Pack, Unpack, Schema, Clone, Copy, Debug, PartialEq)]
+pub struct Reading {
+    pub id: u16,
+    pub value: f32,
+    pub temperature: i8,
+    pub valid: bool,
+}
+
+#[derive(Size, Pack, Unpack, Schema, Clone, Copy, Debug, PartialEq)]
+pub struct SensorFrame {
+    pub seq: u32,
+    pub mode: Mode,
+    pub readings: [Reading; 2],
+    pub origin: Option<(i16, i16)>,
+    pub raw: LE<u64>,
+    pub initial: char,
+    pub stamp: Option<BE<i64>>,
+    pub ratio: f64,
+    pub grid: [[u8; 2]; 3],
+    pub slots: [Option<Reading>; 2],
+}
+
+/// A struct with scaled fields, which generators describe next to the encoded values.
+#[derive(Size, Pack, Unpack, Schema, Clone, Copy, Debug, PartialEq)]
+pub struct Climate {
+    #[lightpack(scale = 0.01, offset = -40.0, as = i16)]
+    pub temperature: f32,
+    #[lightpack(scale = 0.5, as = u8)]
+    pub humidity: f32,
+}
+
+/// A struct with odd-width integers, which have no native counterpart in most targets.
+#[derive(Size, Pack, Unpack, Schema, Clone, Copy, Debug, PartialEq)]
+pub struct Registers {
+    pub status: U24,
+    pub offset: I24,
+    pub uptime: U40,
+    pub serial: U48,
+    pub drift: LE<I48>,
+}
+
+pub fn registers() -> Registers {
+    Registers {
+        status: U24::new(0x010203).unwrap(),
+        offset: I24::new(-2).unwrap(),
+        uptime: U40::new(0x0102030405).unwrap(),
+        serial: U48::new(0xfedcba987654).unwrap(),
+        drift: LE(I48::new(-300).unwrap()),
+    }
+}
+
+/// A struct with BCD values, which generators represent by their decimal value.
+#[derive(Size, Pack, Unpack, Schema, Clone, Copy, Debug, PartialEq)]
+pub struct Clock {
+    pub time: DateTime,
+    pub counter: Bcd<u32>,
+}
+
+pub fn clock() -> Clock {
+    Clock {
+        time: DateTime { seconds: 30, minutes: 37, hours: 13, weekday: 2, day: 19, month: 10, year: 26 },
+        counter: Bcd::new(12_345_678).unwrap(),
+    }
+}
+
+/// A struct with reduced-precision floats, which generators represent by their value.
+#[derive(Size, Pack, Unpack, Schema, Clone, Copy, Debug, PartialEq)]
+pub struct Halves {
+    pub half: F16,
+    pub brain: BF16,
+    pub tiny: LE<F16>,
+    pub e4m3: Minifloat<4, 3>,
+    pub e5m2: Minifloat<5, 2>,
+}
+
+/// Values that round (a tie to even, a subnormal and a carry into the exponent).
+pub fn halves() -> Halves {
+    Halves {
+        half: F16::from_f64(1.00048828125),
+        brain: BF16::from_f64(-2.7),
+        tiny: LE(F16::from_f64(1e-6)),
+        e4m3: Minifloat::from_f64(0.3),
+        e5m2: Minifloat::from_f64(-1500.0),
+    }
+}
+
+pub fn frame() -> SensorFrame {
+    SensorFrame {
+        seq: 0xdeadbeef,
+        mode: Mode::Auto,
+        readings: [
+            Reading { id: 1, value: 1.5, temperature: -20, valid: true },
+            Reading { id: 515, value: -0.25, temperature: 35, valid: false },
+        ],
+        origin: Some((-3, 1000)),
+        raw: LE(0x0102030405060708),
+        initial: 'λ',
+        stamp: None,
+        ratio: -1e10,
+        grid: [[1, 2], [3, 4], [5, 6]],
+        slots: [None, Some(Reading { id: 9, value: 2.0, temperature: 0, valid: true })],
+    }
+}
+
+pub fn pack<B, P>(value: &P) -> Vec<u8> where B: ByteOrder, P: Pack {
+    let mut buffer = vec![0u8; P::SIZE];
+    value.pack::<B>(&mut buffer);
+    buffer
+}
+
+/// The bytes as comma-separated decimal numbers.
+pub fn byte_list(bytes: &[u8]) -> String {
+    let items: Vec<String> = bytes.iter().map(|b| b.to_string()).collect();
+    items.join(", ")
+}
+
+/// Compares the output with the snapshot in `tests/snapshots`, or updates
+/// the snapshot if `UPDATE_SNAPSHOTS` is set.
+pub fn assert_snapshot(name: &str, actual: &str) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("snapshots").join(name);
+    if env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::write(&path, actual).unwrap();
+    }
+    let expected = fs::read_to_string(&path).unwrap();
+    assert_eq!(actual, expected, "Output differs from snapshot {}, set UPDATE_SNAPSHOTS=1 to update it", name);
+}
@@ -0,0 +1,136 @@
+use std::{fs, path::PathBuf, process::Command};
+
+use lightpack::{Schema, Size, extra::LE, int::{U24, U48}, schema::Endianness, varint::Varint};
+use lightpack_codegen::{Error, kaitai::Generator};
+
+mod common;
+
+use common::{Climate, Clock, Halves, Mode, Reading, Registers, SensorFrame, assert_snapshot};
+
+#[test]
+fn snapshot() {
+    let spec = Generator::new::<SensorFrame>("sensor_frame").generate().unwrap();
+
+    assert_snapshot("sensor_frame.ksy", &spec);
+}
+
+#[test]
+fn byte_order() {
+    let spec = Generator::new::<SensorFrame>("sensor_frame").byte_order(Endianness::Little).generate().unwrap();
+
+    assert!(spec.contains("meta:\n  id: sensor_frame\n  endian: le\n"));
+    assert!(spec.contains("  - id: raw\n    type: u8le\n"));
+    assert!(spec.contains("  - id: stamp\n    type: s8be\n    if: stamp_present != 0\n"));
+}
+
+#[test]
+fn enum_messages() {
+    let spec = Generator::new::<Mode>("mode").generate().unwrap();
+
+    assert!(spec.contains("seq:\n  - id: value\n    type: s2\n    enum: mode\n"));
+    assert!(spec.contains("enums:\n  mode:\n    -1: 'off'\n    1: 'on'\n    700: auto\n"));
+}
+
+#[test]
+fn yaml_1_1() {
+    #[derive(Size, Schema)]
+    #[allow(dead_code)]
+    struct Switch {
+        on: bool,
+        mode: Mode,
+        reading: Option<Reading>,
+    }
+
+    let spec = Generator::new::<Switch>("switch").generate().unwrap();
+    assert!(spec.contains("  - id: 'on'\n"));
+
+    // PyYAML implements YAML 1.1, which reads unquoted `on` and `off` as booleans
+    let script = r#"import json, sys
+
+try:
+    import yaml
+except ImportError:
+    print("skip")
+    sys.exit(0)
+
+spec = yaml.safe_load(sys.stdin)
+print(json.dumps({"ids": [a["id"] for a in spec["seq"]], "mode": spec["enums"]["mode"]}, sort_keys=True))
+"#;
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("kaitai");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("switch.ksy"), &spec).unwrap();
+    fs::write(dir.join("parse.py"), script).unwrap();
+
+    let output = match Command::new("python3").arg(dir.join("parse.py")).stdin(fs::File::open(dir.join("switch.ksy")).unwrap()).output() {
+        Ok(output) => output,
+        Err(e) => {
+            eprintln!("Skipping YAML test, could not run python3: {}", e);
+            return;
+        },
+    };
+    assert!(output.status.success(), "Parsing the spec failed: {}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    if stdout.trim() == "skip" {
+        eprintln!("Skipping YAML test, PyYAML is not installed");
+        return;
+    }
+    assert_eq!(stdout.trim(), r#"{"ids": ["on", "mode", "reading_present", "reading", "reading_padding"], "mode": {"-1": "off", "1": "on", "700": "auto"}}"#);
+}
+
+#[test]
+fn unsupported_messages() {
+    #[derive(Size, Schema)]
+    #[allow(dead_code)]
+    struct Swapped {
+        reading: LE<Reading>,
+    }
+
+    assert_eq!(Generator::new::<u8>("byte").generate(), Err(Error::Unnamed));
+    assert!(matches!(Generator::new::<Swapped>("swapped").generate(), Err(Error::Unsupported(_))));
+
+    #[derive(Size, Schema)]
+    #[allow(dead_code)]
+    struct Counter {
+        count: Option<Varint<u32>>,
+    }
+
+    assert_eq!(Generator::new::<Counter>("counter").generate(), Err(Error::Unsupported("varints".to_owned())));
+}
+
+#[test]
+fn scaled_fields() {
+    let spec = Generator::new::<Climate>("climate").generate().unwrap();
+
+    assert!(spec.contains("  - id: temperature\n    type: s2\n  - id: humidity\n    type: u1\ninstances:\n  temperature_value:\n    value: temperature * 0.01 - 40.0\n  humidity_value:\n    value: humidity * 0.5\n"));
+}
+
+#[test]
+fn odd_width_integers() {
+    #[derive(Size, Schema)]
+    #[allow(dead_code)]
+    struct Counters {
+        events: U24,
+        bytes: LE<U48>,
+    }
+
+    let spec = Generator::new::<Counters>("counters").generate().unwrap();
+    assert!(spec.contains("  - id: events\n    type: b24be\n  - id: bytes\n    type: b48le\n"));
+    assert_eq!(Generator::new::<Registers>("registers").generate(), Err(Error::Unsupported("signed 24-bit integers".to_owned())));
+}
+
+#[test]
+fn bcd() {
+    let spec = Generator::new::<Clock>("clock").generate().unwrap();
+
+    assert!(spec.contains("  - id: counter\n    type: u4\n    doc: packed BCD\n"));
+    assert!(spec.contains("  date_time:\n    seq:\n      - id: seconds\n        type: u1\n        doc: packed BCD\n"));
+}
+
+#[test]
+fn floats() {
+    let spec = Generator::new::<Halves>("halves").generate().unwrap();
+
+    assert!(spec.contains("  - id: half\n    type: u2\n    doc: float with 5 exponent and 10 mantissa bits\n"));
+    assert!(spec.contains("  - id: tiny\n    type: u2le\n    doc: float with 5 exponent and 10 mantissa bits\n"));
+    assert!(spec.contains("  - id: e4m3\n    type: u1\n    doc: float with 4 exponent and 3 mantissa bits\n"));
+}
@@ -0,0 +1,344 @@
+use std::{env, fs, path::PathBuf};
+
+use lightpack::{Schema, Size, bcd::Bcd, extra::{LE, Reserved}, fixed::Fixed, float::{BF16, F16, Minifloat}, int::{I48, U24}, varint::{Varint, ZigZag}};
+use lightpack_codegen::lock::{Change, Compatibility, Error, Lock, UPDATE_VAR};
+
+mod v1 {
+    use super::*;
+
+    #[derive(Size, Schema)]
+    #[repr(u8)]
+    #[allow(dead_code)]
+    pub enum Mode {
+        Off = 0,
+        On = 1,
+    }
+
+    #[derive(Size, Schema)]
+    #[allow(dead_code)]
+    pub struct Reading {
+        pub id: u16,
+        pub value: i32,
+    }
+
+    #[derive(Size, Schema)]
+    #[allow(dead_code)]
+    pub struct Frame {
+        pub seq: u32,
+        pub mode: Mode,
+        pub readings: [Reading; 2],
+        pub origin: Option<(i8, i8)>,
+    }
+}
+
+fn changes(lock: Lock, locked: &Lock) -> Vec<Change> {
+    lock.diff(&locked.render().unwrap()).unwrap()
+}
+
+fn compatible(ty: &str, description: &str) -> Change {
+    Change { ty: ty.to_owned(), compatibility: Compatibility::Compatible, description: description.to_owned() }
+}
+
+fn breaking(ty: &str, description: &str) -> Change {
+    Change { ty: ty.to_owned(), compatibility: Compatibility::Breaking, description: description.to_owned() }
+}
+
+#[test]
+fn render() {
+    let lock = Lock::new().add::<v1::Frame>().catch_all::<v1::Mode>().render().unwrap();
+
+    assert!(lock.ends_with(r#"
+enum Mode: u8 (catch-all)
+    Off = 0
+    On = 1
+
+struct Reading (6 bytes)
+    id: u16
+    value: i32
+
+struct Frame (20 bytes)
+    seq: u32
+    mode: Mode
+    readings: [Reading; 2]
+    origin: Option<(i8, i8)>
+"#));
+    assert_eq!(Lock::new().add::<v1::Frame>().diff(&lock).unwrap(), vec![]);
+}
+
+#[test]
+fn odd_width_integers() {
+    #[derive(Size, Schema)]
+    #[allow(dead_code)]
+    struct Registers {
+        status: U24,
+        drift: LE<I48>,
+    }
+
+    assert!(Lock::new().add::<Registers>().render().unwrap().ends_with("struct Registers (9 bytes)\n    status: U24\n    drift: LE<I48>\n"));
+}
+
+#[test]
+fn bcd() {
+    #[derive(Size, Schema)]
+    #[allow(dead_code)]
+    struct Meter {
+        reading: Bcd<u32>,
+    }
+
+    assert!(Lock::new().add::<Meter>().render().unwrap().ends_with("struct Meter (4 bytes)\n    reading: Bcd<u32>\n"));
+}
+
+#[test]
+fn fixed_point() {
+    #[derive(Size, Schema)]
+    #[allow(dead_code)]
+    struct Reading {
+        temperature: Fixed<i16, 8>,
+        ratio: LE<Fixed<u32, 16>>,
+    }
+
+    let lock = Lock::new().add::<Reading>().render().unwrap();
+    assert!(lock.contains("struct FixedI16F8 (2 bytes)\n    value: i16 (scale 0.00390625, offset 0.0)\n"));
+    assert!(lock.contains("struct FixedU32F16 (4 bytes)\n    value: u32 (scale 1.52587890625e-5, offset 0.0)\n"));
+    assert!(lock.ends_with("struct Reading (6 bytes)\n    temperature: FixedI16F8\n    ratio: LE<FixedU32F16>\n"));
+}
+
+#[test]
+fn floats() {
+    #[derive(Size, Schema)]
+    #[allow(dead_code)]
+    struct Halves {
+        half: LE<F16>,
+        brain: BF16,
+        tiny: Minifloat<4, 3>,
+    }
+
+    assert!(Lock::new().add::<Halves>().render().unwrap().ends_with("struct Halves (5 bytes)\n    half: LE<F16>\n    brain: BF16\n    tiny: Minifloat<4, 3>\n"));
+}
+
+#[test]
+fn struct_changes() {
+    mod v2 {
+        use super::*;
+
+        #[derive(Size, Schema)]
+        #[allow(dead_code)]
+        pub struct Reading {
+            pub id: u16,
+            pub value: i32,
+            pub unit: u8,
+        }
+
+        #[derive(Size, Schema)]
+        #[allow(dead_code)]
+        pub struct Frame {
+            pub sequence: u32,
+            pub mode: v1::Mode,
+            pub readings: [Reading; 2],
+            pub origin: Option<(i8, i8)>,
+            pub flags: LE<u16>,
+            pub reserved: Reserved<2>,
+            pub count: Varint<u32>,
+            pub delta: ZigZag<i16>,
+        }
+
+        #[derive(Size, Schema)]
+        #[allow(dead_code)]
+        pub struct Reordered {
+            pub mode: v1::Mode,
+            pub seq: u32,
+            pub readings: [v1::Reading; 2],
+        }
+    }
+
+    assert_eq!(changes(Lock::new().add::<v2::Frame>(), &Lock::new().add::<v1::Frame>()), vec![
+        breaking("Reading", "appended field unit: u8, which changes the layout of Frame"),
+        compatible("Frame", "renamed field seq to sequence"),
+        compatible("Frame", "appended field flags: LE<u16>"),
+        compatible("Frame", "appended field reserved: Reserved<2>"),
+        compatible("Frame", "appended field count: Varint<u32>"),
+        compatible("Frame", "appended field delta: ZigZag<i16>"),
+    ]);
+
+    let reordered = Lock::new().add::<v2::Reordered>();
+    let diff = reordered.diff(&Lock::new().add::<v1::Frame>().render().unwrap().replace("Frame", "Reordered")).unwrap();
+    assert_eq!(diff, vec![
+        breaking("Reordered", "moved field seq"),
+        breaking("Reordered", "moved field mode"),
+        breaking("Reordered", "removed field origin"),
+    ]);
+}
+
+#[test]
+fn type_changes() {
+    mod v2 {
+        use super::*;
+
+        #[derive(Size, Schema)]
+        #[allow(dead_code)]
+        pub struct Reading {
+            pub id: u32,
+            pub value: i32,
+        }
+    }
+
+    assert_eq!(changes(Lock::new().add::<v2::Reading>(), &Lock::new().add::<v1::Reading>()), vec![
+        breaking("Reading", "changed type of field id from u16 to u32"),
+    ]);
+    assert_eq!(changes(Lock::new().add::<v1::Reading>(), &Lock::new().add::<v1::Frame>()), vec![
+        breaking("Mode", "type is no longer locked"),
+        breaking("Frame", "type is no longer locked"),
+    ]);
+    assert_eq!(changes(Lock::new().add::<v1::Frame>(), &Lock::new().add::<v1::Reading>()), vec![
+        compatible("Mode", "added type"),
+        compatible("Frame", "added type"),
+    ]);
+}
+
+#[test]
+fn scaled_fields() {
+    #[derive(Size, Schema)]
+    #[allow(dead_code)]
+    struct Climate {
+        #[lightpack(scale = 0.01, offset = -40.0, as = i16)]
+        temperature: f32,
+    }
+
+    mod v2 {
+        use super::*;
+
+        #[derive(Size, Schema)]
+        #[allow(dead_code)]
+        pub struct Climate {
+            #[lightpack(scale = 0.1, offset = -40.0, as = i16)]
+            pub temperature: f32,
+        }
+    }
+
+    assert!(Lock::new().add::<Climate>().render().unwrap().contains("    temperature: i16 (scale 0.01, offset -40.0)\n"));
+    assert_eq!(changes(Lock::new().add::<v2::Climate>(), &Lock::new().add::<Climate>()), vec![
+        breaking("Climate", "changed type of field temperature from i16 (scale 0.01, offset -40.0) to i16 (scale 0.1, offset -40.0)"),
+    ]);
+}
+
+#[test]
+fn enum_changes() {
+    mod v2 {
+        use super::*;
+
+        #[derive(Size, Schema)]
+        #[repr(u8)]
+        #[allow(dead_code)]
+        pub enum Mode {
+            Disabled = 0,
+            On = 1,
+            Auto = 2,
+        }
+    }
+
+    mod v3 {
+        use super::*;
+
+        #[derive(Size, Schema)]
+        #[repr(u16)]
+        #[allow(dead_code)]
+        pub enum Mode {
+            Off = 0,
+        }
+    }
+
+    assert_eq!(changes(Lock::new().add::<v2::Mode>().catch_all::<v2::Mode>(), &Lock::new().add::<v1::Mode>()), vec![
+        compatible("Mode", "renamed variant Off to Disabled"),
+        compatible("Mode", "added variant Auto = 2"),
+    ]);
+    assert_eq!(changes(Lock::new().add::<v2::Mode>(), &Lock::new().add::<v1::Mode>()), vec![
+        compatible("Mode", "renamed variant Off to Disabled"),
+        breaking("Mode", "added variant Auto = 2"),
+    ]);
+    assert_eq!(changes(Lock::new().add::<v3::Mode>(), &Lock::new().add::<v1::Mode>()), vec![
+        breaking("Mode", "changed representation from u8 to u16"),
+        breaking("Mode", "removed variant On = 1"),
+    ]);
+}
+
+#[test]
+fn malformed() {
+    let lock = Lock::new().add::<v1::Reading>();
+
+    assert!(matches!(lock.diff("    id: u16"), Err(Error::Malformed { line: 1, .. })));
+    assert!(matches!(lock.diff("struct Reading\n"), Err(Error::Malformed { line: 1, .. })));
+    assert!(matches!(lock.diff("enum Mode: u8\n    Off = zero"), Err(Error::Malformed { line: 2, .. })));
+}
+
+#[test]
+fn check() {
+    mod v2 {
+        use super::*;
+
+        #[derive(Size, Schema)]
+        #[allow(dead_code)]
+        pub struct Reading {
+            pub id: u16,
+            pub value: i32,
+            pub unit: u8,
+        }
+
+        #[derive(Size, Schema)]
+        #[allow(dead_code)]
+        pub struct Frame {
+            pub seq: u16,
+        }
+    }
+
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("lock");
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("wire.lock");
+    let _ = fs::remove_file(&path);
+    env::remove_var(UPDATE_VAR);
+
+    // A missing lock file is only created on request
+    assert!(matches!(Lock::new().add::<v1::Reading>().check(&path), Err(Error::Missing(_))));
+    env::set_var(UPDATE_VAR, "0");
+    assert!(matches!(Lock::new().add::<v1::Reading>().check(&path), Err(Error::Missing(_))));
+    env::set_var(UPDATE_VAR, "1");
+    assert_eq!(Lock::new().add::<v1::Reading>().check(&path).unwrap(), vec![compatible("Reading", "added type")]);
+    assert_eq!(fs::read_to_string(&path).unwrap(), Lock::new().add::<v1::Reading>().render().unwrap());
+    env::remove_var(UPDATE_VAR);
+    assert_eq!(Lock::new().add::<v1::Reading>().check(&path).unwrap(), vec![]);
+
+    // Changes have to be accepted explicitly
+    let appended = vec![compatible("Reading", "appended field unit: u8")];
+    assert!(matches!(Lock::new().add::<v2::Reading>().check(&path), Err(Error::Outdated(changes)) if changes == appended));
+    env::set_var(UPDATE_VAR, "");
+    assert!(matches!(Lock::new().add::<v2::Reading>().check(&path), Err(Error::Outdated(_))));
+    env::set_var(UPDATE_VAR, "1");
+    assert_eq!(Lock::new().add::<v2::Reading>().check(&path).unwrap(), appended);
+    assert_eq!(fs::read_to_string(&path).unwrap(), Lock::new().add::<v2::Reading>().render().unwrap());
+
+    // Breaking changes require LIGHTPACK_UPDATE_LOCK=breaking
+    let lock = Lock::new().add::<v2::Reading>().add::<v2::Frame>();
+    let error = Lock::new().add::<v2::Frame>().check(&path).unwrap_err();
+    assert!(error.to_string().ends_with(&format!("rerun with {}=breaking to update the lock file", UPDATE_VAR)));
+    env::set_var(UPDATE_VAR, "breaking");
+    assert_eq!(Lock::new().add::<v2::Frame>().check(&path).unwrap(), vec![
+        breaking("Reading", "type is no longer locked"),
+        compatible("Frame", "added type"),
+    ]);
+    env::remove_var(UPDATE_VAR);
+    assert!(lock.check(&path).is_err());
+}
+
+#[test]
+fn update() {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("lock_update");
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("wire.lock");
+    let _ = fs::remove_file(&path);
+
+    assert_eq!(Lock::new().add::<v1::Reading>().update(&path).unwrap(), vec![compatible("Reading", "added type")]);
+    assert_eq!(Lock::new().add::<v1::Frame>().update(&path).unwrap(), vec![
+        compatible("Mode", "added type"),
+        compatible("Frame", "added type"),
+    ]);
+    assert_eq!(fs::read_to_string(&path).unwrap(), Lock::new().add::<v1::Frame>().render().unwrap());
+}
@@ -0,0 +1,267 @@
+use std::{fs, path::PathBuf, process::Command};
+
+use lightpack::{Schema, Size, byteorder::{BigEndian, LittleEndian}, extra::Reserved};
+use lightpack_codegen::python::Generator;
+
+mod common;
+
+use common::{Climate, Clock, Halves, Reading, Registers, SensorFrame, byte_list, clock, frame, halves, pack, registers};
+
+/// The Python value corresponding to [`frame`].
+const PY_FRAME: &str = r#"codec.SensorFrame(
+    seq=0xdeadbeef,
+    mode=codec.Mode.Auto,
+    readings=[
+        codec.Reading(id=1, value=1.5, temperature=-20, valid=True),
+        codec.Reading(id=515, value=-0.25, temperature=35, valid=False),
+    ],
+    origin=(-3, 1000),
+    raw=0x0102030405060708,
+    initial="λ",
+    stamp=None,
+    ratio=-1e10,
+    grid=[[1, 2], [3, 4], [5, 6]],
+    slots=[None, codec.Reading(id=9, value=2.0, temperature=0, valid=True)],
+)"#;
+
+fn py_bytes(bytes: &[u8]) -> String {
+    format!("bytes([{}])", byte_list(bytes))
+}
+
+#[test]
+fn golden_vectors() {
+    let module = Generator::new().add::<SensorFrame>().generate().unwrap();
+
+    let big_endian = pack::<BigEndian, _>(&frame());
+    let little_endian = pack::<LittleEndian, _>(&frame());
+    let mut invalid_mode = big_endian.clone();
+    invalid_mode[SensorFrame::OFFSET_MODE + 1] = 2;
+    let reading = pack::<BigEndian, _>(&frame().readings[1]);
+
+    let script = format!(r#"import struct
+
+import codec
+
+frame = {py_frame}
+
+for golden, little_endian in [({big_endian}, False), ({little_endian}, True)]:
+    assert frame.pack(little_endian) == golden
+    assert codec.SensorFrame.unpack(golden, little_endian) == frame
+
+try:
+    codec.SensorFrame.unpack({invalid_mode})
+    raise AssertionError("Unpacking an invalid mode succeeded")
+except ValueError:
+    pass
+
+assert struct.unpack(codec.READING_FORMAT, {reading}) == (515, -0.25, 35, False)
+assert not hasattr(codec, "SENSOR_FRAME_FORMAT")
+
+print(codec.SENSOR_FRAME_SIZE)
+"#,
+        py_frame = PY_FRAME,
+        big_endian = py_bytes(&big_endian),
+        little_endian = py_bytes(&little_endian),
+        invalid_mode = py_bytes(&invalid_mode),
+        reading = py_bytes(&reading),
+    );
+
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("python");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("codec.py"), module).unwrap();
+    fs::write(dir.join("test.py"), script).unwrap();
+
+    let output = match Command::new("python3").arg(dir.join("test.py")).output() {
+        Ok(output) => output,
+        Err(e) => {
+            eprintln!("Skipping Python test, could not run python3: {}", e);
+            return;
+        },
+    };
+    assert!(output.status.success(), "Running the generated codec failed: {}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8(output.stdout).unwrap().trim(), SensorFrame::SIZE.to_string());
+}
+
+#[test]
+fn declarations() {
+    let module = Generator::new().add::<SensorFrame>().generate().unwrap();
+
+    assert!(module.contains("class Mode(IntEnum):\n    Off = -1\n    On = 1\n    Auto = 700\n"));
+    assert!(module.contains(r#"@dataclass
+class SensorFrame:
+    seq: int
+    mode: Mode
+    readings: List[Reading]
+    origin: Optional[Tuple[int, int]]
+    raw: int
+    initial: str
+    stamp: Optional[int]
+    ratio: float
+    grid: List[List[int]]
+    slots: List[Optional[Reading]]
+"#));
+    assert!(module.contains("SENSOR_FRAME_SIZE = 80\n"));
+    assert!(module.contains(&format!("READING_FORMAT = \"{}\"\n", Reading::STRUCT_FORMAT.unwrap())));
+    assert!(module.contains("    def pack_into(self, buffer: bytearray, offset: int = 0, little_endian: bool = False) -> None:"));
+    assert!(module.contains("            raw=struct.unpack_from(\"<Q\", data, offset + 27)[0],"));
+    assert!(module.contains("            stamp=(struct.unpack_from(\">q\", data, offset + 40)[0] if data[offset + 39] != 0 else None),"));
+}
+
+#[test]
+fn reserved_names() {
+    #[derive(Size, Schema)]
+    #[allow(dead_code)]
+    struct Route {
+        from: u8,
+        r#in: (u8,),
+    }
+
+    let module = Generator::new().add::<Route>().generate().unwrap();
+
+    assert!(module.contains("    from_: int\n    in_: Tuple[int]\n"));
+    assert!(module.contains("            in_=(struct.unpack_from(_order(little_endian) + \"B\", data, offset + 1)[0],),"));
+}
+
+#[test]
+fn reserved() {
+    #[derive(Size, Schema)]
+    #[allow(dead_code)]
+    struct Padded {
+        id: u8,
+        _reserved: Reserved<3>,
+        value: (u16, Reserved<1>),
+    }
+
+    let module = Generator::new().add::<Padded>().generate().unwrap();
+
+    assert!(module.contains("class Padded:\n    id: int\n    value: Tuple[int, None]\n"));
+    assert!(module.contains("PADDED_FORMAT = \">B3xHx\"\n"));
+
+    let script = r#"import codec
+
+buffer = bytearray(b"\xff" * codec.PADDED_SIZE)
+codec.Padded(id=7, value=(0x0102, None)).pack_into(buffer)
+assert buffer == bytes([7, 0, 0, 0, 1, 2, 0]), buffer
+assert codec.Padded.unpack(bytes([7, 9, 9, 9, 1, 2, 9])) == codec.Padded(id=7, value=(0x0102, None))
+"#;
+
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("python_reserved");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("codec.py"), module).unwrap();
+    fs::write(dir.join("test.py"), script).unwrap();
+
+    let output = match Command::new("python3").arg(dir.join("test.py")).output() {
+        Ok(output) => output,
+        Err(e) => {
+            eprintln!("Skipping Python test, could not run python3: {}", e);
+            return;
+        },
+    };
+    assert!(output.status.success(), "Running the generated codec failed: {}", String::from_utf8_lossy(&output.stderr));
+}
+
+#[test]
+fn scaled_fields() {
+    let module = Generator::new().add::<Climate>().generate().unwrap();
+
+    assert!(module.contains("CLIMATE_SCALE_TEMPERATURE = 0.01\nCLIMATE_VALUE_OFFSET_TEMPERATURE = -40.0\n"));
+    assert!(module.contains("class Climate:\n    temperature: int  # value = temperature * 0.01 - 40.0\n    humidity: int  # value = humidity * 0.5\n"));
+}
+
+#[test]
+fn odd_width_integers() {
+    let module = Generator::new().add::<Registers>().generate().unwrap();
+
+    assert!(!module.contains("REGISTERS_FORMAT"));
+    let script = format!(r#"import codec
+
+registers = codec.Registers(status=0x010203, offset=-2, uptime=0x0102030405, serial=0xfedcba987654, drift=-300)
+for golden, little_endian in [({big_endian}, False), ({little_endian}, True)]:
+    assert registers.pack(little_endian) == golden, registers.pack(little_endian)
+    assert codec.Registers.unpack(golden, little_endian) == registers
+"#,
+        big_endian = py_bytes(&pack::<BigEndian, _>(&registers())),
+        little_endian = py_bytes(&pack::<LittleEndian, _>(&registers())),
+    );
+
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("python_odd_width");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("codec.py"), module).unwrap();
+    fs::write(dir.join("test.py"), script).unwrap();
+
+    let output = match Command::new("python3").arg(dir.join("test.py")).output() {
+        Ok(output) => output,
+        Err(e) => {
+            eprintln!("Skipping Python test, could not run python3: {}", e);
+            return;
+        },
+    };
+    assert!(output.status.success(), "Running the generated codec failed: {}", String::from_utf8_lossy(&output.stderr));
+}
+
+#[test]
+fn bcd() {
+    let module = Generator::new().add::<Clock>().generate().unwrap();
+
+    let golden = pack::<BigEndian, _>(&clock());
+    let mut invalid = golden.clone();
+    invalid[Clock::SIZE - 1] = 0x1a;
+    let script = format!(r#"import codec
+
+clock = codec.Clock(time=codec.DateTime(seconds=30, minutes=37, hours=13, weekday=2, day=19, month=10, year=26), counter=12345678)
+assert clock.pack() == {golden}, clock.pack()
+assert codec.Clock.unpack({golden}) == clock
+try:
+    codec.Clock.unpack({invalid})
+    raise AssertionError("invalid BCD was accepted")
+except ValueError:
+    pass
+"#,
+        golden = py_bytes(&golden),
+        invalid = py_bytes(&invalid),
+    );
+
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("python_bcd");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("codec.py"), module).unwrap();
+    fs::write(dir.join("test.py"), script).unwrap();
+
+    let output = match Command::new("python3").arg(dir.join("test.py")).output() {
+        Ok(output) => output,
+        Err(e) => {
+            eprintln!("Skipping Python test, could not run python3: {}", e);
+            return;
+        },
+    };
+    assert!(output.status.success(), "Running the generated codec failed: {}", String::from_utf8_lossy(&output.stderr));
+}
+
+#[test]
+fn floats() {
+    let module = Generator::new().add::<Halves>().generate().unwrap();
+
+    let golden = pack::<BigEndian, _>(&halves());
+    let script = format!(r#"import codec
+
+halves = codec.Halves(half=1.00048828125, brain=-2.7, tiny=1e-6, e4m3=0.3, e5m2=-1500.0)
+assert halves.pack() == {golden}, halves.pack()
+decoded = codec.Halves.unpack({golden})
+assert decoded == codec.Halves(half=1.0, brain=-2.703125, tiny=17 / 2**24, e4m3=0.3125, e5m2=-1536.0), decoded
+"#,
+        golden = py_bytes(&golden),
+    );
+
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("python_floats");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("codec.py"), module).unwrap();
+    fs::write(dir.join("test.py"), script).unwrap();
+
+    let output = match Command::new("python3").arg(dir.join("test.py")).output() {
+        Ok(output) => output,
+        Err(e) => {
+            eprintln!("Skipping Python test, could not run python3: {}", e);
+            return;
+        },
+    };
+    assert!(output.status.success(), "Running the generated codec failed: {}", String::from_utf8_lossy(&output.stderr));
+}
@@ -0,0 +1,104 @@
+-- Generated by lightpack-codegen. Do not edit.
+
+local proto = Proto("sensor", "Sensor Protocol")
+
+local mode_values = {
+  [-1] = "Off",
+  [1] = "On",
+  [700] = "Auto",
+}
+
+local fields = {
+  ["seq"] = ProtoField.uint32("sensor.seq", "seq", base.DEC),
+  ["mode"] = ProtoField.int16("sensor.mode", "mode", base.DEC, mode_values),
+  ["readings"] = ProtoField.none("sensor.readings", "readings"),
+  ["readings.id"] = ProtoField.uint16("sensor.readings.id", "id", base.DEC),
+  ["readings.value"] = ProtoField.float("sensor.readings.value", "value"),
+  ["readings.temperature"] = ProtoField.int8("sensor.readings.temperature", "temperature", base.DEC),
+  ["readings.valid"] = ProtoField.bool("sensor.readings.valid", "valid"),
+  ["origin.present"] = ProtoField.bool("sensor.origin.present", "origin present"),
+  ["origin"] = ProtoField.none("sensor.origin", "origin"),
+  ["origin.0"] = ProtoField.int16("sensor.origin.0", "0", base.DEC),
+  ["origin.1"] = ProtoField.int16("sensor.origin.1", "1", base.DEC),
+  ["raw"] = ProtoField.uint64("sensor.raw", "raw", base.DEC),
+  ["initial"] = ProtoField.uint32("sensor.initial", "initial", base.HEX),
+  ["stamp.present"] = ProtoField.bool("sensor.stamp.present", "stamp present"),
+  ["stamp"] = ProtoField.int64("sensor.stamp", "stamp", base.DEC),
+  ["ratio"] = ProtoField.double("sensor.ratio", "ratio"),
+  ["grid"] = ProtoField.uint8("sensor.grid", "grid", base.DEC),
+  ["slots.present"] = ProtoField.bool("sensor.slots.present", "slots present"),
+  ["slots"] = ProtoField.none("sensor.slots", "slots"),
+  ["slots.id"] = ProtoField.uint16("sensor.slots.id", "id", base.DEC),
+  ["slots.value"] = ProtoField.float("sensor.slots.value", "value"),
+  ["slots.temperature"] = ProtoField.int8("sensor.slots.temperature", "temperature", base.DEC),
+  ["slots.valid"] = ProtoField.bool("sensor.slots.valid", "valid"),
+}
+proto.fields = fields
+
+local MESSAGE_SIZE = 80
+
+local function dissect_message(tvb, tree, offset)
+  tree:add(fields["seq"], tvb(offset, 4))
+  tree:add(fields["mode"], tvb(offset + 4, 2))
+  local t1 = tree:add(tvb(offset + 6, 16), "readings")
+  for i0 = 0, 1 do
+    local t2 = t1:add(fields["readings"], tvb(offset + 6 + 8 * i0, 8))
+    t2:append_text(" [" .. i0 .. "]")
+    t2:add(fields["readings.id"], tvb(offset + 6 + 8 * i0, 2))
+    t2:add(fields["readings.value"], tvb(offset + 8 + 8 * i0, 4))
+    t2:add(fields["readings.temperature"], tvb(offset + 12 + 8 * i0, 1))
+    t2:add(fields["readings.valid"], tvb(offset + 13 + 8 * i0, 1))
+  end
+  tree:add(fields["origin.present"], tvb(offset + 22, 1))
+  if tvb(offset + 22, 1):uint() ~= 0 then
+    local t3 = tree:add(fields["origin"], tvb(offset + 23, 4))
+    t3:add(fields["origin.0"], tvb(offset + 23, 2))
+    t3:add(fields["origin.1"], tvb(offset + 25, 2))
+  end
+  tree:add_le(fields["raw"], tvb(offset + 27, 8))
+  tree:add(fields["initial"], tvb(offset + 35, 4))
+  tree:add(fields["stamp.present"], tvb(offset + 39, 1))
+  if tvb(offset + 39, 1):uint() ~= 0 then
+    tree:add(fields["stamp"], tvb(offset + 40, 8))
+  end
+  tree:add(fields["ratio"], tvb(offset + 48, 8))
+  local t4 = tree:add(tvb(offset + 56, 6), "grid")
+  for i0 = 0, 2 do
+    local t5 = t4:add(tvb(offset + 56 + 2 * i0, 2), "grid")
+    t5:append_text(" [" .. i0 .. "]")
+    for i1 = 0, 1 do
+      t5:add(fields["grid"], tvb(offset + 56 + 2 * i0 + i1, 1))
+    end
+  end
+  local t6 = tree:add(tvb(offset + 62, 18), "slots")
+  for i0 = 0, 1 do
+    t6:add(fields["slots.present"], tvb(offset + 62 + 9 * i0, 1))
+    if tvb(offset + 62 + 9 * i0, 1):uint() ~= 0 then
+      local t7 = t6:add(fields["slots"], tvb(offset + 63 + 9 * i0, 8))
+      t7:append_text(" [" .. i0 .. "]")
+      t7:add(fields["slots.id"], tvb(offset + 63 + 9 * i0, 2))
+      t7:add(fields["slots.value"], tvb(offset + 65 + 9 * i0, 4))
+      t7:add(fields["slots.temperature"], tvb(offset + 69 + 9 * i0, 1))
+      t7:add(fields["slots.valid"], tvb(offset + 70 + 9 * i0, 1))
+    end
+  end
+end
+
+function proto.dissector(tvb, pinfo, tree)
+  pinfo.cols.protocol = proto.name
+  local offset = 0
+  while offset < tvb:len() do
+    if tvb:len() - offset < MESSAGE_SIZE then
+      pinfo.desegment_offset = offset
+      pinfo.desegment_len = MESSAGE_SIZE - (tvb:len() - offset)
+      return
+    end
+    dissect_message(tvb, tree:add(proto, tvb(offset, MESSAGE_SIZE)), offset)
+    offset = offset + MESSAGE_SIZE
+  end
+end
+
+DissectorTable.get("udp.port"):add(5000, proto)
+DissectorTable.get("udp.port"):add_for_decode_as(proto)
+DissectorTable.get("tcp.port"):add(5001, proto)
+DissectorTable.get("tcp.port"):add_for_decode_as(proto)
@@ -0,0 +1,272 @@
+use std::{fs, path::PathBuf, process::Command};
+
+use lightpack::{Schema, Size, byteorder::{BigEndian, LittleEndian}};
+use lightpack_codegen::{Error, typescript::Generator};
+
+mod common;
+
+use common::{Climate, Clock, Halves, Registers, SensorFrame, byte_list, clock, frame, halves, pack, registers};
+
+/// The JavaScript value corresponding to [`frame`].
+const JS_FRAME: &str = r#"{
+  seq: 0xdeadbeef,
+  mode: codec.Mode.Auto,
+  readings: [
+    { id: 1, value: 1.5, temperature: -20, valid: true },
+    { id: 515, value: -0.25, temperature: 35, valid: false },
+  ],
+  origin: [-3, 1000],
+  raw: 0x0102030405060708n,
+  initial: 'λ',
+  stamp: null,
+  ratio: -1e10,
+  grid: [[1, 2], [3, 4], [5, 6]],
+  slots: [null, { id: 9, value: 2, temperature: 0, valid: true }],
+}"#;
+
+fn js_array(bytes: &[u8]) -> String {
+    format!("[{}]", byte_list(bytes))
+}
+
+#[test]
+fn golden_vectors() {
+    let module = Generator::new().javascript().add::<SensorFrame>().generate().unwrap();
+
+    let big_endian = pack::<BigEndian, _>(&frame());
+    let little_endian = pack::<LittleEndian, _>(&frame());
+    let mut invalid_mode = big_endian.clone();
+    invalid_mode[SensorFrame::OFFSET_MODE + 1] = 2;
+
+    let script = format!(r#"import assert from 'node:assert/strict';
+import * as codec from './codec.mjs';
+
+const frame = {js_frame};
+
+for (const [golden, littleEndian] of [[{big_endian}, false], [{little_endian}, true]]) {{
+  const buffer = new Uint8Array(codec.SENSOR_FRAME_SIZE);
+  codec.encodeSensorFrame(frame, new DataView(buffer.buffer), 0, littleEndian);
+  assert.deepEqual(Array.from(buffer), golden);
+
+  const decoded = codec.decodeSensorFrame(new DataView(Uint8Array.from(golden).buffer), 0, littleEndian);
+  assert.deepEqual(decoded, frame);
+}}
+
+const invalid = new DataView(Uint8Array.from({invalid_mode}).buffer);
+assert.throws(() => codec.decodeSensorFrame(invalid, 0, false), RangeError);
+assert.throws(() => codec.encodeSensorFrame({{ ...frame, initial: 'ab' }}, new DataView(new ArrayBuffer(codec.SENSOR_FRAME_SIZE))), RangeError);
+
+console.log(codec.SENSOR_FRAME_SIZE);
+"#,
+        js_frame = JS_FRAME,
+        big_endian = js_array(&big_endian),
+        little_endian = js_array(&little_endian),
+        invalid_mode = js_array(&invalid_mode),
+    );
+
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("typescript");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("codec.mjs"), module).unwrap();
+    fs::write(dir.join("test.mjs"), script).unwrap();
+
+    let output = match Command::new("node").arg(dir.join("test.mjs")).output() {
+        Ok(output) => output,
+        Err(e) => {
+            eprintln!("Skipping TypeScript test, could not run node: {}", e);
+            return;
+        },
+    };
+    assert!(output.status.success(), "Running the generated codec failed: {}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8(output.stdout).unwrap().trim(), SensorFrame::SIZE.to_string());
+}
+
+#[test]
+fn type_check() {
+    let module = Generator::new().add::<SensorFrame>().generate().unwrap();
+
+    let script = format!(r#"import * as codec from './codec';
+
+const frame: codec.SensorFrame = {js_frame};
+
+const view = new DataView(new ArrayBuffer(codec.SENSOR_FRAME_SIZE));
+codec.encodeSensorFrame(frame, view);
+const decoded: codec.SensorFrame = codec.decodeSensorFrame(view, 0, true);
+console.log(decoded.seq);
+"#,
+        js_frame = JS_FRAME,
+    );
+
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("typescript_check");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("codec.ts"), module).unwrap();
+    fs::write(dir.join("check.ts"), script).unwrap();
+
+    let compiler = std::env::var("TSC").unwrap_or_else(|_| "tsc".to_owned());
+    let output = match Command::new(&compiler)
+        .args(["--strict", "--noEmit", "--target", "es2020", "--moduleResolution", "node"])
+        .arg(dir.join("codec.ts"))
+        .arg(dir.join("check.ts"))
+        .output() {
+        Ok(output) => output,
+        Err(e) => {
+            eprintln!("Skipping TypeScript type check, could not run {}: {}", compiler, e);
+            return;
+        },
+    };
+    assert!(output.status.success(), "Type checking the generated module failed: {}", String::from_utf8_lossy(&output.stdout));
+}
+
+#[test]
+fn declarations() {
+    let module = Generator::new().add::<SensorFrame>().generate().unwrap();
+
+    assert!(module.contains("export const Mode = {\n  Off: -1,\n  On: 1,\n  Auto: 700,\n} as const;"));
+    assert!(module.contains("export type Mode = typeof Mode[keyof typeof Mode];"));
+    assert!(module.contains(r#"export interface SensorFrame {
+  seq: number;
+  mode: Mode;
+  readings: Reading[];
+  origin: [number, number] | null;
+  raw: bigint;
+  initial: string;
+  stamp: bigint | null;
+  ratio: number;
+  grid: number[][];
+  slots: (Reading | null)[];
+}"#));
+    assert!(module.contains("export const SENSOR_FRAME_SIZE = 80;"));
+    assert!(module.contains("export function encodeSensorFrame(value: SensorFrame, view: DataView, offset: number = 0, littleEndian: boolean = false): void {"));
+    assert!(module.contains("    raw: view.getBigUint64(offset + 27, true),"));
+    assert!(module.contains("    stamp: view.getUint8(offset + 39) !== 0 ? view.getBigInt64(offset + 40, false) : null,"));
+}
+
+#[test]
+fn javascript() {
+    let module = Generator::new().javascript().add::<SensorFrame>().generate().unwrap();
+
+    assert!(!module.contains("interface"));
+    assert!(!module.contains("as const"));
+    assert!(module.contains("export function decodeSensorFrame(view, offset = 0, littleEndian = false) {"));
+}
+
+#[test]
+fn nested_options() {
+    #[derive(Size, Schema)]
+    #[allow(dead_code)]
+    struct Nested {
+        value: Option<Option<u8>>,
+    }
+
+    assert!(matches!(Generator::new().add::<Nested>().generate(), Err(Error::Unsupported(_))));
+    assert!(matches!(Generator::new().javascript().add::<Nested>().generate(), Err(Error::Unsupported(_))));
+}
+
+#[test]
+fn scaled_fields() {
+    let module = Generator::new().add::<Climate>().generate().unwrap();
+
+    assert!(module.contains("  /** The encoded value, the value is `temperature * 0.01 - 40.0`. */\n  temperature: number;\n"));
+    assert!(module.contains("export const CLIMATE_SCALE_HUMIDITY = 0.5;\nexport const CLIMATE_VALUE_OFFSET_HUMIDITY = 0.0;\n"));
+}
+
+#[test]
+fn odd_width_integers() {
+    let module = Generator::new().javascript().add::<Registers>().generate().unwrap();
+
+    let script = format!(r#"import assert from 'node:assert/strict';
+import * as codec from './codec.mjs';
+
+const registers = {{ status: 0x010203, offset: -2, uptime: 0x0102030405, serial: 0xfedcba987654, drift: -300 }};
+for (const [golden, littleEndian] of [[{big_endian}, false], [{little_endian}, true]]) {{
+  const buffer = new Uint8Array(codec.REGISTERS_SIZE);
+  codec.encodeRegisters(registers, new DataView(buffer.buffer), 0, littleEndian);
+  assert.deepEqual(Array.from(buffer), golden);
+  assert.deepEqual(codec.decodeRegisters(new DataView(Uint8Array.from(golden).buffer), 0, littleEndian), registers);
+}}
+"#,
+        big_endian = js_array(&pack::<BigEndian, _>(&registers())),
+        little_endian = js_array(&pack::<LittleEndian, _>(&registers())),
+    );
+
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("typescript_odd_width");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("codec.mjs"), module).unwrap();
+    fs::write(dir.join("test.mjs"), script).unwrap();
+
+    let output = match Command::new("node").arg(dir.join("test.mjs")).output() {
+        Ok(output) => output,
+        Err(e) => {
+            eprintln!("Skipping TypeScript test, could not run node: {}", e);
+            return;
+        },
+    };
+    assert!(output.status.success(), "Running the generated codec failed: {}", String::from_utf8_lossy(&output.stderr));
+}
+
+#[test]
+fn bcd() {
+    let module = Generator::new().javascript().add::<Clock>().generate().unwrap();
+
+    let golden = pack::<LittleEndian, _>(&clock());
+    let mut invalid = golden.clone();
+    invalid[Clock::SIZE - 1] = 0x1a;
+    let script = format!(r#"import assert from 'node:assert/strict';
+import * as codec from './codec.mjs';
+
+const clock = {{ time: {{ seconds: 30, minutes: 37, hours: 13, weekday: 2, day: 19, month: 10, year: 26 }}, counter: 12345678 }};
+const buffer = new Uint8Array(codec.CLOCK_SIZE);
+codec.encodeClock(clock, new DataView(buffer.buffer), 0, true);
+assert.deepEqual(Array.from(buffer), {golden});
+assert.deepEqual(codec.decodeClock(new DataView(Uint8Array.from({golden}).buffer), 0, true), clock);
+assert.throws(() => codec.decodeClock(new DataView(Uint8Array.from({invalid}).buffer), 0, true), RangeError);
+"#,
+        golden = js_array(&golden),
+        invalid = js_array(&invalid),
+    );
+
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("typescript_bcd");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("codec.mjs"), module).unwrap();
+    fs::write(dir.join("test.mjs"), script).unwrap();
+
+    let output = match Command::new("node").arg(dir.join("test.mjs")).output() {
+        Ok(output) => output,
+        Err(e) => {
+            eprintln!("Skipping TypeScript test, could not run node: {}", e);
+            return;
+        },
+    };
+    assert!(output.status.success(), "Running the generated codec failed: {}", String::from_utf8_lossy(&output.stderr));
+}
+
+#[test]
+fn floats() {
+    let module = Generator::new().javascript().add::<Halves>().generate().unwrap();
+
+    let golden = pack::<BigEndian, _>(&halves());
+    let script = format!(r#"import assert from 'node:assert/strict';
+import * as codec from './codec.mjs';
+
+const halves = {{ half: 1.00048828125, brain: -2.7, tiny: 1e-6, e4m3: 0.3, e5m2: -1500 }};
+const buffer = new Uint8Array(codec.HALVES_SIZE);
+codec.encodeHalves(halves, new DataView(buffer.buffer), 0, false);
+assert.deepEqual(Array.from(buffer), {golden});
+assert.deepEqual(codec.decodeHalves(new DataView(Uint8Array.from({golden}).buffer), 0, false),
+    {{ half: 1, brain: -2.703125, tiny: 17 / 2 ** 24, e4m3: 0.3125, e5m2: -1536 }});
+"#,
+        golden = js_array(&golden),
+    );
+
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("typescript_floats");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("codec.mjs"), module).unwrap();
+    fs::write(dir.join("test.mjs"), script).unwrap();
+
+    let output = match Command::new("node").arg(dir.join("test.mjs")).output() {
+        Ok(output) => output,
+        Err(e) => {
+            eprintln!("Skipping TypeScript test, could not run node: {}", e);
+            return;
+        },
+    };
+    assert!(output.status.success(), "Running the generated codec failed: {}", String::from_utf8_lossy(&output.stderr));
+}
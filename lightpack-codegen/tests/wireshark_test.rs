@@ -0,0 +1,80 @@
+use lightpack::{Schema, Size, schema::Endianness};
+use lightpack_codegen::{Error, wireshark::Generator};
+
+mod common;
+
+use common::{Climate, Clock, Halves, Mode, Registers, SensorFrame, assert_snapshot};
+
+#[test]
+fn snapshot() {
+    let dissector = Generator::new::<SensorFrame>("sensor")
+        .description("Sensor Protocol")
+        .udp_port(5000)
+        .tcp_port(5001)
+        .generate()
+        .unwrap();
+
+    assert_snapshot("sensor.lua", &dissector);
+}
+
+#[test]
+fn byte_order() {
+    let dissector = Generator::new::<SensorFrame>("sensor").byte_order(Endianness::Little).generate().unwrap();
+
+    assert!(dissector.contains("  tree:add_le(fields[\"seq\"], tvb(offset, 4))\n"));
+    assert!(dissector.contains("    tree:add(fields[\"stamp\"], tvb(offset + 40, 8))\n"));
+    assert!(dissector.contains("  tree:add_le(fields[\"raw\"], tvb(offset + 27, 8))\n"));
+}
+
+#[test]
+fn enum_messages() {
+    let dissector = Generator::new::<Mode>("mode").generate().unwrap();
+
+    assert!(dissector.contains("  [\"mode\"] = ProtoField.int16(\"mode.mode\", \"Mode\", base.DEC, mode_values),\n"));
+    assert!(dissector.contains("local MESSAGE_SIZE = 2\n"));
+}
+
+#[test]
+fn unsupported_messages() {
+    #[derive(Size, Schema)]
+    struct Empty;
+
+    assert_eq!(Generator::new::<u8>("byte").generate(), Err(Error::Unnamed));
+    assert!(matches!(Generator::new::<Empty>("empty").generate(), Err(Error::Unsupported(_))));
+}
+
+#[test]
+fn scaled_fields() {
+    let dissector = Generator::new::<Climate>("climate").byte_order(Endianness::Little).generate().unwrap();
+
+    assert!(dissector.contains("  tree:add_le(fields[\"temperature\"], tvb(offset, 2)):append_text(string.format(\" (%g)\", tvb(offset, 2):le_int() * 0.01 - 40.0))\n"));
+    assert!(dissector.contains("  tree:add(fields[\"humidity\"], tvb(offset + 2, 1)):append_text(string.format(\" (%g)\", tvb(offset + 2, 1):uint() * 0.5))\n"));
+}
+
+#[test]
+fn odd_width_integers() {
+    let dissector = Generator::new::<Registers>("registers").generate().unwrap();
+
+    assert!(dissector.contains("  [\"status\"] = ProtoField.uint24(\"registers.status\", \"status\", base.DEC),\n"));
+    assert!(dissector.contains("  [\"offset\"] = ProtoField.int24(\"registers.offset\", \"offset\", base.DEC),\n"));
+    assert!(dissector.contains("  tree:add(fields[\"uptime\"], tvb(offset + 6, 5))\n"));
+    assert!(dissector.contains("  tree:add_le(fields[\"drift\"], tvb(offset + 17, 6))\n"));
+}
+
+#[test]
+fn bcd() {
+    let dissector = Generator::new::<Clock>("clock").generate().unwrap();
+
+    assert!(dissector.contains("  [\"counter\"] = ProtoField.uint32(\"clock.counter\", \"counter\", base.HEX),\n"));
+    assert!(dissector.contains("  [\"time.hours\"] = ProtoField.uint8(\"clock.time.hours\", \"hours\", base.HEX),\n"));
+}
+
+#[test]
+fn floats() {
+    let dissector = Generator::new::<Halves>("halves").generate().unwrap();
+
+    assert!(dissector.contains("local function decode_float(bits, exp_bits, mantissa_bits)\n"));
+    assert!(dissector.contains("  [\"half\"] = ProtoField.uint16(\"halves.half\", \"half\", base.HEX),\n"));
+    assert!(dissector.contains("  tree:add(fields[\"half\"], tvb(offset, 2)):append_text(string.format(\" (%g)\", decode_float(tvb(offset, 2):uint(), 5, 10)))\n"));
+    assert!(dissector.contains("decode_float(tvb(offset + 4, 2):le_uint(), 5, 10)"));
+}
@@ -3,25 +3,43 @@
 //! ```
 //! #[derive(Size, Pack, Unpack)] 
 //! ```
+//! 
+//! as well as `#[derive(Schema)]` for static descriptions of the wire format and
+//! `#[derive(View)]` for in-place access to packed buffers of fixed-size fields.
+//! With `#[lightpack(serde)]`, `#[derive(Schema)]` additionally generates serde
+//! impls that are consistent with the schema.
 
 mod pack;
+mod schema;
+mod serde;
 mod size;
 mod unpack;
 mod util;
+mod view;
 
 use proc_macro::TokenStream;
 
-#[proc_macro_derive(Size)]
+#[proc_macro_derive(Size, attributes(lightpack))]
 pub fn derive_size(input: TokenStream) -> TokenStream {
     size::derive_size(input.into()).into()
 }
 
-#[proc_macro_derive(Pack)]
+#[proc_macro_derive(Pack, attributes(lightpack))]
 pub fn derive_pack(input: TokenStream) -> TokenStream {
     pack::derive_pack(input.into()).into()
 }
 
-#[proc_macro_derive(Unpack)]
+#[proc_macro_derive(Schema, attributes(lightpack))]
+pub fn derive_schema(input: TokenStream) -> TokenStream {
+    schema::derive_schema(input.into()).into()
+}
+
+#[proc_macro_derive(Unpack, attributes(lightpack))]
 pub fn derive_unpack(input: TokenStream) -> TokenStream {
     unpack::derive_unpack(input.into()).into()
 }
+
+#[proc_macro_derive(View, attributes(lightpack))]
+pub fn derive_view(input: TokenStream) -> TokenStream {
+    view::derive_view(input.into()).into()
+}
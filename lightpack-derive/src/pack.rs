@@ -1,41 +1,65 @@
 use proc_macro2::TokenStream;
 use quote::quote;
-use syn::{DeriveInput, Data, Fields, Index, Ident, Type};
+use syn::{DeriveInput, Data, Field, Fields, Index, Ident, Type};
 
-use crate::util::{repr_type, type_to_turbofish};
+use crate::util::{conversion, encoded_size, repr_type};
 
 pub fn derive_pack(input: TokenStream) -> TokenStream {
     let input: DeriveInput = syn::parse2(input).expect("Could not parse derive input");
     let name: &Ident = &input.ident;
 
-    let pack_impl = match &input.data {
+    let (pack_impl, try_pack_impl) = match &input.data {
         Data::Struct(s) => {
-            let (fields, tys) = match &s.fields {
+            let fields: Vec<(TokenStream, &Field)> = match &s.fields {
                 Fields::Named(fs) => fs.named.iter()
                     .map(|f| {
                         let ident = f.ident.as_ref().expect("#[derive(Pack)] requires fields to be named");
-                        (quote! { self.#ident }, &f.ty)
+                        (quote! { self.#ident }, f)
                     })
-                    .unzip(),
+                    .collect(),
                 Fields::Unnamed(fs) => fs.unnamed.iter()
                     .enumerate()
                     .map(|(i, f)| {
                         // We need to do this, otherwise the quoter will append
                         // a number literal suffix, which isn't supported.
                         let index = Index::from(i);
-                        (quote! { self.#index }, &f.ty)
+                        (quote! { self.#index }, f)
                     })
-                    .unzip(),
-                Fields::Unit => (Vec::new(), Vec::new()),
+                    .collect(),
+                Fields::Unit => Vec::new(),
             };
 
-            let turbofish_tys = tys.into_iter()
-                .cloned()
-                .map(type_to_turbofish);
+            let sizes: Vec<TokenStream> = fields.iter()
+                .map(|(value, f)| encoded_size(f, value.clone()))
+                .collect();
 
-            quote! {
-                #(#fields.pack::<B>(buffer); let buffer = &mut buffer[<#turbofish_tys>::SIZE..];)*
-            }
+            // Fields with a conversion are packed as their encoded representation
+            let (packs, try_packs): (Vec<TokenStream>, Vec<TokenStream>) = fields.iter()
+                .map(|(value, f)| match conversion(f) {
+                    Some(conversion) => {
+                        let repr = conversion.encode(quote! { #value });
+                        (
+                            quote! { ::lightpack::Pack::pack::<B>(&#repr.expect("Value is out of range for its #[lightpack(as = ...)] type"), buffer) },
+                            quote! { ::lightpack::Pack::try_pack::<B>(&#repr?, buffer)? },
+                        )
+                    },
+                    None => (quote! { #value.pack::<B>(buffer) }, quote! { #value.try_pack::<B>(buffer)? }),
+                })
+                .unzip();
+
+            (
+                quote! {
+                    #(#packs; let buffer = &mut buffer[#sizes..];)*
+                },
+                quote! {
+                    let size = ::lightpack::Size::encoded_size(self);
+                    if buffer.len() < size {
+                        return Err(::lightpack::pack::Error::BufferTooSmall { actual: buffer.len(), expected: size });
+                    }
+                    #(#try_packs; let buffer = &mut buffer[#sizes..];)*
+                    Ok(())
+                },
+            )
         },
         Data::Enum(_) => {
             let repr_type: Type = repr_type(&input)
@@ -43,9 +67,14 @@ pub fn derive_pack(input: TokenStream) -> TokenStream {
             
             // TODO: Verify that enum also derives Copy?
 
-            quote! {
-                (*self as #repr_type).pack::<B>(buffer)
-            }
+            (
+                quote! {
+                    (*self as #repr_type).pack::<B>(buffer)
+                },
+                quote! {
+                    (*self as #repr_type).try_pack::<B>(buffer)
+                },
+            )
         },
         Data::Union(_) => unimplemented!("#[derive(Pack)] is not supported for unions yet!"),
     };
@@ -65,6 +94,10 @@ pub fn derive_pack(input: TokenStream) -> TokenStream {
             fn pack<B>(&self, buffer: &mut [u8]) where B: ::lightpack::byteorder::ByteOrder {
                 #pack_impl
             }
+
+            fn try_pack<B>(&self, buffer: &mut [u8]) -> ::lightpack::pack::Result<()> where B: ::lightpack::byteorder::ByteOrder {
+                #try_pack_impl
+            }
         }
     }
 }
@@ -0,0 +1,123 @@
+use proc_macro2::{TokenStream, Span};
+use quote::quote;
+use syn::{DeriveInput, Data, Expr, Fields, Ident, Type, ext::IdentExt, parse_quote};
+
+use crate::{serde::derive_serde, util::{conversion, encoded_type, repr_type, type_to_ident}};
+
+pub fn derive_schema(input: TokenStream) -> TokenStream {
+    let input: DeriveInput = syn::parse2(input).expect("Could not parse derive input");
+    let name: &Ident = &input.ident;
+    let name_str = name.to_string();
+
+    let mut hash_names = false;
+    let mut serde = false;
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("lightpack")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("hash_names") {
+                hash_names = true;
+                Ok(())
+            } else if meta.path.is_ident("serde") {
+                serde = true;
+                Ok(())
+            } else {
+                Err(meta.error("unsupported lightpack attribute"))
+            }
+        }).expect("Could not parse #[lightpack(...)] attribute");
+    }
+
+    let hash_impl = if hash_names {
+        quote! { const SCHEMA_HASH: u64 = <Self as ::lightpack::Schema>::SCHEMA.fingerprint(true); }
+    } else {
+        quote! {}
+    };
+
+    let serde_impl = if serde {
+        derive_serde(&input)
+    } else {
+        quote! {}
+    };
+
+    let schema_expr = match &input.data {
+        Data::Struct(s) => {
+            let (field_names, field_tys): (Vec<String>, Vec<Type>) = match &s.fields {
+                Fields::Named(fs) => fs.named.iter()
+                    .map(|f| (f.ident.as_ref().expect("#[derive(Schema)] requires fields to be named").unraw().to_string(), encoded_type(f)))
+                    .unzip(),
+                Fields::Unnamed(fs) => fs.unnamed.iter()
+                    .enumerate()
+                    .map(|(i, f)| (i.to_string(), encoded_type(f)))
+                    .unzip(),
+                Fields::Unit => (Vec::new(), Vec::new()),
+            };
+
+            let (scales, offsets): (Vec<Expr>, Vec<Expr>) = s.fields.iter()
+                .map(|f| match conversion(f) {
+                    Some(conversion) => (conversion.scale, conversion.offset),
+                    None => (parse_quote! { 1.0 }, parse_quote! { 0.0 }),
+                })
+                .unzip();
+
+            quote! {
+                ::lightpack::schema::Type::Struct {
+                    name: #name_str,
+                    fields: &[#(::lightpack::schema::Field {
+                        name: #field_names,
+                        ty: &<#field_tys as ::lightpack::Schema>::SCHEMA,
+                        scale: (#scales) as f64,
+                        offset: (#offsets) as f64,
+                    },)*],
+                }
+            }
+        },
+        Data::Enum(e) => {
+            let repr_type: Type = repr_type(&input)
+                .expect("#[derive(Schema)] currently only supports enums with a #[repr]");
+            let repr_primitive = type_to_ident(&repr_type)
+                .map(|i| Ident::new(&i.to_string().to_uppercase(), Span::call_site()))
+                .expect("#[derive(Schema)] currently only supports enums with a primitive #[repr]");
+
+            let variants: Vec<&Ident> = e.variants.iter().map(|v| &v.ident).collect();
+            let variant_names = variants.iter().map(|v| v.unraw().to_string());
+
+            quote! {
+                ::lightpack::schema::Type::Enum {
+                    name: #name_str,
+                    repr: ::lightpack::schema::Primitive::#repr_primitive,
+                    variants: &[#(::lightpack::schema::Variant {
+                        name: #variant_names,
+                        value: #name::#variants as i128,
+                    },)*],
+                }
+            }
+        },
+        Data::Union(_) => unimplemented!("#[derive(Schema)] is not supported for unions yet!"),
+    };
+
+    let impl_type_params = {
+        let types = input.generics.type_params();
+        quote! { <#(#types,)*> }
+    };
+
+    let where_clause = {
+        let types = input.generics.type_params();
+        quote! { #(#types: ::lightpack::Schema,)* }
+    };
+
+    quote! {
+        impl #impl_type_params ::lightpack::Schema for #name #impl_type_params where #where_clause {
+            const SCHEMA: ::lightpack::schema::Type = #schema_expr;
+            #hash_impl
+        }
+
+        #[allow(dead_code)]
+        impl #impl_type_params #name #impl_type_params where #where_clause {
+            /// The format string for Python's `struct` module describing the
+            /// big-endian encoding (e.g. `">hh"`), if it can be expressed as one.
+            pub const STRUCT_FORMAT: ::core::option::Option<&'static str> = ::lightpack::schema::StructFormat::as_str(
+                &::lightpack::schema::StructFormat::new(&<Self as ::lightpack::Schema>::SCHEMA, ::lightpack::schema::Endianness::Big)
+            );
+        }
+
+        #serde_impl
+    }
+}
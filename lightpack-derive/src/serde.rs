@@ -0,0 +1,363 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{DeriveInput, Data, Field, Fields, Ident, Index, Type, TypePath, ext::IdentExt};
+
+use crate::util::{conversion, repr_type};
+
+/// Generates `Serialize` and `Deserialize` impls that are consistent with the
+/// schema. Human-readable formats skip `Reserved` fields and see the logical
+/// value of converted fields and the names of enum variants, while binary
+/// formats (like `lightpack::serde`) see exactly the packed representation.
+pub fn derive_serde(input: &DeriveInput) -> TokenStream {
+    match &input.data {
+        Data::Struct(s) => derive_struct(input, &s.fields),
+        Data::Enum(e) => derive_enum(input, e.variants.iter().map(|v| &v.ident).collect()),
+        Data::Union(_) => unimplemented!("#[lightpack(serde)] is not supported for unions yet!"),
+    }
+}
+
+/// Whether the field only reserves space, i.e. is of type `Reserved<N>`.
+fn is_reserved(field: &Field) -> bool {
+    match &field.ty {
+        Type::Path(TypePath { qself: None, path }) => path.segments.last().is_some_and(|s| s.ident == "Reserved"),
+        _ => false,
+    }
+}
+
+fn derive_struct(input: &DeriveInput, fields: &Fields) -> TokenStream {
+    let name = &input.ident;
+    let name_str = name.to_string();
+    let params: Vec<&Ident> = input.generics.type_params().map(|p| &p.ident).collect();
+    let private = quote! { ::lightpack::serde::__private };
+    let serde = quote! { #private::serde };
+    let custom = |error: TokenStream| quote! { |e| <#error as #serde::ser::Error>::custom(::core::format_args!("{:?}", e)) };
+    let custom_de = |error: TokenStream| quote! { |e| <#error as #serde::de::Error>::custom(::core::format_args!("{:?}", e)) };
+
+    let fields: Vec<&Field> = fields.iter().collect();
+    let members: Vec<TokenStream> = fields.iter()
+        .enumerate()
+        .map(|(i, f)| match &f.ident {
+            Some(ident) => quote! { #ident },
+            None => {
+                let index = Index::from(i);
+                quote! { #index }
+            },
+        })
+        .collect();
+    let field_names: Vec<String> = fields.iter()
+        .enumerate()
+        .map(|(i, f)| f.ident.as_ref().map_or_else(|| i.to_string(), |ident| ident.unraw().to_string()))
+        .collect();
+    let vars: Vec<Ident> = (0..fields.len()).map(|i| format_ident!("__field{}", i)).collect();
+    let len = fields.len();
+    let reserved = fields.iter().filter(|f| is_reserved(f)).count();
+    let named = matches!(fields.first(), Some(Field { ident: Some(_), .. }));
+    let newtype = !named && len == 1;
+
+    // Serializes a field by passing a reference to its value to `emit`, or
+    // calls `skip` for Reserved fields in human-readable formats
+    let serialize_field = |f: &Field, member: &TokenStream, emit: &dyn Fn(TokenStream) -> TokenStream, skip: TokenStream| -> TokenStream {
+        let value = emit(quote! { &self.#member });
+        match conversion(f) {
+            Some(conversion) => {
+                let repr_ty = &conversion.repr;
+                let repr = conversion.encode(quote! { self.#member });
+                let map_err = custom(quote! { __S::Error });
+                let encoded = emit(quote! { &__repr });
+                quote! {
+                    if __human_readable {
+                        #value
+                    } else {
+                        let __repr: #repr_ty = #repr.map_err(#map_err)?;
+                        #encoded
+                    }
+                }
+            },
+            None if is_reserved(f) && !newtype => quote! {
+                if __human_readable {
+                    #skip
+                } else {
+                    #value
+                }
+            },
+            None => value,
+        }
+    };
+
+    let serialize_body = if len == 0 {
+        quote! { #serde::Serializer::serialize_unit_struct(__serializer, #name_str) }
+    } else if newtype {
+        let body = serialize_field(fields[0], &members[0], &|value| quote! {
+            return #serde::Serializer::serialize_newtype_struct(__serializer, #name_str, #value);
+        }, quote! {});
+        quote! { #body }
+    } else if named {
+        let statements = fields.iter().zip(&members).zip(&field_names).map(|((f, member), field_name)| {
+            serialize_field(f, member, &|value| quote! {
+                #serde::ser::SerializeStruct::serialize_field(&mut __state, #field_name, #value)?;
+            }, quote! {
+                #serde::ser::SerializeStruct::skip_field(&mut __state, #field_name)?;
+            })
+        });
+        quote! {
+            let mut __state = #serde::Serializer::serialize_struct(__serializer, #name_str, if __human_readable { #len - #reserved } else { #len })?;
+            #(#statements)*
+            #serde::ser::SerializeStruct::end(__state)
+        }
+    } else {
+        let statements = fields.iter().zip(&members).map(|(f, member)| {
+            serialize_field(f, member, &|value| quote! {
+                #serde::ser::SerializeTupleStruct::serialize_field(&mut __state, #value)?;
+            }, quote! {})
+        });
+        quote! {
+            let mut __state = #serde::Serializer::serialize_tuple_struct(__serializer, #name_str, if __human_readable { #len - #reserved } else { #len })?;
+            #(#statements)*
+            #serde::ser::SerializeTupleStruct::end(__state)
+        }
+    };
+
+    // Deserializes a field's value with `next::<T>()`, which yields a `Result<T, error>`
+    let deserialize_field = |f: &Field, next: &dyn Fn(&Type) -> TokenStream, error: TokenStream| -> TokenStream {
+        let ty = &f.ty;
+        let value = next(ty);
+        match conversion(f) {
+            Some(conversion) => {
+                let repr_ty = &conversion.repr;
+                let repr = next(repr_ty);
+                let decoded = conversion.decode(quote! { __repr });
+                let map_err = custom_de(error);
+                quote! {
+                    if __human_readable {
+                        #value
+                    } else {
+                        let __repr: #repr_ty = #repr?;
+                        #decoded.map_err(#map_err)
+                    }
+                }
+            },
+            None => value,
+        }
+    };
+
+    let seq_fields = fields.iter().enumerate().map(|(i, f)| {
+        let var = &vars[i];
+        let ty = &f.ty;
+        let value = deserialize_field(f, &|ty| quote! {
+            match #serde::de::SeqAccess::next_element::<#ty>(&mut __seq) {
+                Ok(Some(__value)) => Ok(__value),
+                Ok(None) => Err(#serde::de::Error::invalid_length(#i, &self)),
+                Err(e) => Err(e),
+            }
+        }, quote! { __A::Error });
+        if is_reserved(f) && !newtype {
+            quote! { let #var: #ty = if __human_readable { ::core::default::Default::default() } else { (#value)? }; }
+        } else {
+            quote! { let #var: #ty = (#value)?; }
+        }
+    });
+
+    let map_fields: Vec<TokenStream> = fields.iter().enumerate().map(|(i, f)| {
+        let var = &vars[i];
+        let field_name = &field_names[i];
+        let value = deserialize_field(f, &|ty| quote! {
+            #serde::de::MapAccess::next_value::<#ty>(&mut __map)
+        }, quote! { __A::Error });
+        quote! {
+            Some(#i) => {
+                if #var.is_some() {
+                    return Err(<__A::Error as #serde::de::Error>::duplicate_field(#field_name));
+                }
+                #var = Some((#value)?);
+            }
+        }
+    }).collect();
+    let map_results = fields.iter().enumerate().map(|(i, f)| {
+        let var = &vars[i];
+        let field_name = &field_names[i];
+        if is_reserved(f) {
+            quote! { let #var = #var.unwrap_or_default(); }
+        } else {
+            quote! {
+                let #var = match #var {
+                    Some(__value) => __value,
+                    None => return Err(<__A::Error as #serde::de::Error>::missing_field(#field_name)),
+                };
+            }
+        }
+    });
+    let tys = fields.iter().map(|f| &f.ty);
+
+    let construct = if len == 0 {
+        quote! { #name }
+    } else if named {
+        let idents = fields.iter().map(|f| &f.ident);
+        quote! { #name { #(#idents: #vars,)* } }
+    } else {
+        quote! { #name(#(#vars,)*) }
+    };
+
+    let visit_map = if named {
+        quote! {
+            #[allow(unused_variables)]
+            fn visit_map<__A>(self, mut __map: __A) -> ::core::result::Result<Self::Value, __A::Error> where __A: #serde::de::MapAccess<'de> {
+                let __human_readable = self.human_readable;
+                #(let mut #vars: ::core::option::Option<#tys> = None;)*
+                while let Some(__key) = #serde::de::MapAccess::next_key_seed(&mut __map, #private::FieldIndex(FIELDS))? {
+                    match __key {
+                        #(#map_fields)*
+                        _ => {
+                            #serde::de::MapAccess::next_value::<#serde::de::IgnoredAny>(&mut __map)?;
+                        },
+                    }
+                }
+                #(#map_results)*
+                Ok(#construct)
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    let visit_other = if len == 0 {
+        quote! {
+            fn visit_unit<__E>(self) -> ::core::result::Result<Self::Value, __E> where __E: #serde::de::Error {
+                Ok(#name)
+            }
+        }
+    } else if newtype {
+        let ty = &fields[0].ty;
+        let value = deserialize_field(fields[0], &|ty| quote! {
+            <#ty as #serde::Deserialize>::deserialize(__deserializer)
+        }, quote! { __D::Error });
+        quote! {
+            #[allow(unused_variables)]
+            fn visit_newtype_struct<__D>(self, __deserializer: __D) -> ::core::result::Result<Self::Value, __D::Error> where __D: #serde::Deserializer<'de> {
+                let __human_readable = self.human_readable;
+                let __field0: #ty = (#value)?;
+                Ok(#name(__field0))
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    let deserialize_call = if len == 0 {
+        quote! { #serde::Deserializer::deserialize_unit_struct(__deserializer, #name_str, __visitor) }
+    } else if newtype {
+        quote! { #serde::Deserializer::deserialize_newtype_struct(__deserializer, #name_str, __visitor) }
+    } else if named {
+        quote! { #serde::Deserializer::deserialize_struct(__deserializer, #name_str, FIELDS, __visitor) }
+    } else {
+        quote! { #serde::Deserializer::deserialize_tuple_struct(__deserializer, #name_str, if __human_readable { #len - #reserved } else { #len }, __visitor) }
+    };
+
+    quote! {
+        impl<#(#params,)*> #serde::Serialize for #name<#(#params,)*> where #(#params: #serde::Serialize,)* {
+            #[allow(unused_variables)]
+            fn serialize<__S>(&self, __serializer: __S) -> ::core::result::Result<__S::Ok, __S::Error> where __S: #serde::Serializer {
+                let __human_readable = #serde::Serializer::is_human_readable(&__serializer);
+                #serialize_body
+            }
+        }
+
+        impl<'de, #(#params,)*> #serde::Deserialize<'de> for #name<#(#params,)*> where #(#params: #serde::Deserialize<'de>,)* {
+            fn deserialize<__D>(__deserializer: __D) -> ::core::result::Result<Self, __D::Error> where __D: #serde::Deserializer<'de> {
+                #[allow(dead_code)]
+                const FIELDS: &[&str] = &[#(#field_names,)*];
+
+                struct __Visitor<#(#params,)*> {
+                    human_readable: bool,
+                    marker: ::core::marker::PhantomData<fn() -> #name<#(#params,)*>>,
+                }
+
+                impl<'de, #(#params,)*> #serde::de::Visitor<'de> for __Visitor<#(#params,)*> where #(#params: #serde::Deserialize<'de>,)* {
+                    type Value = #name<#(#params,)*>;
+
+                    fn expecting(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                        f.write_str(::core::concat!("struct ", #name_str))
+                    }
+
+                    #[allow(unused_mut, unused_variables)]
+                    fn visit_seq<__A>(self, mut __seq: __A) -> ::core::result::Result<Self::Value, __A::Error> where __A: #serde::de::SeqAccess<'de> {
+                        let __human_readable = self.human_readable;
+                        #(#seq_fields)*
+                        Ok(#construct)
+                    }
+
+                    #visit_map
+
+                    #visit_other
+                }
+
+                let __human_readable = #serde::Deserializer::is_human_readable(&__deserializer);
+                let __visitor = __Visitor { human_readable: __human_readable, marker: ::core::marker::PhantomData };
+                #deserialize_call
+            }
+        }
+    }
+}
+
+fn derive_enum(input: &DeriveInput, variants: Vec<&Ident>) -> TokenStream {
+    let name = &input.ident;
+    let name_str = name.to_string();
+    let repr: Type = repr_type(input).expect("#[lightpack(serde)] requires enums to have a #[repr]");
+    let private = quote! { ::lightpack::serde::__private };
+    let serde = quote! { #private::serde };
+
+    let variant_names: Vec<String> = variants.iter().map(|v| v.unraw().to_string()).collect();
+    let indices: Vec<u32> = (0..variants.len() as u32).collect();
+    let positions: Vec<usize> = (0..variants.len()).collect();
+
+    quote! {
+        impl #serde::Serialize for #name {
+            fn serialize<__S>(&self, __serializer: __S) -> ::core::result::Result<__S::Ok, __S::Error> where __S: #serde::Serializer {
+                if #serde::Serializer::is_human_readable(&__serializer) {
+                    match self {
+                        #(#name::#variants => #serde::Serializer::serialize_unit_variant(__serializer, #name_str, #indices, #variant_names),)*
+                    }
+                } else {
+                    let __value: #repr = match self {
+                        #(#name::#variants => #name::#variants as #repr,)*
+                    };
+                    #serde::Serialize::serialize(&__value, __serializer)
+                }
+            }
+        }
+
+        impl<'de> #serde::Deserialize<'de> for #name {
+            fn deserialize<__D>(__deserializer: __D) -> ::core::result::Result<Self, __D::Error> where __D: #serde::Deserializer<'de> {
+                const VARIANTS: &[&str] = &[#(#variant_names,)*];
+
+                struct __Visitor;
+
+                impl<'de> #serde::de::Visitor<'de> for __Visitor {
+                    type Value = #name;
+
+                    fn expecting(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                        f.write_str(::core::concat!("enum ", #name_str))
+                    }
+
+                    fn visit_enum<__A>(self, __data: __A) -> ::core::result::Result<Self::Value, __A::Error> where __A: #serde::de::EnumAccess<'de> {
+                        let (__index, __variant) = #serde::de::EnumAccess::variant_seed(__data, #private::FieldIndex(VARIANTS))?;
+                        #serde::de::VariantAccess::unit_variant(__variant)?;
+                        match __index {
+                            #(Some(#positions) => Ok(#name::#variants),)*
+                            _ => Err(<__A::Error as #serde::de::Error>::custom(::core::concat!("unknown variant of enum ", #name_str))),
+                        }
+                    }
+                }
+
+                if #serde::Deserializer::is_human_readable(&__deserializer) {
+                    #serde::Deserializer::deserialize_enum(__deserializer, #name_str, VARIANTS, __Visitor)
+                } else {
+                    let __value = <#repr as #serde::Deserialize>::deserialize(__deserializer)?;
+                    #(if __value == #name::#variants as #repr {
+                        return Ok(#name::#variants);
+                    })*
+                    Err(<__D::Error as #serde::de::Error>::custom(::core::format_args!("invalid value {} for enum {}", __value, #name_str)))
+                }
+            }
+        }
+    }
+}
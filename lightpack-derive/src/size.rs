@@ -1,27 +1,111 @@
-use proc_macro2::TokenStream;
-use syn::{DeriveInput, Ident, Type, Data, Fields};
+use proc_macro2::{TokenStream, Span};
+use syn::{DeriveInput, Ident, Index, Type, Data, Fields, ext::IdentExt};
 use quote::quote;
 
-use crate::util::{repr_type, type_to_turbofish};
+use crate::util::{encoded_size, encoded_type, fixed_size, repr_type, type_name, type_to_turbofish};
 
 pub fn derive_size(input: TokenStream) -> TokenStream {
     let input: DeriveInput = syn::parse2(input).expect("Could not parse derive input");
     let name: &Ident = &input.ident;
 
+    let impl_type_params = {
+        let types = input.generics.type_params();
+        quote! { <#(#types,)*> }
+    };
+
+    let where_clause = {
+        let types = input.generics.type_params();
+        quote! { #(#types: ::lightpack::Size,)* }
+    };
+
+    let mut layout_impl = quote! {};
+    let mut encoded_size_impl = quote! {};
+    let mut fixed_size_impl = quote! {};
+
     let size_expr = match input.data {
         Data::Struct(s) => {
-            let field_tys: Vec<Type> = match s.fields {
-                Fields::Named(fs) => fs.named.into_iter()
-                    .map(|f| f.ty)
-                    .collect(),
-                Fields::Unnamed(fs) => fs.unnamed.into_iter()
-                    .map(|f| f.ty)
-                    .collect(),
-                Fields::Unit => Vec::new(),
+            // Fields are packed back to back with their actual encoded size
+            let encoded_sizes = s.fields.iter().enumerate().map(|(i, f)| {
+                let value = match &f.ident {
+                    Some(ident) => quote! { self.#ident },
+                    None => {
+                        let index = Index::from(i);
+                        quote! { self.#index }
+                    },
+                };
+                encoded_size(f, value)
+            });
+            encoded_size_impl = quote! {
+                fn encoded_size(&self) -> usize {
+                    0 #(+ #encoded_sizes)*
+                }
+            };
+
+            let fixed_sizes: Vec<TokenStream> = s.fields.iter().map(fixed_size).collect();
+            fixed_size_impl = quote! {
+                const FIXED_SIZE: bool = true #(&& #fixed_sizes)*;
+            };
+
+            let (field_names, field_tys): (Vec<String>, Vec<Type>) = match s.fields {
+                Fields::Named(fs) => fs.named.iter()
+                    .map(|f| (f.ident.as_ref().expect("#[derive(Size)] requires fields to be named").unraw().to_string(), encoded_type(f)))
+                    .unzip(),
+                Fields::Unnamed(fs) => fs.unnamed.iter()
+                    .enumerate()
+                    .map(|(i, f)| (i.to_string(), encoded_type(f)))
+                    .unzip(),
+                Fields::Unit => (Vec::new(), Vec::new()),
+            };
+
+            let type_names: Vec<String> = field_tys.iter().map(type_name).collect();
+            let turbofish_tys: Vec<Type> = field_tys.into_iter().map(type_to_turbofish).collect();
+
+            // The offset of each field is the sum of the sizes of the preceding fields,
+            // which only holds for all values if the fields have a fixed size. Using
+            // the constants with variable-size fields fails to compile.
+            let offset_consts: Vec<Ident> = field_names.iter()
+                .map(|n| Ident::new(&format!("OFFSET_{}", n.to_uppercase()), Span::call_site()))
+                .collect();
+            let offset_exprs = offset_consts.iter().enumerate().map(|(i, c)| {
+                let preceding = &turbofish_tys[..i];
+                let preceding_fixed = &fixed_sizes[..i];
+                let message = format!("`{}` requires the preceding fields to have a fixed size", c);
+                quote! {{
+                    ::core::assert!(true #(&& #preceding_fixed)*, #message);
+                    0 #(+ <#preceding>::SIZE)*
+                }}
+            });
+            let offset_docs = field_names.iter().map(|n| format!(
+                "The offset of the `{}` field in bytes. Only available if the preceding fields have a fixed size.",
+                n,
+            ));
+
+            layout_impl = quote! {
+                #[allow(dead_code)]
+                impl #impl_type_params #name #impl_type_params where #where_clause {
+                    #(
+                        #[doc = #offset_docs]
+                        pub const #offset_consts: usize = #offset_exprs;
+                    )*
+
+                    /// The offsets and sizes of the encoded fields. Only available if all
+                    /// fields have a fixed size. Type names are written as in the source,
+                    /// with generic parameters left unsubstituted.
+                    pub const LAYOUT: &'static [::lightpack::layout::FieldLayout] = {
+                        ::core::assert!(<Self as ::lightpack::Size>::FIXED_SIZE, "`LAYOUT` requires all fields to have a fixed size");
+                        &[
+                            #(::lightpack::layout::FieldLayout {
+                                name: #field_names,
+                                offset: Self::#offset_consts,
+                                size: <#turbofish_tys>::SIZE,
+                                type_name: #type_names,
+                            },)*
+                        ]
+                    };
+                }
             };
 
-            field_tys.into_iter()
-                .map(type_to_turbofish)
+            turbofish_tys.iter()
                 .map(|t| quote! { <#t>::SIZE })
                 .reduce(|e1, e2| quote! { #e1 + #e2 })
                 .unwrap_or_else(|| quote! { 0 })
@@ -37,19 +121,14 @@ pub fn derive_size(input: TokenStream) -> TokenStream {
         Data::Union(_) => unimplemented!("#[derive(Size)] is not supported for unions yet!"),
     };
 
-    let impl_type_params = {
-        let types = input.generics.type_params();
-        quote! { <#(#types,)*> }
-    };
-
-    let where_clause = {
-        let types = input.generics.type_params();
-        quote! { #(#types: ::lightpack::Size,)* }
-    };
-
     quote! {
         impl #impl_type_params ::lightpack::Size for #name #impl_type_params where #where_clause {
             const SIZE: usize = #size_expr;
+            #fixed_size_impl
+
+            #encoded_size_impl
         }
+
+        #layout_impl
     }
 }
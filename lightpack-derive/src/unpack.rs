@@ -1,33 +1,62 @@
 use proc_macro2::{TokenStream, Span};
 use quote::quote;
-use syn::{DeriveInput, Data, Fields, Type, Ident, Expr};
+use syn::{DeriveInput, Data, Field, Fields, Type, Ident, Expr};
 
-use crate::util::{repr_type, type_to_ident, type_to_turbofish};
+use crate::util::{conversion, encoded_size, encoded_type, repr_type, type_to_ident, type_to_turbofish};
 
 pub fn derive_unpack(input: TokenStream) -> TokenStream {
     let input: DeriveInput = syn::parse2(input).expect("Could not parse derive input");
     let name: &Ident = &input.ident;
 
+    let mut unpack_safely_impl = quote! {};
+
     let unpack_impl = match &input.data {
-        Data::Struct(s) => match &s.fields {
-            Fields::Named(fs) => {
-                let (fields, turbofish_tys): (Vec<&Ident>, Vec<Type>) = fs.named.iter()
-                    .map(|f| (f.ident.as_ref().expect("#[derive(Unpack)] requires fields to be named"), type_to_turbofish(f.ty.clone())))
-                    .unzip();
-                quote! {
-                    #(let #fields = <#turbofish_tys>::unpack::<B>(buffer)?; let buffer = &buffer[<#turbofish_tys>::SIZE..];)*
-                    Ok(#name { #(#fields),* })
-                }
-            },
-            Fields::Unnamed(fs) => {
-                let vars: Vec<Ident> = (0..fs.unnamed.len()).map(|i| Ident::new(&format!("x{}", i), Span::call_site())).collect();
-                let turbofish_tys: Vec<Type> = fs.unnamed.iter().map(|f| type_to_turbofish(f.ty.clone())).collect();
-                quote! {
-                    #(let #vars = <#turbofish_tys>::unpack::<B>(buffer)?; let buffer = &buffer[<#turbofish_tys>::SIZE..];)*
-                    Ok(#name(#(#vars),*))
+        Data::Struct(s) => {
+            let fields: Vec<&Field> = s.fields.iter().collect();
+            let vars: Vec<Ident> = match &s.fields {
+                Fields::Named(fs) => fs.named.iter()
+                    .map(|f| f.ident.clone().expect("#[derive(Unpack)] requires fields to be named"))
+                    .collect(),
+                _ => (0..fields.len()).map(|i| Ident::new(&format!("x{}", i), Span::call_site())).collect(),
+            };
+            let turbofish_tys: Vec<Type> = fields.iter().map(|f| type_to_turbofish(encoded_type(f))).collect();
+
+            // Fields with a conversion are unpacked from their encoded representation
+            let unpacks = |unpack: TokenStream| -> Vec<TokenStream> {
+                fields.iter().zip(&turbofish_tys).map(|(f, ty)| match conversion(f) {
+                    Some(conversion) => {
+                        let value = conversion.decode(quote! { <#ty>::#unpack? });
+                        quote! { #value? }
+                    },
+                    None => quote! { <#ty>::#unpack? },
+                }).collect()
+            };
+            let unpacks_in_place = unpacks(quote! { unpack::<B>(buffer) });
+            let safe_unpacks = unpacks(quote! { unpack_safely::<B>(&buffer[__offset..]).map_err(|e| e.at_offset(__offset)) });
+            let sizes: Vec<TokenStream> = fields.iter().zip(&vars)
+                .map(|(f, var)| encoded_size(f, quote! { #var }))
+                .collect();
+
+            let constructor = match &s.fields {
+                Fields::Named(_) => quote! { #name { #(#vars),* } },
+                Fields::Unnamed(_) => quote! { #name(#(#vars),*) },
+                Fields::Unit => quote! { #name },
+            };
+
+            // Each field checks the buffer size itself, since the encoded size
+            // of the struct is only known after unpacking
+            unpack_safely_impl = quote! {
+                fn unpack_safely<B>(buffer: &[u8]) -> ::lightpack::unpack::Result<Self> where B: ::lightpack::byteorder::ByteOrder {
+                    let __offset = 0;
+                    #(let #vars = #safe_unpacks; let __offset = __offset + #sizes;)*
+                    Ok(#constructor)
                 }
-            },
-            Fields::Unit => quote! { Ok(#name) },
+            };
+
+            quote! {
+                #(let #vars = #unpacks_in_place; let buffer = &buffer[#sizes..];)*
+                Ok(#constructor)
+            }
         },
         Data::Enum(ref e) => {
             let repr_type: Type = repr_type(&input)
@@ -71,6 +100,8 @@ pub fn derive_unpack(input: TokenStream) -> TokenStream {
             fn unpack<B>(buffer: &[u8]) -> ::lightpack::unpack::Result<Self> where B: ::lightpack::byteorder::ByteOrder {
                 #unpack_impl
             }
+
+            #unpack_safely_impl
         }
     }
 }
@@ -1,5 +1,6 @@
-use proc_macro2::{Ident, Span};
-use syn::{Type, DeriveInput, TypePath, Path, PathArguments, AngleBracketedGenericArguments, Token, punctuated::Punctuated, PathSegment};
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::{quote, ToTokens};
+use syn::{Type, DeriveInput, Expr, Field, TypePath, Path, PathArguments, AngleBracketedGenericArguments, Token, parse_quote, punctuated::Punctuated, PathSegment};
 
 /// Fetches the `#[repr(...)]` type.
 pub fn repr_type(input: &DeriveInput) -> Option<Type> {
@@ -53,3 +54,105 @@ fn path_args_to_turbofish(args: PathArguments) -> PathArguments {
         _ => args,
     }
 }
+
+/// Renders a type as it would be written in source code.
+pub fn type_name(t: &Type) -> String {
+    t.to_token_stream()
+        .to_string()
+        .replace(" :: ", "::")
+        .replace(":: ", "::")
+        .replace(" < ", "<")
+        .replace("< ", "<")
+        .replace(" >", ">")
+        .replace(" ,", ",")
+        .replace("[ ", "[")
+        .replace(" ]", "]")
+        .replace(" ;", ";")
+        .replace("( ", "(")
+        .replace(" )", ")")
+        .replace("& ", "&")
+}
+
+/// A field's `#[lightpack(as = ..., scale = ..., offset = ...)]` conversion.
+pub struct Conversion {
+    /// The encoded type.
+    pub repr: Type,
+    pub scale: Expr,
+    pub offset: Expr,
+}
+
+impl Conversion {
+    /// Converts the field value (an expression) to its encoded representation,
+    /// yielding a `::lightpack::pack::Result`.
+    pub fn encode(&self, value: TokenStream) -> TokenStream {
+        let Conversion { repr, scale, offset } = self;
+        quote! { ::lightpack::convert::to_repr::<_, #repr>(#value, (#scale) as f64, (#offset) as f64) }
+    }
+
+    /// Converts the encoded representation (an expression) back to the field
+    /// value, yielding a `::lightpack::unpack::Result`.
+    pub fn decode(&self, repr_value: TokenStream) -> TokenStream {
+        let Conversion { repr, scale, offset } = self;
+        quote! { ::lightpack::convert::from_repr::<#repr, _>(#repr_value, (#scale) as f64, (#offset) as f64) }
+    }
+}
+
+/// Parses a field's conversion attribute, if any.
+pub fn conversion(field: &Field) -> Option<Conversion> {
+    let mut repr: Option<Type> = None;
+    let mut scale: Option<Expr> = None;
+    let mut offset: Option<Expr> = None;
+    for attr in field.attrs.iter().filter(|a| a.path().is_ident("lightpack")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("as") {
+                repr = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("scale") {
+                scale = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("offset") {
+                offset = Some(meta.value()?.parse()?);
+            } else {
+                return Err(meta.error("unsupported lightpack field attribute"));
+            }
+            Ok(())
+        }).expect("Could not parse #[lightpack(...)] field attribute");
+    }
+
+    match repr {
+        Some(repr) => Some(Conversion {
+            repr,
+            scale: scale.unwrap_or_else(|| parse_quote! { 1.0 }),
+            offset: offset.unwrap_or_else(|| parse_quote! { 0.0 }),
+        }),
+        None if scale.is_some() || offset.is_some() => panic!("#[lightpack(scale = ..., offset = ...)] requires the encoded type, e.g. `as = i16`"),
+        None => None,
+    }
+}
+
+/// The type a field is encoded as, i.e. the conversion's type or the field's own type.
+pub fn encoded_type(field: &Field) -> Type {
+    conversion(field).map_or_else(|| field.ty.clone(), |c| c.repr)
+}
+
+/// The encoded size of a field with the given value (an expression). Converted
+/// fields always occupy the size of their encoded type.
+pub fn encoded_size(field: &Field, value: TokenStream) -> TokenStream {
+    match conversion(field) {
+        Some(conversion) => {
+            let repr = type_to_turbofish(conversion.repr);
+            quote! { <#repr>::SIZE }
+        },
+        None => quote! { ::lightpack::Size::encoded_size(&#value) },
+    }
+}
+
+/// Whether a field is always encoded with the same size (a `bool` expression).
+/// Converted fields always occupy the size of their encoded type.
+pub fn fixed_size(field: &Field) -> TokenStream {
+    match conversion(field) {
+        Some(_) => quote! { true },
+        None => {
+            let ty = &field.ty;
+            quote! { <#ty as ::lightpack::Size>::FIXED_SIZE }
+        },
+    }
+}
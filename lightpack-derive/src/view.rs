@@ -0,0 +1,153 @@
+use proc_macro2::{TokenStream, Span};
+use quote::quote;
+use syn::{DeriveInput, Data, Field, Fields, Ident, Type};
+
+use crate::util::{conversion, encoded_type, fixed_size};
+
+pub fn derive_view(input: TokenStream) -> TokenStream {
+    let input: DeriveInput = syn::parse2(input).expect("Could not parse derive input");
+    let name: &Ident = &input.ident;
+    let vis = &input.vis;
+    let view_name = Ident::new(&format!("{}View", name), Span::call_site());
+    let view_mut_name = Ident::new(&format!("{}ViewMut", name), Span::call_site());
+
+    let members: Vec<&Field> = match &input.data {
+        Data::Struct(s) => match &s.fields {
+            Fields::Named(fs) => fs.named.iter().collect(),
+            Fields::Unnamed(_) => unimplemented!("#[derive(View)] is not supported for tuple structs yet!"),
+            Fields::Unit => Vec::new(),
+        },
+        Data::Enum(_) => unimplemented!("#[derive(View)] is not supported for enums yet!"),
+        Data::Union(_) => unimplemented!("#[derive(View)] is not supported for unions yet!"),
+    };
+    let fields: Vec<&Ident> = members.iter()
+        .map(|f| f.ident.as_ref().expect("#[derive(View)] requires fields to be named"))
+        .collect();
+    let tys: Vec<&Type> = members.iter().map(|f| &f.ty).collect();
+    let encoded_tys: Vec<Type> = members.iter().map(|f| encoded_type(f)).collect();
+
+    // The offset of each field is the sum of the sizes of the preceding fields,
+    // so views are rejected at compile time unless all fields have a fixed size.
+    let fixed_sizes: Vec<TokenStream> = members.iter().map(|f| fixed_size(f)).collect();
+    let fixed_check = quote! {
+        /// Rejects fields whose encoded size depends on the value.
+        const FIXED_SIZE: () = ::core::assert!(true #(&& #fixed_sizes)*, "#[derive(View)] requires all fields to have a fixed size");
+    };
+    let offsets: Vec<TokenStream> = (0..fields.len())
+        .map(|i| {
+            let preceding = &encoded_tys[..i];
+            quote! { 0 #(+ <#preceding as ::lightpack::Size>::SIZE)* }
+        })
+        .collect();
+
+    // Fields with a conversion are accessed via their encoded representation
+    let mut getter_exprs: Vec<TokenStream> = Vec::new();
+    let mut setter_stmts: Vec<TokenStream> = Vec::new();
+    let mut try_setter_exprs: Vec<TokenStream> = Vec::new();
+    for ((f, offset), encoded) in members.iter().zip(&offsets).zip(&encoded_tys) {
+        let ty = &f.ty;
+        let unpack = quote! { <#encoded as ::lightpack::Unpack>::unpack::<B>(&self.buffer[#offset..]) };
+        match conversion(f) {
+            Some(conversion) => {
+                let value = conversion.decode(quote! { #unpack? });
+                let repr = conversion.encode(quote! { value });
+                getter_exprs.push(value);
+                setter_stmts.push(quote! { ::lightpack::Pack::pack::<B>(&#repr.expect("Value is out of range for its #[lightpack(as = ...)] type"), &mut self.buffer[#offset..]); });
+                try_setter_exprs.push(quote! { ::lightpack::Pack::try_pack::<B>(&#repr?, &mut self.buffer[#offset..]) });
+            },
+            None => {
+                getter_exprs.push(quote! { <#ty as ::lightpack::Unpack>::unpack::<B>(&self.buffer[#offset..]) });
+                setter_stmts.push(quote! { ::lightpack::Pack::pack::<B>(&value, &mut self.buffer[#offset..]); });
+                try_setter_exprs.push(quote! { ::lightpack::Pack::try_pack::<B>(&value, &mut self.buffer[#offset..]) });
+            },
+        }
+    }
+
+    let setters: Vec<Ident> = fields.iter()
+        .map(|f| Ident::new(&format!("set_{}", f), Span::call_site()))
+        .collect();
+    let try_setters: Vec<Ident> = fields.iter()
+        .map(|f| Ident::new(&format!("try_set_{}", f), Span::call_site()))
+        .collect();
+
+    let type_params: Vec<&Ident> = input.generics.type_params().map(|p| &p.ident).collect();
+
+    let getter_docs = fields.iter().map(|f| format!("Decodes the `{}` field.", f));
+    let getter_docs_mut = getter_docs.clone();
+    let setter_docs = fields.iter().map(|f| format!("Encodes the `{}` field in place. Panics if the value is out of range.", f));
+    let try_setter_docs = fields.iter().map(|f| format!("Encodes the `{}` field in place, or returns an error if the value is out of range.", f));
+    let view_doc = format!("A read-only view of a packed [`{}`].", name);
+    let view_mut_doc = format!("A mutable view of a packed [`{}`] that can patch individual fields in place.", name);
+
+    quote! {
+        #[doc = #view_doc]
+        #vis struct #view_name<'a, #(#type_params,)* B> {
+            buffer: &'a [u8],
+            _marker: ::core::marker::PhantomData<(#(#type_params,)* B)>,
+        }
+
+        #[allow(dead_code)]
+        impl<'a, #(#type_params,)* B> #view_name<'a, #(#type_params,)* B>
+        where
+            #(#type_params: ::lightpack::Pack + ::lightpack::Unpack,)*
+            B: ::lightpack::byteorder::ByteOrder,
+        {
+            #fixed_check
+
+            /// Creates a view over the given buffer.
+            /// Accessors panic if the buffer is too small.
+            pub fn new(buffer: &'a [u8]) -> Self {
+                #[allow(clippy::let_unit_value)]
+                let () = Self::FIXED_SIZE;
+                Self { buffer, _marker: ::core::marker::PhantomData }
+            }
+
+            #(
+                #[doc = #getter_docs]
+                pub fn #fields(&self) -> ::lightpack::unpack::Result<#tys> {
+                    #getter_exprs
+                }
+            )*
+        }
+
+        #[doc = #view_mut_doc]
+        #vis struct #view_mut_name<'a, #(#type_params,)* B> {
+            buffer: &'a mut [u8],
+            _marker: ::core::marker::PhantomData<(#(#type_params,)* B)>,
+        }
+
+        #[allow(dead_code)]
+        impl<'a, #(#type_params,)* B> #view_mut_name<'a, #(#type_params,)* B>
+        where
+            #(#type_params: ::lightpack::Pack + ::lightpack::Unpack,)*
+            B: ::lightpack::byteorder::ByteOrder,
+        {
+            #fixed_check
+
+            /// Creates a mutable view over the given buffer.
+            /// Accessors panic if the buffer is too small.
+            pub fn new(buffer: &'a mut [u8]) -> Self {
+                #[allow(clippy::let_unit_value)]
+                let () = Self::FIXED_SIZE;
+                Self { buffer, _marker: ::core::marker::PhantomData }
+            }
+
+            #(
+                #[doc = #getter_docs_mut]
+                pub fn #fields(&self) -> ::lightpack::unpack::Result<#tys> {
+                    #getter_exprs
+                }
+
+                #[doc = #setter_docs]
+                pub fn #setters(&mut self, value: #tys) {
+                    #setter_stmts
+                }
+
+                #[doc = #try_setter_docs]
+                pub fn #try_setters(&mut self, value: #tys) -> ::lightpack::pack::Result<()> {
+                    #try_setter_exprs
+                }
+            )*
+        }
+    }
+}
@@ -0,0 +1,242 @@
+//! Binary-coded decimals.
+//!
+//! [`Bcd<T>`](Bcd) stores a decimal integer as packed BCD, i.e. one decimal
+//! digit per nibble with the most significant digit in the most significant
+//! nibble, and is encoded like the underlying integer `T` (`u8`, `u16` or
+//! `u32`). For example, `Bcd<u16>` encodes `1234` as `0x1234`. Nibbles
+//! greater than 9 are rejected on unpack with [`Error::InvalidBcd`].
+//!
+//! [`DateTime`] decodes seven time-keeping registers in the order seconds,
+//! minutes, hours, weekday, day, month and year, as used by real-time clock
+//! chips like the DS1307, DS1337 and DS3231 (starting at register `0x00`):
+//!
+//! ```ignore
+//! let now = DateTime::unpack::<BigEndian>(&registers)?;
+//! (now.hours, now.minutes)
+//! // => (13, 37)
+//! ```
+
+use core::fmt;
+
+use byteorder::ByteOrder;
+
+use crate::{Pack, Schema, Size, Unpack, pack, schema::{Field, Type}, unpack::{Error, Result}};
+
+/// A decimal integer encoded as packed BCD in a `T`.
+#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Clone, Copy, Default)]
+pub struct Bcd<T>(T);
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// Integers that can store a [`Bcd`].
+pub trait Digits: sealed::Sealed + Copy + Pack + Unpack {
+    /// The number of decimal digits that fit into the type.
+    const DIGITS: u32;
+
+    #[doc(hidden)]
+    fn into_u32(self) -> u32;
+
+    #[doc(hidden)]
+    fn from_u32(value: u32) -> Self;
+}
+
+macro_rules! impl_digits {
+    ($($t:ty),*) => {
+        $(
+            impl sealed::Sealed for $t {}
+
+            impl Digits for $t {
+                const DIGITS: u32 = 2 * <$t>::BITS / 8;
+
+                fn into_u32(self) -> u32 {
+                    self as u32
+                }
+
+                fn from_u32(value: u32) -> Self {
+                    value as $t
+                }
+            }
+        )*
+    };
+}
+
+impl_digits!(u8, u16, u32);
+
+/// Encodes a value as packed BCD (which has to fit into 8 digits).
+fn encode(mut value: u32) -> u32 {
+    let mut bits = 0;
+    let mut shift = 0;
+    while value != 0 {
+        bits |= (value % 10) << shift;
+        value /= 10;
+        shift += 4;
+    }
+    bits
+}
+
+/// Decodes packed BCD, or returns `None` if a nibble is greater than 9.
+pub(crate) fn decode(mut bits: u32) -> Option<u32> {
+    let mut value = 0;
+    let mut factor = 1;
+    while bits != 0 {
+        let digit = bits & 0xf;
+        if digit > 9 {
+            return None;
+        }
+        value += digit * factor;
+        bits >>= 4;
+        factor *= 10;
+    }
+    Some(value)
+}
+
+impl<T> Bcd<T> where T: Digits {
+    /// Creates a value, or returns `None` if it has too many digits for `T`.
+    pub fn new(value: T) -> Option<Self> {
+        (value.into_u32() < 10u32.saturating_pow(T::DIGITS)).then_some(Self(value))
+    }
+
+    /// The decimal value.
+    pub fn get(self) -> T {
+        self.0
+    }
+
+    /// Creates a value from its packed BCD bits, or returns `None` if a
+    /// nibble is greater than 9.
+    pub fn from_bits(bits: T) -> Option<Self> {
+        decode(bits.into_u32()).map(|value| Self(T::from_u32(value)))
+    }
+
+    /// The packed BCD bits.
+    pub fn to_bits(self) -> T {
+        T::from_u32(encode(self.0.into_u32()))
+    }
+}
+
+impl<T> fmt::Display for Bcd<T> where T: fmt::Display {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T> Size for Bcd<T> where T: Digits {
+    const SIZE: usize = T::SIZE;
+}
+
+impl<T> Schema for Bcd<T> where T: Digits + Schema {
+    const SCHEMA: Type = match T::SCHEMA {
+        Type::Primitive(p) => Type::Bcd(p),
+        _ => unreachable!(),
+    };
+}
+
+impl<T> Pack for Bcd<T> where T: Digits {
+    fn pack<B>(&self, buffer: &mut [u8]) where B: ByteOrder {
+        self.to_bits().pack::<B>(buffer)
+    }
+}
+
+impl<T> Unpack for Bcd<T> where T: Digits {
+    fn unpack<B>(buffer: &[u8]) -> Result<Self> where B: ByteOrder {
+        let bits = T::unpack::<B>(buffer)?;
+        Self::from_bits(bits).ok_or(Error::InvalidBcd(bits.into_u32()))
+    }
+}
+
+/// The date and time registers of a real-time clock: seconds, minutes,
+/// hours, weekday, day, month and year, one BCD byte each.
+///
+/// Unpacking ignores the oscillator flag in bit 7 of the seconds and the
+/// century flag in bit 7 of the month, and converts hours in 12-hour mode
+/// (bit 6 set, with bit 5 denoting PM) to 24-hour format. Packing writes the
+/// hours in 24-hour mode with all flags cleared, and panics (or fails with
+/// [`pack::Error::OutOfRange`] in [`Pack::try_pack`]) if a field has more
+/// than two digits.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
+pub struct DateTime {
+    pub seconds: u8,
+    pub minutes: u8,
+    pub hours: u8,
+    /// The day of the week, whose numbering is application-defined.
+    pub weekday: u8,
+    pub day: u8,
+    pub month: u8,
+    /// The year within the century.
+    pub year: u8,
+}
+
+impl DateTime {
+    /// The registers in order, or `None` if a field has more than two digits.
+    fn registers(&self) -> Option<[u8; 7]> {
+        let mut registers = [self.seconds, self.minutes, self.hours, self.weekday, self.day, self.month, self.year];
+        for register in registers.iter_mut() {
+            *register = Bcd::new(*register)?.to_bits();
+        }
+        Some(registers)
+    }
+}
+
+/// Decodes a BCD register.
+fn register(bits: u8) -> Result<u8> {
+    Ok(Bcd::from_bits(bits).ok_or(Error::InvalidBcd(bits.into()))?.get())
+}
+
+impl Size for DateTime {
+    const SIZE: usize = 7;
+}
+
+/// Describes the registers as written by [`Pack`], i.e. in 24-hour mode
+/// without flags.
+impl Schema for DateTime {
+    const SCHEMA: Type = Type::Struct {
+        name: "DateTime",
+        fields: &[
+            Field::new("seconds", &<Bcd<u8>>::SCHEMA),
+            Field::new("minutes", &<Bcd<u8>>::SCHEMA),
+            Field::new("hours", &<Bcd<u8>>::SCHEMA),
+            Field::new("weekday", &<Bcd<u8>>::SCHEMA),
+            Field::new("day", &<Bcd<u8>>::SCHEMA),
+            Field::new("month", &<Bcd<u8>>::SCHEMA),
+            Field::new("year", &<Bcd<u8>>::SCHEMA),
+        ],
+    };
+}
+
+impl Pack for DateTime {
+    fn pack<B>(&self, buffer: &mut [u8]) where B: ByteOrder {
+        let registers = self.registers().expect("DateTime field is out of range for BCD");
+        buffer[..Self::SIZE].copy_from_slice(&registers);
+    }
+
+    fn try_pack<B>(&self, buffer: &mut [u8]) -> pack::Result<()> where B: ByteOrder {
+        if buffer.len() < Self::SIZE {
+            return Err(pack::Error::BufferTooSmall { actual: buffer.len(), expected: Self::SIZE });
+        }
+        let registers = self.registers().ok_or(pack::Error::OutOfRange)?;
+        buffer[..Self::SIZE].copy_from_slice(&registers);
+        Ok(())
+    }
+}
+
+impl Unpack for DateTime {
+    fn unpack<B>(buffer: &[u8]) -> Result<Self> where B: ByteOrder {
+        let hours = if buffer[2] & 0x40 != 0 {
+            // 12-hour mode, where 12 AM is midnight and 12 PM is noon
+            let hours = register(buffer[2] & 0x1f)? % 12;
+            if buffer[2] & 0x20 != 0 { hours + 12 } else { hours }
+        } else {
+            register(buffer[2] & 0x3f)?
+        };
+        Ok(Self {
+            seconds: register(buffer[0] & 0x7f)?,
+            minutes: register(buffer[1])?,
+            hours,
+            weekday: register(buffer[3])?,
+            day: register(buffer[4])?,
+            month: register(buffer[5] & 0x7f)?,
+            year: register(buffer[6])?,
+        })
+    }
+}
@@ -0,0 +1,270 @@
+//! Lists with a variable number of elements and a fixed encoded size.
+//!
+//! A [`BoundedVec<T, N>`](BoundedVec) holds up to `N` elements without
+//! allocating. It is encoded as a length prefix of type `L` (`u16` by
+//! default) followed by `N` element slots, i.e. its [`Size::SIZE`] is
+//! `L::SIZE + N * T::SIZE`. Only the used slots are packed, the remaining
+//! ones are filled with zero bytes:
+//!
+//! ```ignore
+//! let mut readings = BoundedVec::<u16, 4, u8>::new();
+//! readings.push(7).unwrap();
+//! readings.pack::<BigEndian>(&mut buffer);
+//! // => buffer == [1, 0, 7, 0, 0, 0, 0, 0, 0]
+//! ```
+//!
+//! Unpacking rejects length prefixes greater than `N` with
+//! [`Error::LengthExceedsCapacity`].
+
+use core::{fmt, hash::{Hash, Hasher}, marker::PhantomData, mem::MaybeUninit, ops::{Deref, DerefMut}, ptr, slice};
+
+use byteorder::ByteOrder;
+
+use crate::{Pack, Schema, Size, Unpack, pack, schema::Type, unpack::{Error, Result}};
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// Integers that can encode the length of a [`BoundedVec`].
+pub trait Length: sealed::Sealed + Copy + Pack + Unpack {
+    /// The largest length the type can encode.
+    const MAX: usize;
+
+    #[doc(hidden)]
+    fn to_usize(self) -> usize;
+
+    #[doc(hidden)]
+    fn from_usize(value: usize) -> Self;
+}
+
+macro_rules! impl_length {
+    ($($t:ty),*) => {
+        $(
+            impl sealed::Sealed for $t {}
+
+            impl Length for $t {
+                const MAX: usize = <$t>::MAX as usize;
+
+                fn to_usize(self) -> usize {
+                    self as usize
+                }
+
+                fn from_usize(value: usize) -> Self {
+                    value as $t
+                }
+            }
+        )*
+    };
+}
+
+impl_length!(u8, u16, u32);
+
+/// A list of up to `N` elements with a length prefix of type `L`.
+pub struct BoundedVec<T, const N: usize, L = u16> {
+    elements: [MaybeUninit<T>; N],
+    len: usize,
+    prefix: PhantomData<L>,
+}
+
+impl<T, const N: usize, L> BoundedVec<T, N, L> where L: Length {
+    /// Rejects capacities that the length prefix cannot encode.
+    const VALID: () = assert!(N <= L::MAX, "the capacity exceeds the maximum of the length prefix");
+
+    /// Creates an empty list.
+    pub fn new() -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::VALID;
+        Self { elements: core::array::from_fn(|_| MaybeUninit::uninit()), len: 0, prefix: PhantomData }
+    }
+
+    /// Creates a list from a slice, or returns `None` if it has more than `N` elements.
+    pub fn from_slice(elements: &[T]) -> Option<Self> where T: Clone {
+        if elements.len() > N {
+            return None;
+        }
+        let mut vec = Self::new();
+        for element in elements {
+            vec.push_unchecked(element.clone());
+        }
+        Some(vec)
+    }
+}
+
+impl<T, const N: usize, L> BoundedVec<T, N, L> {
+    /// The maximum number of elements.
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Whether the list is full.
+    pub fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    /// Appends an element, or returns it if the list is full.
+    pub fn push(&mut self, value: T) -> core::result::Result<(), T> {
+        if self.is_full() {
+            return Err(value);
+        }
+        self.push_unchecked(value);
+        Ok(())
+    }
+
+    /// Appends an element to a list that is not full.
+    fn push_unchecked(&mut self, value: T) {
+        self.elements[self.len] = MaybeUninit::new(value);
+        self.len += 1;
+    }
+
+    /// Removes the last element.
+    pub fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        // SAFETY: The element was initialized and is no longer part of the list
+        Some(unsafe { self.elements[self.len].assume_init_read() })
+    }
+
+    /// Removes all elements.
+    pub fn clear(&mut self) {
+        let elements: *mut [T] = self.as_mut_slice();
+        // Reset the length first, so that a panicking destructor cannot cause a double drop
+        self.len = 0;
+        // SAFETY: The elements were initialized and are no longer part of the list
+        unsafe { ptr::drop_in_place(elements) }
+    }
+
+    /// The elements as slice.
+    pub fn as_slice(&self) -> &[T] {
+        // SAFETY: The first `len` elements are initialized and `MaybeUninit<T>` has the layout of `T`
+        unsafe { slice::from_raw_parts(self.elements.as_ptr() as *const T, self.len) }
+    }
+
+    /// The elements as mutable slice.
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        // SAFETY: The first `len` elements are initialized and `MaybeUninit<T>` has the layout of `T`
+        unsafe { slice::from_raw_parts_mut(self.elements.as_mut_ptr() as *mut T, self.len) }
+    }
+}
+
+impl<T, const N: usize, L> Drop for BoundedVec<T, N, L> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<T, const N: usize, L> Default for BoundedVec<T, N, L> where L: Length {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize, L> Clone for BoundedVec<T, N, L> where T: Clone, L: Length {
+    fn clone(&self) -> Self {
+        let mut vec = Self::new();
+        for element in self.iter() {
+            vec.push_unchecked(element.clone());
+        }
+        vec
+    }
+}
+
+impl<T, const N: usize, L> Deref for BoundedVec<T, N, L> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T, const N: usize, L> DerefMut for BoundedVec<T, N, L> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        self.as_mut_slice()
+    }
+}
+
+impl<'a, T, const N: usize, L> IntoIterator for &'a BoundedVec<T, N, L> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T, const N: usize, L> fmt::Debug for BoundedVec<T, N, L> where T: fmt::Debug {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_slice(), f)
+    }
+}
+
+impl<T, const N: usize, L> PartialEq for BoundedVec<T, N, L> where T: PartialEq {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl<T, const N: usize, L> Eq for BoundedVec<T, N, L> where T: Eq {}
+
+impl<T, const N: usize, L> Hash for BoundedVec<T, N, L> where T: Hash {
+    fn hash<H>(&self, state: &mut H) where H: Hasher {
+        self.as_slice().hash(state)
+    }
+}
+
+impl<T, const N: usize, L> Size for BoundedVec<T, N, L> where T: Size, L: Size {
+    const SIZE: usize = L::SIZE + N * T::SIZE;
+}
+
+/// Described as the length prefix followed by all `N` element slots.
+impl<T, const N: usize, L> Schema for BoundedVec<T, N, L> where T: Schema, L: Schema {
+    const SCHEMA: Type = Type::Tuple(&[L::SCHEMA, <[T; N]>::SCHEMA]);
+}
+
+impl<T, const N: usize, L> Pack for BoundedVec<T, N, L> where T: Pack, L: Length {
+    fn pack<B>(&self, buffer: &mut [u8]) where B: ByteOrder {
+        L::from_usize(self.len).pack::<B>(buffer);
+        let mut buffer = &mut buffer[L::SIZE..];
+        for element in self.iter() {
+            // Elements may not write their whole slot (e.g. `None`)
+            buffer[..T::SIZE].fill(0);
+            element.pack::<B>(buffer);
+            buffer = &mut buffer[T::SIZE..];
+        }
+        buffer[..(N - self.len) * T::SIZE].fill(0);
+    }
+
+    fn try_pack<B>(&self, buffer: &mut [u8]) -> pack::Result<()> where B: ByteOrder {
+        if buffer.len() < Self::SIZE {
+            return Err(pack::Error::BufferTooSmall { actual: buffer.len(), expected: Self::SIZE });
+        }
+        L::from_usize(self.len).pack::<B>(buffer);
+        let mut buffer = &mut buffer[L::SIZE..];
+        for element in self.iter() {
+            buffer[..T::SIZE].fill(0);
+            element.try_pack::<B>(buffer)?;
+            buffer = &mut buffer[T::SIZE..];
+        }
+        buffer[..(N - self.len) * T::SIZE].fill(0);
+        Ok(())
+    }
+}
+
+impl<T, const N: usize, L> Unpack for BoundedVec<T, N, L> where T: Unpack, L: Length {
+    fn unpack<B>(buffer: &[u8]) -> Result<Self> where B: ByteOrder {
+        let len = L::unpack::<B>(buffer)?.to_usize();
+        if len > N {
+            return Err(Error::LengthExceedsCapacity { len, capacity: N });
+        }
+        // Elements that were already unpacked are dropped with the list if an error occurs
+        let mut vec = Self::new();
+        let mut buffer = &buffer[L::SIZE..];
+        for _ in 0..len {
+            vec.push_unchecked(T::unpack::<B>(buffer)?);
+            buffer = &buffer[T::SIZE..];
+        }
+        Ok(vec)
+    }
+}
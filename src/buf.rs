@@ -0,0 +1,113 @@
+//! Reading and writing packed values via `bytes`.
+//!
+//! With the `bytes` feature, [`BufMutExt::put_packed`] appends a value to a
+//! `bytes::BufMut` and [`BufExt::get_unpacked`] consumes one from a
+//! `bytes::Buf`, e.g. a `BytesMut`:
+//!
+//! ```ignore
+//! let mut buffer = BytesMut::new();
+//! buffer.put_packed::<BigEndian, _>(&message)?;
+//! let message = buffer.get_unpacked::<BigEndian, Message>()?;
+//! ```
+//!
+//! Values that span multiple chunks (e.g. of a `Chain`) are copied through a
+//! scratch buffer, which lives on the stack for values of up to
+//! [`SCRATCH_SIZE`] bytes. Since `Buf` can only be read by consuming it,
+//! such values are only copied once `T::SIZE` bytes are available, so that
+//! an incomplete value keeps its bytes for a retry. Values with a variable
+//! encoded size, e.g. [`Varint`](crate::varint::Varint)s, only consume their
+//! actual size.
+
+use alloc::vec;
+
+use byteorder::ByteOrder;
+use bytes::{Buf, BufMut};
+
+use crate::{Pack, Unpack, pack, unpack};
+
+/// The maximum size of values whose scratch buffer is allocated on the stack.
+pub const SCRATCH_SIZE: usize = 256;
+
+/// Writes packed values to a `bytes::BufMut`.
+pub trait BufMutExt: BufMut {
+    /// Packs the value and advances the buffer by its encoded size, or
+    /// returns an error if the remaining capacity is too small or a value is
+    /// out of range (without writing anything).
+    fn put_packed<B, T>(&mut self, value: &T) -> pack::Result<()> where B: ByteOrder, T: Pack {
+        let size = value.encoded_size();
+        if self.remaining_mut() < size {
+            return Err(pack::Error::BufferTooSmall { actual: self.remaining_mut(), expected: size });
+        }
+        if size <= SCRATCH_SIZE {
+            let mut scratch = [0; SCRATCH_SIZE];
+            value.try_pack::<B>(&mut scratch[..size])?;
+            self.put_slice(&scratch[..size]);
+        } else {
+            let mut scratch = vec![0; size];
+            value.try_pack::<B>(&mut scratch)?;
+            self.put_slice(&scratch);
+        }
+        Ok(())
+    }
+}
+
+impl<M> BufMutExt for M where M: BufMut + ?Sized {}
+
+/// Reads packed values from a `bytes::Buf`.
+pub trait BufExt: Buf {
+    /// Unpacks a value and advances the buffer by its encoded size, or
+    /// returns [`unpack::Error::BufferTooSmall`] without advancing if the
+    /// remaining bytes do not hold a complete value. A value that spans
+    /// multiple chunks is reported as incomplete until `T::SIZE` bytes
+    /// remain. Up to `T::SIZE` bytes are consumed if the value is invalid.
+    fn get_unpacked<B, T>(&mut self) -> unpack::Result<T> where B: ByteOrder, T: Unpack {
+        let available = self.remaining().min(T::SIZE);
+        if self.chunk().len() >= available {
+            let result = T::unpack_safely::<B>(&self.chunk()[..available]);
+            match &result {
+                Ok(value) => self.advance(value.encoded_size()),
+                Err(unpack::Error::BufferTooSmall { .. }) => {},
+                Err(_) => self.advance(available),
+            }
+            result
+        } else if available < T::SIZE {
+            // Copying the bytes of the following chunks would consume them
+            Err(unpack::Error::BufferTooSmall { actual: available, expected: T::SIZE })
+        } else if available <= SCRATCH_SIZE {
+            let mut scratch = [0; SCRATCH_SIZE];
+            get_spanning::<B, T, Self>(self, &mut scratch[..available])
+        } else {
+            let mut scratch = vec![0; available];
+            get_spanning::<B, T, Self>(self, &mut scratch)
+        }
+    }
+}
+
+/// Unpacks a value that spans multiple chunks by copying one chunk at a time
+/// to the scratch buffer (which can hold `T::SIZE` bytes, all of them
+/// available) until the value is complete.
+fn get_spanning<B, T, U>(buf: &mut U, scratch: &mut [u8]) -> unpack::Result<T> where B: ByteOrder, T: Unpack, U: Buf + ?Sized {
+    let mut len = 0;
+    loop {
+        let chunk = buf.chunk();
+        let copied = chunk.len().min(scratch.len() - len);
+        scratch[len..len + copied].copy_from_slice(&chunk[..copied]);
+        match T::unpack_safely::<B>(&scratch[..len + copied]) {
+            Ok(value) => {
+                buf.advance(value.encoded_size() - len);
+                return Ok(value);
+            },
+            // The copied bytes are all part of the value, which is not complete yet
+            Err(unpack::Error::BufferTooSmall { .. }) if len + copied < scratch.len() => {
+                buf.advance(copied);
+                len += copied;
+            },
+            Err(e) => {
+                buf.advance(copied);
+                return Err(e);
+            },
+        }
+    }
+}
+
+impl<U> BufExt for U where U: Buf + ?Sized {}
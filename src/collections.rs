@@ -0,0 +1,426 @@
+//! Encodings for fixed-capacity collections.
+//!
+//! A [`Collection`] holds up to [`Collection::CAPACITY`] items and can be
+//! encoded in two ways, both starting with a length prefix of type `L`
+//! (`u16` by default):
+//!
+//! - [`Padded`] packs the used items and fills the remaining capacity with
+//!   zero bytes, so that the encoding always occupies [`Size::SIZE`] bytes
+//!   (like [`BoundedVec`]).
+//! - [`Compact`] packs only the used items. Its [`Size::SIZE`] is the
+//!   maximum, while [`Size::encoded_size`] is the actual size. Like
+//!   [`varint`](crate::varint)s, it is packed back to back with the
+//!   following fields and [`Unpack::unpack_safely`] only requires the
+//!   actual size.
+//!
+//! Strings are encoded as their UTF-8 bytes and rejected with
+//! [`Error::InvalidUtf8`] on unpack. Length prefixes greater than the
+//! capacity are rejected with [`Error::LengthExceedsCapacity`].
+//!
+//! With the `heapless` and `arrayvec` features, `heapless::Vec`,
+//! `heapless::String`, `arrayvec::ArrayVec` and `arrayvec::ArrayString` are
+//! collections and also implement the traits directly with the padded
+//! encoding and a `u16` prefix:
+//!
+//! ```ignore
+//! #[derive(Size, Pack, Unpack)]
+//! struct Frame {
+//!     readings: heapless::Vec<u16, 8>,
+//!     label: Compact<heapless::String<16>, u8>,
+//! }
+//! ```
+
+use core::marker::PhantomData;
+
+use byteorder::ByteOrder;
+
+use crate::{Pack, Schema, Size, Unpack, bounded::{BoundedVec, Length}, pack, schema::Type, unpack::{Error, Result}};
+
+/// Collections with a fixed capacity.
+pub trait Collection: Sized {
+    /// The encoded item type.
+    type Item: Size;
+
+    /// The maximum number of items.
+    const CAPACITY: usize;
+
+    /// The items to encode.
+    fn items(&self) -> &[Self::Item];
+
+    /// Decodes a collection with `len` items (at most the capacity) from the
+    /// start of the buffer.
+    fn unpack_items<B>(buffer: &[u8], len: usize) -> Result<Self> where B: ByteOrder, Self::Item: Unpack;
+
+    /// Creates a collection from the items, or returns `None` if there are
+    /// more than the capacity (or they do not form a valid collection).
+    fn from_items<I>(items: I) -> Option<Self> where I: IntoIterator<Item = Self::Item>;
+
+    /// The collection as string, for strings (whose items are UTF-8 bytes).
+    fn as_str(&self) -> Option<&str> {
+        None
+    }
+
+    /// Creates a string, or returns `None` if it is too long or the
+    /// collection is not a string.
+    fn try_from_str(_s: &str) -> Option<Self> {
+        None
+    }
+}
+
+/// A collection encoded with a length prefix and its full capacity.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
+pub struct Padded<C, L = u16>(pub C, PhantomData<L>);
+
+/// A collection encoded with a length prefix and only the used items.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
+pub struct Compact<C, L = u16>(pub C, PhantomData<L>);
+
+impl<C, L> Padded<C, L> {
+    /// Wraps a collection.
+    pub fn new(collection: C) -> Self {
+        Self(collection, PhantomData)
+    }
+}
+
+impl<C, L> Compact<C, L> {
+    /// Wraps a collection.
+    pub fn new(collection: C) -> Self {
+        Self(collection, PhantomData)
+    }
+}
+
+/// Rejects capacities that the length prefix cannot encode.
+pub(crate) struct Check<C, L>(PhantomData<(C, L)>);
+
+impl<C, L> Check<C, L> where C: Collection, L: Length {
+    pub(crate) const VALID: () = assert!(C::CAPACITY <= L::MAX, "the capacity exceeds the maximum of the length prefix");
+}
+
+/// Packs the length prefix and the used items and returns the encoded size.
+fn pack_items<B, C, L>(collection: &C, buffer: &mut [u8]) -> usize where B: ByteOrder, C: Collection, C::Item: Pack, L: Length {
+    #[allow(clippy::let_unit_value)]
+    let () = Check::<C, L>::VALID;
+    let items = collection.items();
+    L::from_usize(items.len()).pack::<B>(buffer);
+    let mut offset = L::SIZE;
+    for item in items {
+        // Items may not write their whole slot (e.g. `None`)
+        buffer[offset..offset + C::Item::SIZE].fill(0);
+        item.pack::<B>(&mut buffer[offset..]);
+        offset += C::Item::SIZE;
+    }
+    offset
+}
+
+/// Packs the length prefix and the used items with [`Pack::try_pack`] and
+/// returns the encoded size.
+fn try_pack_items<B, C, L>(collection: &C, buffer: &mut [u8]) -> pack::Result<usize> where B: ByteOrder, C: Collection, C::Item: Pack, L: Length {
+    #[allow(clippy::let_unit_value)]
+    let () = Check::<C, L>::VALID;
+    let items = collection.items();
+    let size = L::SIZE + items.len() * C::Item::SIZE;
+    if buffer.len() < size {
+        return Err(pack::Error::BufferTooSmall { actual: buffer.len(), expected: size });
+    }
+    L::from_usize(items.len()).pack::<B>(buffer);
+    let mut offset = L::SIZE;
+    for item in items {
+        buffer[offset..offset + C::Item::SIZE].fill(0);
+        item.try_pack::<B>(&mut buffer[offset..])?;
+        offset += C::Item::SIZE;
+    }
+    Ok(offset)
+}
+
+/// Unpacks the length prefix and the used items.
+fn unpack_items<B, C, L>(buffer: &[u8]) -> Result<C> where B: ByteOrder, C: Collection, C::Item: Unpack, L: Length {
+    let len = L::unpack::<B>(buffer)?.to_usize();
+    if len > C::CAPACITY {
+        return Err(Error::LengthExceedsCapacity { len, capacity: C::CAPACITY });
+    }
+    C::unpack_items::<B>(&buffer[L::SIZE..], len)
+}
+
+/// Unpacks the length prefix and the used items and returns an error if the
+/// buffer is too small for them.
+fn unpack_items_safely<B, C, L>(buffer: &[u8]) -> Result<C> where B: ByteOrder, C: Collection, C::Item: Unpack, L: Length {
+    let len = L::unpack_safely::<B>(buffer)?.to_usize();
+    if len > C::CAPACITY {
+        return Err(Error::LengthExceedsCapacity { len, capacity: C::CAPACITY });
+    }
+    let size = L::SIZE + len * C::Item::SIZE;
+    if buffer.len() < size {
+        return Err(Error::BufferTooSmall { actual: buffer.len(), expected: size });
+    }
+    C::unpack_items::<B>(&buffer[L::SIZE..], len)
+}
+
+/// The schema of a collection with the padded encoding.
+const fn padded_schema<C, L>() -> Type where C: Collection, C::Item: Schema, L: Schema {
+    Type::Tuple(&[L::SCHEMA, Type::Array { element: &C::Item::SCHEMA, len: C::CAPACITY }])
+}
+
+/// The padded size of a collection.
+const fn padded_size<C, L>() -> usize where C: Collection, L: Size {
+    L::SIZE + C::CAPACITY * C::Item::SIZE
+}
+
+/// Packs a collection with the padded encoding.
+fn pack_padded<B, C, L>(collection: &C, buffer: &mut [u8]) where B: ByteOrder, C: Collection, C::Item: Pack, L: Length {
+    let size = pack_items::<B, C, L>(collection, buffer);
+    buffer[size..padded_size::<C, L>()].fill(0);
+}
+
+/// Packs a collection with the padded encoding and [`Pack::try_pack`].
+fn try_pack_padded<B, C, L>(collection: &C, buffer: &mut [u8]) -> pack::Result<()> where B: ByteOrder, C: Collection, C::Item: Pack, L: Length {
+    let padded = padded_size::<C, L>();
+    if buffer.len() < padded {
+        return Err(pack::Error::BufferTooSmall { actual: buffer.len(), expected: padded });
+    }
+    let size = try_pack_items::<B, C, L>(collection, buffer)?;
+    buffer[size..padded].fill(0);
+    Ok(())
+}
+
+impl<C, L> Size for Padded<C, L> where C: Collection, L: Size {
+    const SIZE: usize = padded_size::<C, L>();
+}
+
+impl<C, L> Pack for Padded<C, L> where C: Collection, C::Item: Pack, L: Length {
+    fn pack<B>(&self, buffer: &mut [u8]) where B: ByteOrder {
+        pack_padded::<B, C, L>(&self.0, buffer)
+    }
+
+    fn try_pack<B>(&self, buffer: &mut [u8]) -> pack::Result<()> where B: ByteOrder {
+        try_pack_padded::<B, C, L>(&self.0, buffer)
+    }
+}
+
+impl<C, L> Unpack for Padded<C, L> where C: Collection, C::Item: Unpack, L: Length {
+    fn unpack<B>(buffer: &[u8]) -> Result<Self> where B: ByteOrder {
+        Ok(Self::new(unpack_items::<B, C, L>(buffer)?))
+    }
+}
+
+/// Described as the length prefix followed by all item slots.
+impl<C, L> Schema for Padded<C, L> where C: Collection, C::Item: Schema, L: Schema {
+    const SCHEMA: Type = padded_schema::<C, L>();
+}
+
+impl<C, L> Size for Compact<C, L> where C: Collection, L: Size {
+    const SIZE: usize = padded_size::<C, L>();
+    const FIXED_SIZE: bool = false;
+
+    fn encoded_size(&self) -> usize {
+        L::SIZE + self.0.items().len() * C::Item::SIZE
+    }
+}
+
+impl<C, L> Pack for Compact<C, L> where C: Collection, C::Item: Pack, L: Length {
+    fn pack<B>(&self, buffer: &mut [u8]) where B: ByteOrder {
+        pack_items::<B, C, L>(&self.0, buffer);
+    }
+
+    fn try_pack<B>(&self, buffer: &mut [u8]) -> pack::Result<()> where B: ByteOrder {
+        try_pack_items::<B, C, L>(&self.0, buffer)?;
+        Ok(())
+    }
+}
+
+impl<C, L> Unpack for Compact<C, L> where C: Collection, C::Item: Unpack, L: Length {
+    fn unpack<B>(buffer: &[u8]) -> Result<Self> where B: ByteOrder {
+        Ok(Self::new(unpack_items::<B, C, L>(buffer)?))
+    }
+
+    fn unpack_safely<B>(buffer: &[u8]) -> Result<Self> where B: ByteOrder {
+        Ok(Self::new(unpack_items_safely::<B, C, L>(buffer)?))
+    }
+}
+
+impl<T, const N: usize, L> Collection for BoundedVec<T, N, L> where T: Size, L: Length {
+    type Item = T;
+
+    const CAPACITY: usize = N;
+
+    fn items(&self) -> &[T] {
+        self.as_slice()
+    }
+
+    fn unpack_items<B>(buffer: &[u8], len: usize) -> Result<Self> where B: ByteOrder, T: Unpack {
+        let mut vec = Self::new();
+        for i in 0..len {
+            // The length does not exceed the capacity
+            let _ = vec.push(T::unpack::<B>(&buffer[i * T::SIZE..])?);
+        }
+        Ok(vec)
+    }
+
+    fn from_items<I>(items: I) -> Option<Self> where I: IntoIterator<Item = T> {
+        let mut vec = Self::new();
+        for item in items {
+            vec.push(item).ok()?;
+        }
+        Some(vec)
+    }
+}
+
+/// Validates the UTF-8 bytes of a string with `len` bytes.
+#[cfg(any(feature = "heapless", feature = "arrayvec"))]
+fn utf8(buffer: &[u8], len: usize) -> Result<&str> {
+    core::str::from_utf8(&buffer[..len]).map_err(|_| Error::InvalidUtf8)
+}
+
+/// Implements the traits with the padded encoding and a `u16` prefix.
+#[cfg(any(feature = "heapless", feature = "arrayvec"))]
+macro_rules! impl_padded {
+    ($({$($params:tt)*} $t:ty),*) => {
+        $(
+            impl<$($params)*> Size for $t where $t: Collection {
+                const SIZE: usize = padded_size::<Self, u16>();
+            }
+
+            impl<$($params)*> Schema for $t where $t: Collection, <$t as Collection>::Item: Schema {
+                const SCHEMA: Type = padded_schema::<Self, u16>();
+            }
+
+            impl<$($params)*> Pack for $t where $t: Collection, <$t as Collection>::Item: Pack {
+                fn pack<B>(&self, buffer: &mut [u8]) where B: ByteOrder {
+                    pack_padded::<B, Self, u16>(self, buffer)
+                }
+
+                fn try_pack<B>(&self, buffer: &mut [u8]) -> pack::Result<()> where B: ByteOrder {
+                    try_pack_padded::<B, Self, u16>(self, buffer)
+                }
+            }
+
+            impl<$($params)*> Unpack for $t where $t: Collection, <$t as Collection>::Item: Unpack {
+                fn unpack<B>(buffer: &[u8]) -> Result<Self> where B: ByteOrder {
+                    unpack_items::<B, Self, u16>(buffer)
+                }
+            }
+        )*
+    };
+}
+
+#[cfg(feature = "heapless")]
+impl<T, const N: usize> Collection for heapless::Vec<T, N> where T: Size {
+    type Item = T;
+
+    const CAPACITY: usize = N;
+
+    fn items(&self) -> &[T] {
+        self.as_slice()
+    }
+
+    fn unpack_items<B>(buffer: &[u8], len: usize) -> Result<Self> where B: ByteOrder, T: Unpack {
+        let mut vec = Self::new();
+        for i in 0..len {
+            // The length does not exceed the capacity
+            let _ = vec.push(T::unpack::<B>(&buffer[i * T::SIZE..])?);
+        }
+        Ok(vec)
+    }
+
+    fn from_items<I>(items: I) -> Option<Self> where I: IntoIterator<Item = T> {
+        let mut vec = Self::new();
+        for item in items {
+            vec.push(item).ok()?;
+        }
+        Some(vec)
+    }
+}
+
+#[cfg(feature = "heapless")]
+impl<const N: usize> Collection for heapless::String<N> {
+    type Item = u8;
+
+    const CAPACITY: usize = N;
+
+    fn items(&self) -> &[u8] {
+        self.as_bytes()
+    }
+
+    fn unpack_items<B>(buffer: &[u8], len: usize) -> Result<Self> where B: ByteOrder {
+        let mut string = Self::new();
+        // The length does not exceed the capacity
+        let _ = string.push_str(utf8(buffer, len)?);
+        Ok(string)
+    }
+
+    fn from_items<I>(items: I) -> Option<Self> where I: IntoIterator<Item = u8> {
+        Self::from_utf8(heapless::Vec::from_items(items)?).ok()
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        Some(self)
+    }
+
+    fn try_from_str(s: &str) -> Option<Self> {
+        s.try_into().ok()
+    }
+}
+
+#[cfg(feature = "heapless")]
+impl_padded!({T, const N: usize} heapless::Vec<T, N>, {const N: usize} heapless::String<N>);
+
+#[cfg(feature = "arrayvec")]
+impl<T, const N: usize> Collection for arrayvec::ArrayVec<T, N> where T: Size {
+    type Item = T;
+
+    const CAPACITY: usize = N;
+
+    fn items(&self) -> &[T] {
+        self.as_slice()
+    }
+
+    fn unpack_items<B>(buffer: &[u8], len: usize) -> Result<Self> where B: ByteOrder, T: Unpack {
+        let mut vec = Self::new();
+        for i in 0..len {
+            // The length does not exceed the capacity
+            let _ = vec.try_push(T::unpack::<B>(&buffer[i * T::SIZE..])?);
+        }
+        Ok(vec)
+    }
+
+    fn from_items<I>(items: I) -> Option<Self> where I: IntoIterator<Item = T> {
+        let mut vec = Self::new();
+        for item in items {
+            vec.try_push(item).ok()?;
+        }
+        Some(vec)
+    }
+}
+
+#[cfg(feature = "arrayvec")]
+impl<const N: usize> Collection for arrayvec::ArrayString<N> {
+    type Item = u8;
+
+    const CAPACITY: usize = N;
+
+    fn items(&self) -> &[u8] {
+        self.as_bytes()
+    }
+
+    fn unpack_items<B>(buffer: &[u8], len: usize) -> Result<Self> where B: ByteOrder {
+        let mut string = Self::new();
+        // The length does not exceed the capacity
+        let _ = string.try_push_str(utf8(buffer, len)?);
+        Ok(string)
+    }
+
+    fn from_items<I>(items: I) -> Option<Self> where I: IntoIterator<Item = u8> {
+        let bytes = arrayvec::ArrayVec::<u8, N>::from_items(items)?;
+        Self::try_from_str(core::str::from_utf8(&bytes).ok()?)
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        Some(self)
+    }
+
+    fn try_from_str(s: &str) -> Option<Self> {
+        Self::from(s).ok()
+    }
+}
+
+#[cfg(feature = "arrayvec")]
+impl_padded!({T, const N: usize} arrayvec::ArrayVec<T, N>, {const N: usize} arrayvec::ArrayString<N>);
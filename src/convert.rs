@@ -0,0 +1,136 @@
+//! Conversions between numeric fields and their encoded representation.
+//!
+//! These back the `#[lightpack(as = ..., scale = ..., offset = ...)]` field
+//! attribute of the derives, which transmits a field in a different (usually
+//! narrower) numeric type:
+//!
+//! ```ignore
+//! #[derive(Size, Pack, Unpack)]
+//! struct Status {
+//!     /// Encoded as `u8`.
+//!     #[lightpack(as = u8)]
+//!     percentage: u32,
+//!     /// Encoded as `i16` in units of 0.01 °C, starting at -40 °C.
+//!     #[lightpack(scale = 0.01, offset = -40.0, as = i16)]
+//!     temperature: f32,
+//! }
+//! ```
+//!
+//! A value is encoded as `(value - offset) / scale` (rounded to the nearest
+//! integer, with ties away from zero, if the encoded type is an integer) and
+//! decoded as `encoded * scale + offset`. Without `scale` and `offset`,
+//! integers are converted exactly. Values that do not fit into the target
+//! type are rejected, which makes [`Pack::pack`](crate::Pack::pack) panic,
+//! while [`Pack::try_pack`](crate::Pack::try_pack) returns
+//! [`pack::Error::OutOfRange`] and [`Unpack::unpack`](crate::Unpack::unpack)
+//! returns [`unpack::Error::OutOfRange`].
+
+use crate::{pack, unpack};
+
+/// A numeric value in a common representation.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Value {
+    Integer(i128),
+    Float(f64),
+}
+
+impl Value {
+    fn to_f64(self) -> f64 {
+        match self {
+            Value::Integer(value) => value as f64,
+            Value::Float(value) => value,
+        }
+    }
+
+    /// Rounds the value to the nearest integer (with ties away from zero),
+    /// or `None` if it is NaN or infinite.
+    fn round(self) -> Option<i128> {
+        match self {
+            Value::Integer(value) => Some(value),
+            Value::Float(value) if value.is_finite() => {
+                // Casts truncate towards zero (and saturate at the bounds of i128)
+                let truncated = value as i128;
+                let fraction = value - truncated as f64;
+                Some(if fraction >= 0.5 {
+                    truncated.saturating_add(1)
+                } else if fraction <= -0.5 {
+                    truncated.saturating_sub(1)
+                } else {
+                    truncated
+                })
+            },
+            Value::Float(_) => None,
+        }
+    }
+}
+
+/// Numeric types that fields can be converted from and to.
+pub trait Number: Copy {
+    /// The value in the common representation.
+    fn to_value(self) -> Value;
+
+    /// Converts from the common representation, or returns `None` if the
+    /// value does not fit.
+    fn from_value(value: Value) -> Option<Self>;
+}
+
+macro_rules! impl_integer {
+    ($($t:ty),*) => {
+        $(
+            impl Number for $t {
+                fn to_value(self) -> Value {
+                    Value::Integer(self as i128)
+                }
+
+                fn from_value(value: Value) -> Option<Self> {
+                    <$t>::try_from(value.round()?).ok()
+                }
+            }
+        )*
+    };
+}
+
+impl_integer!(u8, u16, u32, u64, i8, i16, i32, i64);
+
+impl Number for f32 {
+    fn to_value(self) -> Value {
+        Value::Float(self.into())
+    }
+
+    fn from_value(value: Value) -> Option<Self> {
+        let value = value.to_f64();
+        let converted = value as f32;
+        // Finite values that are too large for `f32` would become infinite
+        (converted.is_finite() || !value.is_finite()).then_some(converted)
+    }
+}
+
+impl Number for f64 {
+    fn to_value(self) -> Value {
+        Value::Float(self)
+    }
+
+    fn from_value(value: Value) -> Option<Self> {
+        Some(value.to_f64())
+    }
+}
+
+/// Converts a field value to its encoded representation.
+pub fn to_repr<T, R>(value: T, scale: f64, offset: f64) -> pack::Result<R> where T: Number, R: Number {
+    let value = if scale == 1.0 && offset == 0.0 {
+        value.to_value()
+    } else {
+        Value::Float((value.to_value().to_f64() - offset) / scale)
+    };
+    R::from_value(value).ok_or(pack::Error::OutOfRange)
+}
+
+/// Converts an encoded representation back to the field value.
+pub fn from_repr<R, T>(repr: R, scale: f64, offset: f64) -> unpack::Result<T> where R: Number, T: Number {
+    let value = if scale == 1.0 && offset == 0.0 {
+        repr.to_value()
+    } else {
+        Value::Float(repr.to_value().to_f64() * scale + offset)
+    };
+    T::from_value(value).ok_or(unpack::Error::OutOfRange)
+}
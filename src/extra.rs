@@ -2,7 +2,7 @@
 
 use byteorder::{LittleEndian, BigEndian, ByteOrder};
 
-use crate::{Size, Pack, Unpack, unpack::{Result, self}};
+use crate::{Size, Pack, Unpack, Schema, pack, schema::{Endianness, Type}, unpack::{Result, self}};
 
 /// A wrapper that always encodes the type as little endian.
 #[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Clone, Copy)]
@@ -14,34 +14,94 @@ pub struct BE<T>(pub T);
 
 impl<T> Size for LE<T> where T: Size {
     const SIZE: usize = T::SIZE;
+    const FIXED_SIZE: bool = T::FIXED_SIZE;
+
+    fn encoded_size(&self) -> usize {
+        self.0.encoded_size()
+    }
 }
 
 impl<T> Size for BE<T> where T: Size {
     const SIZE: usize = T::SIZE;
+    const FIXED_SIZE: bool = T::FIXED_SIZE;
+
+    fn encoded_size(&self) -> usize {
+        self.0.encoded_size()
+    }
 }
 
 impl<T> Pack for LE<T> where T: Pack {
     fn pack<B>(&self, buffer: &mut [u8]) where B: ByteOrder {
         self.0.pack::<LittleEndian>(buffer)
     }
+
+    fn try_pack<B>(&self, buffer: &mut [u8]) -> pack::Result<()> where B: ByteOrder {
+        self.0.try_pack::<LittleEndian>(buffer)
+    }
 }
 
 impl<T> Pack for BE<T> where T: Pack {
     fn pack<B>(&self, buffer: &mut [u8]) where B: ByteOrder {
         self.0.pack::<BigEndian>(buffer)
     }
+
+    fn try_pack<B>(&self, buffer: &mut [u8]) -> pack::Result<()> where B: ByteOrder {
+        self.0.try_pack::<BigEndian>(buffer)
+    }
+}
+
+impl<T> Schema for LE<T> where T: Schema {
+    const SCHEMA: Type = Type::Endian(Endianness::Little, &T::SCHEMA);
+}
+
+impl<T> Schema for BE<T> where T: Schema {
+    const SCHEMA: Type = Type::Endian(Endianness::Big, &T::SCHEMA);
 }
 
 impl<T> Unpack for LE<T> where T: Unpack {
     fn unpack<B>(buffer: &[u8]) -> Result<Self> where B: ByteOrder {
         Ok(Self(T::unpack::<LittleEndian>(buffer)?))
     }
+
+    fn unpack_safely<B>(buffer: &[u8]) -> Result<Self> where B: ByteOrder {
+        Ok(Self(T::unpack_safely::<LittleEndian>(buffer)?))
+    }
 }
 
 impl<T> Unpack for BE<T> where T: Unpack {
     fn unpack<B>(buffer: &[u8]) -> Result<Self> where B: ByteOrder {
         Ok(Self(T::unpack::<BigEndian>(buffer)?))
     }
+
+    fn unpack_safely<B>(buffer: &[u8]) -> Result<Self> where B: ByteOrder {
+        Ok(Self(T::unpack_safely::<BigEndian>(buffer)?))
+    }
+}
+
+/// `N` reserved bytes, e.g. for future fields. They are packed as zeros and
+/// ignored when unpacking, and skipped by the serde impls of types deriving
+/// `Schema` with `#[lightpack(serde)]` in human-readable formats.
+#[derive(Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Clone, Copy)]
+pub struct Reserved<const N: usize>;
+
+impl<const N: usize> Size for Reserved<N> {
+    const SIZE: usize = N;
+}
+
+impl<const N: usize> Pack for Reserved<N> {
+    fn pack<B>(&self, buffer: &mut [u8]) where B: ByteOrder {
+        buffer[..N].fill(0);
+    }
+}
+
+impl<const N: usize> Schema for Reserved<N> {
+    const SCHEMA: Type = Type::Reserved(N);
+}
+
+impl<const N: usize> Unpack for Reserved<N> {
+    fn unpack<B>(_buffer: &[u8]) -> Result<Self> where B: ByteOrder {
+        Ok(Self)
+    }
 }
 
 /// A small convenience trait similar to [`Into`] and [`TryInto`].
@@ -73,6 +133,6 @@ pub trait PackSize {
 impl<T> PackSize for T where T: Pack {
     fn pack_size<B>(&self, buffer: &mut [u8]) -> usize where B: ByteOrder {
         self.pack::<B>(buffer);
-        T::SIZE
+        self.encoded_size()
     }
 }
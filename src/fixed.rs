@@ -0,0 +1,419 @@
+//! Fixed-point numbers in Q format.
+//!
+//! A [`Fixed<I, FRAC_BITS>`](Fixed) stores a number as an integer `I` that
+//! counts units of `2^-FRAC_BITS`, e.g. `Fixed<i16, 8>` (Q8.8) represents
+//! `1.5` as `384`. It is encoded exactly like the underlying integer:
+//!
+//! ```ignore
+//! #[derive(Size, Pack, Unpack)]
+//! struct Reading {
+//!     temperature: Fixed<i16, 8>,
+//! }
+//!
+//! let reading = Reading::unpack::<BigEndian>(&[0x01, 0x80])?;
+//! reading.temperature.to_f32()
+//! // => 1.5
+//! ```
+//!
+//! Conversions from floats round to the nearest representable value (or
+//! according to an explicit [`Rounding`]) and are available in checked and
+//! saturating variants. Arithmetic is exact for additions and subtractions
+//! and rounds products and quotients to the nearest value; the operators
+//! panic on overflow, while the `checked_*` and `saturating_*` methods
+//! provide the alternatives.
+//!
+//! In schemas, a fixed-point number is a struct named after its format (e.g.
+//! `FixedI16F8`) with a single field scaled by `2^-FRAC_BITS`, so code
+//! generators and lock files keep the Q format.
+
+use core::{fmt, ops::{Add, Div, Mul, Neg, Sub}};
+
+use byteorder::ByteOrder;
+
+use crate::{Pack, Schema, Size, Unpack, schema::{Field, Type}, unpack::Result};
+
+/// A fixed-point number with `FRAC_BITS` fractional bits, stored as `I`.
+#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Clone, Copy, Default)]
+pub struct Fixed<I, const FRAC_BITS: u32>(I);
+
+/// How to round values that are not exactly representable.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
+pub enum Rounding {
+    /// To the nearest value, with ties rounded away from zero.
+    #[default]
+    Nearest,
+    /// Towards negative infinity.
+    Floor,
+    /// Towards positive infinity.
+    Ceil,
+    /// Towards zero.
+    TowardZero,
+}
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// Integers that can store a [`Fixed`].
+pub trait Base: sealed::Sealed + Copy {
+    /// The number of bits of the type.
+    const BITS: u32;
+
+    #[doc(hidden)]
+    const MIN: i128;
+
+    #[doc(hidden)]
+    const MAX: i128;
+
+    #[doc(hidden)]
+    fn to_i128(self) -> i128;
+
+    /// Truncates the value to the type.
+    #[doc(hidden)]
+    fn from_i128(value: i128) -> Self;
+}
+
+macro_rules! impl_base {
+    ($($t:ty),*) => {
+        $(
+            impl sealed::Sealed for $t {}
+
+            impl Base for $t {
+                const BITS: u32 = <$t>::BITS;
+                const MIN: i128 = <$t>::MIN as i128;
+                const MAX: i128 = <$t>::MAX as i128;
+
+                fn to_i128(self) -> i128 {
+                    self as i128
+                }
+
+                fn from_i128(value: i128) -> Self {
+                    value as $t
+                }
+            }
+        )*
+    };
+}
+
+impl_base!(u8, u16, u32, u64, i8, i16, i32, i64);
+
+/// Divides the magnitudes with the given rounding (applied to the signed quotient).
+fn divide(numerator: u128, denominator: u128, negative: bool, rounding: Rounding) -> u128 {
+    let quotient = numerator / denominator;
+    let remainder = numerator % denominator;
+    let round_up = match rounding {
+        Rounding::Nearest => remainder >= denominator - remainder,
+        Rounding::Floor => negative && remainder != 0,
+        Rounding::Ceil => !negative && remainder != 0,
+        Rounding::TowardZero => false,
+    };
+    quotient + round_up as u128
+}
+
+impl<I, const FRAC_BITS: u32> Fixed<I, FRAC_BITS> where I: Base {
+    /// Rejects formats with more fractional bits than the integer has.
+    const VALID: () = assert!(FRAC_BITS <= I::BITS, "FRAC_BITS exceeds the bits of the base integer");
+
+    /// The number of fractional bits.
+    pub const FRAC_BITS: u32 = FRAC_BITS;
+
+    /// The smallest representable value.
+    pub fn min_value() -> Self {
+        Self::from_bits(I::from_i128(I::MIN))
+    }
+
+    /// The largest representable value.
+    pub fn max_value() -> Self {
+        Self::from_bits(I::from_i128(I::MAX))
+    }
+
+    /// Creates a number from its raw representation, i.e. in units of `2^-FRAC_BITS`.
+    pub fn from_bits(bits: I) -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::VALID;
+        Self(bits)
+    }
+
+    /// The raw representation, i.e. the number in units of `2^-FRAC_BITS`.
+    pub fn to_bits(self) -> I {
+        self.0
+    }
+
+    /// Creates a number from an integer, or `None` if it is out of range.
+    pub fn checked_from_int(value: i64) -> Option<Self> {
+        Self::from_i128((value as i128).checked_mul(1 << FRAC_BITS)?)
+    }
+
+    /// Converts the raw representation, or `None` if it is out of range.
+    fn from_i128(bits: i128) -> Option<Self> {
+        (I::MIN..=I::MAX).contains(&bits).then(|| Self::from_bits(I::from_i128(bits)))
+    }
+
+    /// Converts the raw representation, clamping it to the range.
+    fn saturating_from_i128(bits: i128) -> Self {
+        Self::from_bits(I::from_i128(bits.clamp(I::MIN, I::MAX)))
+    }
+
+    /// The value of one unit as float.
+    fn scale() -> f64 {
+        (1u128 << FRAC_BITS) as f64
+    }
+
+    /// Converts a float with the given rounding, or `None` if it is NaN or
+    /// out of range.
+    pub fn from_f64_rounded(value: f64, rounding: Rounding) -> Option<Self> {
+        if value.is_nan() {
+            return None;
+        }
+        let scaled = value * Self::scale();
+        // Casts truncate towards zero (and saturate at the bounds of i128)
+        let truncated = scaled as i128;
+        let fraction = scaled - truncated as f64;
+        let bits = match rounding {
+            Rounding::Nearest if fraction >= 0.5 => truncated.saturating_add(1),
+            Rounding::Nearest if fraction <= -0.5 => truncated.saturating_sub(1),
+            Rounding::Floor if fraction < 0.0 => truncated.saturating_sub(1),
+            Rounding::Ceil if fraction > 0.0 => truncated.saturating_add(1),
+            _ => truncated,
+        };
+        Self::from_i128(bits)
+    }
+
+    /// Converts a float, rounding to the nearest value, or `None` if it is
+    /// NaN or out of range.
+    pub fn checked_from_f64(value: f64) -> Option<Self> {
+        Self::from_f64_rounded(value, Rounding::Nearest)
+    }
+
+    /// Converts a float, rounding to the nearest value and clamping it to
+    /// the range. NaN is converted to zero.
+    pub fn saturating_from_f64(value: f64) -> Self {
+        match Self::checked_from_f64(value) {
+            Some(fixed) => fixed,
+            None if value.is_nan() => Self::from_bits(I::from_i128(0)),
+            None if value < 0.0 => Self::min_value(),
+            None => Self::max_value(),
+        }
+    }
+
+    /// Converts a float like [`Fixed::saturating_from_f64`].
+    pub fn saturating_from_f32(value: f32) -> Self {
+        Self::saturating_from_f64(value.into())
+    }
+
+    /// Converts a float like [`Fixed::checked_from_f64`].
+    pub fn checked_from_f32(value: f32) -> Option<Self> {
+        Self::checked_from_f64(value.into())
+    }
+
+    /// The number as `f64`, which is exact unless the base integer has more
+    /// than 53 significant bits.
+    pub fn to_f64(self) -> f64 {
+        self.0.to_i128() as f64 / Self::scale()
+    }
+
+    /// The number as `f32`, rounded to the nearest representable value.
+    pub fn to_f32(self) -> f32 {
+        self.to_f64() as f32
+    }
+
+    /// Adds two numbers, or returns `None` on overflow.
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        Self::from_i128(self.0.to_i128() + rhs.0.to_i128())
+    }
+
+    /// Subtracts two numbers, or returns `None` on overflow.
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+        Self::from_i128(self.0.to_i128() - rhs.0.to_i128())
+    }
+
+    /// Multiplies two numbers with the given rounding, or returns `None` on
+    /// overflow.
+    pub fn checked_mul_rounded(self, rhs: Self, rounding: Rounding) -> Option<Self> {
+        Self::from_i128(self.mul_bits(rhs, rounding)?)
+    }
+
+    /// Multiplies two numbers, or returns `None` on overflow.
+    pub fn checked_mul(self, rhs: Self) -> Option<Self> {
+        self.checked_mul_rounded(rhs, Rounding::Nearest)
+    }
+
+    /// Divides two numbers with the given rounding, or returns `None` on
+    /// overflow or division by zero.
+    pub fn checked_div_rounded(self, rhs: Self, rounding: Rounding) -> Option<Self> {
+        Self::from_i128(self.div_bits(rhs, rounding)?)
+    }
+
+    /// Divides two numbers, or returns `None` on overflow or division by zero.
+    pub fn checked_div(self, rhs: Self) -> Option<Self> {
+        self.checked_div_rounded(rhs, Rounding::Nearest)
+    }
+
+    /// Adds two numbers, clamping the result to the range.
+    pub fn saturating_add(self, rhs: Self) -> Self {
+        Self::saturating_from_i128(self.0.to_i128() + rhs.0.to_i128())
+    }
+
+    /// Subtracts two numbers, clamping the result to the range.
+    pub fn saturating_sub(self, rhs: Self) -> Self {
+        Self::saturating_from_i128(self.0.to_i128() - rhs.0.to_i128())
+    }
+
+    /// Multiplies two numbers, clamping the result to the range.
+    pub fn saturating_mul(self, rhs: Self) -> Self {
+        let negative = (self.0.to_i128() < 0) != (rhs.0.to_i128() < 0);
+        match self.mul_bits(rhs, Rounding::Nearest) {
+            Some(bits) => Self::saturating_from_i128(bits),
+            None if negative => Self::min_value(),
+            None => Self::max_value(),
+        }
+    }
+
+    /// The raw representation of the product.
+    fn mul_bits(self, rhs: Self, rounding: Rounding) -> Option<i128> {
+        let (a, b) = (self.0.to_i128(), rhs.0.to_i128());
+        let negative = (a < 0) != (b < 0);
+        // The product of the magnitudes of two 64-bit integers fits into 128 bits
+        let product = a.unsigned_abs() * b.unsigned_abs();
+        let magnitude = divide(product, 1 << FRAC_BITS, negative, rounding);
+        let magnitude = i128::try_from(magnitude).ok()?;
+        Some(if negative { -magnitude } else { magnitude })
+    }
+
+    /// The raw representation of the quotient.
+    fn div_bits(self, rhs: Self, rounding: Rounding) -> Option<i128> {
+        let (a, b) = (self.0.to_i128(), rhs.0.to_i128());
+        if b == 0 {
+            return None;
+        }
+        let negative = (a < 0) != (b < 0);
+        let magnitude = divide(a.unsigned_abs() << FRAC_BITS, b.unsigned_abs(), negative, rounding);
+        let magnitude = i128::try_from(magnitude).ok()?;
+        Some(if negative { -magnitude } else { magnitude })
+    }
+}
+
+impl<I, const FRAC_BITS: u32> fmt::Display for Fixed<I, FRAC_BITS> where I: Base {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.to_f64(), f)
+    }
+}
+
+impl<I, const FRAC_BITS: u32> From<Fixed<I, FRAC_BITS>> for f64 where I: Base {
+    fn from(fixed: Fixed<I, FRAC_BITS>) -> Self {
+        fixed.to_f64()
+    }
+}
+
+impl<I, const FRAC_BITS: u32> Add for Fixed<I, FRAC_BITS> where I: Base {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        self.checked_add(rhs).expect("attempt to add with overflow")
+    }
+}
+
+impl<I, const FRAC_BITS: u32> Sub for Fixed<I, FRAC_BITS> where I: Base {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        self.checked_sub(rhs).expect("attempt to subtract with overflow")
+    }
+}
+
+impl<I, const FRAC_BITS: u32> Mul for Fixed<I, FRAC_BITS> where I: Base {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        self.checked_mul(rhs).expect("attempt to multiply with overflow")
+    }
+}
+
+impl<I, const FRAC_BITS: u32> Div for Fixed<I, FRAC_BITS> where I: Base {
+    type Output = Self;
+
+    fn div(self, rhs: Self) -> Self {
+        assert!(rhs.0.to_i128() != 0, "attempt to divide by zero");
+        self.checked_div(rhs).expect("attempt to divide with overflow")
+    }
+}
+
+impl<I, const FRAC_BITS: u32> Neg for Fixed<I, FRAC_BITS> where I: Base + Neg {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::from_i128(-self.0.to_i128()).expect("attempt to negate with overflow")
+    }
+}
+
+impl<I, const FRAC_BITS: u32> Size for Fixed<I, FRAC_BITS> where I: Base + Size {
+    const SIZE: usize = {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::VALID;
+        I::SIZE
+    };
+}
+
+impl<I, const FRAC_BITS: u32> Pack for Fixed<I, FRAC_BITS> where I: Base + Pack {
+    fn pack<B>(&self, buffer: &mut [u8]) where B: ByteOrder {
+        self.0.pack::<B>(buffer)
+    }
+}
+
+impl<I, const FRAC_BITS: u32> Unpack for Fixed<I, FRAC_BITS> where I: Base + Unpack {
+    fn unpack<B>(buffer: &[u8]) -> Result<Self> where B: ByteOrder {
+        Ok(Self::from_bits(I::unpack::<B>(buffer)?))
+    }
+}
+
+/// The longest schema name, e.g. `FixedI64F64`.
+const NAME_CAPACITY: usize = 11;
+
+/// Writes the decimal digits of a number below 100.
+const fn push_number(name: &mut [u8; NAME_CAPACITY], mut len: usize, number: u32) -> usize {
+    if number >= 10 {
+        name[len] = b'0' + (number / 10) as u8;
+        len += 1;
+    }
+    name[len] = b'0' + (number % 10) as u8;
+    len + 1
+}
+
+impl<I, const FRAC_BITS: u32> Fixed<I, FRAC_BITS> where I: Base {
+    /// The schema name padded to [`NAME_CAPACITY`] and its length, e.g.
+    /// `FixedI16F8` for `Fixed<i16, 8>`.
+    const NAME_BYTES: ([u8; NAME_CAPACITY], usize) = {
+        let mut name = [0; NAME_CAPACITY];
+        let mut len = 0;
+        while len < 5 {
+            name[len] = b"Fixed"[len];
+            len += 1;
+        }
+        name[len] = if I::MIN < 0 { b'I' } else { b'U' };
+        len = push_number(&mut name, len + 1, I::BITS);
+        name[len] = b'F';
+        len = push_number(&mut name, len + 1, FRAC_BITS);
+        (name, len)
+    };
+
+    const NAME: &'static str = {
+        let bytes: &'static [u8; NAME_CAPACITY] = &Self::NAME_BYTES.0;
+        match core::str::from_utf8(bytes.split_at(Self::NAME_BYTES.1).0) {
+            Ok(name) => name,
+            Err(_) => panic!("schema names are ASCII"),
+        }
+    };
+}
+
+/// Described as a struct with a single field `value`, the raw integer with a
+/// scale of `2^-FRAC_BITS`.
+impl<I, const FRAC_BITS: u32> Schema for Fixed<I, FRAC_BITS> where I: Base + Schema {
+    const SCHEMA: Type = {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::VALID;
+        Type::Struct {
+            name: Self::NAME,
+            fields: &[Field { name: "value", ty: &I::SCHEMA, scale: 1.0 / (1u128 << FRAC_BITS) as f64, offset: 0.0 }],
+        }
+    };
+}
@@ -0,0 +1,257 @@
+//! Reduced-precision floats.
+//!
+//! [`F16`] (IEEE 754 half precision), [`BF16`] (bfloat16) and the 8-bit
+//! [`Minifloat`] formats store floats in fewer bits and are encoded like
+//! their raw bits, i.e. as `u16` or `u8` (described as
+//! [`Type::Float`] in schemas). The conversions are implemented in
+//! software and therefore available in `no_std` environments. They round to
+//! the nearest representable value (with ties to even), produce infinities
+//! for values that are out of range, keep subnormals and signed zeros and
+//! map NaNs to quiet NaNs with the same sign:
+//!
+//! ```ignore
+//! let half = F16::from_f32(1.5);
+//! half.to_bits()
+//! // => 0x3e00
+//! ```
+//!
+//! All formats follow the IEEE 754 conventions: an exponent of all ones
+//! denotes infinities (with a zero mantissa) and NaNs, and an exponent of
+//! zero denotes zeros and subnormals. Comparisons follow the float
+//! semantics, i.e. NaNs are not equal to anything and `-0.0 == 0.0`.
+
+use core::{cmp::Ordering, fmt};
+
+use byteorder::ByteOrder;
+
+use crate::{Pack, Schema, Size, Unpack, schema::{Primitive, Type}, unpack::Result};
+
+/// An IEEE 754 half-precision float (1 sign, 5 exponent and 10 mantissa bits).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct F16(u16);
+
+/// A bfloat16 float (1 sign, 8 exponent and 7 mantissa bits), i.e. the most
+/// significant half of an `f32`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BF16(u16);
+
+/// An 8-bit float with `EXP_BITS` exponent and `MANTISSA_BITS` mantissa
+/// bits, which have to add up to 7, e.g. `Minifloat<4, 3>` (E4M3) or
+/// `Minifloat<5, 2>` (E5M2).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Minifloat<const EXP_BITS: u32, const MANTISSA_BITS: u32>(u8);
+
+/// 2 to the given power, which has to be in the range of normal `f64`s.
+fn power_of_two(exp: i32) -> f64 {
+    f64::from_bits(((exp + 1023) as u64) << 52)
+}
+
+/// Shifts the value right, rounding to the nearest value with ties to even.
+fn shift_right_even(value: u64, shift: u32) -> u64 {
+    if shift == 0 {
+        return value;
+    }
+    if shift >= 64 {
+        // The value is less than half of the smallest unit
+        return 0;
+    }
+    let truncated = value >> shift;
+    let remainder = value & ((1 << shift) - 1);
+    let half = 1 << (shift - 1);
+    if remainder > half || (remainder == half && truncated & 1 == 1) {
+        truncated + 1
+    } else {
+        truncated
+    }
+}
+
+/// Encodes a float in the format with the given number of exponent and
+/// mantissa bits (and a sign bit).
+fn encode(value: f64, exp_bits: u32, mantissa_bits: u32) -> u64 {
+    let bits = value.to_bits();
+    let sign = (bits >> 63) << (exp_bits + mantissa_bits);
+    let exp_mask = (1u64 << exp_bits) - 1;
+    let raw_exp = ((bits >> 52) & 0x7ff) as i32;
+    let raw_mantissa = bits & ((1 << 52) - 1);
+
+    if raw_exp == 0x7ff {
+        if raw_mantissa == 0 {
+            return sign | (exp_mask << mantissa_bits);
+        }
+        // Keep the most significant payload bits and set the quiet bit
+        let payload = raw_mantissa >> (52 - mantissa_bits);
+        return sign | (exp_mask << mantissa_bits) | payload | (1 << (mantissa_bits - 1));
+    }
+    if raw_exp == 0 && raw_mantissa == 0 {
+        return sign;
+    }
+
+    // The value is `significand * 2^exp`
+    let (significand, exp) = if raw_exp == 0 {
+        (raw_mantissa, -1074)
+    } else {
+        (raw_mantissa | (1 << 52), raw_exp - 1075)
+    };
+    let bias = (1i32 << (exp_bits - 1)) - 1;
+    let min_exp = 1 - bias;
+    let magnitude = exp + 63 - significand.leading_zeros() as i32;
+    if magnitude > bias {
+        return sign | (exp_mask << mantissa_bits);
+    }
+
+    // The exponent of the least significant mantissa bit in the target format
+    let unit = magnitude.max(min_exp) - mantissa_bits as i32;
+    let mut mantissa = if exp >= unit {
+        significand << (exp - unit)
+    } else {
+        shift_right_even(significand, (unit - exp) as u32)
+    };
+    let mut unit = unit;
+    if mantissa >> (mantissa_bits + 1) != 0 {
+        // Rounding carried into the next power of two
+        mantissa >>= 1;
+        unit += 1;
+    }
+
+    if mantissa >> mantissa_bits == 0 {
+        // Subnormal (or zero after rounding)
+        sign | mantissa
+    } else {
+        let biased = unit + mantissa_bits as i32 + bias;
+        if biased >= exp_mask as i32 {
+            sign | (exp_mask << mantissa_bits)
+        } else {
+            sign | ((biased as u64) << mantissa_bits) | (mantissa & ((1 << mantissa_bits) - 1))
+        }
+    }
+}
+
+/// Decodes a float in the format with the given number of exponent and
+/// mantissa bits (and a sign bit).
+pub(crate) fn decode(bits: u64, exp_bits: u32, mantissa_bits: u32) -> f64 {
+    let negative = (bits >> (exp_bits + mantissa_bits)) & 1 == 1;
+    let exp_mask = (1u64 << exp_bits) - 1;
+    let raw_exp = (bits >> mantissa_bits) & exp_mask;
+    let mantissa = bits & ((1 << mantissa_bits) - 1);
+    let bias = (1i32 << (exp_bits - 1)) - 1;
+
+    let magnitude = if raw_exp == exp_mask {
+        if mantissa == 0 {
+            f64::INFINITY
+        } else {
+            // Move the payload to the most significant mantissa bits
+            return f64::from_bits(((negative as u64) << 63) | (0x7ff << 52) | (mantissa << (52 - mantissa_bits)));
+        }
+    } else if raw_exp == 0 {
+        mantissa as f64 * power_of_two(1 - bias - mantissa_bits as i32)
+    } else {
+        (mantissa | (1 << mantissa_bits)) as f64 * power_of_two(raw_exp as i32 - bias - mantissa_bits as i32)
+    };
+    if negative { -magnitude } else { magnitude }
+}
+
+macro_rules! impl_float {
+    ($name:ident $(<$(const $param:ident: u32),*>)?, $bits:ty, $repr:ident, $exp_bits:expr, $mantissa_bits:expr, $check:expr) => {
+        impl $(<$(const $param: u32),*>)? $name $(<$($param),*>)? {
+            /// Creates a float from its raw bits.
+            pub const fn from_bits(bits: $bits) -> Self {
+                Self(bits)
+            }
+
+            /// The raw bits.
+            pub const fn to_bits(self) -> $bits {
+                self.0
+            }
+
+            /// Converts an `f64`, rounding to the nearest representable value.
+            pub fn from_f64(value: f64) -> Self {
+                #[allow(clippy::let_unit_value)]
+                let () = $check;
+                Self(encode(value, $exp_bits, $mantissa_bits) as $bits)
+            }
+
+            /// Converts an `f32`, rounding to the nearest representable value.
+            pub fn from_f32(value: f32) -> Self {
+                Self::from_f64(value.into())
+            }
+
+            /// The value as `f64` (exact).
+            pub fn to_f64(self) -> f64 {
+                decode(self.0.into(), $exp_bits, $mantissa_bits)
+            }
+
+            /// The value as `f32` (exact).
+            pub fn to_f32(self) -> f32 {
+                self.to_f64() as f32
+            }
+
+            /// Whether the value is NaN.
+            pub fn is_nan(self) -> bool {
+                self.to_f64().is_nan()
+            }
+        }
+
+        impl $(<$(const $param: u32),*>)? PartialEq for $name $(<$($param),*>)? {
+            fn eq(&self, other: &Self) -> bool {
+                self.to_f64() == other.to_f64()
+            }
+        }
+
+        impl $(<$(const $param: u32),*>)? PartialOrd for $name $(<$($param),*>)? {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                self.to_f64().partial_cmp(&other.to_f64())
+            }
+        }
+
+        impl $(<$(const $param: u32),*>)? fmt::Display for $name $(<$($param),*>)? {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Display::fmt(&self.to_f64(), f)
+            }
+        }
+
+        impl $(<$(const $param: u32),*>)? From<$name $(<$($param),*>)?> for f32 {
+            fn from(value: $name $(<$($param),*>)?) -> Self {
+                value.to_f32()
+            }
+        }
+
+        impl $(<$(const $param: u32),*>)? From<$name $(<$($param),*>)?> for f64 {
+            fn from(value: $name $(<$($param),*>)?) -> Self {
+                value.to_f64()
+            }
+        }
+
+        impl $(<$(const $param: u32),*>)? Size for $name $(<$($param),*>)? {
+            const SIZE: usize = <$bits>::SIZE;
+        }
+
+        impl $(<$(const $param: u32),*>)? Pack for $name $(<$($param),*>)? {
+            fn pack<B>(&self, buffer: &mut [u8]) where B: ByteOrder {
+                self.0.pack::<B>(buffer)
+            }
+        }
+
+        impl $(<$(const $param: u32),*>)? Unpack for $name $(<$($param),*>)? {
+            fn unpack<B>(buffer: &[u8]) -> Result<Self> where B: ByteOrder {
+                Ok(Self(<$bits>::unpack::<B>(buffer)?))
+            }
+        }
+
+        impl $(<$(const $param: u32),*>)? Schema for $name $(<$($param),*>)? {
+            const SCHEMA: Type = {
+                #[allow(clippy::let_unit_value)]
+                let () = $check;
+                Type::Float { repr: Primitive::$repr, exp_bits: $exp_bits, mantissa_bits: $mantissa_bits }
+            };
+        }
+    };
+}
+
+impl_float!(F16, u16, U16, 5, 10, ());
+impl_float!(BF16, u16, U16, 8, 7, ());
+impl_float!(Minifloat<const EXP_BITS: u32, const MANTISSA_BITS: u32>, u8, U8, EXP_BITS, MANTISSA_BITS, Self::VALID);
+
+impl<const EXP_BITS: u32, const MANTISSA_BITS: u32> Minifloat<EXP_BITS, MANTISSA_BITS> {
+    /// Rejects formats that do not fit into 8 bits.
+    const VALID: () = assert!(EXP_BITS >= 2 && MANTISSA_BITS >= 1 && EXP_BITS + MANTISSA_BITS == 7, "the exponent and mantissa bits have to add up to 7");
+}
@@ -0,0 +1,74 @@
+//! A minimal handshake for checking that two peers agree on the layout of
+//! their messages before exchanging them.
+//!
+//! Each peer sends the [`Schema::SCHEMA_HASH`] of its message type as a
+//! big-endian `u64` and compares it with the one received from the other
+//! peer. The transport is abstracted by closures, which keeps the handshake
+//! usable without `std`:
+//!
+//! ```ignore
+//! handshake::exchange::<Frame, _>(
+//!     |message| stream.write_all(message),
+//!     |message| stream.read_exact(message),
+//! )?;
+//! ```
+
+use byteorder::{BigEndian, ByteOrder};
+
+use crate::Schema;
+
+/// The encoded size of a handshake message in bytes.
+pub const SIZE: usize = 8;
+
+/// The peers use different layouts.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct Mismatch {
+    /// The local fingerprint.
+    pub local: u64,
+    /// The fingerprint received from the peer.
+    pub remote: u64,
+}
+
+/// An error during a handshake.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Error<E> {
+    /// Sending or receiving a handshake message failed.
+    Transport(E),
+    /// The peers use different layouts.
+    Mismatch(Mismatch),
+}
+
+impl<E> From<Mismatch> for Error<E> {
+    fn from(mismatch: Mismatch) -> Self {
+        Error::Mismatch(mismatch)
+    }
+}
+
+/// The handshake message announcing the layout of `T`.
+pub fn message<T>() -> [u8; SIZE] where T: Schema {
+    let mut message = [0; SIZE];
+    BigEndian::write_u64(&mut message, T::SCHEMA_HASH);
+    message
+}
+
+/// Checks a handshake message received from the peer against the layout of `T`.
+pub fn verify<T>(message: &[u8; SIZE]) -> Result<(), Mismatch> where T: Schema {
+    let remote = BigEndian::read_u64(message);
+    if remote == T::SCHEMA_HASH {
+        Ok(())
+    } else {
+        Err(Mismatch { local: T::SCHEMA_HASH, remote })
+    }
+}
+
+/// Performs a handshake by sending the message for `T` and verifying the
+/// message received from the peer.
+pub fn exchange<T, E>(
+    send: impl FnOnce(&[u8; SIZE]) -> Result<(), E>,
+    receive: impl FnOnce(&mut [u8; SIZE]) -> Result<(), E>,
+) -> Result<(), Error<E>> where T: Schema {
+    send(&message::<T>()).map_err(Error::Transport)?;
+    let mut remote = [0; SIZE];
+    receive(&mut remote).map_err(Error::Transport)?;
+    Ok(verify::<T>(&remote)?)
+}
@@ -0,0 +1,149 @@
+//! Integers with odd widths.
+//!
+//! [`U24`], [`I24`], [`U40`], [`U48`] and [`I48`] are stored in the next
+//! larger native integer and encoded in exactly 3, 5 or 6 bytes, e.g. for
+//! audio samples or hardware registers:
+//!
+//! ```ignore
+//! #[derive(Size, Pack, Unpack)]
+//! struct Sample {
+//!     left: I24,
+//!     right: I24,
+//! }
+//!
+//! let sample = Sample::unpack::<LittleEndian>(&[0xff, 0xff, 0xff, 0x01, 0x00, 0x00])?;
+//! (sample.left.get(), sample.right.get())
+//! // => (-1, 1)
+//! ```
+//!
+//! Signed values are sign-extended when decoded. Conversions from the native
+//! integer are checked (via [`new`](U24::new) or `TryFrom`), while
+//! conversions to it are lossless (via [`get`](U24::get) or `From`).
+
+use core::fmt;
+
+use byteorder::ByteOrder;
+
+use crate::{Pack, Schema, Size, Unpack, convert::{self, Number}, schema::{Primitive, Type}, unpack::Result};
+
+/// The error when converting an integer that does not fit into the narrower type.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct TryFromIntError;
+
+impl fmt::Display for TryFromIntError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("out of range integral type conversion attempted")
+    }
+}
+
+macro_rules! impl_int {
+    ($(#[$attr:meta])* $name:ident, $native:ty, $bits:expr, $min:expr, $max:expr, $write:ident, $read:ident, $wide:ty, $($from:ty),*) => {
+        $(#[$attr])*
+        #[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Clone, Copy, Default)]
+        pub struct $name($native);
+
+        impl $name {
+            /// The number of bits of the type.
+            pub const BITS: u32 = $bits;
+
+            /// The smallest representable value.
+            pub const MIN: Self = Self($min);
+
+            /// The largest representable value.
+            pub const MAX: Self = Self($max);
+
+            /// Creates a value, or returns `None` if it is out of range.
+            pub const fn new(value: $native) -> Option<Self> {
+                if value >= Self::MIN.0 && value <= Self::MAX.0 {
+                    Some(Self(value))
+                } else {
+                    None
+                }
+            }
+
+            /// The value as native integer.
+            pub const fn get(self) -> $native {
+                self.0
+            }
+        }
+
+        impl TryFrom<$native> for $name {
+            type Error = TryFromIntError;
+
+            fn try_from(value: $native) -> core::result::Result<Self, Self::Error> {
+                Self::new(value).ok_or(TryFromIntError)
+            }
+        }
+
+        impl From<$name> for $native {
+            fn from(value: $name) -> Self {
+                value.0
+            }
+        }
+
+        $(
+            impl From<$from> for $name {
+                fn from(value: $from) -> Self {
+                    Self(value.into())
+                }
+            }
+        )*
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Display::fmt(&self.0, f)
+            }
+        }
+
+        impl Size for $name {
+            const SIZE: usize = $bits / 8;
+        }
+
+        impl Schema for $name {
+            const SCHEMA: Type = Type::Primitive(Primitive::$name);
+        }
+
+        impl Pack for $name {
+            fn pack<B>(&self, buffer: &mut [u8]) where B: ByteOrder {
+                B::$write(buffer, self.0 as $wide, Self::SIZE);
+            }
+        }
+
+        impl Unpack for $name {
+            fn unpack<B>(buffer: &[u8]) -> Result<Self> where B: ByteOrder {
+                Ok(Self(B::$read(buffer, Self::SIZE) as $native))
+            }
+        }
+
+        impl Number for $name {
+            fn to_value(self) -> convert::Value {
+                self.0.to_value()
+            }
+
+            fn from_value(value: convert::Value) -> Option<Self> {
+                Self::new(<$native>::from_value(value)?)
+            }
+        }
+    };
+}
+
+impl_int!(
+    /// A 24-bit unsigned integer.
+    U24, u32, 24, 0, (1 << 24) - 1, write_uint, read_uint, u64, u8, u16
+);
+impl_int!(
+    /// A 24-bit signed integer.
+    I24, i32, 24, -(1 << 23), (1 << 23) - 1, write_int, read_int, i64, i8, i16, u8, u16
+);
+impl_int!(
+    /// A 40-bit unsigned integer.
+    U40, u64, 40, 0, (1 << 40) - 1, write_uint, read_uint, u64, u8, u16, u32
+);
+impl_int!(
+    /// A 48-bit unsigned integer.
+    U48, u64, 48, 0, (1 << 48) - 1, write_uint, read_uint, u64, u8, u16, u32
+);
+impl_int!(
+    /// A 48-bit signed integer.
+    I48, i64, 48, -(1 << 47), (1 << 47) - 1, write_int, read_int, i64, i8, i16, i32, u8, u16, u32
+);
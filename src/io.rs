@@ -0,0 +1,171 @@
+//! Reading and writing packed values via `embedded-io`.
+//!
+//! With the `embedded-io` feature, [`WriteExt::write_packed`] and
+//! [`ReadExt::read_unpacked`] transfer a value over any blocking
+//! `embedded_io::Write` or `embedded_io::Read`, e.g. a UART. With the
+//! `embedded-io-async` feature, `AsyncWriteExt::write_packed_async` and
+//! `AsyncReadExt::read_unpacked_async` do the same for `embedded_io_async`:
+//!
+//! ```ignore
+//! uart.write_packed::<BigEndian, _>(&message)?;
+//! let reply = uart.read_unpacked::<BigEndian, Reply>()?;
+//! // or with `embedded_io_async`
+//! let reply = uart.read_unpacked_async::<BigEndian, Reply>().await?;
+//! ```
+//!
+//! The value is packed into (or unpacked from) a scratch buffer of
+//! [`SCRATCH_SIZE`] bytes on the stack, so `T::SIZE` must not exceed it,
+//! which is checked at compile time. Values with a variable encoded size,
+//! e.g. [`Varint`](crate::varint::Varint)s, only transfer their actual size:
+//! they are read in steps until they are complete, without reading past
+//! their end.
+
+use core::{fmt, marker::PhantomData};
+
+use byteorder::ByteOrder;
+use embedded_io::ReadExactError;
+
+use crate::{Pack, Size, Unpack, pack, unpack};
+
+/// An error while reading or writing a packed value.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Error<E> {
+    /// The underlying reader or writer failed.
+    Io(E),
+    /// The reader reached its end before the value was complete.
+    UnexpectedEof,
+    Pack(pack::Error),
+    Unpack(unpack::Error),
+}
+
+impl<E> fmt::Display for Error<E> where E: fmt::Debug {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "I/O error: {:?}", e),
+            Error::UnexpectedEof => f.write_str("unexpected end of input"),
+            Error::Pack(e) => write!(f, "could not pack value: {:?}", e),
+            Error::Unpack(e) => write!(f, "could not unpack value: {:?}", e),
+        }
+    }
+}
+
+impl<E> From<ReadExactError<E>> for Error<E> {
+    fn from(error: ReadExactError<E>) -> Self {
+        match error {
+            ReadExactError::UnexpectedEof => Error::UnexpectedEof,
+            ReadExactError::Other(e) => Error::Io(e),
+        }
+    }
+}
+
+/// The result type for reading and writing packed values.
+pub type Result<T, E> = core::result::Result<T, Error<E>>;
+
+/// The maximum size of values that can be read or written.
+pub const SCRATCH_SIZE: usize = 256;
+
+/// Rejects values that do not fit in the scratch buffer.
+struct Check<T>(PhantomData<T>);
+
+impl<T> Check<T> where T: Size {
+    const VALID: () = assert!(T::SIZE <= SCRATCH_SIZE, "T::SIZE exceeds the SCRATCH_SIZE of the I/O helpers");
+}
+
+/// Packs a value into the scratch buffer and returns its encoded size.
+fn pack_scratch<B, T>(value: &T, scratch: &mut [u8; SCRATCH_SIZE]) -> pack::Result<usize> where B: ByteOrder, T: Pack {
+    #[allow(clippy::let_unit_value)]
+    let () = Check::<T>::VALID;
+    let size = value.encoded_size();
+    value.try_pack::<B>(&mut scratch[..size])?;
+    Ok(size)
+}
+
+/// The next step of reading a value.
+enum Step<T> {
+    /// The value is complete.
+    Done(T),
+    /// The value needs the given number of bytes (in total) to continue.
+    Read(usize),
+}
+
+/// Unpacks a value from the bytes read so far, or returns how many bytes
+/// are needed.
+fn unpack_scratch<B, T, E>(scratch: &[u8]) -> Result<Step<T>, E> where B: ByteOrder, T: Unpack {
+    #[allow(clippy::let_unit_value)]
+    let () = Check::<T>::VALID;
+    match T::unpack_safely::<B>(scratch) {
+        Ok(value) => Ok(Step::Done(value)),
+        Err(unpack::Error::BufferTooSmall { expected, .. }) if expected > scratch.len() && expected <= T::SIZE => Ok(Step::Read(expected)),
+        Err(e) => Err(Error::Unpack(e)),
+    }
+}
+
+/// Writes packed values to an `embedded_io::Write`.
+pub trait WriteExt: embedded_io::Write {
+    /// Packs the value and writes its encoded size.
+    fn write_packed<B, T>(&mut self, value: &T) -> Result<(), Self::Error> where B: ByteOrder, T: Pack {
+        let mut scratch = [0; SCRATCH_SIZE];
+        let size = pack_scratch::<B, T>(value, &mut scratch).map_err(Error::Pack)?;
+        self.write_all(&scratch[..size]).map_err(Error::Io)
+    }
+}
+
+impl<W> WriteExt for W where W: embedded_io::Write + ?Sized {}
+
+/// Reads packed values from an `embedded_io::Read`.
+pub trait ReadExt: embedded_io::Read {
+    /// Reads the bytes of a value and unpacks it.
+    fn read_unpacked<B, T>(&mut self) -> Result<T, Self::Error> where B: ByteOrder, T: Unpack {
+        let mut scratch = [0; SCRATCH_SIZE];
+        let mut len = 0;
+        loop {
+            match unpack_scratch::<B, T, Self::Error>(&scratch[..len])? {
+                Step::Done(value) => return Ok(value),
+                Step::Read(size) => {
+                    self.read_exact(&mut scratch[len..size])?;
+                    len = size;
+                },
+            }
+        }
+    }
+}
+
+impl<R> ReadExt for R where R: embedded_io::Read + ?Sized {}
+
+/// Writes packed values to an `embedded_io_async::Write`.
+#[cfg(feature = "embedded-io-async")]
+#[allow(async_fn_in_trait)]
+pub trait AsyncWriteExt: embedded_io_async::Write {
+    /// Packs the value and writes its encoded size.
+    async fn write_packed_async<B, T>(&mut self, value: &T) -> Result<(), Self::Error> where B: ByteOrder, T: Pack {
+        let mut scratch = [0; SCRATCH_SIZE];
+        let size = pack_scratch::<B, T>(value, &mut scratch).map_err(Error::Pack)?;
+        self.write_all(&scratch[..size]).await.map_err(Error::Io)
+    }
+}
+
+#[cfg(feature = "embedded-io-async")]
+impl<W> AsyncWriteExt for W where W: embedded_io_async::Write + ?Sized {}
+
+/// Reads packed values from an `embedded_io_async::Read`.
+#[cfg(feature = "embedded-io-async")]
+#[allow(async_fn_in_trait)]
+pub trait AsyncReadExt: embedded_io_async::Read {
+    /// Reads the bytes of a value and unpacks it.
+    async fn read_unpacked_async<B, T>(&mut self) -> Result<T, Self::Error> where B: ByteOrder, T: Unpack {
+        let mut scratch = [0; SCRATCH_SIZE];
+        let mut len = 0;
+        loop {
+            match unpack_scratch::<B, T, Self::Error>(&scratch[..len])? {
+                Step::Done(value) => return Ok(value),
+                Step::Read(size) => {
+                    self.read_exact(&mut scratch[len..size]).await?;
+                    len = size;
+                },
+            }
+        }
+    }
+}
+
+#[cfg(feature = "embedded-io-async")]
+impl<R> AsyncReadExt for R where R: embedded_io_async::Read + ?Sized {}
@@ -0,0 +1,20 @@
+//! Field layouts of derived types.
+
+/// The position of a single field within a type's encoded representation.
+///
+/// Tables of these are emitted as `LAYOUT` constants by `#[derive(Size)]`,
+/// alongside `OFFSET_<FIELD>` constants for each field. Both are only
+/// available if the fields involved have a fixed size, see
+/// [`Size::FIXED_SIZE`](crate::Size::FIXED_SIZE).
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct FieldLayout {
+    /// The field's name (or index for tuple structs).
+    pub name: &'static str,
+    /// The field's offset from the start of the encoded type in bytes.
+    pub offset: usize,
+    /// The field's encoded size in bytes.
+    pub size: usize,
+    /// The field's type as written in the source, e.g. `Option<T>` for a
+    /// generic field, with type parameters left unsubstituted.
+    pub type_name: &'static str,
+}
@@ -31,15 +31,71 @@
 //! Point::unpack::<BigEndian>(&[0, 3, 0, 4]).unwrap()
 //! // => Point { x: 3, y: 4 }
 //! ```
+//! 
+//! Fields can be transmitted in a different numeric type with
+//! `#[lightpack(as = u8)]`, optionally scaled and offset (see [`convert`]).
+//! [`Pack::try_pack`] reports values that do not fit instead of panicking.
+//! 
+//! Derived structs also expose their field offsets as associated constants
+//! (e.g. `Point::OFFSET_Y == 2`) and as a [`layout::FieldLayout`] table in `Point::LAYOUT`.
+//! Fields are packed back to back with their [`Size::encoded_size`], so the
+//! offsets (and views) only apply to structs without variable-size fields like
+//! [`varint`]s.
+//! 
+//! Deriving [`Schema`] provides a static description of the wire format in
+//! `Point::SCHEMA`, e.g. for generating documentation or codecs in other languages,
+//! and a layout fingerprint in `Point::SCHEMA_HASH`, which peers can compare
+//! with the [`handshake`] helpers.
+//! 
+//! Deriving `View` additionally generates `PointView` and `PointViewMut`, which
+//! read and patch individual fields of an already packed buffer in place:
+//! 
+//! ```ignore
+//! let mut view = PointViewMut::<BigEndian>::new(&mut buffer);
+//! view.set_x(5);
+//! // => buffer == [0, 5, 0, 4]
+//! ```
+//! 
+//! With the `serde` feature, the [`serde`] module additionally encodes types
+//! that only implement serde's `Serialize` and `Deserialize` in the same format,
+//! and `#[derive(Schema)]` with `#[lightpack(serde)]` generates serde impls
+//! that match the schema, e.g. skipping [`Reserved`](extra::Reserved) fields
+//! in human-readable formats like JSON.
+//! With the `embedded-io` and `embedded-io-async` features, the `io` module
+//! reads and writes packed values over `embedded-io` streams, and with the
+//! `bytes` feature, the `buf` module does so for `bytes::Buf` and `BufMut`.
 
 #![no_std]
 
+#[cfg(feature = "bytes")]
+extern crate alloc;
+
+pub mod bcd;
+pub mod bounded;
+#[cfg(feature = "bytes")]
+pub mod buf;
+pub mod collections;
+pub mod convert;
 pub mod extra;
+pub mod fixed;
+pub mod float;
+pub mod handshake;
+pub mod int;
+#[cfg(feature = "embedded-io")]
+pub mod io;
+pub mod layout;
 pub mod pack;
+pub mod schema;
+#[cfg(feature = "serde")]
+pub mod serde;
 pub mod size;
+pub mod string;
+pub mod timestamp;
 pub mod unpack;
+pub mod varint;
 
 pub use pack::Pack;
+pub use schema::Schema;
 pub use size::Size;
 pub use unpack::Unpack;
 
@@ -4,10 +4,41 @@ use byteorder::ByteOrder;
 
 use crate::Size;
 
+/// An error during packing.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Error {
+    /// A value does not fit into its encoded representation, e.g. a field
+    /// with a narrower `#[lightpack(as = ...)]` type.
+    OutOfRange,
+    BufferTooSmall { actual: usize, expected: usize },
+}
+
+/// The result type for fallible packing.
+pub type Result<T> = core::result::Result<T, Error>;
+
 /// Types that can be encoded to a binary representation.
 pub trait Pack: Size {
     /// Encodes `self` to a binary representation.
+    /// Panics if the buffer is too small or a value is out of range.
     fn pack<B>(&self, buffer: &mut [u8]) where B: ByteOrder;
+
+    /// Encodes `self` and returns an error if the buffer is too small or a
+    /// value is out of range.
+    fn try_pack<B>(&self, buffer: &mut [u8]) -> Result<()> where B: ByteOrder {
+        check_size(self, buffer)?;
+        self.pack::<B>(buffer);
+        Ok(())
+    }
+}
+
+/// Checks that the buffer can hold the encoded value.
+fn check_size<T>(value: &T, buffer: &[u8]) -> Result<()> where T: Size + ?Sized {
+    let size = value.encoded_size();
+    if buffer.len() < size {
+        Err(Error::BufferTooSmall { actual: buffer.len(), expected: size })
+    } else {
+        Ok(())
+    }
 }
 
 // TODO: Abstract over this with a macro
@@ -96,48 +127,87 @@ impl<T0> Pack for (T0,) where T0: Pack {
     fn pack<B>(&self, buffer: &mut [u8]) where B: ByteOrder {
         self.0.pack::<B>(buffer);
     }
+
+    fn try_pack<B>(&self, buffer: &mut [u8]) -> Result<()> where B: ByteOrder {
+        self.0.try_pack::<B>(buffer)
+    }
 }
 
 impl<T0, T1> Pack for (T0, T1) where T0: Pack, T1: Pack {
     fn pack<B>(&self, buffer: &mut [u8]) where B: ByteOrder {
         self.0.pack::<B>(buffer);
-        let buffer = &mut buffer[T0::SIZE..];
+        let buffer = &mut buffer[self.0.encoded_size()..];
         self.1.pack::<B>(buffer);
     }
+
+    fn try_pack<B>(&self, buffer: &mut [u8]) -> Result<()> where B: ByteOrder {
+        check_size(self, buffer)?;
+        self.0.try_pack::<B>(buffer)?;
+        let buffer = &mut buffer[self.0.encoded_size()..];
+        self.1.try_pack::<B>(buffer)
+    }
 }
 
 impl<T0, T1, T2> Pack for (T0, T1, T2) where T0: Pack, T1: Pack, T2: Pack {
     fn pack<B>(&self, buffer: &mut [u8]) where B: ByteOrder {
         self.0.pack::<B>(buffer);
-        let buffer = &mut buffer[T0::SIZE..];
+        let buffer = &mut buffer[self.0.encoded_size()..];
         self.1.pack::<B>(buffer);
-        let buffer = &mut buffer[T1::SIZE..];
+        let buffer = &mut buffer[self.1.encoded_size()..];
         self.2.pack::<B>(buffer);
     }
+
+    fn try_pack<B>(&self, buffer: &mut [u8]) -> Result<()> where B: ByteOrder {
+        check_size(self, buffer)?;
+        self.0.try_pack::<B>(buffer)?;
+        let buffer = &mut buffer[self.0.encoded_size()..];
+        self.1.try_pack::<B>(buffer)?;
+        let buffer = &mut buffer[self.1.encoded_size()..];
+        self.2.try_pack::<B>(buffer)
+    }
 }
 
 impl<T0, T1, T2, T3> Pack for (T0, T1, T2, T3) where T0: Pack, T1: Pack, T2: Pack, T3: Pack {
     fn pack<B>(&self, buffer: &mut [u8]) where B: ByteOrder {
         self.0.pack::<B>(buffer);
-        let buffer = &mut buffer[T0::SIZE..];
+        let buffer = &mut buffer[self.0.encoded_size()..];
         self.1.pack::<B>(buffer);
-        let buffer = &mut buffer[T1::SIZE..];
+        let buffer = &mut buffer[self.1.encoded_size()..];
         self.2.pack::<B>(buffer);
-        let buffer = &mut buffer[T2::SIZE..];
+        let buffer = &mut buffer[self.2.encoded_size()..];
         self.3.pack::<B>(buffer);
     }
+
+    fn try_pack<B>(&self, buffer: &mut [u8]) -> Result<()> where B: ByteOrder {
+        check_size(self, buffer)?;
+        self.0.try_pack::<B>(buffer)?;
+        let buffer = &mut buffer[self.0.encoded_size()..];
+        self.1.try_pack::<B>(buffer)?;
+        let buffer = &mut buffer[self.1.encoded_size()..];
+        self.2.try_pack::<B>(buffer)?;
+        let buffer = &mut buffer[self.2.encoded_size()..];
+        self.3.try_pack::<B>(buffer)
+    }
 }
 
 impl<T> Pack for &T where T: Pack {
     fn pack<B>(&self, buffer: &mut [u8]) where B: ByteOrder {
         T::pack::<B>(self, buffer)
     }
+
+    fn try_pack<B>(&self, buffer: &mut [u8]) -> Result<()> where B: ByteOrder {
+        T::try_pack::<B>(self, buffer)
+    }
 }
 
 impl<T> Pack for &mut T where T: Pack {
     fn pack<B>(&self, buffer: &mut [u8]) where B: ByteOrder {
         T::pack::<B>(self, buffer)
     }
+
+    fn try_pack<B>(&self, buffer: &mut [u8]) -> Result<()> where B: ByteOrder {
+        T::try_pack::<B>(self, buffer)
+    }
 }
 
 impl<T> Pack for Option<T> where T: Pack {
@@ -147,13 +217,30 @@ impl<T> Pack for Option<T> where T: Pack {
             None => false.pack::<B>(buffer),
         }
     }
+
+    fn try_pack<B>(&self, buffer: &mut [u8]) -> Result<()> where B: ByteOrder {
+        check_size(self, buffer)?;
+        match self {
+            Some(x) => (true, x).try_pack::<B>(buffer),
+            None => false.try_pack::<B>(buffer),
+        }
+    }
 }
 
 impl<T, const N: usize> Pack for [T; N] where T: Pack {
     fn pack<B>(&self, mut buffer: &mut [u8]) where B: ByteOrder {
         for value in self {
             value.pack::<B>(buffer);
-            buffer = &mut buffer[T::SIZE..];
+            buffer = &mut buffer[value.encoded_size()..];
+        }
+    }
+
+    fn try_pack<B>(&self, mut buffer: &mut [u8]) -> Result<()> where B: ByteOrder {
+        check_size(self, buffer)?;
+        for value in self {
+            value.try_pack::<B>(buffer)?;
+            buffer = &mut buffer[value.encoded_size()..];
         }
+        Ok(())
     }
 }
@@ -0,0 +1,529 @@
+//! The [`Schema`] trait and static descriptions of the wire format.
+//!
+//! A type's schema describes its encoded representation in enough detail
+//! to reconstruct it without access to the Rust type, e.g. to generate
+//! documentation or codecs for other languages. Schemas are plain static data
+//! and therefore available in `no_std` environments.
+//!
+//! Schemas can also be rendered as format strings for Python's `struct`
+//! module at compile time, see [`StructFormat`].
+
+use crate::Size;
+
+/// Types that have a static description of their encoded representation.
+pub trait Schema: Size {
+    /// The description of the type's encoded representation.
+    const SCHEMA: Type;
+
+    /// A stable fingerprint of the encoded layout, see [`Type::fingerprint`].
+    /// Derived types include field and variant names if annotated with
+    /// `#[lightpack(hash_names)]`.
+    const SCHEMA_HASH: u64 = Self::SCHEMA.fingerprint(false);
+}
+
+/// A description of an encoded type.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Type {
+    /// A primitive value.
+    Primitive(Primitive),
+    /// A sequence of values without names, e.g. a tuple. The unit type is
+    /// represented by an empty tuple.
+    Tuple(&'static [Type]),
+    /// A fixed number of values of the same type.
+    Array { element: &'static Type, len: usize },
+    /// A `bool` flag, followed by the value. The value is only meaningful
+    /// if the flag is set, but always occupies space.
+    Option(&'static Type),
+    /// A value that is always encoded with the given endianness, regardless
+    /// of the endianness used for the surrounding value.
+    Endian(Endianness, &'static Type),
+    /// A sequence of named fields.
+    Struct { name: &'static str, fields: &'static [Field] },
+    /// A primitive discriminant with a fixed set of valid values.
+    Enum { name: &'static str, repr: Primitive, variants: &'static [Variant] },
+    /// The given number of bytes that are written as zeros and ignored when
+    /// reading, e.g. space reserved for future fields.
+    Reserved(usize),
+    /// An integer encoded as LEB128, zigzag-mapped if the primitive is
+    /// signed (see [`varint`](crate::varint)). Values containing varints
+    /// have a variable encoded size.
+    Varint(Primitive),
+    /// A decimal integer encoded as packed BCD in an unsigned primitive,
+    /// one digit per nibble (see [`bcd`](crate::bcd)).
+    Bcd(Primitive),
+    /// A reduced-precision float with a sign bit and the given numbers of
+    /// exponent and mantissa bits, encoded as its raw bits in the unsigned
+    /// `repr` (see [`float`](crate::float)).
+    Float { repr: Primitive, exp_bits: u32, mantissa_bits: u32 },
+}
+
+/// A named field of a struct.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Field {
+    /// The field's name (or index for tuple structs).
+    pub name: &'static str,
+    /// The field's type.
+    pub ty: &'static Type,
+    /// The scale of a field with a `#[lightpack(as = ..., scale = ...)]`
+    /// conversion, i.e. the field's value is `encoded * scale + offset`
+    /// (see [`convert`](crate::convert)), or `1.0`.
+    pub scale: f64,
+    /// The offset of a field with a conversion, or `0.0`.
+    pub offset: f64,
+}
+
+/// A variant of an enum.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct Variant {
+    /// The variant's name.
+    pub name: &'static str,
+    /// The variant's discriminant.
+    pub value: i128,
+}
+
+/// A primitive type.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Primitive {
+    U8,
+    U16,
+    U32,
+    U64,
+    I8,
+    I16,
+    I32,
+    I64,
+    F32,
+    F64,
+    Bool,
+    /// A Unicode scalar value, encoded as `u32`.
+    Char,
+    /// An unsigned integer encoded in 3 bytes, see [`int`](crate::int).
+    U24,
+    /// A signed integer encoded in 3 bytes.
+    I24,
+    /// An unsigned integer encoded in 5 bytes.
+    U40,
+    /// An unsigned integer encoded in 6 bytes.
+    U48,
+    /// A signed integer encoded in 6 bytes.
+    I48,
+}
+
+/// A byte order.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Endianness {
+    Big,
+    Little,
+}
+
+impl Field {
+    /// A field that is encoded as its value.
+    pub const fn new(name: &'static str, ty: &'static Type) -> Self {
+        Self { name, ty, scale: 1.0, offset: 0.0 }
+    }
+
+    /// Whether the field's value differs from the encoded value, i.e. the
+    /// field has a scale or an offset.
+    pub const fn is_scaled(&self) -> bool {
+        self.scale != 1.0 || self.offset != 0.0
+    }
+}
+
+impl Type {
+    /// The encoded size of the described type in bytes, i.e. the maximum
+    /// size for varints.
+    pub const fn size(&self) -> usize {
+        match self {
+            Type::Primitive(p) => p.size(),
+            Type::Tuple(tys) => {
+                let mut size = 0;
+                let mut i = 0;
+                while i < tys.len() {
+                    size += tys[i].size();
+                    i += 1;
+                }
+                size
+            },
+            Type::Array { element, len } => element.size() * *len,
+            Type::Option(ty) => ty.size() + 1,
+            Type::Endian(_, ty) => ty.size(),
+            Type::Struct { fields, .. } => {
+                let mut size = 0;
+                let mut i = 0;
+                while i < fields.len() {
+                    size += fields[i].ty.size();
+                    i += 1;
+                }
+                size
+            },
+            Type::Enum { repr, .. } => repr.size(),
+            Type::Reserved(len) => *len,
+            Type::Varint(p) => (p.size() * 8).div_ceil(7),
+            Type::Bcd(p) => p.size(),
+            Type::Float { repr, .. } => repr.size(),
+        }
+    }
+}
+
+/// Computes the 64-bit FNV-1a hash of a structural encoding of types.
+struct Fingerprint {
+    hash: u64,
+    names: bool,
+}
+
+impl Fingerprint {
+    const fn byte(mut self, byte: u8) -> Self {
+        self.hash ^= byte as u64;
+        self.hash = self.hash.wrapping_mul(0x100000001b3);
+        self
+    }
+
+    const fn bytes(mut self, bytes: &[u8]) -> Self {
+        let mut i = 0;
+        while i < bytes.len() {
+            self = self.byte(bytes[i]);
+            i += 1;
+        }
+        self
+    }
+
+    const fn len(self, len: usize) -> Self {
+        self.bytes(&(len as u64).to_le_bytes())
+    }
+
+    const fn name(self, name: &str) -> Self {
+        if self.names {
+            self.len(name.len()).bytes(name.as_bytes())
+        } else {
+            self
+        }
+    }
+
+    const fn primitive(self, p: Primitive) -> Self {
+        self.byte(match p {
+            Primitive::U8 => 0,
+            Primitive::U16 => 1,
+            Primitive::U32 => 2,
+            Primitive::U64 => 3,
+            Primitive::I8 => 4,
+            Primitive::I16 => 5,
+            Primitive::I32 => 6,
+            Primitive::I64 => 7,
+            Primitive::F32 => 8,
+            Primitive::F64 => 9,
+            Primitive::Bool => 10,
+            Primitive::Char => 11,
+            Primitive::U24 => 12,
+            Primitive::I24 => 13,
+            Primitive::U40 => 14,
+            Primitive::U48 => 15,
+            Primitive::I48 => 16,
+        })
+    }
+
+    const fn ty(mut self, ty: &Type) -> Self {
+        match ty {
+            Type::Primitive(p) => self.byte(0).primitive(*p),
+            Type::Tuple(tys) => {
+                self = self.byte(1).len(tys.len());
+                let mut i = 0;
+                while i < tys.len() {
+                    self = self.ty(&tys[i]);
+                    i += 1;
+                }
+                self
+            },
+            Type::Array { element, len } => self.byte(2).len(*len).ty(element),
+            Type::Option(ty) => self.byte(3).ty(ty),
+            Type::Endian(order, ty) => self.byte(4).byte(match order {
+                Endianness::Big => 0,
+                Endianness::Little => 1,
+            }).ty(ty),
+            Type::Struct { name, fields } => {
+                self = self.byte(5).name(name).len(fields.len());
+                let mut i = 0;
+                while i < fields.len() {
+                    let field = &fields[i];
+                    self = self.name(field.name).ty(field.ty);
+                    if field.is_scaled() {
+                        self = self.bytes(&field.scale.to_bits().to_le_bytes()).bytes(&field.offset.to_bits().to_le_bytes());
+                    }
+                    i += 1;
+                }
+                self
+            },
+            Type::Enum { name, repr, variants } => {
+                self = self.byte(6).name(name).primitive(*repr).len(variants.len());
+                let mut i = 0;
+                while i < variants.len() {
+                    self = self.name(variants[i].name).bytes(&variants[i].value.to_le_bytes());
+                    i += 1;
+                }
+                self
+            },
+            Type::Reserved(len) => self.byte(7).len(*len),
+            Type::Varint(p) => self.byte(8).primitive(*p),
+            Type::Bcd(p) => self.byte(9).primitive(*p),
+            Type::Float { repr, exp_bits, mantissa_bits } => self.byte(10).primitive(*repr).byte(*exp_bits as u8).byte(*mantissa_bits as u8),
+        }
+    }
+}
+
+impl Type {
+    /// A stable 64-bit fingerprint of the encoded layout, i.e. the kinds,
+    /// order and sizes of the values, enum discriminants, endianness
+    /// overrides and the scales and offsets of fields. Field, variant and
+    /// type names are only included if `names` is set. Peers can compare
+    /// fingerprints to detect incompatible layouts.
+    pub const fn fingerprint(&self, names: bool) -> u64 {
+        Fingerprint { hash: 0xcbf29ce484222325, names }.ty(self).hash
+    }
+}
+
+impl Primitive {
+    /// The encoded size of the primitive in bytes.
+    pub const fn size(self) -> usize {
+        match self {
+            Primitive::U8 | Primitive::I8 | Primitive::Bool => 1,
+            Primitive::U16 | Primitive::I16 => 2,
+            Primitive::U24 | Primitive::I24 => 3,
+            Primitive::U40 => 5,
+            Primitive::U48 | Primitive::I48 => 6,
+            Primitive::U32 | Primitive::I32 | Primitive::F32 | Primitive::Char => 4,
+            Primitive::U64 | Primitive::I64 | Primitive::F64 => 8,
+        }
+    }
+
+    /// Whether the primitive is a signed integer or a float.
+    pub const fn is_signed(self) -> bool {
+        matches!(self, Primitive::I8 | Primitive::I16 | Primitive::I24 | Primitive::I32 | Primitive::I48 | Primitive::I64 | Primitive::F32 | Primitive::F64)
+    }
+
+    /// Whether the primitive is a floating-point number.
+    pub const fn is_float(self) -> bool {
+        matches!(self, Primitive::F32 | Primitive::F64)
+    }
+}
+
+/// The maximum length of a [`StructFormat`] in bytes.
+pub const STRUCT_FORMAT_CAPACITY: usize = 256;
+
+/// A format string for Python's `struct` module (e.g. `">hh"`) that
+/// describes the encoded representation of a type.
+///
+/// Nested structs, tuples and options are flattened, enums are described by
+/// their `#[repr]`, chars as `I` and reserved bytes as padding (`x`). The
+/// format can be built in const contexts, and derived types expose the
+/// big-endian format as `STRUCT_FORMAT`.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct StructFormat {
+    bytes: [u8; STRUCT_FORMAT_CAPACITY],
+    len: usize,
+    valid: bool,
+}
+
+impl StructFormat {
+    /// Builds the format string for the given type and byte order.
+    pub const fn new(ty: &Type, order: Endianness) -> Self {
+        let mut format = Self { bytes: [0; STRUCT_FORMAT_CAPACITY], len: 0, valid: true };
+        let prefix = match order {
+            Endianness::Big => b'>',
+            Endianness::Little => b'<',
+        };
+        format.valid = format.push(prefix) && format.push_type(ty, order, order);
+        format
+    }
+
+    /// The format string, or `None` if the type contains values with a
+    /// different byte order, varints, odd-width integers or reduced-precision
+    /// floats other than half precision (which a single format string cannot
+    /// express) or if the format exceeds [`STRUCT_FORMAT_CAPACITY`].
+    pub const fn as_str(&self) -> Option<&str> {
+        if !self.valid {
+            return None;
+        }
+        let (bytes, _) = self.bytes.split_at(self.len);
+        match core::str::from_utf8(bytes) {
+            Ok(s) => Some(s),
+            Err(_) => unreachable!(),
+        }
+    }
+
+    const fn push(&mut self, byte: u8) -> bool {
+        if self.len == STRUCT_FORMAT_CAPACITY {
+            return false;
+        }
+        self.bytes[self.len] = byte;
+        self.len += 1;
+        true
+    }
+
+    const fn push_count(&mut self, count: usize) -> bool {
+        if count >= 10 && !self.push_count(count / 10) {
+            return false;
+        }
+        self.push(b'0' + (count % 10) as u8)
+    }
+
+    /// Appends the codes of the given type, which is encoded with `current`
+    /// byte order in a format with byte order `order`.
+    const fn push_type(&mut self, ty: &Type, order: Endianness, current: Endianness) -> bool {
+        match ty {
+            Type::Primitive(p) => self.push_primitive(*p, order, current),
+            Type::Tuple(tys) => {
+                let mut i = 0;
+                while i < tys.len() {
+                    if !self.push_type(&tys[i], order, current) {
+                        return false;
+                    }
+                    i += 1;
+                }
+                true
+            },
+            Type::Array { element: Type::Primitive(p), len } if *len != 1 => {
+                self.push_count(*len) && self.push_primitive(*p, order, current)
+            },
+            Type::Array { element, len } => {
+                let mut i = 0;
+                while i < *len {
+                    if !self.push_type(element, order, current) {
+                        return false;
+                    }
+                    i += 1;
+                }
+                true
+            },
+            Type::Option(ty) => self.push(b'?') && self.push_type(ty, order, current),
+            Type::Endian(current, ty) => self.push_type(ty, order, *current),
+            Type::Struct { fields, .. } => {
+                let mut i = 0;
+                while i < fields.len() {
+                    if !self.push_type(fields[i].ty, order, current) {
+                        return false;
+                    }
+                    i += 1;
+                }
+                true
+            },
+            Type::Enum { repr, .. } => self.push_primitive(*repr, order, current),
+            Type::Reserved(0) => true,
+            Type::Reserved(1) => self.push(b'x'),
+            Type::Reserved(len) => self.push_count(*len) && self.push(b'x'),
+            Type::Varint(_) => false,
+            Type::Bcd(p) => self.push_primitive(*p, order, current),
+            // Only half-precision floats have a code
+            Type::Float { exp_bits: 5, mantissa_bits: 10, .. } => {
+                matches!((order, current), (Endianness::Big, Endianness::Big) | (Endianness::Little, Endianness::Little)) && self.push(b'e')
+            },
+            Type::Float { .. } => false,
+        }
+    }
+
+    const fn push_primitive(&mut self, p: Primitive, order: Endianness, current: Endianness) -> bool {
+        // Single bytes are unaffected by the byte order
+        if p.size() > 1 && !matches!((order, current), (Endianness::Big, Endianness::Big) | (Endianness::Little, Endianness::Little)) {
+            return false;
+        }
+        self.push(match p {
+            Primitive::U8 => b'B',
+            Primitive::U16 => b'H',
+            Primitive::U32 | Primitive::Char => b'I',
+            Primitive::U64 => b'Q',
+            Primitive::I8 => b'b',
+            Primitive::I16 => b'h',
+            Primitive::I32 => b'i',
+            Primitive::I64 => b'q',
+            Primitive::F32 => b'f',
+            Primitive::F64 => b'd',
+            Primitive::Bool => b'?',
+            // The `struct` module has no codes for odd widths
+            Primitive::U24 | Primitive::I24 | Primitive::U40 | Primitive::U48 | Primitive::I48 => return false,
+        })
+    }
+}
+
+impl Schema for u8 {
+    const SCHEMA: Type = Type::Primitive(Primitive::U8);
+}
+
+impl Schema for u16 {
+    const SCHEMA: Type = Type::Primitive(Primitive::U16);
+}
+
+impl Schema for u32 {
+    const SCHEMA: Type = Type::Primitive(Primitive::U32);
+}
+
+impl Schema for u64 {
+    const SCHEMA: Type = Type::Primitive(Primitive::U64);
+}
+
+impl Schema for i8 {
+    const SCHEMA: Type = Type::Primitive(Primitive::I8);
+}
+
+impl Schema for i16 {
+    const SCHEMA: Type = Type::Primitive(Primitive::I16);
+}
+
+impl Schema for i32 {
+    const SCHEMA: Type = Type::Primitive(Primitive::I32);
+}
+
+impl Schema for i64 {
+    const SCHEMA: Type = Type::Primitive(Primitive::I64);
+}
+
+impl Schema for f32 {
+    const SCHEMA: Type = Type::Primitive(Primitive::F32);
+}
+
+impl Schema for f64 {
+    const SCHEMA: Type = Type::Primitive(Primitive::F64);
+}
+
+impl Schema for bool {
+    const SCHEMA: Type = Type::Primitive(Primitive::Bool);
+}
+
+impl Schema for char {
+    const SCHEMA: Type = Type::Primitive(Primitive::Char);
+}
+
+impl Schema for () {
+    const SCHEMA: Type = Type::Tuple(&[]);
+}
+
+// TODO: Abstract over the tuple size with a macro
+
+impl<T0> Schema for (T0,) where T0: Schema {
+    const SCHEMA: Type = Type::Tuple(&[T0::SCHEMA]);
+}
+
+impl<T0, T1> Schema for (T0, T1) where T0: Schema, T1: Schema {
+    const SCHEMA: Type = Type::Tuple(&[T0::SCHEMA, T1::SCHEMA]);
+}
+
+impl<T0, T1, T2> Schema for (T0, T1, T2) where T0: Schema, T1: Schema, T2: Schema {
+    const SCHEMA: Type = Type::Tuple(&[T0::SCHEMA, T1::SCHEMA, T2::SCHEMA]);
+}
+
+impl<T0, T1, T2, T3> Schema for (T0, T1, T2, T3) where T0: Schema, T1: Schema, T2: Schema, T3: Schema {
+    const SCHEMA: Type = Type::Tuple(&[T0::SCHEMA, T1::SCHEMA, T2::SCHEMA, T3::SCHEMA]);
+}
+
+impl<T> Schema for &T where T: Schema {
+    const SCHEMA: Type = T::SCHEMA;
+}
+
+impl<T> Schema for &mut T where T: Schema {
+    const SCHEMA: Type = T::SCHEMA;
+}
+
+impl<T> Schema for Option<T> where T: Schema {
+    const SCHEMA: Type = Type::Option(&T::SCHEMA);
+}
+
+impl<T, const N: usize> Schema for [T; N] where T: Schema {
+    const SCHEMA: Type = Type::Array { element: &T::SCHEMA, len: N };
+}
@@ -0,0 +1,809 @@
+//! A serde [`Serializer`] and [`Deserializer`] for the wire format, which
+//! make types that only implement `Serialize` and `Deserialize` encodable:
+//!
+//! ```ignore
+//! let mut buffer = [0u8; 4];
+//! let size = lightpack::serde::to_slice::<BigEndian, _>(&Point { x: 3, y: 4 }, &mut buffer)?;
+//! // => size == 4, buffer == [0, 3, 0, 4]
+//! let point: Point = lightpack::serde::from_slice::<BigEndian, _>(&buffer)?;
+//! ```
+//!
+//! Values are encoded exactly like their [`Pack`](crate::Pack) counterparts:
+//! primitives with their fixed width, structs, tuples and arrays as the
+//! concatenation of their fields and `Some` as a `1` flag followed by the
+//! value. Since serde provides neither the size of a value nor the
+//! `#[repr]` of an enum, the format differs in a few places:
+//!
+//! - `None` cannot be encoded or decoded, as the size of the missing value
+//!   is unknown.
+//! - Enum variants are encoded by their index as `u32`, followed by the
+//!   fields of the variant (if any). The encoded size thus depends on the
+//!   variant.
+//! - `u128` and `i128` are supported (with 16 bytes).
+//! - Strings, byte slices, sequences of unknown length and maps are not
+//!   supported, as they have no fixed width.
+//!
+//! [`LE`] and [`BE`] implement `Serialize` and `Deserialize` transparently,
+//! i.e. as their inner value in human-readable formats like JSON, but keep
+//! their fixed byte order with this module's serializer. Conversely,
+//! [`Value`] serializes an already packed buffer by walking the [`Schema`](crate::Schema),
+//! e.g. to expose binary messages in a JSON API without decoding them first.
+//!
+//! Types deriving `Schema` with `#[lightpack(serde)]` get serde impls that
+//! are consistent with their schema, so one definition serves both APIs:
+//! human-readable formats skip [`Reserved`] fields and see converted fields
+//! as their logical value and enums by their variant names, while binary
+//! formats (including this module's serializer) see exactly the packed
+//! representation, e.g. enums as their `#[repr]` value.
+//!
+//! The wrapper types ([`Varint`](crate::varint::Varint), the odd-width
+//! integers, [`Fixed`](crate::fixed::Fixed), the reduced-precision floats,
+//! [`Bcd`](crate::bcd::Bcd), the timestamps, the fixed-capacity strings and
+//! the collections) implement `Serialize` and `Deserialize` the same way:
+//! human-readable formats see their logical value (e.g. a fixed-point number
+//! as float, a string as string and a collection as sequence), binary formats
+//! their packed representation. This module's serializer keeps the encoding
+//! of varints and odd-width integers, so they are encoded like with `Pack`.
+//!
+//! The format is not self-describing, so `deserialize_any` is not supported.
+
+mod value;
+mod wrappers;
+
+use core::{fmt, marker::PhantomData};
+
+use ::serde::{Deserialize, de::{self, DeserializeSeed, IntoDeserializer, Visitor}, ser::{self, Impossible, Serialize}};
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+
+use crate::{extra::{BE, LE, Reserved}, schema::Endianness, unpack, varint};
+
+pub use value::Value;
+
+/// The newtype struct names identifying values with a fixed byte order.
+const LE_NAME: &str = "lightpack::LE";
+const BE_NAME: &str = "lightpack::BE";
+
+/// The newtype struct names identifying integers with their own encoding.
+const VARINT_NAME: &str = "lightpack::Varint";
+const INT24_NAME: &str = "lightpack::Int24";
+const INT40_NAME: &str = "lightpack::Int40";
+const INT48_NAME: &str = "lightpack::Int48";
+
+/// The encoding of an integer in a newtype struct.
+#[derive(Debug, Clone, Copy)]
+enum Encoding {
+    /// A `u64` encoded as LEB128.
+    Varint,
+    /// A `u64` or `i64` truncated to the given number of bytes.
+    Bytes(usize),
+}
+
+/// The encoding fixed by a newtype struct name, if any.
+fn encoding(name: &str) -> Option<Encoding> {
+    match name {
+        VARINT_NAME => Some(Encoding::Varint),
+        INT24_NAME => Some(Encoding::Bytes(3)),
+        INT40_NAME => Some(Encoding::Bytes(5)),
+        INT48_NAME => Some(Encoding::Bytes(6)),
+        _ => None,
+    }
+}
+
+/// The byte order fixed by a newtype struct name, if any.
+fn fixed_order(name: &str) -> Option<Endianness> {
+    match name {
+        LE_NAME => Some(Endianness::Little),
+        BE_NAME => Some(Endianness::Big),
+        _ => None,
+    }
+}
+
+/// Calls a `ByteOrder` function with the fixed byte order, if any, and `B`
+/// otherwise.
+macro_rules! ordered {
+    ($fixed:expr, $f:ident($($arg:expr),*)) => {
+        match $fixed {
+            None => B::$f($($arg),*),
+            Some(Endianness::Big) => BigEndian::$f($($arg),*),
+            Some(Endianness::Little) => LittleEndian::$f($($arg),*),
+        }
+    };
+}
+
+/// An error during serialization or deserialization.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Error {
+    /// The buffer is too small for the encoded value.
+    BufferTooSmall { actual: usize, expected: usize },
+    /// The decoded value is not a valid `char`.
+    InvalidChar(u32),
+    /// The varint is longer than necessary or exceeds 64 bits.
+    InvalidVarint,
+    /// The value is not supported by the format.
+    Unsupported(&'static str),
+    /// An error raised by a `Serialize` or `Deserialize` implementation
+    /// (the message is dropped since the crate does not allocate).
+    Custom,
+}
+
+/// The result type for serde operations.
+pub type Result<T> = core::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::BufferTooSmall { actual, expected } => write!(f, "buffer too small ({} bytes, expected at least {})", actual, expected),
+            Error::InvalidChar(value) => write!(f, "invalid char {:#x}", value),
+            Error::InvalidVarint => write!(f, "invalid varint"),
+            Error::Unsupported(what) => write!(f, "{} cannot be encoded in the fixed-width format", what),
+            Error::Custom => write!(f, "custom serde error"),
+        }
+    }
+}
+
+impl ::serde::ser::StdError for Error {}
+
+impl ser::Error for Error {
+    fn custom<T>(_msg: T) -> Self where T: fmt::Display {
+        Error::Custom
+    }
+}
+
+impl de::Error for Error {
+    fn custom<T>(_msg: T) -> Self where T: fmt::Display {
+        Error::Custom
+    }
+}
+
+/// Encodes the value to the start of the buffer and returns the encoded size.
+pub fn to_slice<B, T>(value: &T, buffer: &mut [u8]) -> Result<usize> where B: ByteOrder, T: Serialize + ?Sized {
+    let mut serializer = Serializer::<B>::new(buffer);
+    value.serialize(&mut serializer)?;
+    Ok(serializer.position())
+}
+
+/// Decodes a value from the start of the buffer.
+pub fn from_slice<'de, B, T>(buffer: &'de [u8]) -> Result<T> where B: ByteOrder, T: de::Deserialize<'de> {
+    T::deserialize(&mut Deserializer::<B>::new(buffer))
+}
+
+/// A serializer writing to a byte slice.
+pub struct Serializer<'a, B> {
+    buffer: &'a mut [u8],
+    position: usize,
+    fixed: Option<Endianness>,
+    encoding: Option<Encoding>,
+    byte_order: PhantomData<B>,
+}
+
+impl<'a, B> Serializer<'a, B> where B: ByteOrder {
+    /// Creates a serializer writing to the start of the buffer.
+    pub fn new(buffer: &'a mut [u8]) -> Self {
+        Self { buffer, position: 0, fixed: None, encoding: None, byte_order: PhantomData }
+    }
+
+    /// The number of bytes written so far.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Reserves the next `size` bytes and passes them to `write`, along with
+    /// the fixed byte order of an enclosing `LE` or `BE`.
+    fn write(&mut self, size: usize, write: impl FnOnce(&mut [u8], Option<Endianness>)) -> Result<()> {
+        let end = self.position + size;
+        if end > self.buffer.len() {
+            return Err(Error::BufferTooSmall { actual: self.buffer.len(), expected: end });
+        }
+        write(&mut self.buffer[self.position..end], self.fixed);
+        self.position = end;
+        Ok(())
+    }
+}
+
+impl<'a, B> ser::Serializer for &mut Serializer<'a, B> where B: ByteOrder {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = Impossible<(), Error>;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Self;
+    type SerializeTupleVariant = Self;
+    type SerializeMap = Impossible<(), Error>;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Self;
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    fn serialize_bool(self, v: bool) -> Result<()> {
+        self.write(1, |b, _| b[0] = v as u8)
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<()> {
+        self.write(1, |b, _| b[0] = v as u8)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<()> {
+        self.write(2, |b, fixed| ordered!(fixed, write_i16(b, v)))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<()> {
+        self.write(4, |b, fixed| ordered!(fixed, write_i32(b, v)))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<()> {
+        match self.encoding.take() {
+            Some(Encoding::Bytes(size)) => self.write(size, |b, fixed| ordered!(fixed, write_int(b, v, size))),
+            _ => self.write(8, |b, fixed| ordered!(fixed, write_i64(b, v))),
+        }
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<()> {
+        self.write(16, |b, fixed| ordered!(fixed, write_i128(b, v)))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<()> {
+        self.write(1, |b, _| b[0] = v)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<()> {
+        self.write(2, |b, fixed| ordered!(fixed, write_u16(b, v)))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<()> {
+        self.write(4, |b, fixed| ordered!(fixed, write_u32(b, v)))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<()> {
+        match self.encoding.take() {
+            Some(Encoding::Varint) => self.write(varint::encoded_size(v), |b, _| {
+                varint::encode(v, b);
+            }),
+            Some(Encoding::Bytes(size)) => self.write(size, |b, fixed| ordered!(fixed, write_uint(b, v, size))),
+            None => self.write(8, |b, fixed| ordered!(fixed, write_u64(b, v))),
+        }
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<()> {
+        self.write(16, |b, fixed| ordered!(fixed, write_u128(b, v)))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<()> {
+        self.write(4, |b, fixed| ordered!(fixed, write_f32(b, v)))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<()> {
+        self.write(8, |b, fixed| ordered!(fixed, write_f64(b, v)))
+    }
+
+    fn serialize_char(self, v: char) -> Result<()> {
+        self.serialize_u32(v as u32)
+    }
+
+    fn serialize_str(self, _v: &str) -> Result<()> {
+        Err(Error::Unsupported("a string"))
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<()> {
+        Err(Error::Unsupported("a byte slice"))
+    }
+
+    fn serialize_none(self) -> Result<()> {
+        Err(Error::Unsupported("None"))
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<()> where T: Serialize + ?Sized {
+        self.serialize_bool(true)?;
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<()> {
+        Ok(())
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, variant_index: u32, _variant: &'static str) -> Result<()> {
+        self.serialize_u32(variant_index)
+    }
+
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<()> where T: Serialize + ?Sized {
+        if let Some(encoding) = encoding(name) {
+            self.encoding = Some(encoding);
+            let result = value.serialize(&mut *self);
+            self.encoding = None;
+            return result;
+        }
+        match fixed_order(name) {
+            Some(order) => {
+                let outer = self.fixed.replace(order);
+                let result = value.serialize(&mut *self);
+                self.fixed = outer;
+                result
+            },
+            None => value.serialize(self),
+        }
+    }
+
+    fn serialize_newtype_variant<T>(self, _name: &'static str, variant_index: u32, _variant: &'static str, value: &T) -> Result<()> where T: Serialize + ?Sized {
+        self.serialize_u32(variant_index)?;
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Err(Error::Unsupported("a sequence"))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_variant(self, _name: &'static str, variant_index: u32, _variant: &'static str, _len: usize) -> Result<Self> {
+        self.serialize_u32(variant_index)?;
+        Ok(self)
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Err(Error::Unsupported("a map"))
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self> {
+        Ok(self)
+    }
+
+    fn serialize_struct_variant(self, _name: &'static str, variant_index: u32, _variant: &'static str, _len: usize) -> Result<Self> {
+        self.serialize_u32(variant_index)?;
+        Ok(self)
+    }
+
+    fn collect_str<T>(self, _value: &T) -> Result<()> where T: fmt::Display + ?Sized {
+        Err(Error::Unsupported("a string"))
+    }
+}
+
+impl<'a, B> ser::SerializeTuple for &mut Serializer<'a, B> where B: ByteOrder {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()> where T: Serialize + ?Sized {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a, B> ser::SerializeTupleStruct for &mut Serializer<'a, B> where B: ByteOrder {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()> where T: Serialize + ?Sized {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a, B> ser::SerializeTupleVariant for &mut Serializer<'a, B> where B: ByteOrder {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()> where T: Serialize + ?Sized {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a, B> ser::SerializeStruct for &mut Serializer<'a, B> where B: ByteOrder {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, _key: &'static str, value: &T) -> Result<()> where T: Serialize + ?Sized {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a, B> ser::SerializeStructVariant for &mut Serializer<'a, B> where B: ByteOrder {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, _key: &'static str, value: &T) -> Result<()> where T: Serialize + ?Sized {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// A deserializer reading from a byte slice.
+pub struct Deserializer<'de, B> {
+    buffer: &'de [u8],
+    position: usize,
+    fixed: Option<Endianness>,
+    encoding: Option<Encoding>,
+    byte_order: PhantomData<B>,
+}
+
+impl<'de, B> Deserializer<'de, B> where B: ByteOrder {
+    /// Creates a deserializer reading from the start of the buffer.
+    pub fn new(buffer: &'de [u8]) -> Self {
+        Self { buffer, position: 0, fixed: None, encoding: None, byte_order: PhantomData }
+    }
+
+    /// The number of bytes read so far.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Consumes the next `size` bytes.
+    fn read(&mut self, size: usize) -> Result<&'de [u8]> {
+        let end = self.position + size;
+        if end > self.buffer.len() {
+            return Err(Error::BufferTooSmall { actual: self.buffer.len(), expected: end });
+        }
+        let bytes = &self.buffer[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn read_u32(&mut self) -> Result<u32> {
+        let fixed = self.fixed;
+        Ok(ordered!(fixed, read_u32(self.read(4)?)))
+    }
+
+    /// Consumes a varint.
+    fn read_varint(&mut self) -> Result<u64> {
+        let (value, size) = varint::decode(&self.buffer[self.position..], 64).map_err(|e| match e {
+            unpack::Error::BufferTooSmall { expected, .. } => Error::BufferTooSmall { actual: self.buffer.len(), expected: self.position + expected },
+            _ => Error::InvalidVarint,
+        })?;
+        self.position += size;
+        Ok(value)
+    }
+}
+
+impl<'de, B> de::Deserializer<'de> for &mut Deserializer<'de, B> where B: ByteOrder {
+    type Error = Error;
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        Err(Error::Unsupported("a value of unknown type"))
+    }
+
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        visitor.visit_bool(self.read(1)?[0] != 0)
+    }
+
+    fn deserialize_i8<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        visitor.visit_i8(self.read(1)?[0] as i8)
+    }
+
+    fn deserialize_i16<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        visitor.visit_i16(ordered!(self.fixed, read_i16(self.read(2)?)))
+    }
+
+    fn deserialize_i32<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        visitor.visit_i32(ordered!(self.fixed, read_i32(self.read(4)?)))
+    }
+
+    fn deserialize_i64<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        match self.encoding.take() {
+            Some(Encoding::Bytes(size)) => visitor.visit_i64(ordered!(self.fixed, read_int(self.read(size)?, size))),
+            _ => visitor.visit_i64(ordered!(self.fixed, read_i64(self.read(8)?))),
+        }
+    }
+
+    fn deserialize_i128<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        visitor.visit_i128(ordered!(self.fixed, read_i128(self.read(16)?)))
+    }
+
+    fn deserialize_u8<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        visitor.visit_u8(self.read(1)?[0])
+    }
+
+    fn deserialize_u16<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        visitor.visit_u16(ordered!(self.fixed, read_u16(self.read(2)?)))
+    }
+
+    fn deserialize_u32<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        visitor.visit_u32(self.read_u32()?)
+    }
+
+    fn deserialize_u64<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        match self.encoding.take() {
+            Some(Encoding::Varint) => visitor.visit_u64(self.read_varint()?),
+            Some(Encoding::Bytes(size)) => visitor.visit_u64(ordered!(self.fixed, read_uint(self.read(size)?, size))),
+            None => visitor.visit_u64(ordered!(self.fixed, read_u64(self.read(8)?))),
+        }
+    }
+
+    fn deserialize_u128<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        visitor.visit_u128(ordered!(self.fixed, read_u128(self.read(16)?)))
+    }
+
+    fn deserialize_f32<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        visitor.visit_f32(ordered!(self.fixed, read_f32(self.read(4)?)))
+    }
+
+    fn deserialize_f64<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        visitor.visit_f64(ordered!(self.fixed, read_f64(self.read(8)?)))
+    }
+
+    fn deserialize_char<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        let value = self.read_u32()?;
+        visitor.visit_char(char::from_u32(value).ok_or(Error::InvalidChar(value))?)
+    }
+
+    fn deserialize_str<V>(self, _visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        Err(Error::Unsupported("a string"))
+    }
+
+    fn deserialize_string<V>(self, _visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        Err(Error::Unsupported("a string"))
+    }
+
+    fn deserialize_bytes<V>(self, _visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        Err(Error::Unsupported("a byte slice"))
+    }
+
+    fn deserialize_byte_buf<V>(self, _visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        Err(Error::Unsupported("a byte slice"))
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        if self.read(1)?[0] != 0 {
+            visitor.visit_some(self)
+        } else {
+            Err(Error::Unsupported("None"))
+        }
+    }
+
+    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        if let Some(encoding) = encoding(name) {
+            self.encoding = Some(encoding);
+            let result = visitor.visit_newtype_struct(&mut *self);
+            self.encoding = None;
+            return result;
+        }
+        match fixed_order(name) {
+            Some(order) => {
+                let outer = self.fixed.replace(order);
+                let result = visitor.visit_newtype_struct(&mut *self);
+                self.fixed = outer;
+                result
+            },
+            None => visitor.visit_newtype_struct(self),
+        }
+    }
+
+    fn deserialize_seq<V>(self, _visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        Err(Error::Unsupported("a sequence"))
+    }
+
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        visitor.visit_seq(Elements { deserializer: self, remaining: len })
+    }
+
+    fn deserialize_tuple_struct<V>(self, _name: &'static str, len: usize, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_map<V>(self, _visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        Err(Error::Unsupported("a map"))
+    }
+
+    fn deserialize_struct<V>(self, _name: &'static str, fields: &'static [&'static str], visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        self.deserialize_tuple(fields.len(), visitor)
+    }
+
+    fn deserialize_enum<V>(self, _name: &'static str, _variants: &'static [&'static str], visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        visitor.visit_enum(self)
+    }
+
+    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        self.deserialize_u32(visitor)
+    }
+
+    fn deserialize_ignored_any<V>(self, _visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        Err(Error::Unsupported("a value of unknown type"))
+    }
+}
+
+/// The elements of a tuple, struct or variant with a known number of fields.
+struct Elements<'a, 'de, B> {
+    deserializer: &'a mut Deserializer<'de, B>,
+    remaining: usize,
+}
+
+impl<'a, 'de, B> de::SeqAccess<'de> for Elements<'a, 'de, B> where B: ByteOrder {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>> where T: DeserializeSeed<'de> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        self.remaining -= 1;
+        seed.deserialize(&mut *self.deserializer).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.remaining)
+    }
+}
+
+impl<'de, B> de::EnumAccess<'de> for &mut Deserializer<'de, B> where B: ByteOrder {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self)> where V: DeserializeSeed<'de> {
+        let index = self.read_u32()?;
+        let variant = seed.deserialize(IntoDeserializer::<Error>::into_deserializer(index))?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de, B> de::VariantAccess<'de> for &mut Deserializer<'de, B> where B: ByteOrder {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value> where T: DeserializeSeed<'de> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        de::Deserializer::deserialize_tuple(self, len, visitor)
+    }
+
+    fn struct_variant<V>(self, fields: &'static [&'static str], visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        de::Deserializer::deserialize_tuple(self, fields.len(), visitor)
+    }
+}
+
+impl<T> Serialize for LE<T> where T: Serialize {
+    fn serialize<S>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error> where S: ser::Serializer {
+        serializer.serialize_newtype_struct(LE_NAME, &self.0)
+    }
+}
+
+impl<T> Serialize for BE<T> where T: Serialize {
+    fn serialize<S>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error> where S: ser::Serializer {
+        serializer.serialize_newtype_struct(BE_NAME, &self.0)
+    }
+}
+
+impl<'de, T> Deserialize<'de> for LE<T> where T: Deserialize<'de> {
+    fn deserialize<D>(deserializer: D) -> core::result::Result<Self, D::Error> where D: de::Deserializer<'de> {
+        deserializer.deserialize_newtype_struct(LE_NAME, Newtype(PhantomData)).map(LE)
+    }
+}
+
+impl<'de, T> Deserialize<'de> for BE<T> where T: Deserialize<'de> {
+    fn deserialize<D>(deserializer: D) -> core::result::Result<Self, D::Error> where D: de::Deserializer<'de> {
+        deserializer.deserialize_newtype_struct(BE_NAME, Newtype(PhantomData)).map(BE)
+    }
+}
+
+impl<const N: usize> Serialize for Reserved<N> {
+    fn serialize<S>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error> where S: ser::Serializer {
+        let mut tuple = serializer.serialize_tuple(N)?;
+        for _ in 0..N {
+            ser::SerializeTuple::serialize_element(&mut tuple, &0u8)?;
+        }
+        ser::SerializeTuple::end(tuple)
+    }
+}
+
+impl<'de, const N: usize> Deserialize<'de> for Reserved<N> {
+    fn deserialize<D>(deserializer: D) -> core::result::Result<Self, D::Error> where D: de::Deserializer<'de> {
+        deserializer.deserialize_tuple(N, ReservedVisitor::<N>)
+    }
+}
+
+/// Visits and ignores reserved bytes.
+struct ReservedVisitor<const N: usize>;
+
+impl<'de, const N: usize> Visitor<'de> for ReservedVisitor<N> {
+    type Value = Reserved<N>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} reserved bytes", N)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> core::result::Result<Self::Value, A::Error> where A: de::SeqAccess<'de> {
+        for i in 0..N {
+            if seq.next_element::<u8>()?.is_none() {
+                return Err(de::Error::invalid_length(i, &self));
+            }
+        }
+        Ok(Reserved)
+    }
+}
+
+/// Visits the inner value of a newtype struct.
+struct Newtype<T>(PhantomData<T>);
+
+impl<'de, T> Visitor<'de> for Newtype<T> where T: Deserialize<'de> {
+    type Value = T;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a newtype struct")
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> core::result::Result<T, D::Error> where D: de::Deserializer<'de> {
+        T::deserialize(deserializer)
+    }
+}
+
+/// Support for the impls generated by `#[derive(Schema)]` with
+/// `#[lightpack(serde)]`, not public API.
+#[doc(hidden)]
+pub mod __private {
+    use core::fmt;
+
+    use ::serde::de::{self, DeserializeSeed, Visitor};
+
+    pub use ::serde;
+
+    /// Identifies a field or variant by its name or index among the given
+    /// names, yielding `None` for unknown names.
+    pub struct FieldIndex(pub &'static [&'static str]);
+
+    impl<'de> DeserializeSeed<'de> for FieldIndex {
+        type Value = Option<usize>;
+
+        fn deserialize<D>(self, deserializer: D) -> core::result::Result<Self::Value, D::Error> where D: de::Deserializer<'de> {
+            deserializer.deserialize_identifier(self)
+        }
+    }
+
+    impl<'de> Visitor<'de> for FieldIndex {
+        type Value = Option<usize>;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "a field or variant identifier")
+        }
+
+        fn visit_u64<E>(self, value: u64) -> core::result::Result<Self::Value, E> where E: de::Error {
+            Ok(usize::try_from(value).ok().filter(|&i| i < self.0.len()))
+        }
+
+        fn visit_str<E>(self, value: &str) -> core::result::Result<Self::Value, E> where E: de::Error {
+            Ok(self.0.iter().position(|name| *name == value))
+        }
+
+        fn visit_bytes<E>(self, value: &[u8]) -> core::result::Result<Self::Value, E> where E: de::Error {
+            Ok(self.0.iter().position(|name| name.as_bytes() == value))
+        }
+    }
+}
@@ -0,0 +1,351 @@
+//! Serialization of packed buffers described by a schema.
+
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+use ::serde::ser::{self, Error as _, Serialize, SerializeStruct, SerializeTuple, SerializeTupleStruct};
+
+use crate::{Schema, bcd, float, schema::{Endianness, Field, Primitive, Type}, varint};
+
+use super::{BE_NAME, Error, LE_NAME, Result};
+
+/// A packed value together with its schema, which serializes like the
+/// described Rust type would with serde's derives: structs with their field
+/// names, enums as their variant names and tuple structs, tuples and arrays
+/// as sequences. Like the impls generated by `#[lightpack(serde)]`, reserved
+/// fields are skipped and fields with a scale or offset are converted to
+/// their values in human-readable formats. Varints and BCD values are
+/// serialized as their integer values, reduced-precision floats as `f32`.
+///
+/// ```ignore
+/// let value = Value::of::<Frame>(&buffer, Endianness::Big)?;
+/// serde_json::to_string(&value)?
+/// // => {"seq":7,"mode":"On","origin":null}
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Value<'a> {
+    ty: &'a Type,
+    buffer: &'a [u8],
+    byte_order: Endianness,
+}
+
+impl<'a> Value<'a> {
+    /// Creates a value of the given type, packed with the given byte order
+    /// at the start of the buffer.
+    pub fn new(ty: &'a Type, buffer: &'a [u8], byte_order: Endianness) -> Result<Self> {
+        match encoded_size(ty, buffer) {
+            Some(size) => Ok(Self { ty, buffer: &buffer[..size], byte_order }),
+            None => Err(Error::BufferTooSmall { actual: buffer.len(), expected: ty.size() }),
+        }
+    }
+
+    /// Creates a value of type `T`, packed with the given byte order at the
+    /// start of the buffer.
+    pub fn of<T>(buffer: &'a [u8], byte_order: Endianness) -> Result<Self> where T: Schema {
+        Self::new(&T::SCHEMA, buffer, byte_order)
+    }
+
+    /// The value's type.
+    pub fn ty(&self) -> &'a Type {
+        self.ty
+    }
+
+    /// The packed bytes of the value.
+    pub fn bytes(&self) -> &'a [u8] {
+        self.buffer
+    }
+
+    /// The field with the given name if the value is a struct.
+    pub fn field(&self, name: &str) -> Option<Value<'a>> {
+        match self.unwrap_endian() {
+            (Type::Struct { fields, .. }, order) => {
+                let mut offset = 0;
+                for field in fields.iter() {
+                    let child = self.child(field.ty, offset, order);
+                    if field.name == name {
+                        return Some(child);
+                    }
+                    offset += child.buffer.len();
+                }
+                None
+            },
+            _ => None,
+        }
+    }
+
+    /// The element with the given index if the value is a tuple or an array.
+    pub fn element(&self, index: usize) -> Option<Value<'a>> {
+        let (tys, order): (&mut dyn Iterator<Item = &'a Type>, _) = match self.unwrap_endian() {
+            (Type::Tuple(tys), order) => (&mut tys.iter(), order),
+            (Type::Array { element, len }, order) => (&mut (0..*len).map(|_| *element), order),
+            _ => return None,
+        };
+        let mut offset = 0;
+        for (i, ty) in tys.enumerate() {
+            let child = self.child(ty, offset, order);
+            if i == index {
+                return Some(child);
+            }
+            offset += child.buffer.len();
+        }
+        None
+    }
+
+    /// The type without fixed endianness and the byte order of its contents.
+    fn unwrap_endian(&self) -> (&'a Type, Endianness) {
+        let mut ty = self.ty;
+        let mut order = self.byte_order;
+        while let Type::Endian(fixed, inner) = ty {
+            ty = inner;
+            order = *fixed;
+        }
+        (ty, order)
+    }
+
+    /// The value of the given type at the given offset, which is complete
+    /// since the size of the parent value has been checked.
+    fn child(&self, ty: &'a Type, offset: usize, byte_order: Endianness) -> Value<'a> {
+        let buffer = &self.buffer[offset..];
+        let size = encoded_size(ty, buffer).expect("The child value is complete");
+        Value { ty, buffer: &buffer[..size], byte_order }
+    }
+
+    /// Serializes the fields of a struct or the elements of a sequence.
+    fn fields<S>(&self, fields: impl Iterator<Item = &'a Type>, serializer: &mut S) -> core::result::Result<(), S::Error> where S: SerializeTuple {
+        let mut offset = 0;
+        for ty in fields {
+            let child = self.child(ty, offset, self.byte_order);
+            serializer.serialize_element(&child)?;
+            offset += child.buffer.len();
+        }
+        Ok(())
+    }
+}
+
+/// The encoded size of a value of the given type at the start of the buffer,
+/// or `None` if the buffer ends before the value is complete. Like
+/// [`Size::encoded_size`](crate::Size::encoded_size), this is the actual
+/// size for varints and the full size for `None`.
+fn encoded_size(ty: &Type, bytes: &[u8]) -> Option<usize> {
+    /// The size of values packed back to back.
+    fn sequence<'a>(tys: impl Iterator<Item = &'a Type>, bytes: &[u8]) -> Option<usize> {
+        let mut offset = 0;
+        for ty in tys {
+            offset += encoded_size(ty, &bytes[offset..])?;
+        }
+        Some(offset)
+    }
+
+    match ty {
+        Type::Tuple(tys) => sequence(tys.iter(), bytes),
+        Type::Array { element, len } => sequence((0..*len).map(|_| *element), bytes),
+        Type::Struct { fields, .. } => sequence(fields.iter().map(|field| field.ty), bytes),
+        Type::Option(inner) if bytes.first().is_some_and(|flag| *flag != 0) => Some(1 + encoded_size(inner, &bytes[1..])?),
+        Type::Endian(_, inner) => encoded_size(inner, bytes),
+        Type::Varint(_) => match bytes.iter().take(ty.size()).position(|byte| byte & 0x80 == 0) {
+            Some(i) => Some(i + 1),
+            // Overlong encodings are rejected when serializing
+            None if bytes.len() >= ty.size() => Some(ty.size()),
+            None => None,
+        },
+        _ => (bytes.len() >= ty.size()).then_some(ty.size()),
+    }
+}
+
+/// Reads a primitive with the given byte order as `i128` (floats excluded).
+fn read_integer(p: Primitive, bytes: &[u8], order: Endianness) -> i128 {
+    macro_rules! read {
+        ($f:ident $(, $n:expr)?) => {
+            match order {
+                Endianness::Big => BigEndian::$f(bytes $(, $n)?).into(),
+                Endianness::Little => LittleEndian::$f(bytes $(, $n)?).into(),
+            }
+        };
+    }
+
+    match p {
+        Primitive::U8 | Primitive::Bool => bytes[0].into(),
+        Primitive::I8 => (bytes[0] as i8).into(),
+        Primitive::U16 => read!(read_u16),
+        Primitive::U32 | Primitive::Char => read!(read_u32),
+        Primitive::U64 => read!(read_u64),
+        Primitive::I16 => read!(read_i16),
+        Primitive::I32 => read!(read_i32),
+        Primitive::I64 => read!(read_i64),
+        Primitive::U24 | Primitive::U40 | Primitive::U48 => read!(read_uint, p.size()),
+        Primitive::I24 | Primitive::I48 => read!(read_int, p.size()),
+        Primitive::F32 | Primitive::F64 => unreachable!("floats are not integers"),
+    }
+}
+
+impl Serialize for Value<'_> {
+    fn serialize<S>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error> where S: ser::Serializer {
+        let bytes = self.buffer;
+        match self.ty {
+            Type::Primitive(p) => {
+                let value = match p {
+                    Primitive::F32 => return match self.byte_order {
+                        Endianness::Big => serializer.serialize_f32(BigEndian::read_f32(bytes)),
+                        Endianness::Little => serializer.serialize_f32(LittleEndian::read_f32(bytes)),
+                    },
+                    Primitive::F64 => return match self.byte_order {
+                        Endianness::Big => serializer.serialize_f64(BigEndian::read_f64(bytes)),
+                        Endianness::Little => serializer.serialize_f64(LittleEndian::read_f64(bytes)),
+                    },
+                    _ => read_integer(*p, bytes, self.byte_order),
+                };
+                match p {
+                    Primitive::U8 => serializer.serialize_u8(value as u8),
+                    Primitive::U16 => serializer.serialize_u16(value as u16),
+                    Primitive::U32 | Primitive::U24 => serializer.serialize_u32(value as u32),
+                    Primitive::U64 | Primitive::U40 | Primitive::U48 => serializer.serialize_u64(value as u64),
+                    Primitive::I8 => serializer.serialize_i8(value as i8),
+                    Primitive::I16 => serializer.serialize_i16(value as i16),
+                    Primitive::I32 | Primitive::I24 => serializer.serialize_i32(value as i32),
+                    Primitive::I64 | Primitive::I48 => serializer.serialize_i64(value as i64),
+                    Primitive::Bool => serializer.serialize_bool(value != 0),
+                    Primitive::Char => match char::from_u32(value as u32) {
+                        Some(c) => serializer.serialize_char(c),
+                        None => Err(S::Error::custom(Error::InvalidChar(value as u32))),
+                    },
+                    Primitive::F32 | Primitive::F64 => unreachable!(),
+                }
+            },
+            Type::Tuple([]) => serializer.serialize_unit(),
+            Type::Tuple(tys) => {
+                let mut tuple = serializer.serialize_tuple(tys.len())?;
+                self.fields(tys.iter(), &mut tuple)?;
+                tuple.end()
+            },
+            Type::Array { element, len } => {
+                let mut tuple = serializer.serialize_tuple(*len)?;
+                self.fields((0..*len).map(|_| *element), &mut tuple)?;
+                tuple.end()
+            },
+            Type::Option(ty) => {
+                if bytes[0] != 0 {
+                    serializer.serialize_some(&self.child(ty, 1, self.byte_order))
+                } else {
+                    serializer.serialize_none()
+                }
+            },
+            Type::Endian(order, ty) => {
+                let name = match order {
+                    Endianness::Big => BE_NAME,
+                    Endianness::Little => LE_NAME,
+                };
+                serializer.serialize_newtype_struct(name, &self.child(ty, 0, *order))
+            },
+            Type::Struct { name, fields: [] } => serializer.serialize_unit_struct(name),
+            Type::Struct { name, fields: [field] } if is_index(field) => serializer.serialize_newtype_struct(name, &self.child(field.ty, 0, self.byte_order)),
+            Type::Struct { name, fields } if fields.iter().all(is_index) => {
+                let skip = serializer.is_human_readable();
+                let len = fields.iter().filter(|field| !(skip && is_reserved(field))).count();
+                let mut tuple = serializer.serialize_tuple_struct(name, len)?;
+                let mut offset = 0;
+                for field in fields.iter() {
+                    let child = self.child(field.ty, offset, self.byte_order);
+                    if !(skip && is_reserved(field)) {
+                        tuple.serialize_field(&FieldValue(child, field))?;
+                    }
+                    offset += child.buffer.len();
+                }
+                tuple.end()
+            },
+            Type::Struct { name, fields } => {
+                let skip = serializer.is_human_readable();
+                let len = fields.iter().filter(|field| !(skip && is_reserved(field))).count();
+                let mut map = serializer.serialize_struct(name, len)?;
+                let mut offset = 0;
+                for field in fields.iter() {
+                    let child = self.child(field.ty, offset, self.byte_order);
+                    if skip && is_reserved(field) {
+                        map.skip_field(field.name)?;
+                    } else {
+                        map.serialize_field(field.name, &FieldValue(child, field))?;
+                    }
+                    offset += child.buffer.len();
+                }
+                map.end()
+            },
+            Type::Enum { name, repr, variants } => {
+                let value = read_integer(*repr, bytes, self.byte_order);
+                match variants.iter().position(|v| v.value == value) {
+                    Some(index) => serializer.serialize_unit_variant(name, index as u32, variants[index].name),
+                    None => Err(S::Error::custom(format_args!("invalid value {} for enum {}", value, name))),
+                }
+            },
+            Type::Reserved(len) => {
+                let mut tuple = serializer.serialize_tuple(*len)?;
+                for _ in 0..*len {
+                    tuple.serialize_element(&0u8)?;
+                }
+                tuple.end()
+            },
+            Type::Bcd(p) => {
+                let bits = read_integer(*p, bytes, self.byte_order) as u32;
+                let value = match bcd::decode(bits) {
+                    Some(value) => value,
+                    None => return Err(S::Error::custom(format_args!("invalid BCD {:#x}", bits))),
+                };
+                match p {
+                    Primitive::U8 => serializer.serialize_u8(value as u8),
+                    Primitive::U16 => serializer.serialize_u16(value as u16),
+                    _ => serializer.serialize_u32(value),
+                }
+            },
+            Type::Float { repr, exp_bits, mantissa_bits } => {
+                let bits = read_integer(*repr, bytes, self.byte_order) as u64;
+                // All reduced-precision formats are exactly representable as `f32`
+                serializer.serialize_f32(float::decode(bits, *exp_bits, *mantissa_bits) as f32)
+            },
+            Type::Varint(p) => {
+                let value = match varint::decode(bytes, p.size() as u32 * 8) {
+                    Ok((value, _)) => value,
+                    Err(_) => return Err(S::Error::custom(format_args!("invalid varint {:02x?}", bytes))),
+                };
+                match p {
+                    Primitive::U8 => serializer.serialize_u8(value as u8),
+                    Primitive::U16 => serializer.serialize_u16(value as u16),
+                    Primitive::U32 => serializer.serialize_u32(value as u32),
+                    Primitive::U64 => serializer.serialize_u64(value),
+                    Primitive::I8 => serializer.serialize_i8(varint::unzigzag(value) as i8),
+                    Primitive::I16 => serializer.serialize_i16(varint::unzigzag(value) as i16),
+                    Primitive::I32 => serializer.serialize_i32(varint::unzigzag(value) as i32),
+                    Primitive::I64 => serializer.serialize_i64(varint::unzigzag(value)),
+                    _ => Err(S::Error::custom(format_args!("invalid varint type {:?}", p))),
+                }
+            },
+        }
+    }
+}
+
+/// The value of a struct field, which is converted with the field's scale and
+/// offset in human-readable formats.
+struct FieldValue<'a>(Value<'a>, &'a Field);
+
+impl Serialize for FieldValue<'_> {
+    fn serialize<S>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error> where S: ser::Serializer {
+        let FieldValue(value, field) = self;
+        if !(field.is_scaled() && serializer.is_human_readable()) {
+            return value.serialize(serializer);
+        }
+        let encoded = match value.unwrap_endian() {
+            (Type::Primitive(Primitive::F32), Endianness::Big) => BigEndian::read_f32(value.buffer).into(),
+            (Type::Primitive(Primitive::F32), Endianness::Little) => LittleEndian::read_f32(value.buffer).into(),
+            (Type::Primitive(Primitive::F64), Endianness::Big) => BigEndian::read_f64(value.buffer),
+            (Type::Primitive(Primitive::F64), Endianness::Little) => LittleEndian::read_f64(value.buffer),
+            (Type::Primitive(p), order) => read_integer(*p, value.buffer, order) as f64,
+            _ => return value.serialize(serializer),
+        };
+        serializer.serialize_f64(encoded * field.scale + field.offset)
+    }
+}
+
+/// Whether the field only reserves space.
+fn is_reserved(field: &Field) -> bool {
+    matches!(field.ty, Type::Reserved(_))
+}
+
+/// Whether the field belongs to a tuple struct.
+fn is_index(field: &Field) -> bool {
+    field.name.starts_with(|c: char| c.is_ascii_digit())
+}
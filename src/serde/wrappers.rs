@@ -0,0 +1,562 @@
+//! `Serialize` and `Deserialize` for the wrapper types, which use their
+//! logical value in human-readable formats and their packed representation
+//! otherwise.
+
+use core::{fmt, marker::PhantomData};
+
+use ::serde::{Deserialize, Deserializer, Serialize, Serializer, de::{self, MapAccess, SeqAccess, Unexpected, Visitor}, ser::{self, SerializeStruct, SerializeTuple}};
+use byteorder::BigEndian;
+
+use super::{INT24_NAME, INT40_NAME, INT48_NAME, Newtype, VARINT_NAME, __private::FieldIndex};
+use crate::{
+    Pack, Size, Unpack,
+    bcd::{Bcd, DateTime, Digits},
+    bounded::{BoundedVec, Length},
+    collections::{Check, Collection, Compact, Padded},
+    fixed::{Base, Fixed},
+    float::{BF16, F16, Minifloat},
+    int::{I24, I48, U24, U40, U48},
+    string::{AsciiStr, FixedStr, Padding},
+    timestamp::{GpsTime, NtpTimestamp, UnixMillis, UnixSeconds32, UnixSeconds64},
+    varint::{self, Signed, Unsigned, Varint, ZigZag},
+};
+
+/// Serializes packed bytes as a tuple (like `Reserved`).
+fn serialize_bytes<S>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+    let mut tuple = serializer.serialize_tuple(bytes.len())?;
+    for byte in bytes {
+        tuple.serialize_element(byte)?;
+    }
+    tuple.end()
+}
+
+/// Deserializes packed bytes from a tuple and unpacks them.
+fn deserialize_bytes<'de, T, D, const N: usize>(deserializer: D) -> Result<T, D::Error> where T: Unpack, D: Deserializer<'de> {
+    let bytes = deserializer.deserialize_tuple(N, Bytes::<N>)?;
+    // The values consist of single bytes, so the byte order does not matter
+    T::unpack::<BigEndian>(&bytes).map_err(|e| de::Error::custom(format_args!("{:?}", e)))
+}
+
+/// Visits a tuple of `N` bytes.
+struct Bytes<const N: usize>;
+
+impl<'de, const N: usize> Visitor<'de> for Bytes<N> {
+    type Value = [u8; N];
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} bytes", N)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error> where A: SeqAccess<'de> {
+        let mut bytes = [0; N];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(i, &self))?;
+        }
+        Ok(bytes)
+    }
+}
+
+/// Checks that an unsigned value fits into the given number of bits.
+fn check_bits<E>(value: u64, bits: u32, unexpected: Unexpected<'_>) -> Result<u64, E> where E: de::Error {
+    match bits < 64 && value >> bits != 0 {
+        true => Err(E::invalid_value(unexpected, &"an integer in the range of the type")),
+        false => Ok(value),
+    }
+}
+
+/// Deserializes the `u64` of a varint.
+fn deserialize_varint<'de, D>(deserializer: D) -> Result<u64, D::Error> where D: Deserializer<'de> {
+    match deserializer.is_human_readable() {
+        true => u64::deserialize(deserializer),
+        false => deserializer.deserialize_newtype_struct(VARINT_NAME, Newtype(PhantomData)),
+    }
+}
+
+impl<T> Serialize for Varint<T> where T: Unsigned {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        let value = self.0.into_u64();
+        match serializer.is_human_readable() {
+            true => serializer.serialize_u64(value),
+            false => serializer.serialize_newtype_struct(VARINT_NAME, &value),
+        }
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Varint<T> where T: Unsigned {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+        let value = deserialize_varint(deserializer)?;
+        Ok(Self(T::from_u64(check_bits(value, T::BITS, Unexpected::Unsigned(value))?)))
+    }
+}
+
+/// Serialized as the signed value in human-readable formats and as the
+/// zigzag-mapped varint otherwise.
+impl<T> Serialize for ZigZag<T> where T: Signed {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        let zigzag = self.0.zigzag();
+        match serializer.is_human_readable() {
+            true => serializer.serialize_i64(varint::unzigzag(zigzag)),
+            false => serializer.serialize_newtype_struct(VARINT_NAME, &zigzag),
+        }
+    }
+}
+
+impl<'de, T> Deserialize<'de> for ZigZag<T> where T: Signed {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+        let (zigzag, unexpected) = if deserializer.is_human_readable() {
+            let value = i64::deserialize(deserializer)?;
+            (((value << 1) ^ (value >> 63)) as u64, Unexpected::Signed(value))
+        } else {
+            let zigzag = deserialize_varint(deserializer)?;
+            (zigzag, Unexpected::Unsigned(zigzag))
+        };
+        Ok(Self(T::unzigzag(check_bits(zigzag, T::BITS, unexpected)?)))
+    }
+}
+
+/// Implements the traits for odd-width integers, which are serialized as the
+/// native integer in human-readable formats and with their own width otherwise.
+macro_rules! impl_int {
+    ($($name:ident($native:ty, $wide:ty, $unexpected:ident, $encoding:ident)),*) => {
+        $(
+            impl Serialize for $name {
+                fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+                    match serializer.is_human_readable() {
+                        true => self.get().serialize(serializer),
+                        false => serializer.serialize_newtype_struct($encoding, &(self.get() as $wide)),
+                    }
+                }
+            }
+
+            impl<'de> Deserialize<'de> for $name {
+                fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+                    let value = match deserializer.is_human_readable() {
+                        true => <$wide>::from(<$native>::deserialize(deserializer)?),
+                        false => deserializer.deserialize_newtype_struct($encoding, Newtype::<$wide>(PhantomData))?,
+                    };
+                    <$native>::try_from(value).ok()
+                        .and_then(Self::new)
+                        .ok_or_else(|| de::Error::invalid_value(Unexpected::$unexpected(value), &"an integer in the range of the type"))
+                }
+            }
+        )*
+    };
+}
+
+impl_int!(
+    U24(u32, u64, Unsigned, INT24_NAME),
+    I24(i32, i64, Signed, INT24_NAME),
+    U40(u64, u64, Unsigned, INT40_NAME),
+    U48(u64, u64, Unsigned, INT48_NAME),
+    I48(i64, i64, Signed, INT48_NAME)
+);
+
+/// Serialized as `f64` in human-readable formats and as the raw integer
+/// otherwise.
+impl<I, const FRAC_BITS: u32> Serialize for Fixed<I, FRAC_BITS> where I: Base + Serialize {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        match serializer.is_human_readable() {
+            true => serializer.serialize_f64(self.to_f64()),
+            false => self.to_bits().serialize(serializer),
+        }
+    }
+}
+
+impl<'de, I, const FRAC_BITS: u32> Deserialize<'de> for Fixed<I, FRAC_BITS> where I: Base + Deserialize<'de> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+        if !deserializer.is_human_readable() {
+            return Ok(Self::from_bits(I::deserialize(deserializer)?));
+        }
+        let value = f64::deserialize(deserializer)?;
+        Self::checked_from_f64(value).ok_or_else(|| de::Error::invalid_value(Unexpected::Float(value), &"a number in the range of the format"))
+    }
+}
+
+/// Implements the traits for reduced-precision floats, which are serialized
+/// as `f32` in human-readable formats and as their raw bits otherwise.
+macro_rules! impl_float {
+    ($($name:ident $(<$(const $param:ident: u32),*>)?($bits:ty)),*) => {
+        $(
+            impl $(<$(const $param: u32),*>)? Serialize for $name $(<$($param),*>)? {
+                fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+                    match serializer.is_human_readable() {
+                        true => serializer.serialize_f32(self.to_f32()),
+                        false => self.to_bits().serialize(serializer),
+                    }
+                }
+            }
+
+            impl<'de, $($(const $param: u32),*)?> Deserialize<'de> for $name $(<$($param),*>)? {
+                fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+                    match deserializer.is_human_readable() {
+                        true => Ok(Self::from_f64(f64::deserialize(deserializer)?)),
+                        false => Ok(Self::from_bits(<$bits>::deserialize(deserializer)?)),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_float!(F16(u16), BF16(u16), Minifloat<const EXP_BITS: u32, const MANTISSA_BITS: u32>(u8));
+
+/// Serialized as the decimal value in human-readable formats and as the
+/// packed BCD otherwise.
+impl<T> Serialize for Bcd<T> where T: Digits + Serialize {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        match serializer.is_human_readable() {
+            true => self.get().serialize(serializer),
+            false => self.to_bits().serialize(serializer),
+        }
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Bcd<T> where T: Digits + Deserialize<'de> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+        let human_readable = deserializer.is_human_readable();
+        let value = T::deserialize(deserializer)?;
+        match human_readable {
+            true => Self::new(value).ok_or_else(|| de::Error::invalid_value(Unexpected::Unsigned(value.into_u32().into()), &"a value with fewer digits")),
+            false => Self::from_bits(value).ok_or_else(|| de::Error::invalid_value(Unexpected::Unsigned(value.into_u32().into()), &"packed BCD digits")),
+        }
+    }
+}
+
+/// Defines a visitor for a struct with the given fields, which accepts them
+/// as a sequence (like binary formats) or as a map.
+macro_rules! struct_visitor {
+    ($visitor:ident, $name:ident { $($field:ident: $ty:ty),* }) => {
+        struct $visitor;
+
+        impl $visitor {
+            const FIELDS: &'static [&'static str] = &[$(stringify!($field)),*];
+        }
+
+        impl<'de> Visitor<'de> for $visitor {
+            type Value = $name;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "struct {}", stringify!($name))
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<$name, A::Error> where A: SeqAccess<'de> {
+                let mut index = 0;
+                $(
+                    let $field = seq.next_element::<$ty>()?.ok_or_else(|| de::Error::invalid_length(index, &self))?;
+                    index += 1;
+                )*
+                let _ = index;
+                Ok($name { $($field),* })
+            }
+
+            fn visit_map<A>(self, mut map: A) -> Result<$name, A::Error> where A: MapAccess<'de> {
+                $(let mut $field = None;)*
+                while let Some(index) = map.next_key_seed(FieldIndex(Self::FIELDS))? {
+                    let Some(index) = index else {
+                        map.next_value::<de::IgnoredAny>()?;
+                        continue;
+                    };
+                    $(
+                        if Self::FIELDS[index] == stringify!($field) {
+                            if $field.is_some() {
+                                return Err(de::Error::duplicate_field(stringify!($field)));
+                            }
+                            $field = Some(map.next_value::<$ty>()?);
+                        }
+                    )*
+                }
+                Ok($name { $($field: $field.ok_or_else(|| de::Error::missing_field(stringify!($field)))?),* })
+            }
+        }
+    };
+}
+
+struct_visitor!(DateTimeVisitor, DateTime { seconds: u8, minutes: u8, hours: u8, weekday: u8, day: u8, month: u8, year: u8 });
+struct_visitor!(NtpTimestampVisitor, NtpTimestamp { seconds: u32, fraction: u32 });
+struct_visitor!(GpsTimeVisitor, GpsTime { week: u16, time_of_week: u32 });
+
+/// Serialized as a struct of decimal values in human-readable formats and as
+/// the packed registers otherwise.
+impl Serialize for DateTime {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        if !serializer.is_human_readable() {
+            let mut registers = [0; DateTime::SIZE];
+            self.try_pack::<BigEndian>(&mut registers).map_err(|e| ser::Error::custom(format_args!("{:?}", e)))?;
+            return serialize_bytes(&registers, serializer);
+        }
+        let mut state = serializer.serialize_struct("DateTime", 7)?;
+        state.serialize_field("seconds", &self.seconds)?;
+        state.serialize_field("minutes", &self.minutes)?;
+        state.serialize_field("hours", &self.hours)?;
+        state.serialize_field("weekday", &self.weekday)?;
+        state.serialize_field("day", &self.day)?;
+        state.serialize_field("month", &self.month)?;
+        state.serialize_field("year", &self.year)?;
+        state.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for DateTime {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+        match deserializer.is_human_readable() {
+            true => deserializer.deserialize_struct("DateTime", DateTimeVisitor::FIELDS, DateTimeVisitor),
+            false => deserialize_bytes::<Self, D, { DateTime::SIZE }>(deserializer),
+        }
+    }
+}
+
+impl Serialize for NtpTimestamp {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        let mut state = serializer.serialize_struct("NtpTimestamp", 2)?;
+        state.serialize_field("seconds", &self.seconds)?;
+        state.serialize_field("fraction", &self.fraction)?;
+        state.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for NtpTimestamp {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+        deserializer.deserialize_struct("NtpTimestamp", NtpTimestampVisitor::FIELDS, NtpTimestampVisitor)
+    }
+}
+
+impl Serialize for GpsTime {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        let mut state = serializer.serialize_struct("GpsTime", 2)?;
+        state.serialize_field("week", &self.week)?;
+        state.serialize_field("time_of_week", &self.time_of_week)?;
+        state.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for GpsTime {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+        deserializer.deserialize_struct("GpsTime", GpsTimeVisitor::FIELDS, GpsTimeVisitor)
+    }
+}
+
+/// Implements the traits for timestamps that are newtypes, like a derived
+/// tuple struct.
+macro_rules! impl_newtype {
+    ($($name:ident($t:ty)),*) => {
+        $(
+            impl Serialize for $name {
+                fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+                    serializer.serialize_newtype_struct(stringify!($name), &self.0)
+                }
+            }
+
+            impl<'de> Deserialize<'de> for $name {
+                fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+                    deserializer.deserialize_newtype_struct(stringify!($name), Newtype::<$t>(PhantomData)).map(Self)
+                }
+            }
+        )*
+    };
+}
+
+impl_newtype!(UnixSeconds32(u32), UnixSeconds64(u64), UnixMillis(u64));
+
+/// Implements the traits for fixed-capacity strings, which are serialized as
+/// string in human-readable formats and as the padded bytes otherwise.
+macro_rules! impl_str {
+    ($($name:ident($visitor:ident, $expecting:literal)),*) => {
+        $(
+            impl<const N: usize, P> Serialize for $name<N, P> where P: Padding {
+                fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+                    if serializer.is_human_readable() {
+                        return serializer.serialize_str(self.as_str());
+                    }
+                    let mut bytes = [0; N];
+                    self.pack::<BigEndian>(&mut bytes);
+                    serialize_bytes(&bytes, serializer)
+                }
+            }
+
+            impl<'de, const N: usize, P> Deserialize<'de> for $name<N, P> where P: Padding {
+                fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+                    match deserializer.is_human_readable() {
+                        true => deserializer.deserialize_str($visitor(PhantomData)),
+                        false => deserialize_bytes::<Self, D, N>(deserializer),
+                    }
+                }
+            }
+
+            /// Visits a string that fits into the capacity.
+            struct $visitor<const N: usize, P>(PhantomData<P>);
+
+            impl<'de, const N: usize, P> Visitor<'de> for $visitor<N, P> {
+                type Value = $name<N, P>;
+
+                fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    write!(f, $expecting, N)
+                }
+
+                fn visit_str<E>(self, value: &str) -> Result<Self::Value, E> where E: de::Error {
+                    $name::new(value).ok_or_else(|| E::invalid_value(Unexpected::Str(value), &self))
+                }
+            }
+        )*
+    };
+}
+
+impl_str!(FixedStr(FixedStrVisitor, "a string of up to {} bytes"), AsciiStr(AsciiStrVisitor, "an ASCII string of up to {} characters"));
+
+/// Serializes a collection as a sequence (or string) in human-readable
+/// formats and as the length prefix and the items otherwise, followed by zero
+/// bytes for the unused capacity if it is `padded`.
+fn serialize_collection<C, L, S>(collection: &C, padded: bool, serializer: S) -> Result<S::Ok, S::Error>
+where C: Collection, C::Item: Serialize, L: Length + Serialize, S: Serializer {
+    #[allow(clippy::let_unit_value)]
+    let () = Check::<C, L>::VALID;
+    let items = collection.items();
+    if serializer.is_human_readable() {
+        return match collection.as_str() {
+            Some(s) => serializer.serialize_str(s),
+            None => serializer.collect_seq(items),
+        };
+    }
+    let padding = if padded { (C::CAPACITY - items.len()) * C::Item::SIZE } else { 0 };
+    let mut tuple = serializer.serialize_tuple(1 + items.len() + padding)?;
+    tuple.serialize_element(&L::from_usize(items.len()))?;
+    for item in items {
+        tuple.serialize_element(item)?;
+    }
+    for _ in 0..padding {
+        tuple.serialize_element(&0u8)?;
+    }
+    tuple.end()
+}
+
+/// Deserializes a collection serialized by [`serialize_collection`].
+fn deserialize_collection<'de, C, L, D>(padded: bool, deserializer: D) -> Result<C, D::Error>
+where C: Collection, C::Item: Deserialize<'de>, L: Length + Deserialize<'de>, D: Deserializer<'de> {
+    if deserializer.is_human_readable() {
+        // Strings and sequences are both accepted, since only the collection knows which it is
+        return deserializer.deserialize_any(Items::<C, L> { packed: None, marker: PhantomData });
+    }
+    let len = match padded {
+        true => 1 + C::CAPACITY * C::Item::SIZE.max(1),
+        false => 1 + C::CAPACITY,
+    };
+    deserializer.deserialize_tuple(len, Items::<C, L> { packed: Some(padded), marker: PhantomData })
+}
+
+/// Visits the items of a collection, as sequence or string in human-readable
+/// formats (`packed` is `None`), and as the length prefix and the items
+/// otherwise (and the padding if `packed` is `Some(true)`).
+struct Items<C, L> {
+    packed: Option<bool>,
+    marker: PhantomData<(C, L)>,
+}
+
+impl<'de, C, L> Visitor<'de> for Items<C, L> where C: Collection, C::Item: Deserialize<'de>, L: Length + Deserialize<'de> {
+    type Value = C;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a collection of up to {} items", C::CAPACITY)
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<C, E> where E: de::Error {
+        C::try_from_str(value).ok_or_else(|| E::invalid_value(Unexpected::Str(value), &self))
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<C, A::Error> where A: SeqAccess<'de> {
+        let Some(padded) = self.packed else {
+            let mut items = SeqItems { seq: &mut seq, remaining: usize::MAX, index: 0, error: None, marker: PhantomData };
+            let collection = C::from_items(&mut items);
+            if let Some(error) = items.error {
+                return Err(error);
+            }
+            return collection.ok_or_else(|| de::Error::invalid_length(items.index, &self));
+        };
+
+        let len = seq.next_element::<L>()?.ok_or_else(|| de::Error::invalid_length(0, &self))?.to_usize();
+        if len > C::CAPACITY {
+            return Err(de::Error::invalid_length(len, &self));
+        }
+        let mut items = SeqItems { seq: &mut seq, remaining: len, index: 0, error: None, marker: PhantomData };
+        let collection = C::from_items(&mut items);
+        if let Some(error) = items.error {
+            return Err(error);
+        }
+        if items.index < len {
+            return Err(de::Error::invalid_length(1 + items.index, &self));
+        }
+        let collection = collection.ok_or_else(|| de::Error::custom("the items do not form a valid collection"))?;
+        if padded {
+            for i in 0..(C::CAPACITY - len) * C::Item::SIZE {
+                seq.next_element::<u8>()?.ok_or_else(|| de::Error::invalid_length(1 + len + i, &self))?;
+            }
+        }
+        Ok(collection)
+    }
+}
+
+/// The next `remaining` elements of a sequence, stopping at the first error
+/// (which is kept in `error`).
+struct SeqItems<'a, A, E, T> {
+    seq: &'a mut A,
+    remaining: usize,
+    index: usize,
+    error: Option<E>,
+    marker: PhantomData<T>,
+}
+
+impl<'a, 'de, A, T> Iterator for SeqItems<'a, A, A::Error, T> where A: SeqAccess<'de>, T: Deserialize<'de> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.index == self.remaining || self.error.is_some() {
+            return None;
+        }
+        match self.seq.next_element() {
+            Ok(item) => {
+                self.index += item.is_some() as usize;
+                item
+            },
+            Err(error) => {
+                self.error = Some(error);
+                None
+            },
+        }
+    }
+}
+
+/// Serialized as a sequence in human-readable formats and like its packed
+/// representation otherwise, i.e. with the length prefix and all slots.
+impl<T, const N: usize, L> Serialize for BoundedVec<T, N, L> where T: Size + Serialize, L: Length + Serialize {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        serialize_collection::<Self, L, S>(self, true, serializer)
+    }
+}
+
+impl<'de, T, const N: usize, L> Deserialize<'de> for BoundedVec<T, N, L> where T: Size + Deserialize<'de>, L: Length + Deserialize<'de> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+        deserialize_collection::<Self, L, D>(true, deserializer)
+    }
+}
+
+impl<C, L> Serialize for Padded<C, L> where C: Collection, C::Item: Serialize, L: Length + Serialize {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        serialize_collection::<C, L, S>(&self.0, true, serializer)
+    }
+}
+
+impl<'de, C, L> Deserialize<'de> for Padded<C, L> where C: Collection, C::Item: Deserialize<'de>, L: Length + Deserialize<'de> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+        deserialize_collection::<C, L, D>(true, deserializer).map(Self::new)
+    }
+}
+
+impl<C, L> Serialize for Compact<C, L> where C: Collection, C::Item: Serialize, L: Length + Serialize {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        serialize_collection::<C, L, S>(&self.0, false, serializer)
+    }
+}
+
+impl<'de, C, L> Deserialize<'de> for Compact<C, L> where C: Collection, C::Item: Deserialize<'de>, L: Length + Deserialize<'de> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+        deserialize_collection::<C, L, D>(false, deserializer).map(Self::new)
+    }
+}
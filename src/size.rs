@@ -2,8 +2,21 @@
 
 /// Types that have an encoded size.
 pub trait Size {
-    /// The type's encoded size in bytes.
+    /// The type's encoded size in bytes. For types whose encoded size depends
+    /// on the value, such as [`Varint`](crate::varint::Varint), this is the
+    /// maximum encoded size.
     const SIZE: usize;
+
+    /// Whether every value is encoded with exactly [`Size::SIZE`] bytes. Only
+    /// then do fields following a value of this type have a fixed offset.
+    const FIXED_SIZE: bool = true;
+
+    /// The encoded size of the value in bytes, which is [`Size::SIZE`] unless
+    /// the encoded size depends on the value. Tuples, arrays, options and
+    /// derived structs pack their fields back to back with this size.
+    fn encoded_size(&self) -> usize {
+        Self::SIZE
+    }
 }
 
 impl Size for u8 {
@@ -62,32 +75,76 @@ impl Size for () {
 
 impl<T1> Size for (T1,) where T1: Size {
     const SIZE: usize = T1::SIZE;
+    const FIXED_SIZE: bool = T1::FIXED_SIZE;
+
+    fn encoded_size(&self) -> usize {
+        self.0.encoded_size()
+    }
 }
 
 impl<T1, T2> Size for (T1, T2) where T1: Size, T2: Size {
     const SIZE: usize = T1::SIZE + T2::SIZE;
+    const FIXED_SIZE: bool = T1::FIXED_SIZE && T2::FIXED_SIZE;
+
+    fn encoded_size(&self) -> usize {
+        self.0.encoded_size() + self.1.encoded_size()
+    }
 }
 
 impl<T1, T2, T3> Size for (T1, T2, T3) where T1: Size, T2: Size, T3: Size {
     const SIZE: usize = T1::SIZE + T2::SIZE + T3::SIZE;
+    const FIXED_SIZE: bool = T1::FIXED_SIZE && T2::FIXED_SIZE && T3::FIXED_SIZE;
+
+    fn encoded_size(&self) -> usize {
+        self.0.encoded_size() + self.1.encoded_size() + self.2.encoded_size()
+    }
 }
 
 impl<T1, T2, T3, T4> Size for (T1, T2, T3, T4) where T1: Size, T2: Size, T3: Size, T4: Size {
     const SIZE: usize = T1::SIZE + T2::SIZE + T3::SIZE + T4::SIZE;
+    const FIXED_SIZE: bool = T1::FIXED_SIZE && T2::FIXED_SIZE && T3::FIXED_SIZE && T4::FIXED_SIZE;
+
+    fn encoded_size(&self) -> usize {
+        self.0.encoded_size() + self.1.encoded_size() + self.2.encoded_size() + self.3.encoded_size()
+    }
 }
 
 impl<T> Size for &T where T: Size {
     const SIZE: usize = T::SIZE;
+    const FIXED_SIZE: bool = T::FIXED_SIZE;
+
+    fn encoded_size(&self) -> usize {
+        T::encoded_size(self)
+    }
 }
 
 impl<T> Size for &mut T where T: Size {
     const SIZE: usize = T::SIZE;
+    const FIXED_SIZE: bool = T::FIXED_SIZE;
+
+    fn encoded_size(&self) -> usize {
+        T::encoded_size(self)
+    }
 }
 
 impl<T> Size for Option<T> where T: Size {
     const SIZE: usize = T::SIZE + 1;
+    const FIXED_SIZE: bool = T::FIXED_SIZE;
+
+    /// The flag and the value, or [`Size::SIZE`] for `None`.
+    fn encoded_size(&self) -> usize {
+        match self {
+            Some(x) => 1 + x.encoded_size(),
+            None => Self::SIZE,
+        }
+    }
 }
 
 impl<T, const N: usize> Size for [T; N] where T: Size {
     const SIZE: usize = T::SIZE * N;
+    const FIXED_SIZE: bool = T::FIXED_SIZE;
+
+    fn encoded_size(&self) -> usize {
+        self.iter().map(T::encoded_size).sum()
+    }
 }
@@ -0,0 +1,199 @@
+//! Fixed-capacity strings.
+//!
+//! [`FixedStr<N>`](FixedStr) holds a UTF-8 string of up to `N` bytes and
+//! [`AsciiStr<N>`](AsciiStr) an ASCII string of up to `N` characters. Both
+//! are encoded as exactly `N` bytes, padded with NUL bytes (or spaces with
+//! the [`Space`] padding), e.g. for device names or serial numbers:
+//!
+//! ```ignore
+//! #[derive(Size, Pack, Unpack)]
+//! struct Identity {
+//!     name: FixedStr<8>,
+//!     serial: AsciiStr<6, Space>,
+//! }
+//!
+//! let identity = Identity::unpack::<BigEndian>(b"sensor\0\0AB12  ")?;
+//! (identity.name.as_str(), identity.serial.as_str())
+//! // => ("sensor", "AB12")
+//! ```
+//!
+//! Unpacking trims the trailing padding and rejects invalid UTF-8 with
+//! [`Error::InvalidUtf8`] (or non-ASCII bytes with [`Error::InvalidAscii`]).
+//! Trailing padding characters are therefore not preserved in a roundtrip.
+
+use core::{fmt, marker::PhantomData, ops::Deref};
+
+use byteorder::ByteOrder;
+
+use crate::{Pack, Schema, Size, Unpack, schema::Type, unpack::{Error, Result}};
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// The byte that fills the unused capacity of a string.
+pub trait Padding: sealed::Sealed {
+    /// The padding byte.
+    const BYTE: u8;
+}
+
+/// Padding with NUL bytes.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
+pub struct Nul;
+
+/// Padding with spaces.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
+pub struct Space;
+
+impl sealed::Sealed for Nul {}
+impl sealed::Sealed for Space {}
+
+impl Padding for Nul {
+    const BYTE: u8 = 0;
+}
+
+impl Padding for Space {
+    const BYTE: u8 = b' ';
+}
+
+/// The length of the bytes without trailing padding.
+fn trimmed_len(bytes: &[u8], padding: u8) -> usize {
+    bytes.iter().rposition(|&b| b != padding).map_or(0, |i| i + 1)
+}
+
+macro_rules! impl_str {
+    ($(#[$attr:meta])* $name:ident, $valid:expr, $error:expr) => {
+        $(#[$attr])*
+        pub struct $name<const N: usize, P = Nul> {
+            bytes: [u8; N],
+            len: usize,
+            padding: PhantomData<P>,
+        }
+
+        impl<const N: usize, P> $name<N, P> {
+            /// Creates a string, or returns `None` if it is too long (or invalid).
+            pub fn new(s: &str) -> Option<Self> {
+                if s.len() > N || !($valid)(s.as_bytes()) {
+                    return None;
+                }
+                let mut bytes = [0; N];
+                bytes[..s.len()].copy_from_slice(s.as_bytes());
+                Some(Self { bytes, len: s.len(), padding: PhantomData })
+            }
+
+            /// The string.
+            pub fn as_str(&self) -> &str {
+                // SAFETY: The bytes are validated on construction
+                unsafe { core::str::from_utf8_unchecked(&self.bytes[..self.len]) }
+            }
+
+            /// The capacity in bytes.
+            pub const fn capacity(&self) -> usize {
+                N
+            }
+        }
+
+        impl<const N: usize, P> Default for $name<N, P> {
+            fn default() -> Self {
+                Self { bytes: [0; N], len: 0, padding: PhantomData }
+            }
+        }
+
+        impl<const N: usize, P> Clone for $name<N, P> {
+            fn clone(&self) -> Self {
+                *self
+            }
+        }
+
+        impl<const N: usize, P> Copy for $name<N, P> {}
+
+        impl<const N: usize, P> PartialEq for $name<N, P> {
+            fn eq(&self, other: &Self) -> bool {
+                self.as_str() == other.as_str()
+            }
+        }
+
+        impl<const N: usize, P> Eq for $name<N, P> {}
+
+        impl<const N: usize, P> PartialEq<str> for $name<N, P> {
+            fn eq(&self, other: &str) -> bool {
+                self.as_str() == other
+            }
+        }
+
+        impl<const N: usize, P> PartialEq<&str> for $name<N, P> {
+            fn eq(&self, other: &&str) -> bool {
+                self.as_str() == *other
+            }
+        }
+
+        impl<const N: usize, P> core::hash::Hash for $name<N, P> {
+            fn hash<H>(&self, state: &mut H) where H: core::hash::Hasher {
+                self.as_str().hash(state)
+            }
+        }
+
+        impl<const N: usize, P> Deref for $name<N, P> {
+            type Target = str;
+
+            fn deref(&self) -> &str {
+                self.as_str()
+            }
+        }
+
+        impl<const N: usize, P> AsRef<str> for $name<N, P> {
+            fn as_ref(&self) -> &str {
+                self.as_str()
+            }
+        }
+
+        impl<const N: usize, P> fmt::Debug for $name<N, P> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Debug::fmt(self.as_str(), f)
+            }
+        }
+
+        impl<const N: usize, P> fmt::Display for $name<N, P> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Display::fmt(self.as_str(), f)
+            }
+        }
+
+        impl<const N: usize, P> Size for $name<N, P> {
+            const SIZE: usize = N;
+        }
+
+        /// Described as the padded bytes.
+        impl<const N: usize, P> Schema for $name<N, P> {
+            const SCHEMA: Type = <[u8; N]>::SCHEMA;
+        }
+
+        impl<const N: usize, P> Pack for $name<N, P> where P: Padding {
+            fn pack<B>(&self, buffer: &mut [u8]) where B: ByteOrder {
+                buffer[..self.len].copy_from_slice(&self.bytes[..self.len]);
+                buffer[self.len..N].fill(P::BYTE);
+            }
+        }
+
+        impl<const N: usize, P> Unpack for $name<N, P> where P: Padding {
+            fn unpack<B>(buffer: &[u8]) -> Result<Self> where B: ByteOrder {
+                let len = trimmed_len(&buffer[..N], P::BYTE);
+                if !($valid)(&buffer[..len]) {
+                    return Err($error);
+                }
+                let mut bytes = [0; N];
+                bytes[..len].copy_from_slice(&buffer[..len]);
+                Ok(Self { bytes, len, padding: PhantomData })
+            }
+        }
+    };
+}
+
+impl_str!(
+    /// A UTF-8 string of up to `N` bytes, padded with `P`.
+    FixedStr, |bytes| core::str::from_utf8(bytes).is_ok(), Error::InvalidUtf8
+);
+impl_str!(
+    /// An ASCII string of up to `N` characters, padded with `P`.
+    AsciiStr, <[u8]>::is_ascii, Error::InvalidAscii
+);
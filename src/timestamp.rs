@@ -0,0 +1,275 @@
+//! Timestamp wire formats.
+//!
+//! Each type encodes a point in time as a [`Duration`] since its epoch:
+//!
+//! | Type               | Epoch                 | Encoding                                   |
+//! |--------------------|-----------------------|--------------------------------------------|
+//! | [`UnixSeconds32`]  | 1970-01-01            | `u32` seconds                              |
+//! | [`UnixSeconds64`]  | 1970-01-01            | `u64` seconds                              |
+//! | [`UnixMillis`]     | 1970-01-01            | `u64` milliseconds                         |
+//! | [`NtpTimestamp`]   | 1900-01-01            | `u32` seconds and `u32` fraction (32.32)   |
+//! | [`GpsTime`]        | 1980-01-06            | `u16` week and `u32` milliseconds of week  |
+//!
+//! The [`Timestamp`] trait converts them from and to durations since their
+//! own epoch and since the Unix epoch:
+//!
+//! ```ignore
+//! let sent = NtpTimestamp::unpack::<BigEndian>(&buffer)?;
+//! UnixMillis::from_unix(sent.to_unix().unwrap())
+//! ```
+//!
+//! Conversions from durations truncate to the resolution of the format (NTP
+//! fractions are rounded to the nearest value) and return `None` if the time
+//! does not fit. GPS time is not adjusted for leap seconds, i.e. it is ahead
+//! of UTC by the current leap second count (18 seconds since 2017).
+//!
+//! With the `chrono` or `time` features, timestamps can also be converted
+//! from and to `chrono::DateTime<Utc>` and `time::OffsetDateTime`.
+
+use core::time::Duration;
+
+use byteorder::ByteOrder;
+
+use crate::{Pack, Schema, Size, Unpack, schema::{Field, Type}, unpack::Result};
+
+const NANOS_PER_SEC: i128 = 1_000_000_000;
+const SECS_PER_WEEK: u64 = 7 * 24 * 60 * 60;
+
+/// Types that represent a point in time relative to an epoch.
+pub trait Timestamp: Sized {
+    /// The epoch in seconds since the Unix epoch.
+    const EPOCH: i64;
+
+    /// The time since the epoch.
+    fn to_duration(&self) -> Duration;
+
+    /// Creates a timestamp from the time since the epoch, or returns `None`
+    /// if it does not fit.
+    fn from_duration(duration: Duration) -> Option<Self>;
+
+    /// The time since the Unix epoch, or `None` if it is earlier.
+    fn to_unix(&self) -> Option<Duration> {
+        duration_from_nanos(unix_nanos(self))
+    }
+
+    /// Creates a timestamp from the time since the Unix epoch, or returns
+    /// `None` if it does not fit.
+    fn from_unix(duration: Duration) -> Option<Self> {
+        from_unix_nanos(duration.as_nanos() as i128)
+    }
+
+    /// Converts to a `chrono` date-time, or returns `None` if it is out of range.
+    #[cfg(feature = "chrono")]
+    fn to_chrono(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        let nanos = unix_nanos(self);
+        let seconds = i64::try_from(nanos.div_euclid(NANOS_PER_SEC)).ok()?;
+        chrono::DateTime::from_timestamp(seconds, nanos.rem_euclid(NANOS_PER_SEC) as u32)
+    }
+
+    /// Converts from a `chrono` date-time, or returns `None` if it does not fit.
+    #[cfg(feature = "chrono")]
+    fn from_chrono(date_time: chrono::DateTime<chrono::Utc>) -> Option<Self> {
+        from_unix_nanos(date_time.timestamp() as i128 * NANOS_PER_SEC + date_time.timestamp_subsec_nanos() as i128)
+    }
+
+    /// Converts to a `time` date-time (in UTC), or returns `None` if it is
+    /// out of range.
+    #[cfg(feature = "time")]
+    fn to_time(&self) -> Option<time::OffsetDateTime> {
+        time::OffsetDateTime::from_unix_timestamp_nanos(unix_nanos(self)).ok()
+    }
+
+    /// Converts from a `time` date-time, or returns `None` if it does not fit.
+    #[cfg(feature = "time")]
+    fn from_time(date_time: time::OffsetDateTime) -> Option<Self> {
+        from_unix_nanos(date_time.unix_timestamp_nanos())
+    }
+}
+
+/// The nanoseconds since the Unix epoch.
+fn unix_nanos<T>(timestamp: &T) -> i128 where T: Timestamp {
+    timestamp.to_duration().as_nanos() as i128 + T::EPOCH as i128 * NANOS_PER_SEC
+}
+
+/// Creates a timestamp from the nanoseconds since the Unix epoch.
+fn from_unix_nanos<T>(nanos: i128) -> Option<T> where T: Timestamp {
+    T::from_duration(duration_from_nanos(nanos - T::EPOCH as i128 * NANOS_PER_SEC)?)
+}
+
+/// Converts nanoseconds to a duration, or returns `None` if they are negative
+/// or too large.
+fn duration_from_nanos(nanos: i128) -> Option<Duration> {
+    let seconds = u64::try_from(nanos.div_euclid(NANOS_PER_SEC)).ok()?;
+    Some(Duration::new(seconds, nanos.rem_euclid(NANOS_PER_SEC) as u32))
+}
+
+/// Seconds since the Unix epoch as `u32`, which lasts until 2106.
+#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Clone, Copy, Default)]
+pub struct UnixSeconds32(pub u32);
+
+/// Seconds since the Unix epoch as `u64`.
+#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Clone, Copy, Default)]
+pub struct UnixSeconds64(pub u64);
+
+/// Milliseconds since the Unix epoch as `u64`.
+#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Clone, Copy, Default)]
+pub struct UnixMillis(pub u64);
+
+/// An NTP timestamp: seconds since 1900 and a binary fraction of a second
+/// (in units of `2^-32` seconds). Only era 0 (until 2036) is supported.
+#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Clone, Copy, Default)]
+pub struct NtpTimestamp {
+    pub seconds: u32,
+    pub fraction: u32,
+}
+
+/// A GPS time: weeks since 1980-01-06 (without rollover) and milliseconds
+/// since the start of the week.
+#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Clone, Copy, Default)]
+pub struct GpsTime {
+    pub week: u16,
+    pub time_of_week: u32,
+}
+
+impl Timestamp for UnixSeconds32 {
+    const EPOCH: i64 = 0;
+
+    fn to_duration(&self) -> Duration {
+        Duration::from_secs(self.0.into())
+    }
+
+    fn from_duration(duration: Duration) -> Option<Self> {
+        Some(Self(duration.as_secs().try_into().ok()?))
+    }
+}
+
+impl Timestamp for UnixSeconds64 {
+    const EPOCH: i64 = 0;
+
+    fn to_duration(&self) -> Duration {
+        Duration::from_secs(self.0)
+    }
+
+    fn from_duration(duration: Duration) -> Option<Self> {
+        Some(Self(duration.as_secs()))
+    }
+}
+
+impl Timestamp for UnixMillis {
+    const EPOCH: i64 = 0;
+
+    fn to_duration(&self) -> Duration {
+        Duration::from_millis(self.0)
+    }
+
+    fn from_duration(duration: Duration) -> Option<Self> {
+        Some(Self(duration.as_millis().try_into().ok()?))
+    }
+}
+
+impl Timestamp for NtpTimestamp {
+    const EPOCH: i64 = -2_208_988_800;
+
+    fn to_duration(&self) -> Duration {
+        // Rounded to the nearest nanosecond (which may carry into the seconds)
+        let nanos = ((self.fraction as u64 * NANOS_PER_SEC as u64) + (1 << 31)) >> 32;
+        Duration::from_secs(self.seconds.into()) + Duration::from_nanos(nanos)
+    }
+
+    fn from_duration(duration: Duration) -> Option<Self> {
+        // Rounded to the nearest fraction, which is exact for the roundtrip
+        let fraction = (((duration.subsec_nanos() as u64) << 32) + NANOS_PER_SEC as u64 / 2) / NANOS_PER_SEC as u64;
+        Some(Self { seconds: duration.as_secs().try_into().ok()?, fraction: fraction as u32 })
+    }
+}
+
+impl Timestamp for GpsTime {
+    const EPOCH: i64 = 315_964_800;
+
+    fn to_duration(&self) -> Duration {
+        Duration::from_secs(self.week as u64 * SECS_PER_WEEK) + Duration::from_millis(self.time_of_week.into())
+    }
+
+    fn from_duration(duration: Duration) -> Option<Self> {
+        let week = duration.as_secs() / SECS_PER_WEEK;
+        let time_of_week = duration - Duration::from_secs(week * SECS_PER_WEEK);
+        Some(Self { week: week.try_into().ok()?, time_of_week: time_of_week.as_millis() as u32 })
+    }
+}
+
+macro_rules! impl_newtype {
+    ($($name:ident($t:ty)),*) => {
+        $(
+            impl Size for $name {
+                const SIZE: usize = <$t>::SIZE;
+            }
+
+            /// Described like a derived tuple struct.
+            impl Schema for $name {
+                const SCHEMA: Type = Type::Struct { name: stringify!($name), fields: &[Field::new("0", &<$t>::SCHEMA)] };
+            }
+
+            impl Pack for $name {
+                fn pack<B>(&self, buffer: &mut [u8]) where B: ByteOrder {
+                    self.0.pack::<B>(buffer)
+                }
+            }
+
+            impl Unpack for $name {
+                fn unpack<B>(buffer: &[u8]) -> Result<Self> where B: ByteOrder {
+                    Ok(Self(<$t>::unpack::<B>(buffer)?))
+                }
+            }
+        )*
+    };
+}
+
+impl_newtype!(UnixSeconds32(u32), UnixSeconds64(u64), UnixMillis(u64));
+
+impl Size for NtpTimestamp {
+    const SIZE: usize = <(u32, u32)>::SIZE;
+}
+
+impl Schema for NtpTimestamp {
+    const SCHEMA: Type = Type::Struct {
+        name: "NtpTimestamp",
+        fields: &[Field::new("seconds", &u32::SCHEMA), Field::new("fraction", &u32::SCHEMA)],
+    };
+}
+
+impl Pack for NtpTimestamp {
+    fn pack<B>(&self, buffer: &mut [u8]) where B: ByteOrder {
+        (self.seconds, self.fraction).pack::<B>(buffer)
+    }
+}
+
+impl Unpack for NtpTimestamp {
+    fn unpack<B>(buffer: &[u8]) -> Result<Self> where B: ByteOrder {
+        let (seconds, fraction) = <(u32, u32)>::unpack::<B>(buffer)?;
+        Ok(Self { seconds, fraction })
+    }
+}
+
+impl Size for GpsTime {
+    const SIZE: usize = <(u16, u32)>::SIZE;
+}
+
+impl Schema for GpsTime {
+    const SCHEMA: Type = Type::Struct {
+        name: "GpsTime",
+        fields: &[Field::new("week", &u16::SCHEMA), Field::new("time_of_week", &u32::SCHEMA)],
+    };
+}
+
+impl Pack for GpsTime {
+    fn pack<B>(&self, buffer: &mut [u8]) where B: ByteOrder {
+        (self.week, self.time_of_week).pack::<B>(buffer)
+    }
+}
+
+impl Unpack for GpsTime {
+    fn unpack<B>(buffer: &[u8]) -> Result<Self> where B: ByteOrder {
+        let (week, time_of_week) = <(u16, u32)>::unpack::<B>(buffer)?;
+        Ok(Self { week, time_of_week })
+    }
+}
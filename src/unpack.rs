@@ -19,10 +19,37 @@ pub enum Error {
     InvalidEnumValueI32(i32),
     InvalidEnumValueI64(i64),
     BufferTooSmall { actual: usize, expected: usize },
+    /// A varint is longer than necessary or than the maximum for its type.
+    VarintTooLong,
+    /// A varint's value does not fit into its type.
+    VarintOverflow,
+    /// A BCD value (given as raw bits) contains a nibble greater than 9.
+    InvalidBcd(u32),
+    /// A length prefix exceeds the capacity of a bounded list.
+    LengthExceedsCapacity { len: usize, capacity: usize },
+    /// A string is not valid UTF-8.
+    InvalidUtf8,
+    /// A string contains non-ASCII bytes.
+    InvalidAscii,
+    /// A value does not fit into its type, e.g. a field with a wider
+    /// `#[lightpack(as = ...)]` type.
+    OutOfRange,
     /// A user-defined error with some user-defined error code.
     Custom(u32),
 }
 
+impl Error {
+    /// Converts an error of a value that starts at the given offset of a
+    /// larger buffer to an error for the whole buffer, i.e. shifts the sizes
+    /// of [`Error::BufferTooSmall`].
+    pub fn at_offset(self, offset: usize) -> Self {
+        match self {
+            Error::BufferTooSmall { actual, expected } => Error::BufferTooSmall { actual: actual + offset, expected: expected + offset },
+            e => e,
+        }
+    }
+}
+
 /// The result type for unpacked results.
 pub type Result<T> = core::result::Result<T, Error>;
 
@@ -127,39 +154,70 @@ impl<T0> Unpack for (T0,) where T0: Unpack {
     fn unpack<B>(buffer: &[u8]) -> Result<Self> where B: ByteOrder {
         Ok((T0::unpack::<B>(buffer)?,))
     }
+
+    fn unpack_safely<B>(buffer: &[u8]) -> Result<Self> where B: ByteOrder {
+        Ok((T0::unpack_safely::<B>(buffer)?,))
+    }
 }
 
 impl<T0, T1> Unpack for (T0, T1) where T0: Unpack, T1: Unpack {
     fn unpack<B>(buffer: &[u8]) -> Result<Self> where B: ByteOrder {
         let x0 = T0::unpack::<B>(buffer)?;
-        let buffer = &buffer[T0::SIZE..];
+        let buffer = &buffer[x0.encoded_size()..];
         let x1 = T1::unpack::<B>(buffer)?;
         Ok((x0, x1))
     }
+
+    fn unpack_safely<B>(buffer: &[u8]) -> Result<Self> where B: ByteOrder {
+        let x0 = T0::unpack_safely::<B>(buffer)?;
+        let offset = x0.encoded_size();
+        let x1 = T1::unpack_safely::<B>(&buffer[offset..]).map_err(|e| e.at_offset(offset))?;
+        Ok((x0, x1))
+    }
 }
 
 impl<T0, T1, T2> Unpack for (T0, T1, T2) where T0: Unpack, T1: Unpack, T2: Unpack {
     fn unpack<B>(buffer: &[u8]) -> Result<Self> where B: ByteOrder {
         let x0 = T0::unpack::<B>(buffer)?;
-        let buffer = &buffer[T0::SIZE..];
+        let buffer = &buffer[x0.encoded_size()..];
         let x1 = T1::unpack::<B>(buffer)?;
-        let buffer = &buffer[T1::SIZE..];
+        let buffer = &buffer[x1.encoded_size()..];
         let x2 = T2::unpack::<B>(buffer)?;
         Ok((x0, x1, x2))
     }
+
+    fn unpack_safely<B>(buffer: &[u8]) -> Result<Self> where B: ByteOrder {
+        let x0 = T0::unpack_safely::<B>(buffer)?;
+        let offset = x0.encoded_size();
+        let x1 = T1::unpack_safely::<B>(&buffer[offset..]).map_err(|e| e.at_offset(offset))?;
+        let offset = offset + x1.encoded_size();
+        let x2 = T2::unpack_safely::<B>(&buffer[offset..]).map_err(|e| e.at_offset(offset))?;
+        Ok((x0, x1, x2))
+    }
 }
 
 impl<T0, T1, T2, T3> Unpack for (T0, T1, T2, T3) where T0: Unpack, T1: Unpack, T2: Unpack, T3: Unpack {
     fn unpack<B>(buffer: &[u8]) -> Result<Self> where B: ByteOrder {
         let x0 = T0::unpack::<B>(buffer)?;
-        let buffer = &buffer[T0::SIZE..];
+        let buffer = &buffer[x0.encoded_size()..];
         let x1 = T1::unpack::<B>(buffer)?;
-        let buffer = &buffer[T1::SIZE..];
+        let buffer = &buffer[x1.encoded_size()..];
         let x2 = T2::unpack::<B>(buffer)?;
-        let buffer = &buffer[T2::SIZE..];
+        let buffer = &buffer[x2.encoded_size()..];
         let x3 = T3::unpack::<B>(buffer)?;
         Ok((x0, x1, x2, x3))
     }
+
+    fn unpack_safely<B>(buffer: &[u8]) -> Result<Self> where B: ByteOrder {
+        let x0 = T0::unpack_safely::<B>(buffer)?;
+        let offset = x0.encoded_size();
+        let x1 = T1::unpack_safely::<B>(&buffer[offset..]).map_err(|e| e.at_offset(offset))?;
+        let offset = offset + x1.encoded_size();
+        let x2 = T2::unpack_safely::<B>(&buffer[offset..]).map_err(|e| e.at_offset(offset))?;
+        let offset = offset + x2.encoded_size();
+        let x3 = T3::unpack_safely::<B>(&buffer[offset..]).map_err(|e| e.at_offset(offset))?;
+        Ok((x0, x1, x2, x3))
+    }
 }
 
 impl<T> Unpack for Option<T> where T: Unpack {
@@ -171,70 +229,93 @@ impl<T> Unpack for Option<T> where T: Unpack {
             Ok(None)
         }
     }
+
+    fn unpack_safely<B>(buffer: &[u8]) -> Result<Self> where B: ByteOrder {
+        let is_some = bool::unpack_safely::<B>(buffer)?;
+        if is_some {
+            Ok(Some(T::unpack_safely::<B>(&buffer[bool::SIZE..]).map_err(|e| e.at_offset(bool::SIZE))?))
+        } else if buffer.len() < Self::SIZE {
+            // The unused bytes of `None` are still part of the encoding
+            Err(Error::BufferTooSmall { actual: buffer.len(), expected: Self::SIZE })
+        } else {
+            Ok(None)
+        }
+    }
 }
 
 impl<T, const N: usize> Unpack for [T; N] where T: Unpack {
-    fn unpack<B>(mut buffer: &[u8]) -> Result<Self> where B: ByteOrder {
-        // Unfortunately, Rust doesn't provide a great way to initialize
-        // arrays dynamically without the overhead of double initialization
-        // (which additionally would require a `T: Default + Copy` bound
-        // or similar). Therefore we'll use uninitialized memory as per
-        // this trick: https://doc.rust-lang.org/nomicon/unchecked-uninit.html
-
-        if N == 0 {
-            // SAFETY: Empty arrays are always inhabited, without further invariants
-            return Ok(unsafe { mem::zeroed() });
-        }
+    fn unpack<B>(buffer: &[u8]) -> Result<Self> where B: ByteOrder {
+        unpack_array(buffer, T::unpack::<B>)
+    }
+
+    fn unpack_safely<B>(buffer: &[u8]) -> Result<Self> where B: ByteOrder {
+        unpack_array(buffer, T::unpack_safely::<B>)
+    }
+}
 
-        // SAFETY: The type we are claimining to initialize is an array of `MaybeUninit`s
-        // which do not require initialization. For more details on this pattern
-        // see https://doc.rust-lang.org/nomicon/unchecked-uninit.html
-        let mut result: [MaybeUninit<T>; N] = unsafe {
-            MaybeUninit::uninit().assume_init()
-        };
+/// Decodes an array by decoding its elements back to back.
+fn unpack_array<T, const N: usize>(buffer: &[u8], unpack: impl Fn(&[u8]) -> Result<T>) -> Result<[T; N]> where T: Size {
+    // Unfortunately, Rust doesn't provide a great way to initialize
+    // arrays dynamically without the overhead of double initialization
+    // (which additionally would require a `T: Default + Copy` bound
+    // or similar). Therefore we'll use uninitialized memory as per
+    // this trick: https://doc.rust-lang.org/nomicon/unchecked-uninit.html
+
+    if N == 0 {
+        // SAFETY: Empty arrays are always inhabited, without further invariants
+        return Ok(unsafe { mem::zeroed() });
+    }
 
-        // NOTE: If `T::unpack` throws an error in the loop, we need to make
-        // sure that the already parsed `T`s will be dropped. Therefore we'll
-        // use a `Guard` that drops all parsed `T`s if the function exits
-        // before the array is fully parsed (and `mem::forget` is called).
+    // SAFETY: The type we are claimining to initialize is an array of `MaybeUninit`s
+    // which do not require initialization. For more details on this pattern
+    // see https://doc.rust-lang.org/nomicon/unchecked-uninit.html
+    let mut result: [MaybeUninit<T>; N] = unsafe {
+        MaybeUninit::uninit().assume_init()
+    };
 
-        // Inspired by https://github.com/bincode-org/bincode/blob/224e41274b/src/de/impl_core.rs#L31
-        // which is MIT-licensed (Copyright (c) 2014 Ty Overby).
+    // NOTE: If `unpack` returns an error in the loop, we need to make
+    // sure that the already parsed `T`s will be dropped. Therefore we'll
+    // use a `Guard` that drops all parsed `T`s if the function exits
+    // before the array is fully parsed (and `mem::forget` is called).
 
-        struct Guard<'a, T, const N: usize> {
-            result: &'a mut [MaybeUninit<T>; N],
-            initialized_count: usize,
-        }
+    // Inspired by https://github.com/bincode-org/bincode/blob/224e41274b/src/de/impl_core.rs#L31
+    // which is MIT-licensed (Copyright (c) 2014 Ty Overby).
+
+    struct Guard<'a, T, const N: usize> {
+        result: &'a mut [MaybeUninit<T>; N],
+        initialized_count: usize,
+    }
 
-        impl<'a, T, const N: usize> Drop for Guard<'a, T, N> {
-            fn drop(&mut self) {
-                // SAFETY: The first `initialized_count` values are guaranteed to be initialized,
-                // see the for-loop. The cast to `*mut [T]` is safe since `MaybeUninit<T>` and `T`
-                // have the same memory layout and we have mutable/exclusive access already.
-                unsafe {
-                    let initialized: &mut [MaybeUninit<T>] = self.result.get_unchecked_mut(..self.initialized_count);
-                    core::ptr::drop_in_place(initialized as *mut [MaybeUninit<T>] as *mut [T]);
-                }
+    impl<'a, T, const N: usize> Drop for Guard<'a, T, N> {
+        fn drop(&mut self) {
+            // SAFETY: The first `initialized_count` values are guaranteed to be initialized,
+            // see the for-loop. The cast to `*mut [T]` is safe since `MaybeUninit<T>` and `T`
+            // have the same memory layout and we have mutable/exclusive access already.
+            unsafe {
+                let initialized: &mut [MaybeUninit<T>] = self.result.get_unchecked_mut(..self.initialized_count);
+                core::ptr::drop_in_place(initialized as *mut [MaybeUninit<T>] as *mut [T]);
             }
         }
+    }
 
-        let mut guard = Guard { result: &mut result, initialized_count: 0 };
-        
-        for i in 0..N {
-            guard.result[i] = MaybeUninit::new(T::unpack::<B>(buffer)?);
-            guard.initialized_count += 1;
-            buffer = &buffer[T::SIZE..];
-        }
+    let mut guard = Guard { result: &mut result, initialized_count: 0 };
+    
+    let mut offset = 0;
+    for i in 0..N {
+        let value = unpack(&buffer[offset..]).map_err(|e| e.at_offset(offset))?;
+        offset += value.encoded_size();
+        guard.result[i] = MaybeUninit::new(value);
+        guard.initialized_count += 1;
+    }
 
-        // The array is fully initialized, so skip dropping the parsed `T`s.
-        mem::forget(guard);
+    // The array is fully initialized, so skip dropping the parsed `T`s.
+    mem::forget(guard);
 
-        // SAFETY: The array is initialized, `MaybeUninit<T>` and `T` have the same layout and
-        // `MaybeUninit` does not drop, so transmuting `[MaybeUninit<T>; N]` to `[T; N]` is safe.
-        // We cannot use `mem::transmute` since the compiler doesn't accept it for generic lengths,
-        // see https://github.com/rust-lang/rust/issues/61956.
-        Ok(unsafe {
-            (&result as *const _ as *const [T; N]).read()
-        })
-    }
+    // SAFETY: The array is initialized, `MaybeUninit<T>` and `T` have the same layout and
+    // `MaybeUninit` does not drop, so transmuting `[MaybeUninit<T>; N]` to `[T; N]` is safe.
+    // We cannot use `mem::transmute` since the compiler doesn't accept it for generic lengths,
+    // see https://github.com/rust-lang/rust/issues/61956.
+    Ok(unsafe {
+        (&result as *const _ as *const [T; N]).read()
+    })
 }
@@ -0,0 +1,262 @@
+//! Variable-length integers.
+//!
+//! [`Varint`] encodes unsigned integers as LEB128, i.e. in groups of 7 bits
+//! starting with the least significant group, where the most significant bit
+//! of each byte signals that another byte follows. [`ZigZag`] additionally
+//! maps signed integers to unsigned ones (`0, -1, 1, -2, ...` to
+//! `0, 1, 2, 3, ...`), so that small magnitudes result in short encodings.
+//! Both are independent of the byte order.
+//!
+//! Since the encoded size depends on the value, [`Size::SIZE`] is the
+//! maximum encoded size (e.g. 10 bytes for `u64`), while
+//! [`Size::encoded_size`] is the actual size. Tuples, arrays and derived
+//! structs pack varints back to back with their actual size, so such values
+//! only have fixed field offsets if they contain no varints. Standalone
+//! values can also be packed with [`Varint::encode`] and [`Varint::decode`],
+//! which return the encoded size:
+//!
+//! ```ignore
+//! let mut buffer = [0u8; 20];
+//! let mut len = Varint(300u64).encode(&mut buffer);
+//! len += ZigZag(-2i32).encode(&mut buffer[len..]);
+//! // => buffer[..len] == [0xac, 0x02, 0x03]
+//! ```
+//!
+//! Decoding rejects encodings that are longer than necessary with
+//! [`Error::VarintTooLong`] and values that do not fit into the type with
+//! [`Error::VarintOverflow`].
+
+use byteorder::ByteOrder;
+
+use crate::{Pack, Schema, Size, Unpack, schema::Type, unpack::{Error, Result}};
+
+/// An unsigned integer encoded as LEB128.
+#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Clone, Copy, Default)]
+pub struct Varint<T>(pub T);
+
+/// A signed integer encoded as zigzag-mapped LEB128.
+#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Clone, Copy, Default)]
+pub struct ZigZag<T>(pub T);
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// Unsigned integers that can be encoded as [`Varint`].
+pub trait Unsigned: sealed::Sealed + Copy {
+    /// The number of bits of the type.
+    const BITS: u32;
+
+    #[doc(hidden)]
+    fn into_u64(self) -> u64;
+
+    #[doc(hidden)]
+    fn from_u64(value: u64) -> Self;
+}
+
+/// Signed integers that can be encoded as [`ZigZag`].
+pub trait Signed: sealed::Sealed + Copy {
+    /// The number of bits of the type.
+    const BITS: u32;
+
+    #[doc(hidden)]
+    fn zigzag(self) -> u64;
+
+    #[doc(hidden)]
+    fn unzigzag(value: u64) -> Self;
+}
+
+macro_rules! impl_unsigned {
+    ($($t:ty),*) => {
+        $(
+            impl sealed::Sealed for $t {}
+
+            impl Unsigned for $t {
+                const BITS: u32 = <$t>::BITS;
+
+                fn into_u64(self) -> u64 {
+                    self as u64
+                }
+
+                fn from_u64(value: u64) -> Self {
+                    value as $t
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_signed {
+    ($($t:ty),*) => {
+        $(
+            impl sealed::Sealed for $t {}
+
+            impl Signed for $t {
+                const BITS: u32 = <$t>::BITS;
+
+                fn zigzag(self) -> u64 {
+                    ((self << 1) ^ (self >> (<$t>::BITS - 1))) as u64 & (u64::MAX >> (64 - <$t>::BITS))
+                }
+
+                fn unzigzag(value: u64) -> Self {
+                    ((value >> 1) as $t) ^ -((value & 1) as $t)
+                }
+            }
+        )*
+    };
+}
+
+impl_unsigned!(u8, u16, u32, u64);
+impl_signed!(i8, i16, i32, i64);
+
+/// The maximum encoded size of a varint with the given number of bits.
+const fn max_size(bits: u32) -> usize {
+    bits.div_ceil(7) as usize
+}
+
+/// The encoded size of a value.
+pub(crate) fn encoded_size(value: u64) -> usize {
+    max_size(64 - value.leading_zeros()).max(1)
+}
+
+/// Encodes a value and returns the encoded size. Panics if the buffer is too small.
+pub(crate) fn encode(mut value: u64, buffer: &mut [u8]) -> usize {
+    let mut i = 0;
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            buffer[i] = byte;
+            return i + 1;
+        }
+        buffer[i] = byte | 0x80;
+        i += 1;
+    }
+}
+
+/// Decodes a value with the given number of bits and returns it along with
+/// the encoded size.
+pub(crate) fn decode(buffer: &[u8], bits: u32) -> Result<(u64, usize)> {
+    let mut value = 0;
+    for i in 0..max_size(bits) {
+        let byte = *buffer.get(i).ok_or(Error::BufferTooSmall { actual: buffer.len(), expected: i + 1 })?;
+        let shift = 7 * i as u32;
+        let payload = (byte & 0x7f) as u64;
+        if bits - shift < 7 && payload >> (bits - shift) != 0 {
+            return Err(Error::VarintOverflow);
+        }
+        value |= payload << shift;
+        if byte & 0x80 == 0 {
+            // A trailing zero group could have been omitted
+            if byte == 0 && i > 0 {
+                return Err(Error::VarintTooLong);
+            }
+            return Ok((value, i + 1));
+        }
+    }
+    Err(Error::VarintTooLong)
+}
+
+/// Maps a zigzag-encoded value back to a signed one.
+#[cfg(feature = "serde")]
+pub(crate) fn unzigzag(value: u64) -> i64 {
+    i64::unzigzag(value)
+}
+
+impl<T> Varint<T> where T: Unsigned {
+    /// Encodes the value to the start of the buffer and returns the encoded
+    /// size. Panics if the buffer is too small.
+    pub fn encode(&self, buffer: &mut [u8]) -> usize {
+        encode(self.0.into_u64(), buffer)
+    }
+
+    /// Decodes a value from the start of the buffer and returns it along with
+    /// the encoded size.
+    pub fn decode(buffer: &[u8]) -> Result<(Self, usize)> {
+        let (value, size) = decode(buffer, T::BITS)?;
+        Ok((Self(T::from_u64(value)), size))
+    }
+}
+
+impl<T> ZigZag<T> where T: Signed {
+    /// Encodes the value to the start of the buffer and returns the encoded
+    /// size. Panics if the buffer is too small.
+    pub fn encode(&self, buffer: &mut [u8]) -> usize {
+        encode(self.0.zigzag(), buffer)
+    }
+
+    /// Decodes a value from the start of the buffer and returns it along with
+    /// the encoded size.
+    pub fn decode(buffer: &[u8]) -> Result<(Self, usize)> {
+        let (value, size) = decode(buffer, T::BITS)?;
+        Ok((Self(T::unzigzag(value)), size))
+    }
+}
+
+impl<T> Size for Varint<T> where T: Unsigned {
+    const SIZE: usize = max_size(T::BITS);
+    const FIXED_SIZE: bool = false;
+
+    fn encoded_size(&self) -> usize {
+        encoded_size(self.0.into_u64())
+    }
+}
+
+impl<T> Size for ZigZag<T> where T: Signed {
+    const SIZE: usize = max_size(T::BITS);
+    const FIXED_SIZE: bool = false;
+
+    fn encoded_size(&self) -> usize {
+        encoded_size(self.0.zigzag())
+    }
+}
+
+impl<T> Schema for Varint<T> where T: Unsigned + Schema {
+    const SCHEMA: Type = match T::SCHEMA {
+        Type::Primitive(p) => Type::Varint(p),
+        _ => unreachable!(),
+    };
+}
+
+impl<T> Schema for ZigZag<T> where T: Signed + Schema {
+    const SCHEMA: Type = match T::SCHEMA {
+        Type::Primitive(p) => Type::Varint(p),
+        _ => unreachable!(),
+    };
+}
+
+impl<T> Pack for Varint<T> where T: Unsigned {
+    fn pack<B>(&self, buffer: &mut [u8]) where B: ByteOrder {
+        self.encode(buffer);
+    }
+}
+
+impl<T> Pack for ZigZag<T> where T: Signed {
+    fn pack<B>(&self, buffer: &mut [u8]) where B: ByteOrder {
+        self.encode(buffer);
+    }
+}
+
+impl<T> Unpack for Varint<T> where T: Unsigned {
+    fn unpack<B>(buffer: &[u8]) -> Result<Self> where B: ByteOrder {
+        Ok(Self::decode(buffer)?.0)
+    }
+
+    /// Decoding checks the buffer size byte by byte, so a buffer that is
+    /// shorter than [`Size::SIZE`] but holds the complete encoding is
+    /// accepted.
+    fn unpack_safely<B>(buffer: &[u8]) -> Result<Self> where B: ByteOrder {
+        Self::unpack::<B>(buffer)
+    }
+}
+
+impl<T> Unpack for ZigZag<T> where T: Signed {
+    fn unpack<B>(buffer: &[u8]) -> Result<Self> where B: ByteOrder {
+        Ok(Self::decode(buffer)?.0)
+    }
+
+    /// See [`Varint::unpack_safely`].
+    fn unpack_safely<B>(buffer: &[u8]) -> Result<Self> where B: ByteOrder {
+        Self::unpack::<B>(buffer)
+    }
+}
@@ -0,0 +1,83 @@
+use byteorder::{BigEndian, LittleEndian};
+use lightpack::{Pack, Schema, Size, Unpack, bcd::{Bcd, DateTime}, pack, schema::{Primitive, Type}, unpack::Error};
+
+#[test]
+fn encoding() {
+    #[derive(Size, Pack, Unpack, Debug, PartialEq)]
+    struct Meter {
+        digit: Bcd<u8>,
+        reading: Bcd<u32>,
+        tariff: Bcd<u16>,
+    }
+
+    assert_eq!(Meter::SIZE, 7);
+
+    let meter = Meter {
+        digit: Bcd::new(42).unwrap(),
+        reading: Bcd::new(12_345_678).unwrap(),
+        tariff: Bcd::new(905).unwrap(),
+    };
+    let mut buffer = [0u8; Meter::SIZE];
+    meter.pack::<BigEndian>(&mut buffer);
+    assert_eq!(buffer, [0x42, 0x12, 0x34, 0x56, 0x78, 0x09, 0x05]);
+    assert_eq!(Meter::unpack::<BigEndian>(&buffer), Ok(meter));
+
+    Bcd::new(1234u16).unwrap().pack::<LittleEndian>(&mut buffer);
+    assert_eq!(buffer[..2], [0x34, 0x12]);
+}
+
+#[test]
+fn conversions() {
+    assert_eq!(Bcd::new(99u8).map(Bcd::get), Some(99));
+    assert_eq!(Bcd::new(100u8), None);
+    assert_eq!(Bcd::new(10_000u16), None);
+    assert_eq!(Bcd::new(99_999_999u32).unwrap().to_bits(), 0x9999_9999);
+    assert_eq!(Bcd::new(100_000_000u32), None);
+    assert_eq!(Bcd::from_bits(0x0950u16).map(Bcd::get), Some(950));
+    assert_eq!(Bcd::from_bits(0x1au8), None);
+    assert_eq!(Bcd::new(7u8).unwrap().to_string(), "7");
+}
+
+#[test]
+fn invalid_nibbles() {
+    assert_eq!(Bcd::<u8>::unpack::<BigEndian>(&[0xa0]), Err(Error::InvalidBcd(0xa0)));
+    assert_eq!(Bcd::<u16>::unpack::<BigEndian>(&[0x12, 0x3f]), Err(Error::InvalidBcd(0x123f)));
+    assert_eq!(DateTime::unpack::<BigEndian>(&[0, 0x5a, 0, 1, 1, 1, 0]), Err(Error::InvalidBcd(0x5a)));
+}
+
+#[test]
+fn date_time() {
+    let date_time = DateTime { seconds: 59, minutes: 37, hours: 13, weekday: 3, day: 31, month: 12, year: 24 };
+    let mut buffer = [0u8; DateTime::SIZE];
+    date_time.pack::<BigEndian>(&mut buffer);
+    assert_eq!(buffer, [0x59, 0x37, 0x13, 0x03, 0x31, 0x12, 0x24]);
+    assert_eq!(DateTime::unpack::<LittleEndian>(&buffer), Ok(date_time));
+
+    // Oscillator and century flags
+    let flagged = [0xd9, 0x37, 0x13, 0x03, 0x31, 0x92, 0x24];
+    assert_eq!(DateTime::unpack::<BigEndian>(&flagged), Ok(date_time));
+
+    // 12-hour mode
+    let hours = |register| DateTime::unpack::<BigEndian>(&[0, 0, register, 1, 1, 1, 0]).unwrap().hours;
+    assert_eq!(hours(0x40 | 0x12), 0);
+    assert_eq!(hours(0x40 | 0x11), 11);
+    assert_eq!(hours(0x60 | 0x12), 12);
+    assert_eq!(hours(0x60 | 0x01), 13);
+
+    let invalid = DateTime { year: 124, ..date_time };
+    assert_eq!(invalid.try_pack::<BigEndian>(&mut buffer), Err(pack::Error::OutOfRange));
+    assert_eq!(date_time.try_pack::<BigEndian>(&mut buffer[..6]), Err(pack::Error::BufferTooSmall { actual: 6, expected: 7 }));
+}
+
+#[test]
+fn schema() {
+    assert_eq!(Bcd::<u16>::SCHEMA, Type::Bcd(Primitive::U16));
+    assert_eq!(Bcd::<u16>::SCHEMA.size(), 2);
+    assert_ne!(Bcd::<u16>::SCHEMA_HASH, u16::SCHEMA_HASH);
+
+    let Type::Struct { name, fields } = DateTime::SCHEMA else { panic!("DateTime is a struct") };
+    assert_eq!(name, "DateTime");
+    assert_eq!(fields.iter().map(|f| f.name).collect::<Vec<_>>(), ["seconds", "minutes", "hours", "weekday", "day", "month", "year"]);
+    assert!(fields.iter().all(|f| *f.ty == Type::Bcd(Primitive::U8)));
+    assert_eq!(DateTime::SCHEMA.size(), DateTime::SIZE);
+}
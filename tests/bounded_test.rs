@@ -0,0 +1,116 @@
+use std::rc::Rc;
+
+use byteorder::{BigEndian, LittleEndian};
+use lightpack::{Pack, Schema, Size, Unpack, bounded::BoundedVec, pack, schema::{Primitive, Type}, unpack::Error};
+
+#[test]
+fn encoding() {
+    #[derive(Size, Pack, Unpack, Debug, PartialEq)]
+    struct Frame {
+        id: u8,
+        readings: BoundedVec<u16, 4, u8>,
+    }
+
+    assert_eq!(Frame::SIZE, 1 + 1 + 4 * 2);
+    assert_eq!(BoundedVec::<u32, 3>::SIZE, 2 + 3 * 4);
+
+    let frame = Frame { id: 9, readings: BoundedVec::from_slice(&[0x0102, 0x0304]).unwrap() };
+    let mut buffer = [0xffu8; Frame::SIZE];
+    frame.pack::<BigEndian>(&mut buffer);
+    assert_eq!(buffer, [9, 2, 1, 2, 3, 4, 0, 0, 0, 0]);
+    assert_eq!(Frame::unpack::<BigEndian>(&buffer), Ok(frame));
+
+    let empty = BoundedVec::<u16, 2>::new();
+    let mut buffer = [0xffu8; 6];
+    empty.pack::<LittleEndian>(&mut buffer);
+    assert_eq!(buffer, [0; 6]);
+    assert_eq!(BoundedVec::<u16, 2>::unpack::<LittleEndian>(&buffer), Ok(empty));
+}
+
+#[test]
+fn length_check() {
+    assert_eq!(
+        BoundedVec::<u8, 2, u8>::unpack::<BigEndian>(&[3, 1, 2]),
+        Err(Error::LengthExceedsCapacity { len: 3, capacity: 2 }),
+    );
+    assert_eq!(
+        BoundedVec::<char, 2, u8>::unpack::<BigEndian>(&[2, 0, 0, 0, 0x61, 0, 0x11, 0, 0, 0, 0, 0, 0]),
+        Err(Error::InvalidChar(0x110000)),
+    );
+}
+
+#[test]
+fn operations() {
+    let mut vec = BoundedVec::<i8, 2>::new();
+    assert!(vec.is_empty());
+    assert_eq!(vec.capacity(), 2);
+    assert_eq!(vec.push(1), Ok(()));
+    assert_eq!(vec.push(2), Ok(()));
+    assert!(vec.is_full());
+    assert_eq!(vec.push(3), Err(3));
+    vec[0] = -1;
+    assert_eq!(vec.as_slice(), [-1, 2]);
+    assert_eq!(vec.iter().sum::<i8>(), 1);
+    assert_eq!(vec.pop(), Some(2));
+    assert_eq!(vec.clone(), BoundedVec::from_slice(&[-1]).unwrap());
+    vec.clear();
+    assert_eq!(vec.pop(), None);
+    assert_eq!(format!("{:?}", BoundedVec::<u8, 3>::from_slice(&[1, 2]).unwrap()), "[1, 2]");
+    assert!(BoundedVec::<u8, 1>::from_slice(&[1, 2]).is_none());
+}
+
+#[test]
+fn drops_elements() {
+    let rc = Rc::new(());
+    let mut vec = BoundedVec::<Rc<()>, 3>::new();
+    vec.push(rc.clone()).unwrap();
+    vec.push(rc.clone()).unwrap();
+    let cloned = vec.clone();
+    assert_eq!(Rc::strong_count(&rc), 5);
+    drop(vec.pop());
+    assert_eq!(Rc::strong_count(&rc), 4);
+    drop(vec);
+    drop(cloned);
+    assert_eq!(Rc::strong_count(&rc), 1);
+}
+
+#[test]
+fn try_pack() {
+    #[derive(Size, Pack, Debug)]
+    struct Level(#[lightpack(as = u8)] u32);
+
+    let mut levels = BoundedVec::<Level, 2, u8>::new();
+    levels.push(Level(1)).unwrap();
+    let mut buffer = [0u8; 3];
+    assert_eq!(levels.try_pack::<BigEndian>(&mut buffer), Ok(()));
+    assert_eq!(buffer, [1, 1, 0]);
+    assert_eq!(levels.try_pack::<BigEndian>(&mut buffer[..2]), Err(pack::Error::BufferTooSmall { actual: 2, expected: 3 }));
+
+    levels.push(Level(256)).unwrap();
+    assert_eq!(levels.try_pack::<BigEndian>(&mut buffer), Err(pack::Error::OutOfRange));
+}
+
+#[test]
+fn zeroes_slots() {
+    type Options = BoundedVec<Option<u32>, 2>;
+
+    let options = Options::from_slice(&[None]).unwrap();
+    let mut buffer = [0xffu8; Options::SIZE];
+    options.pack::<BigEndian>(&mut buffer);
+    assert_eq!(buffer, [0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+
+    let options = Options::from_slice(&[Some(7), None]).unwrap();
+    buffer.fill(0xff);
+    assert_eq!(options.try_pack::<BigEndian>(&mut buffer), Ok(()));
+    assert_eq!(buffer, [0, 2, 1, 0, 0, 0, 7, 0, 0, 0, 0, 0]);
+    assert_eq!(Options::unpack::<BigEndian>(&buffer), Ok(options));
+}
+
+#[test]
+fn schema() {
+    assert_eq!(BoundedVec::<u16, 4, u8>::SCHEMA, Type::Tuple(&[
+        Type::Primitive(Primitive::U8),
+        Type::Array { element: &Type::Primitive(Primitive::U16), len: 4 },
+    ]));
+    assert_eq!(BoundedVec::<u32, 3>::SCHEMA.size(), BoundedVec::<u32, 3>::SIZE);
+}
@@ -0,0 +1,102 @@
+#![cfg(feature = "bytes")]
+
+use byteorder::{BigEndian, LittleEndian};
+use bytes::{Buf, BufMut, BytesMut};
+use lightpack::{Pack, Size, Unpack, buf::{BufExt, BufMutExt, SCRATCH_SIZE}, pack, unpack, varint::Varint};
+
+#[derive(Size, Pack, Unpack, Debug, PartialEq, Clone, Copy)]
+struct Message {
+    id: u8,
+    value: u32,
+}
+
+const MESSAGE: Message = Message { id: 1, value: 0x02030405 };
+
+#[test]
+fn roundtrip() {
+    let mut buffer = BytesMut::new();
+    buffer.put_packed::<BigEndian, _>(&MESSAGE).unwrap();
+    buffer.put_packed::<LittleEndian, _>(&MESSAGE).unwrap();
+    assert_eq!(buffer[..], [1, 2, 3, 4, 5, 1, 5, 4, 3, 2]);
+
+    assert_eq!(buffer.get_unpacked::<BigEndian, Message>(), Ok(MESSAGE));
+    assert_eq!(buffer.get_unpacked::<LittleEndian, Message>(), Ok(MESSAGE));
+    assert!(buffer.is_empty());
+}
+
+#[test]
+fn non_contiguous_chunks() {
+    let first = [1u8, 2, 3];
+    let second = [4u8, 5, 6];
+    let mut chain = first.chain(&second[..]);
+    assert_eq!(chain.get_unpacked::<BigEndian, Message>(), Ok(MESSAGE));
+    assert_eq!(chain.remaining(), 1);
+
+    let mut first = [0u8; 2];
+    let mut second = [0u8; 4];
+    let mut chain = (&mut first[..]).chain_mut(&mut second[..]);
+    chain.put_packed::<BigEndian, _>(&MESSAGE).unwrap();
+    assert_eq!((first, second), ([1, 2], [3, 4, 5, 0]));
+
+    // Values larger than the stack scratch buffer
+    let large = [7u8; SCRATCH_SIZE + 1];
+    let mut buffer = BytesMut::new();
+    buffer.put_packed::<BigEndian, _>(&large).unwrap();
+    let (first, second) = buffer.split_at(10);
+    assert_eq!(first.chain(second).get_unpacked::<BigEndian, [u8; SCRATCH_SIZE + 1]>(), Ok(large));
+}
+
+#[test]
+fn variable_size() {
+    let mut buffer = BytesMut::new();
+    buffer.put_packed::<BigEndian, _>(&Varint(300u64)).unwrap();
+    buffer.put_packed::<BigEndian, _>(&(Varint(1u32), 2u8)).unwrap();
+    assert_eq!(buffer[..], [0xac, 0x02, 1, 2]);
+
+    assert_eq!(buffer.get_unpacked::<BigEndian, Varint<u64>>(), Ok(Varint(300)));
+    assert_eq!(buffer.get_unpacked::<BigEndian, (Varint<u32>, u8)>(), Ok((Varint(1), 2)));
+    assert!(buffer.is_empty());
+
+    let first = [0x80u8];
+    let second = [0x80u8, 0x01, 9, 9];
+    let mut chain = first.chain(&second[..]);
+    assert_eq!(chain.get_unpacked::<BigEndian, Varint<u32>>(), Ok(Varint(1 << 14)));
+    assert_eq!(chain.remaining(), 2);
+
+    // Spanning values wait for `T::SIZE` bytes, even if they could be complete
+    let mut chain = first.chain(&second[..2]);
+    assert_eq!(chain.get_unpacked::<BigEndian, Varint<u32>>(), Err(unpack::Error::BufferTooSmall { actual: 3, expected: 5 }));
+    assert_eq!(chain.remaining(), 3);
+
+    let mut incomplete = &[0x80u8][..];
+    assert_eq!(incomplete.get_unpacked::<BigEndian, Varint<u32>>(), Err(unpack::Error::BufferTooSmall { actual: 1, expected: 2 }));
+    assert_eq!(incomplete.remaining(), 1);
+}
+
+#[test]
+fn errors() {
+    let mut short = &[1u8, 2, 3][..];
+    assert_eq!(short.get_unpacked::<BigEndian, Message>(), Err(unpack::Error::BufferTooSmall { actual: 3, expected: 5 }));
+    assert_eq!(short.remaining(), 3);
+
+    // A short value that spans multiple chunks keeps its bytes for a retry
+    let mut chain = (&[1u8, 2][..]).chain(&[3u8][..]);
+    assert_eq!(chain.get_unpacked::<BigEndian, u64>(), Err(unpack::Error::BufferTooSmall { actual: 3, expected: 8 }));
+    assert_eq!(chain.remaining(), 3);
+    let mut chain = chain.chain(&[4u8, 5, 6, 7, 8][..]);
+    assert_eq!(chain.get_unpacked::<BigEndian, u64>(), Ok(0x0102030405060708));
+
+    let mut invalid = &[0x00u8, 0x11, 0x00, 0x00, 9][..];
+    assert_eq!(invalid.get_unpacked::<BigEndian, char>(), Err(unpack::Error::InvalidChar(0x110000)));
+    assert_eq!(invalid, [9]);
+
+    let mut buffer = [0u8; 4];
+    let mut full = &mut buffer[..];
+    assert_eq!(full.put_packed::<BigEndian, _>(&MESSAGE), Err(pack::Error::BufferTooSmall { actual: 4, expected: 5 }));
+
+    #[derive(Size, Pack)]
+    struct Level(#[lightpack(as = u8)] u32);
+    let mut buffer = BytesMut::new();
+    assert_eq!(buffer.put_packed::<BigEndian, _>(&Level(256)), Err(pack::Error::OutOfRange));
+    assert!(buffer.is_empty());
+}
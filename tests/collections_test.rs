@@ -0,0 +1,102 @@
+use byteorder::{BigEndian, LittleEndian};
+use lightpack::{Pack, Schema, Size, Unpack, bounded::BoundedVec, collections::{Compact, Padded}, pack, schema::{Primitive, Type}, unpack::Error};
+
+#[test]
+fn padded_and_compact() {
+    let vec = BoundedVec::<u16, 3>::from_slice(&[0x0102]).unwrap();
+    assert_eq!(Padded::<BoundedVec<u16, 3>, u8>::SIZE, 1 + 3 * 2);
+    assert_eq!(Compact::<BoundedVec<u16, 3>, u8>::SIZE, 1 + 3 * 2);
+    assert_eq!(Padded::<BoundedVec<u16, 3>, u8>::SCHEMA, Type::Tuple(&[
+        Type::Primitive(Primitive::U8),
+        Type::Array { element: &Type::Primitive(Primitive::U16), len: 3 },
+    ]));
+
+    let mut buffer = [0xffu8; 7];
+    Padded::<_, u8>::new(vec.clone()).pack::<BigEndian>(&mut buffer);
+    assert_eq!(buffer, [1, 1, 2, 0, 0, 0, 0]);
+    assert_eq!(Padded::<BoundedVec<u16, 3>, u8>::unpack::<BigEndian>(&buffer), Ok(Padded::new(vec.clone())));
+
+    let compact = Compact::<_, u8>::new(vec.clone());
+    let mut buffer = [0xffu8; 7];
+    compact.pack::<LittleEndian>(&mut buffer);
+    assert_eq!(compact.encoded_size(), 3);
+    assert_eq!(buffer, [1, 2, 1, 0xff, 0xff, 0xff, 0xff]);
+    assert_eq!(Compact::<BoundedVec<u16, 3>, u8>::unpack::<LittleEndian>(&buffer[..3]).unwrap().0, vec);
+    assert_eq!(Compact::<BoundedVec<u16, 3>, u8>::unpack_safely::<LittleEndian>(&buffer[..3]).unwrap().0, vec);
+    assert_eq!(
+        Compact::<BoundedVec<u16, 3>, u8>::unpack_safely::<LittleEndian>(&buffer[..2]),
+        Err(Error::BufferTooSmall { actual: 2, expected: 3 }),
+    );
+
+    assert_eq!(
+        Compact::<BoundedVec<u16, 3>, u8>::unpack::<BigEndian>(&[4]),
+        Err(Error::LengthExceedsCapacity { len: 4, capacity: 3 }),
+    );
+    assert_eq!(compact.try_pack::<BigEndian>(&mut buffer[..2]), Err(pack::Error::BufferTooSmall { actual: 2, expected: 3 }));
+    assert_eq!(Padded::<_, u8>::new(vec).try_pack::<BigEndian>(&mut buffer[..3]), Err(pack::Error::BufferTooSmall { actual: 3, expected: 7 }));
+
+    // Items that do not write their whole slot
+    let options = BoundedVec::<Option<u8>, 2>::from_slice(&[None]).unwrap();
+    let mut buffer = [0xffu8; 5];
+    Padded::<_, u8>::new(options.clone()).pack::<BigEndian>(&mut buffer);
+    assert_eq!(buffer, [1, 0, 0, 0, 0]);
+    buffer.fill(0xff);
+    assert_eq!(Compact::<_, u8>::new(options).try_pack::<BigEndian>(&mut buffer), Ok(()));
+    assert_eq!(buffer, [1, 0, 0, 0xff, 0xff]);
+}
+
+#[cfg(feature = "heapless")]
+#[test]
+fn heapless() {
+    #[derive(Size, Pack, Unpack, Debug, PartialEq)]
+    struct Frame {
+        readings: heapless::Vec<u16, 2>,
+        label: Compact<heapless::String<4>, u8>,
+    }
+
+    assert_eq!(Frame::SIZE, 2 + 2 * 2 + 1 + 4);
+
+    let frame = Frame {
+        readings: heapless::Vec::from_slice(&[7]).unwrap(),
+        label: Compact::new(heapless::String::try_from("ab").unwrap()),
+    };
+    assert_eq!(frame.encoded_size(), 2 + 2 * 2 + 1 + 2);
+    let mut buffer = [0u8; Frame::SIZE];
+    frame.pack::<BigEndian>(&mut buffer);
+    assert_eq!(buffer[..frame.encoded_size()], [0, 1, 0, 7, 0, 0, 2, b'a', b'b']);
+    assert_eq!(Frame::unpack::<BigEndian>(&buffer), Ok(frame));
+    assert_eq!(Frame::unpack_safely::<BigEndian>(&buffer[..9]).unwrap().label.0, "ab");
+    assert_eq!(Frame::unpack_safely::<BigEndian>(&buffer[..8]), Err(Error::BufferTooSmall { actual: 8, expected: 9 }));
+
+    assert_eq!(heapless::Vec::<u16, 2>::SCHEMA, Type::Tuple(&[
+        Type::Primitive(Primitive::U16),
+        Type::Array { element: &Type::Primitive(Primitive::U16), len: 2 },
+    ]));
+
+    assert_eq!(heapless::String::<4>::unpack::<BigEndian>(&[0, 2, 0xc3, 0x28, 0, 0]), Err(Error::InvalidUtf8));
+    assert_eq!(
+        heapless::Vec::<u8, 2>::unpack::<BigEndian>(&[0, 3, 1, 2]),
+        Err(Error::LengthExceedsCapacity { len: 3, capacity: 2 }),
+    );
+}
+
+#[cfg(feature = "arrayvec")]
+#[test]
+fn arrayvec() {
+    use arrayvec::{ArrayString, ArrayVec};
+
+    assert_eq!(ArrayVec::<u32, 2>::SIZE, 2 + 2 * 4);
+    assert_eq!(ArrayString::<3>::SIZE, 2 + 3);
+
+    let vec: ArrayVec<u32, 2> = [0x01020304].into_iter().collect();
+    let mut buffer = [0xffu8; 10];
+    vec.pack::<LittleEndian>(&mut buffer);
+    assert_eq!(buffer, [1, 0, 4, 3, 2, 1, 0, 0, 0, 0]);
+    assert_eq!(ArrayVec::<u32, 2>::unpack::<LittleEndian>(&buffer), Ok(vec));
+
+    let string = ArrayString::<3>::from("ä").unwrap();
+    let mut buffer = [0xffu8; 5];
+    Compact::<_, u8>::new(string).pack::<BigEndian>(&mut buffer);
+    assert_eq!(buffer[..3], [2, 0xc3, 0xa4]);
+    assert_eq!(Compact::<ArrayString<3>, u8>::unpack::<BigEndian>(&buffer).unwrap().0, string);
+}
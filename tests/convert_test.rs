@@ -0,0 +1,124 @@
+use byteorder::{BigEndian, LittleEndian};
+use lightpack::{Pack, Schema, Size, Unpack, View, pack, schema::{Field, Primitive, Type}, unpack};
+
+#[derive(Size, Pack, Unpack, Schema, View, Debug, PartialEq, Clone, Copy)]
+struct Status {
+    #[lightpack(as = u8)]
+    percentage: u32,
+    #[lightpack(scale = 0.01, offset = -40.0, as = i16)]
+    temperature: f32,
+    flags: u8,
+}
+
+#[derive(Size, Pack, Unpack, Debug, PartialEq)]
+struct Ticks(#[lightpack(scale = 1000, as = u16)] u64);
+
+#[test]
+fn encoded_types() {
+    assert_eq!(Status::SIZE, 4);
+    assert_eq!(Status::SCHEMA, Type::Struct {
+        name: "Status",
+        fields: &[
+            Field { name: "percentage", ty: &Type::Primitive(Primitive::U8), scale: 1.0, offset: 0.0 },
+            Field { name: "temperature", ty: &Type::Primitive(Primitive::I16), scale: 0.01, offset: -40.0 },
+            Field { name: "flags", ty: &Type::Primitive(Primitive::U8), scale: 1.0, offset: 0.0 },
+        ],
+    });
+}
+
+#[test]
+fn roundtrip() {
+    let status = Status { percentage: 85, temperature: 21.5, flags: 3 };
+    let mut buffer = [0u8; Status::SIZE];
+    status.pack::<BigEndian>(&mut buffer);
+
+    // (21.5 + 40) / 0.01 = 6150
+    assert_eq!(buffer, [85, 0x18, 0x06, 3]);
+    assert_eq!(Status::unpack::<BigEndian>(&buffer), Ok(status));
+
+    status.pack::<LittleEndian>(&mut buffer);
+    assert_eq!(buffer, [85, 0x06, 0x18, 3]);
+    assert_eq!(Status::unpack::<LittleEndian>(&buffer), Ok(status));
+
+    Ticks(42_000).pack::<BigEndian>(&mut buffer);
+    assert_eq!(buffer[..2], [0, 42]);
+    assert_eq!(Ticks::unpack::<BigEndian>(&buffer), Ok(Ticks(42_000)));
+}
+
+#[test]
+fn rounding() {
+    let mut buffer = [0u8; Status::SIZE];
+    Status { percentage: 0, temperature: -39.996, flags: 0 }.pack::<BigEndian>(&mut buffer);
+    assert_eq!(buffer[1..3], [0, 0]);
+    Status { percentage: 0, temperature: -39.994, flags: 0 }.pack::<BigEndian>(&mut buffer);
+    assert_eq!(buffer[1..3], [0, 1]);
+
+    Ticks(1_500).pack::<BigEndian>(&mut buffer);
+    assert_eq!(buffer[..2], [0, 2]);
+}
+
+#[test]
+fn out_of_range() {
+    let mut buffer = [0u8; Status::SIZE];
+    let status = Status { percentage: 256, temperature: 0.0, flags: 0 };
+    assert_eq!(status.try_pack::<BigEndian>(&mut buffer), Err(pack::Error::OutOfRange));
+
+    let status = Status { percentage: 0, temperature: 300.0, flags: 0 };
+    assert_eq!(status.try_pack::<BigEndian>(&mut buffer), Err(pack::Error::OutOfRange));
+
+    let status = Status { percentage: 0, temperature: f32::NAN, flags: 0 };
+    assert_eq!(status.try_pack::<BigEndian>(&mut buffer), Err(pack::Error::OutOfRange));
+
+    // The decoded value does not fit into the field type
+    #[derive(Size, Unpack, Debug, PartialEq)]
+    struct Narrow(#[lightpack(as = i16)] u8);
+    assert_eq!(Narrow::unpack::<BigEndian>(&[0xff, 0xff]), Err(unpack::Error::OutOfRange));
+    assert_eq!(Narrow::unpack::<BigEndian>(&[0x00, 0xff]), Ok(Narrow(255)));
+}
+
+#[test]
+#[should_panic(expected = "out of range")]
+fn pack_panics_when_out_of_range() {
+    let mut buffer = [0u8; Status::SIZE];
+    Status { percentage: 1000, temperature: 0.0, flags: 0 }.pack::<BigEndian>(&mut buffer);
+}
+
+#[test]
+fn try_pack() {
+    #[derive(Size, Pack)]
+    struct Frame {
+        seq: u16,
+        statuses: [Status; 2],
+    }
+
+    let status = Status { percentage: 50, temperature: 0.0, flags: 1 };
+    let mut buffer = [0u8; Frame::SIZE];
+    assert_eq!(Frame { seq: 1, statuses: [status; 2] }.try_pack::<BigEndian>(&mut buffer), Ok(()));
+    assert_eq!(buffer, [0, 1, 50, 0x0f, 0xa0, 1, 50, 0x0f, 0xa0, 1]);
+
+    let invalid = Status { percentage: 500, ..status };
+    assert_eq!(Frame { seq: 1, statuses: [status, invalid] }.try_pack::<BigEndian>(&mut buffer), Err(pack::Error::OutOfRange));
+    assert_eq!(
+        Frame { seq: 1, statuses: [status; 2] }.try_pack::<BigEndian>(&mut buffer[..9]),
+        Err(pack::Error::BufferTooSmall { actual: 9, expected: 10 }),
+    );
+    assert_eq!(5u32.try_pack::<BigEndian>(&mut buffer[..3]), Err(pack::Error::BufferTooSmall { actual: 3, expected: 4 }));
+}
+
+#[test]
+fn view() {
+    let mut buffer = [0u8; Status::SIZE];
+    Status { percentage: 10, temperature: -40.0, flags: 7 }.pack::<BigEndian>(&mut buffer);
+
+    let mut view = StatusViewMut::<BigEndian>::new(&mut buffer);
+    assert_eq!(view.percentage(), Ok(10));
+    assert_eq!(view.temperature(), Ok(-40.0));
+    view.set_temperature(25.0);
+    assert_eq!(view.flags(), Ok(7));
+    assert_eq!(view.try_set_temperature(300.0), Err(pack::Error::OutOfRange));
+    assert_eq!(view.try_set_percentage(256), Err(pack::Error::OutOfRange));
+    assert_eq!(view.try_set_flags(7), Ok(()));
+
+    assert_eq!(buffer, [10, 0x19, 0x64, 7]);
+    assert_eq!(StatusView::<BigEndian>::new(&buffer).temperature(), Ok(25.0));
+}
@@ -0,0 +1,120 @@
+use byteorder::{BigEndian, LittleEndian};
+use lightpack::{Pack, Schema, Size, Unpack, fixed::{Fixed, Rounding}, schema::{Primitive, Type}};
+
+type Q8 = Fixed<i16, 8>;
+type UQ4 = Fixed<u8, 4>;
+
+fn q8(value: f64) -> Q8 {
+    Q8::checked_from_f64(value).unwrap()
+}
+
+#[test]
+fn encoding() {
+    #[derive(Size, Pack, Unpack, Schema, Debug, PartialEq, Clone, Copy)]
+    struct Reading {
+        temperature: Q8,
+        ratio: Fixed<u32, 16>,
+    }
+
+    assert_eq!(Reading::SIZE, 6);
+
+    let reading = Reading { temperature: q8(1.5), ratio: Fixed::from_bits(0x0001_8000) };
+    let mut buffer = [0u8; Reading::SIZE];
+    reading.pack::<BigEndian>(&mut buffer);
+    assert_eq!(buffer, [0x01, 0x80, 0x00, 0x01, 0x80, 0x00]);
+    assert_eq!(Reading::unpack::<BigEndian>(&buffer), Ok(reading));
+
+    reading.pack::<LittleEndian>(&mut buffer);
+    assert_eq!(buffer[..2], [0x80, 0x01]);
+    assert_eq!(Reading::unpack::<LittleEndian>(&buffer).unwrap().ratio.to_f64(), 1.5);
+}
+
+#[test]
+fn schema() {
+    let Type::Struct { name, fields } = Q8::SCHEMA else { panic!("Fixed is described as a struct") };
+    assert_eq!(name, "FixedI16F8");
+    assert_eq!(fields.len(), 1);
+    assert_eq!(*fields[0].ty, Type::Primitive(Primitive::I16));
+    assert_eq!((fields[0].scale, fields[0].offset), (1.0 / 256.0, 0.0));
+    assert_eq!(Q8::SCHEMA.size(), Q8::SIZE);
+
+    let Type::Struct { name, fields } = Fixed::<u64, 64>::SCHEMA else { panic!("Fixed is described as a struct") };
+    assert_eq!(name, "FixedU64F64");
+    assert_eq!(fields[0].scale, 2f64.powi(-64));
+    assert_eq!(Fixed::<u8, 0>::SCHEMA.fingerprint(false), Fixed::<u8, 0>::SCHEMA_HASH);
+
+    assert_ne!(Q8::SCHEMA_HASH, i16::SCHEMA_HASH);
+    assert_ne!(Q8::SCHEMA_HASH, Fixed::<i16, 4>::SCHEMA_HASH);
+    assert_ne!(UQ4::SCHEMA_HASH, Fixed::<i8, 4>::SCHEMA_HASH);
+}
+
+#[test]
+fn conversions() {
+    assert_eq!(q8(1.5).to_bits(), 384);
+    assert_eq!(q8(-1.5).to_bits(), -384);
+    assert_eq!(q8(-1.5).to_f32(), -1.5);
+    assert_eq!(Q8::from_bits(1).to_f64(), 1.0 / 256.0);
+    assert_eq!(Q8::checked_from_int(-3).unwrap().to_f64(), -3.0);
+    assert_eq!(Q8::checked_from_int(128), None);
+    assert_eq!(Q8::max_value().to_f64(), 127.99609375);
+    assert_eq!(Q8::min_value().to_f64(), -128.0);
+    assert_eq!(q8(2.25).to_string(), "2.25");
+    assert_eq!(f64::from(q8(2.25)), 2.25);
+
+    // Half a unit is 1/512
+    let half = 1.0 / 512.0;
+    assert_eq!(q8(half).to_bits(), 1);
+    assert_eq!(q8(-half).to_bits(), -1);
+    assert_eq!(q8(half * 0.9).to_bits(), 0);
+    assert_eq!(Q8::from_f64_rounded(half * 3.0, Rounding::Floor).unwrap().to_bits(), 1);
+    assert_eq!(Q8::from_f64_rounded(-half, Rounding::Floor).unwrap().to_bits(), -1);
+    assert_eq!(Q8::from_f64_rounded(half * 0.1, Rounding::Ceil).unwrap().to_bits(), 1);
+    assert_eq!(Q8::from_f64_rounded(-half * 3.0, Rounding::TowardZero).unwrap().to_bits(), -1);
+
+    assert_eq!(Q8::checked_from_f64(128.0), None);
+    assert_eq!(Q8::checked_from_f64(f64::NAN), None);
+    assert_eq!(Q8::saturating_from_f64(1e9), Q8::max_value());
+    assert_eq!(Q8::saturating_from_f32(f32::NEG_INFINITY), Q8::min_value());
+    assert_eq!(Q8::saturating_from_f64(f64::NAN).to_bits(), 0);
+    assert_eq!(UQ4::saturating_from_f64(-1.0), UQ4::min_value());
+    assert_eq!(UQ4::checked_from_f32(15.9375).unwrap().to_bits(), 255);
+
+    assert_eq!(Fixed::<i64, 32>::checked_from_f64(-0.75).unwrap().to_bits(), -3 << 30);
+    assert_eq!(Fixed::<u64, 64>::from_bits(1 << 63).to_f64(), 0.5);
+}
+
+#[test]
+fn arithmetic() {
+    assert_eq!(q8(1.5) + q8(2.25), q8(3.75));
+    assert_eq!(q8(1.5) - q8(2.25), q8(-0.75));
+    assert_eq!(q8(1.5) * q8(-2.5), q8(-3.75));
+    assert_eq!(q8(-3.75) / q8(1.5), q8(-2.5));
+    assert_eq!(-q8(1.5), q8(-1.5));
+
+    // 1/256 * 1/2 rounds to the nearest unit (away from zero for ties)
+    assert_eq!((Q8::from_bits(1) * q8(0.5)).to_bits(), 1);
+    assert_eq!(Q8::from_bits(1).checked_mul_rounded(q8(0.5), Rounding::Floor), Some(Q8::from_bits(0)));
+    assert_eq!(Q8::from_bits(-1).checked_mul_rounded(q8(0.5), Rounding::Floor), Some(Q8::from_bits(-1)));
+    assert_eq!(q8(1.0).checked_div_rounded(q8(3.0), Rounding::Ceil), Some(Q8::from_bits(86)));
+    assert_eq!(q8(1.0) / q8(3.0), Q8::from_bits(85));
+
+    assert_eq!(q8(100.0).checked_add(q8(100.0)), None);
+    assert_eq!(q8(-100.0).checked_sub(q8(100.0)), None);
+    assert_eq!(q8(16.0).checked_mul(q8(8.0)), None);
+    assert_eq!(q8(1.0).checked_div(q8(0.0)), None);
+    assert_eq!(q8(100.0).saturating_add(q8(100.0)), Q8::max_value());
+    assert_eq!(q8(-100.0).saturating_sub(q8(100.0)), Q8::min_value());
+    assert_eq!(q8(-16.0).saturating_mul(q8(8.0)), Q8::min_value());
+    assert_eq!(UQ4::from_bits(0).checked_sub(UQ4::from_bits(1)), None);
+
+    let big = Fixed::<u64, 32>::max_value();
+    assert_eq!(big.checked_mul(big), None);
+    assert_eq!(big.saturating_mul(big), big);
+    assert_eq!(Fixed::<u64, 64>::from_bits(1 << 63) * Fixed::from_bits(1 << 63), Fixed::from_bits(1 << 62));
+}
+
+#[test]
+#[should_panic(expected = "attempt to add with overflow")]
+fn overflow() {
+    let _ = q8(100.0) + q8(100.0);
+}
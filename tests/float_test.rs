@@ -0,0 +1,145 @@
+use byteorder::{BigEndian, LittleEndian};
+use lightpack::{Pack, Schema, Size, Unpack, extra::LE, float::{BF16, F16, Minifloat}, schema::{Endianness, Primitive, StructFormat, Type}};
+
+type E4M3 = Minifloat<4, 3>;
+type E5M2 = Minifloat<5, 2>;
+
+#[test]
+fn half() {
+    assert_eq!(F16::from_f32(0.0).to_bits(), 0x0000);
+    assert_eq!(F16::from_f32(-0.0).to_bits(), 0x8000);
+    assert_eq!(F16::from_f32(1.0).to_bits(), 0x3c00);
+    assert_eq!(F16::from_f32(1.5).to_bits(), 0x3e00);
+    assert_eq!(F16::from_f32(-2.0).to_bits(), 0xc000);
+    assert_eq!(F16::from_f32(65504.0).to_bits(), 0x7bff);
+    assert_eq!(F16::from_f64(0.1).to_bits(), 0x2e66);
+
+    // Overflow
+    assert_eq!(F16::from_f32(65519.0).to_bits(), 0x7bff);
+    assert_eq!(F16::from_f32(65520.0).to_bits(), 0x7c00);
+    assert_eq!(F16::from_f32(-1e10).to_bits(), 0xfc00);
+    assert_eq!(F16::from_f32(f32::INFINITY).to_bits(), 0x7c00);
+
+    // Ties to even
+    assert_eq!(F16::from_f64(1.0 + 2f64.powi(-11)).to_bits(), 0x3c00);
+    assert_eq!(F16::from_f64(1.0 + 3.0 * 2f64.powi(-11)).to_bits(), 0x3c02);
+    assert_eq!(F16::from_f64(1.0 + 2f64.powi(-11) + 2f64.powi(-30)).to_bits(), 0x3c01);
+
+    // Subnormals
+    assert_eq!(F16::from_f64(2f64.powi(-24)).to_bits(), 0x0001);
+    assert_eq!(F16::from_f64(2f64.powi(-25)).to_bits(), 0x0000);
+    assert_eq!(F16::from_f64(1.5 * 2f64.powi(-25)).to_bits(), 0x0001);
+    assert_eq!(F16::from_f64(-3.0 * 2f64.powi(-25)).to_bits(), 0x8002);
+    assert_eq!(F16::from_f64(2f64.powi(-14) - 2f64.powi(-25)).to_bits(), 0x0400);
+    assert_eq!(F16::from_bits(0x03ff).to_f64(), 1023.0 * 2f64.powi(-24));
+    assert_eq!(F16::from_f64(f64::MIN_POSITIVE).to_bits(), 0x0000);
+
+    // NaNs
+    assert_eq!(F16::from_f32(f32::NAN).to_bits() & 0x7e00, 0x7e00);
+    assert_eq!(F16::from_f32(-f32::NAN).to_bits() & 0xfe00, 0xfe00);
+    assert_eq!(F16::from_f32(f32::from_bits(0x7f800001)).to_bits(), 0x7e00);
+    assert!(F16::from_bits(0x7c01).is_nan());
+    assert!(F16::from_bits(0x7c01).to_f32().is_nan());
+    assert_eq!(F16::from_bits(0xfc00).to_f32(), f32::NEG_INFINITY);
+}
+
+#[test]
+fn half_roundtrips() {
+    for bits in 0..=u16::MAX {
+        let value = F16::from_bits(bits);
+        if value.is_nan() {
+            assert!(F16::from_f32(value.to_f32()).is_nan());
+        } else {
+            assert_eq!(F16::from_f32(value.to_f32()).to_bits(), bits);
+        }
+    }
+}
+
+#[test]
+fn bfloat() {
+    assert_eq!(BF16::from_f32(1.0).to_bits(), 0x3f80);
+    assert_eq!(BF16::from_f32(core::f32::consts::PI).to_bits(), 0x4049);
+    assert_eq!(BF16::from_f32(f32::MAX).to_bits(), 0x7f80);
+    assert_eq!(BF16::from_f32(f32::from_bits(1)).to_bits(), 0x0000);
+    assert_eq!(BF16::from_f32(f32::from_bits(0x8000_8001)).to_bits(), 0x8001);
+    assert_eq!(BF16::from_bits(0x0001).to_f32(), f32::from_bits(0x0001_0000));
+
+    // Compare with the well-known bit trick for rounding f32 to bfloat16
+    for i in 0..(1u32 << 20) {
+        let bits = i.wrapping_mul(0x9e37_79b9) ^ (i << 3);
+        let value = f32::from_bits(bits);
+        if value.is_nan() {
+            assert!(BF16::from_f32(value).is_nan());
+        } else {
+            let expected = ((bits as u64 + 0x7fff + ((bits as u64 >> 16) & 1)) >> 16) as u16;
+            assert_eq!(BF16::from_f32(value).to_bits(), expected, "{:#x}", bits);
+        }
+    }
+}
+
+#[test]
+fn minifloats() {
+    assert_eq!(E4M3::from_f32(1.0).to_bits(), 0x38);
+    assert_eq!(E4M3::from_f32(240.0).to_bits(), 0x77);
+    assert_eq!(E4M3::from_f32(250.0).to_bits(), 0x78);
+    assert_eq!(E4M3::from_f32(2f32.powi(-9)).to_bits(), 0x01);
+    assert_eq!(E4M3::from_bits(0x01).to_f32(), 2f32.powi(-9));
+    assert!(E4M3::from_f32(f32::NAN).is_nan());
+    assert_eq!(E5M2::from_f32(57344.0).to_bits(), 0x7b);
+    assert_eq!(E5M2::from_f32(-1.5).to_bits(), 0xbe);
+
+    for bits in 0..=u8::MAX {
+        if !E4M3::from_bits(bits).is_nan() {
+            assert_eq!(E4M3::from_f64(E4M3::from_bits(bits).to_f64()).to_bits(), bits);
+        }
+        if !E5M2::from_bits(bits).is_nan() {
+            assert_eq!(E5M2::from_f64(E5M2::from_bits(bits).to_f64()).to_bits(), bits);
+        }
+    }
+}
+
+#[test]
+fn comparisons() {
+    assert_eq!(F16::from_f32(0.0), F16::from_f32(-0.0));
+    assert_ne!(F16::from_f32(f32::NAN), F16::from_f32(f32::NAN));
+    assert!(F16::from_f32(-1.0) < F16::from_f32(0.5));
+    assert_eq!(F16::from_f32(1.5).to_string(), "1.5");
+    assert_eq!(f64::from(BF16::from_f32(2.0)), 2.0);
+}
+
+#[test]
+fn encoding() {
+    #[derive(Size, Pack, Unpack, Debug, PartialEq)]
+    struct Activations {
+        half: F16,
+        brain: BF16,
+        mini: E4M3,
+    }
+
+    assert_eq!(Activations::SIZE, 5);
+
+    let value = Activations { half: F16::from_f32(1.5), brain: BF16::from_f32(1.0), mini: E4M3::from_f32(-2.0) };
+    let mut buffer = [0u8; Activations::SIZE];
+    value.pack::<BigEndian>(&mut buffer);
+    assert_eq!(buffer, [0x3e, 0x00, 0x3f, 0x80, 0xc0]);
+    assert_eq!(Activations::unpack::<BigEndian>(&buffer), Ok(value));
+
+    F16::from_f32(1.5).pack::<LittleEndian>(&mut buffer);
+    assert_eq!(buffer[..2], [0x00, 0x3e]);
+}
+
+#[test]
+fn schema() {
+    assert_eq!(F16::SCHEMA, Type::Float { repr: Primitive::U16, exp_bits: 5, mantissa_bits: 10 });
+    assert_eq!(BF16::SCHEMA, Type::Float { repr: Primitive::U16, exp_bits: 8, mantissa_bits: 7 });
+    assert_eq!(E4M3::SCHEMA, Type::Float { repr: Primitive::U8, exp_bits: 4, mantissa_bits: 3 });
+    assert_eq!(E5M2::SCHEMA.size(), 1);
+    assert_ne!(F16::SCHEMA_HASH, u16::SCHEMA_HASH);
+    assert_ne!(F16::SCHEMA_HASH, BF16::SCHEMA_HASH);
+    assert_ne!(E4M3::SCHEMA_HASH, E5M2::SCHEMA_HASH);
+
+    // Only IEEE half precision has a `struct` format character
+    assert_eq!(StructFormat::new(&<(F16, u8)>::SCHEMA, Endianness::Big).as_str(), Some(">eB"));
+    assert_eq!(StructFormat::new(&<LE<F16>>::SCHEMA, Endianness::Big).as_str(), None);
+    assert_eq!(StructFormat::new(&BF16::SCHEMA, Endianness::Big).as_str(), None);
+}
@@ -0,0 +1,51 @@
+use lightpack::{Schema, Size, handshake::{self, Error, Mismatch}};
+
+#[derive(Size, Schema)]
+#[allow(dead_code)]
+struct V1 {
+    id: u16,
+    value: i32,
+}
+
+#[derive(Size, Schema)]
+#[allow(dead_code)]
+struct V2 {
+    id: u16,
+    value: i64,
+}
+
+#[test]
+fn messages() {
+    assert_eq!(handshake::message::<V1>(), V1::SCHEMA_HASH.to_be_bytes());
+    assert_eq!(handshake::verify::<V1>(&handshake::message::<V1>()), Ok(()));
+    assert_eq!(
+        handshake::verify::<V1>(&handshake::message::<V2>()),
+        Err(Mismatch { local: V1::SCHEMA_HASH, remote: V2::SCHEMA_HASH }),
+    );
+}
+
+#[test]
+fn exchange() {
+    let mut sent = Vec::new();
+    let result = handshake::exchange::<V1, ()>(
+        |message| {
+            sent.extend_from_slice(message);
+            Ok(())
+        },
+        |message| {
+            *message = handshake::message::<V1>();
+            Ok(())
+        },
+    );
+    assert_eq!(result, Ok(()));
+    assert_eq!(sent, handshake::message::<V1>());
+
+    let result = handshake::exchange::<V1, ()>(|_| Ok(()), |message| {
+        *message = handshake::message::<V2>();
+        Ok(())
+    });
+    assert_eq!(result, Err(Error::Mismatch(Mismatch { local: V1::SCHEMA_HASH, remote: V2::SCHEMA_HASH })));
+
+    let result = handshake::exchange::<V1, &str>(|_| Ok(()), |_| Err("disconnected"));
+    assert_eq!(result, Err(Error::Transport("disconnected")));
+}
@@ -0,0 +1,93 @@
+use byteorder::{BigEndian, LittleEndian};
+use lightpack::{Pack, Schema, Size, Unpack, int::{I24, I48, TryFromIntError, U24, U40, U48}, schema::{Endianness, Primitive, StructFormat, Type}};
+
+#[test]
+fn encoding() {
+    #[derive(Size, Pack, Unpack, Debug, PartialEq)]
+    struct Registers {
+        counter: U24,
+        offset: I24,
+        address: U40,
+        timestamp: U48,
+        delta: I48,
+    }
+
+    assert_eq!(Registers::SIZE, 3 + 3 + 5 + 6 + 6);
+
+    let registers = Registers {
+        counter: U24::new(0x010203).unwrap(),
+        offset: I24::new(-2).unwrap(),
+        address: U40::new(0x01_0203_0405).unwrap(),
+        timestamp: U48::MAX,
+        delta: I48::MIN,
+    };
+    let mut buffer = [0u8; Registers::SIZE];
+    registers.pack::<BigEndian>(&mut buffer);
+    assert_eq!(buffer, [
+        0x01, 0x02, 0x03,
+        0xff, 0xff, 0xfe,
+        0x01, 0x02, 0x03, 0x04, 0x05,
+        0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        0x80, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ]);
+    assert_eq!(Registers::unpack::<BigEndian>(&buffer), Ok(registers));
+
+    U24::from(0x0102u16).pack::<LittleEndian>(&mut buffer);
+    assert_eq!(buffer[..3], [0x02, 0x01, 0x00]);
+}
+
+#[test]
+fn sign_extension() {
+    assert_eq!(I24::unpack::<BigEndian>(&[0xff, 0xff, 0xff]).unwrap().get(), -1);
+    assert_eq!(I24::unpack::<LittleEndian>(&[0x00, 0x00, 0x80]), Ok(I24::MIN));
+    assert_eq!(I24::unpack::<BigEndian>(&[0x7f, 0xff, 0xff]), Ok(I24::MAX));
+    assert_eq!(I48::unpack::<LittleEndian>(&[0xfe, 0xff, 0xff, 0xff, 0xff, 0xff]).unwrap().get(), -2);
+    assert_eq!(U24::unpack::<BigEndian>(&[0xff, 0xff, 0xff]).unwrap().get(), 0xff_ffff);
+}
+
+#[test]
+fn conversions() {
+    assert_eq!(U24::MAX.get(), 16_777_215);
+    assert_eq!(I24::MIN.get(), -8_388_608);
+    assert_eq!(I48::MAX.get(), (1 << 47) - 1);
+    assert_eq!(U40::BITS, 40);
+
+    assert_eq!(U24::new(1 << 24), None);
+    assert_eq!(I24::new(1 << 23), None);
+    assert_eq!(I24::new(-(1 << 23) - 1), None);
+    assert_eq!(U24::try_from(1u32 << 24), Err(TryFromIntError));
+    assert_eq!(U48::try_from(42u64).map(u64::from), Ok(42));
+    assert_eq!(i32::from(I24::from(-5i16)), -5);
+    assert_eq!(I48::from(u32::MAX).get(), u32::MAX as i64);
+    assert_eq!(I24::new(-7).unwrap().to_string(), "-7");
+}
+
+#[test]
+fn converted_fields() {
+    #[derive(Size, Pack, Unpack, Debug, PartialEq)]
+    struct Position {
+        #[lightpack(as = I24)]
+        latitude: i32,
+    }
+
+    assert_eq!(Position::SIZE, 3);
+    let mut buffer = [0u8; Position::SIZE];
+    Position { latitude: -3 }.pack::<BigEndian>(&mut buffer);
+    assert_eq!(buffer, [0xff, 0xff, 0xfd]);
+    assert_eq!(Position::unpack::<BigEndian>(&buffer), Ok(Position { latitude: -3 }));
+    assert!(Position { latitude: 1 << 23 }.try_pack::<BigEndian>(&mut buffer).is_err());
+}
+
+#[test]
+fn schema() {
+    assert_eq!(U24::SCHEMA, Type::Primitive(Primitive::U24));
+    assert_eq!(I48::SCHEMA, Type::Primitive(Primitive::I48));
+    for (ty, size) in [(U24::SCHEMA, U24::SIZE), (I24::SCHEMA, I24::SIZE), (U40::SCHEMA, U40::SIZE), (U48::SCHEMA, U48::SIZE), (I48::SCHEMA, I48::SIZE)] {
+        assert_eq!(ty.size(), size);
+    }
+    assert_ne!(U24::SCHEMA_HASH, I24::SCHEMA_HASH);
+    assert_ne!(U24::SCHEMA_HASH, u32::SCHEMA_HASH);
+
+    // Python's struct module has no 3-byte codes
+    assert_eq!(StructFormat::new(&<(u8, I24)>::SCHEMA, Endianness::Big).as_str(), None);
+}
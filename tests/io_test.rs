@@ -0,0 +1,89 @@
+#![cfg(feature = "embedded-io")]
+
+use byteorder::{BigEndian, LittleEndian};
+use lightpack::{Pack, Size, Unpack, io::{Error, ReadExt, WriteExt}, pack, unpack, varint::Varint};
+
+#[derive(Size, Pack, Unpack, Debug, PartialEq, Clone, Copy)]
+struct Message {
+    id: u8,
+    value: u16,
+}
+
+const MESSAGE: Message = Message { id: 1, value: 0x0203 };
+
+#[test]
+fn roundtrip() {
+    let mut buffer = [0u8; 8];
+    let mut writer = &mut buffer[..];
+    writer.write_packed::<BigEndian, _>(&MESSAGE).unwrap();
+    writer.write_packed::<LittleEndian, _>(&MESSAGE).unwrap();
+    assert_eq!(writer.len(), 2);
+    assert_eq!(buffer[..6], [1, 2, 3, 1, 3, 2]);
+
+    let mut reader = &buffer[..];
+    assert_eq!(reader.read_unpacked::<BigEndian, Message>(), Ok(MESSAGE));
+    assert_eq!(reader.read_unpacked::<LittleEndian, Message>(), Ok(MESSAGE));
+    assert_eq!(reader.read_unpacked::<BigEndian, Message>(), Err(Error::UnexpectedEof));
+}
+
+#[test]
+fn variable_size() {
+    #[derive(Size, Pack, Unpack, Debug, PartialEq)]
+    struct Reading {
+        id: u8,
+        value: Varint<u32>,
+        flags: u8,
+    }
+
+    let reading = Reading { id: 1, value: Varint(300), flags: 7 };
+    let mut buffer = [0xffu8; 8];
+    let mut writer = &mut buffer[..];
+    writer.write_packed::<BigEndian, _>(&reading).unwrap();
+    assert_eq!(writer.len(), 4);
+    assert_eq!(buffer[..4], [1, 0xac, 0x02, 7]);
+
+    // The value is read without consuming the following bytes
+    let mut reader = &buffer[..];
+    assert_eq!(reader.read_unpacked::<BigEndian, Reading>(), Ok(reading));
+    assert_eq!(reader, [0xff; 4]);
+    assert_eq!((&buffer[..3]).read_unpacked::<BigEndian, Reading>(), Err(Error::UnexpectedEof));
+}
+
+#[test]
+fn errors() {
+    #[derive(Size, Pack, Unpack, Debug, PartialEq)]
+    struct Level(#[lightpack(as = u8)] u32);
+
+    let mut buffer = [0u8; 2];
+    let mut writer = &mut buffer[..];
+    assert_eq!(writer.write_packed::<BigEndian, _>(&Level(256)), Err(Error::Pack(pack::Error::OutOfRange)));
+    assert!(matches!(writer.write_packed::<BigEndian, _>(&7u32), Err(Error::Io(_))));
+
+    let mut reader = &[0x00u8, 0x11, 0x00, 0x00][..];
+    assert_eq!(reader.read_unpacked::<BigEndian, char>(), Err(Error::Unpack(unpack::Error::InvalidChar(0x110000))));
+}
+
+#[cfg(feature = "embedded-io-async")]
+#[test]
+fn async_roundtrip() {
+    use core::{future::Future, pin::pin, task::{Context, Poll, Waker}};
+    use lightpack::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Polls a future that completes without waiting.
+    fn block_on<F>(future: F) -> F::Output where F: Future {
+        let mut future = pin!(future);
+        match future.as_mut().poll(&mut Context::from_waker(Waker::noop())) {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("the future is not ready"),
+        }
+    }
+
+    let mut buffer = [0u8; Message::SIZE];
+    let mut writer = &mut buffer[..];
+    block_on(AsyncWriteExt::write_packed_async::<BigEndian, _>(&mut writer, &MESSAGE)).unwrap();
+    assert_eq!(buffer, [1, 2, 3]);
+
+    let mut reader = &buffer[..];
+    assert_eq!(block_on(AsyncReadExt::read_unpacked_async::<BigEndian, Message>(&mut reader)), Ok(MESSAGE));
+    assert_eq!(block_on(AsyncReadExt::read_unpacked_async::<BigEndian, Message>(&mut reader)), Err(Error::UnexpectedEof));
+}
@@ -0,0 +1,202 @@
+use lightpack::{Schema, Size, extra::{BE, LE}, schema::{Endianness, Field, Primitive, StructFormat, Type, Variant}};
+
+#[test]
+fn primitives() {
+    assert_eq!(u8::SCHEMA, Type::Primitive(Primitive::U8));
+    assert_eq!(i64::SCHEMA, Type::Primitive(Primitive::I64));
+    assert_eq!(char::SCHEMA, Type::Primitive(Primitive::Char));
+    assert_eq!(<()>::SCHEMA, Type::Tuple(&[]));
+
+    assert!(Primitive::I16.is_signed());
+    assert!(!Primitive::U16.is_signed());
+    assert!(Primitive::F32.is_float());
+    assert_eq!(Primitive::Char.size(), char::SIZE);
+}
+
+#[test]
+fn compound_types() {
+    assert_eq!(<[u16; 3]>::SCHEMA, Type::Array { element: &Type::Primitive(Primitive::U16), len: 3 });
+    assert_eq!(<Option<bool>>::SCHEMA, Type::Option(&Type::Primitive(Primitive::Bool)));
+    assert_eq!(<(u8, i8)>::SCHEMA, Type::Tuple(&[Type::Primitive(Primitive::U8), Type::Primitive(Primitive::I8)]));
+    assert_eq!(<LE<u32>>::SCHEMA, Type::Endian(Endianness::Little, &Type::Primitive(Primitive::U32)));
+    assert_eq!(<BE<u32>>::SCHEMA, Type::Endian(Endianness::Big, &Type::Primitive(Primitive::U32)));
+}
+
+#[test]
+fn structs() {
+    #[derive(Size, Schema)]
+    #[allow(dead_code)]
+    struct Point {
+        x: i16,
+        y: i16,
+    }
+
+    #[derive(Size, Schema)]
+    #[allow(dead_code)]
+    struct Tuple(u8, Option<Point>);
+
+    #[derive(Size, Schema)]
+    struct Unit;
+
+    assert_eq!(Point::SCHEMA, Type::Struct {
+        name: "Point",
+        fields: &[
+            Field { name: "x", ty: &Type::Primitive(Primitive::I16), scale: 1.0, offset: 0.0 },
+            Field { name: "y", ty: &Type::Primitive(Primitive::I16), scale: 1.0, offset: 0.0 },
+        ],
+    });
+    assert_eq!(Tuple::SCHEMA, Type::Struct {
+        name: "Tuple",
+        fields: &[
+            Field { name: "0", ty: &Type::Primitive(Primitive::U8), scale: 1.0, offset: 0.0 },
+            Field { name: "1", ty: &Type::Option(&Point::SCHEMA), scale: 1.0, offset: 0.0 },
+        ],
+    });
+    assert_eq!(Unit::SCHEMA, Type::Struct { name: "Unit", fields: &[] });
+}
+
+#[test]
+fn generic_structs() {
+    #[derive(Size, Schema)]
+    #[allow(dead_code)]
+    struct Pair<L, R> {
+        left: L,
+        right: [R; 2],
+    }
+
+    assert_eq!(Pair::<u8, BE<u16>>::SCHEMA, Type::Struct {
+        name: "Pair",
+        fields: &[
+            Field { name: "left", ty: &Type::Primitive(Primitive::U8), scale: 1.0, offset: 0.0 },
+            Field { name: "right", ty: &Type::Array { element: &Type::Endian(Endianness::Big, &Type::Primitive(Primitive::U16)), len: 2 }, scale: 1.0, offset: 0.0 },
+        ],
+    });
+}
+
+#[test]
+fn primitive_enums() {
+    #[derive(Size, Schema)]
+    #[repr(i16)]
+    #[allow(dead_code)]
+    enum Mode {
+        Off = -1,
+        On = 4,
+        Auto,
+    }
+
+    assert_eq!(Mode::SCHEMA, Type::Enum {
+        name: "Mode",
+        repr: Primitive::I16,
+        variants: &[
+            Variant { name: "Off", value: -1 },
+            Variant { name: "On", value: 4 },
+            Variant { name: "Auto", value: 5 },
+        ],
+    });
+}
+
+#[test]
+fn sizes() {
+    #[derive(Size, Schema)]
+    #[allow(dead_code)]
+    struct Nested {
+        a: (u8, LE<u32>),
+        b: [Option<i16>; 3],
+        c: (),
+    }
+
+    assert_eq!(Nested::SCHEMA.size(), Nested::SIZE);
+    assert_eq!(<[Option<char>; 2]>::SCHEMA.size(), <[Option<char>; 2]>::SIZE);
+}
+
+#[test]
+fn struct_formats() {
+    #[derive(Size, Schema)]
+    #[allow(dead_code)]
+    struct Point {
+        x: i16,
+        y: i16,
+    }
+
+    #[derive(Size, Schema)]
+    #[repr(u8)]
+    #[allow(dead_code)]
+    enum Mode {
+        Off,
+        On,
+    }
+
+    #[derive(Size, Schema)]
+    #[allow(dead_code)]
+    struct Frame {
+        mode: Mode,
+        origin: Option<Point>,
+        samples: [u16; 12],
+        corners: [Point; 2],
+        id: (char, bool, f64),
+        flags: BE<u32>,
+    }
+
+    assert_eq!(Point::STRUCT_FORMAT, Some(">hh"));
+    assert_eq!(Mode::STRUCT_FORMAT, Some(">B"));
+    assert_eq!(Frame::STRUCT_FORMAT, Some(">B?hh12HhhhhI?dI"));
+    assert_eq!(StructFormat::new(&Frame::SCHEMA, Endianness::Little).as_str(), None);
+    assert_eq!(StructFormat::new(&Point::SCHEMA, Endianness::Little).as_str(), Some("<hh"));
+    assert_eq!(StructFormat::new(&<[LE<u8>; 1]>::SCHEMA, Endianness::Big).as_str(), Some(">B"));
+    assert_eq!(StructFormat::new(&<[u64; 300]>::SCHEMA, Endianness::Big).as_str(), Some(">300Q"));
+    assert_eq!(StructFormat::new(&<[(u8, u8); 200]>::SCHEMA, Endianness::Big).as_str(), None);
+}
+
+#[test]
+fn fingerprints() {
+    #[derive(Size, Schema)]
+    #[allow(dead_code)]
+    struct Point {
+        x: i16,
+        y: i16,
+    }
+
+    #[derive(Size, Schema)]
+    #[allow(dead_code)]
+    struct Renamed {
+        left: i16,
+        top: i16,
+    }
+
+    #[derive(Size, Schema)]
+    #[lightpack(hash_names)]
+    #[allow(dead_code)]
+    struct Named {
+        x: i16,
+        y: i16,
+    }
+
+    #[derive(Size, Schema)]
+    #[repr(u8)]
+    #[allow(dead_code)]
+    enum Mode {
+        Off,
+        On,
+    }
+
+    #[derive(Size, Schema)]
+    #[repr(u8)]
+    #[allow(dead_code)]
+    enum Shifted {
+        Off = 1,
+        On = 2,
+    }
+
+    assert_eq!(Point::SCHEMA_HASH, Renamed::SCHEMA_HASH);
+    assert_eq!(Point::SCHEMA_HASH, Point::SCHEMA.fingerprint(false));
+    assert_ne!(Named::SCHEMA_HASH, Point::SCHEMA_HASH);
+    assert_eq!(Named::SCHEMA_HASH, Named::SCHEMA.fingerprint(true));
+    assert_ne!(Mode::SCHEMA_HASH, Shifted::SCHEMA_HASH);
+    assert_ne!(<(i16, u16)>::SCHEMA_HASH, <(u16, i16)>::SCHEMA_HASH);
+    assert_ne!(u32::SCHEMA_HASH, <LE<u32>>::SCHEMA_HASH);
+    assert_ne!(<[u8; 2]>::SCHEMA_HASH, <[u8; 3]>::SCHEMA_HASH);
+    assert_ne!(<Option<u8>>::SCHEMA_HASH, <(bool, u8)>::SCHEMA_HASH);
+
+    // The fingerprint must be stable across compilations and platforms
+    assert_eq!(u8::SCHEMA_HASH, 0x08328807b4eb6fed);
+}
@@ -0,0 +1,387 @@
+#![cfg(feature = "serde")]
+
+use byteorder::{BigEndian, LittleEndian};
+use lightpack::{Pack, Schema, Size, Unpack, bcd::{Bcd, DateTime}, bounded::BoundedVec, collections::{Compact, Padded}, extra::{BE, LE, Reserved}, fixed::Fixed, float::F16, int::{I24, I48, U24, U48}, schema::Endianness, serde::{self, Error, Value}, string::FixedStr, timestamp::{NtpTimestamp, UnixSeconds32}, varint::{Varint, ZigZag}};
+use ::serde::{Deserialize, Serialize};
+
+#[derive(Size, Pack, Unpack, Serialize, Deserialize, Debug, PartialEq, Clone, Copy)]
+struct Point {
+    x: i16,
+    y: i16,
+}
+
+#[derive(Size, Pack, Unpack, Serialize, Deserialize, Debug, PartialEq, Clone, Copy)]
+struct Frame {
+    seq: u32,
+    valid: bool,
+    marker: char,
+    origin: Option<Point>,
+    samples: [f32; 2],
+    pair: (u8, i64),
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+enum Command {
+    Stop,
+    Move(Point),
+    Turn { degrees: i16, fast: bool },
+}
+
+#[test]
+fn matches_pack() {
+    let frame = Frame { seq: 7, valid: true, marker: 'x', origin: Some(Point { x: -1, y: 2 }), samples: [1.5, -0.25], pair: (3, -4) };
+
+    let mut packed = [0u8; Frame::SIZE];
+    frame.pack::<BigEndian>(&mut packed);
+    let mut serialized = [0u8; Frame::SIZE];
+    assert_eq!(serde::to_slice::<BigEndian, _>(&frame, &mut serialized), Ok(Frame::SIZE));
+    assert_eq!(serialized, packed);
+    assert_eq!(serde::from_slice::<BigEndian, Frame>(&packed), Ok(frame));
+
+    frame.pack::<LittleEndian>(&mut packed);
+    serde::to_slice::<LittleEndian, _>(&frame, &mut serialized).unwrap();
+    assert_eq!(serialized, packed);
+    assert_eq!(serde::from_slice::<LittleEndian, Frame>(&packed), Ok(frame));
+}
+
+#[test]
+fn enums() {
+    let mut buffer = [0u8; 8];
+
+    assert_eq!(serde::to_slice::<BigEndian, _>(&Command::Stop, &mut buffer), Ok(4));
+    assert_eq!(buffer[..4], [0, 0, 0, 0]);
+    assert_eq!(serde::to_slice::<BigEndian, _>(&Command::Move(Point { x: 3, y: 4 }), &mut buffer), Ok(8));
+    assert_eq!(buffer, [0, 0, 0, 1, 0, 3, 0, 4]);
+    assert_eq!(serde::from_slice::<BigEndian, Command>(&buffer), Ok(Command::Move(Point { x: 3, y: 4 })));
+    assert_eq!(serde::to_slice::<LittleEndian, _>(&Command::Turn { degrees: -90, fast: true }, &mut buffer), Ok(7));
+    assert_eq!(buffer[..7], [2, 0, 0, 0, 0xa6, 0xff, 1]);
+    assert_eq!(serde::from_slice::<LittleEndian, Command>(&buffer), Ok(Command::Turn { degrees: -90, fast: true }));
+
+    assert_eq!(serde::from_slice::<BigEndian, Command>(&[0, 0, 0, 3]), Err(Error::Custom));
+}
+
+#[test]
+fn wide_integers() {
+    let mut buffer = [0u8; 32];
+    let value = (u128::MAX - 1, i128::MIN);
+    assert_eq!(serde::to_slice::<BigEndian, _>(&value, &mut buffer), Ok(32));
+    assert_eq!(serde::from_slice::<BigEndian, (u128, i128)>(&buffer), Ok(value));
+}
+
+#[test]
+fn errors() {
+    let mut buffer = [0u8; 3];
+    assert_eq!(serde::to_slice::<BigEndian, _>(&Point { x: 1, y: 2 }, &mut buffer), Err(Error::BufferTooSmall { actual: 3, expected: 4 }));
+    assert_eq!(serde::from_slice::<BigEndian, Point>(&[0, 1, 0]), Err(Error::BufferTooSmall { actual: 3, expected: 4 }));
+    assert_eq!(serde::from_slice::<BigEndian, char>(&[0, 0x11, 0, 0]), Err(Error::InvalidChar(0x110000)));
+
+    assert_eq!(serde::to_slice::<BigEndian, _>(&None::<u8>, &mut buffer), Err(Error::Unsupported("None")));
+    assert_eq!(serde::from_slice::<BigEndian, Option<u8>>(&[0, 0]), Err(Error::Unsupported("None")));
+    assert_eq!(serde::to_slice::<BigEndian, _>("abc", &mut buffer), Err(Error::Unsupported("a string")));
+    assert_eq!(serde::to_slice::<BigEndian, _>(&[1u8, 2][..], &mut buffer), Err(Error::Unsupported("a sequence")));
+    assert_eq!(serde::from_slice::<BigEndian, &str>(&buffer), Err(Error::Unsupported("a string")));
+}
+
+#[derive(Size, Pack, Unpack, Schema, Serialize, Deserialize, Debug, PartialEq, Clone, Copy)]
+#[repr(u8)]
+enum Mode {
+    Off = 0,
+    On = 5,
+}
+
+#[derive(Size, Pack, Unpack, Schema, Serialize, Deserialize, Debug, PartialEq, Clone, Copy)]
+struct Id(u16);
+
+#[derive(Size, Pack, Unpack, Schema, Serialize, Deserialize, Debug, PartialEq, Clone, Copy)]
+struct Pair(i8, bool);
+
+#[derive(Size, Pack, Unpack, Schema, Serialize, Deserialize, Debug, PartialEq, Clone, Copy)]
+struct Message {
+    id: Id,
+    mode: Mode,
+    little: LE<u32>,
+    big: BE<i16>,
+    pair: Pair,
+    origin: Option<(i16, i16)>,
+    samples: [f64; 2],
+    marker: char,
+    unit: (),
+}
+
+#[test]
+fn fixed_byte_order() {
+    let value = (LE(0x0102u16), BE(0x0304u16), 0x0506u16);
+
+    let mut packed = [0u8; 6];
+    value.pack::<BigEndian>(&mut packed);
+    let mut serialized = [0u8; 6];
+    serde::to_slice::<BigEndian, _>(&value, &mut serialized).unwrap();
+    assert_eq!(serialized, [2, 1, 3, 4, 5, 6]);
+    assert_eq!(serialized, packed);
+    assert_eq!(serde::from_slice::<BigEndian, (LE<u16>, BE<u16>, u16)>(&serialized), Ok(value));
+
+    serde::to_slice::<LittleEndian, _>(&value, &mut serialized).unwrap();
+    assert_eq!(serialized, [2, 1, 3, 4, 6, 5]);
+    assert_eq!(serde::from_slice::<LittleEndian, (LE<u16>, BE<u16>, u16)>(&serialized), Ok(value));
+
+    assert_eq!(serde_json::to_string(&value).unwrap(), "[258,772,1286]");
+    assert_eq!(serde_json::from_str::<(LE<u16>, BE<u16>, u16)>("[258,772,1286]").unwrap(), value);
+}
+
+#[test]
+fn values() {
+    let mut message = Message {
+        id: Id(42),
+        mode: Mode::On,
+        little: LE(7),
+        big: BE(-3),
+        pair: Pair(-1, true),
+        origin: Some((10, -20)),
+        samples: [0.5, -1.25],
+        marker: '\u{e9}',
+        unit: (),
+    };
+
+    for order in [Endianness::Big, Endianness::Little] {
+        let mut buffer = [0u8; Message::SIZE];
+        match order {
+            Endianness::Big => message.pack::<BigEndian>(&mut buffer),
+            Endianness::Little => message.pack::<LittleEndian>(&mut buffer),
+        }
+        let value = Value::of::<Message>(&buffer, order).unwrap();
+        assert_eq!(serde_json::to_value(value).unwrap(), serde_json::to_value(message).unwrap());
+        assert_eq!(serde_json::to_value(value.field("mode").unwrap()).unwrap(), serde_json::json!("On"));
+        assert_eq!(serde_json::to_value(value.field("samples").unwrap().element(1).unwrap()).unwrap(), serde_json::json!(-1.25));
+        assert_eq!(value.field("big").unwrap().bytes(), [0xff, 0xfd]);
+        assert!(value.field("missing").is_none());
+        assert!(value.field("samples").unwrap().element(2).is_none());
+    }
+
+    message.origin = None;
+    let mut buffer = [0u8; Message::SIZE];
+    message.pack::<BigEndian>(&mut buffer);
+    let json = serde_json::to_value(Value::of::<Message>(&buffer, Endianness::Big).unwrap()).unwrap();
+    assert_eq!(json, serde_json::to_value(message).unwrap());
+    assert_eq!(json["origin"], serde_json::Value::Null);
+    assert_eq!(json["id"], serde_json::json!(42));
+    assert_eq!(json["pair"], serde_json::json!([-1, true]));
+
+    buffer[Message::OFFSET_MODE] = 3;
+    assert!(serde_json::to_value(Value::of::<Message>(&buffer, Endianness::Big).unwrap()).is_err());
+    assert_eq!(Value::of::<Message>(&buffer[1..], Endianness::Big).unwrap_err(), Error::BufferTooSmall { actual: Message::SIZE - 1, expected: Message::SIZE });
+}
+
+#[test]
+fn varint_values() {
+    #[derive(Size, Pack, Schema)]
+    struct Counter {
+        count: Varint<u32>,
+        deltas: [ZigZag<i16>; 2],
+        last: Option<Varint<u64>>,
+        flag: bool,
+    }
+
+    let counter = Counter { count: Varint(300), deltas: [ZigZag(-1), ZigZag(-300)], last: Some(Varint(1)), flag: true };
+    let mut buffer = [0xeeu8; Counter::SIZE];
+    counter.pack::<BigEndian>(&mut buffer);
+    let len = counter.encoded_size();
+
+    let value = Value::of::<Counter>(&buffer[..len], Endianness::Big).unwrap();
+    assert_eq!(value.bytes().len(), len);
+    assert_eq!(value.field("flag").unwrap().bytes(), [1]);
+    assert_eq!(serde_json::to_value(value).unwrap(), serde_json::json!({
+        "count": 300,
+        "deltas": [-1, -300],
+        "last": 1,
+        "flag": true,
+    }));
+    assert_eq!(Value::of::<Counter>(&buffer[..len - 1], Endianness::Big).unwrap_err(), Error::BufferTooSmall { actual: len - 1, expected: Counter::SIZE });
+
+    // Overlong encodings
+    assert!(serde_json::to_value(Value::of::<Varint<u8>>(&[0x80, 0x00], Endianness::Big).unwrap()).is_err());
+}
+
+#[test]
+fn bcd_values() {
+    let mut buffer = [0u8; 2];
+    Bcd::new(1234u16).unwrap().pack::<BigEndian>(&mut buffer);
+    assert_eq!(serde_json::to_value(Value::of::<Bcd<u16>>(&buffer, Endianness::Big).unwrap()).unwrap(), serde_json::json!(1234));
+    assert!(serde_json::to_value(Value::of::<Bcd<u16>>(&[0x1a, 0x00], Endianness::Big).unwrap()).is_err());
+
+    let registers = [0x30, 0x37, 0x13, 0x02, 0x19, 0x10, 0x26];
+    assert_eq!(serde_json::to_value(Value::of::<DateTime>(&registers, Endianness::Big).unwrap()).unwrap(), serde_json::json!({
+        "seconds": 30, "minutes": 37, "hours": 13, "weekday": 2, "day": 19, "month": 10, "year": 26,
+    }));
+}
+
+#[test]
+fn odd_width_values() {
+    #[derive(Size, Pack, Schema)]
+    struct Registers {
+        status: U24,
+        offset: I24,
+        serial: U48,
+        drift: LE<I48>,
+    }
+
+    let registers = Registers { status: U24::MAX, offset: I24::new(-2).unwrap(), serial: U48::new(0x0102_0304_0506).unwrap(), drift: LE(I48::MIN) };
+    let mut buffer = [0u8; Registers::SIZE];
+    registers.pack::<BigEndian>(&mut buffer);
+
+    assert_eq!(serde_json::to_value(Value::of::<Registers>(&buffer, Endianness::Big).unwrap()).unwrap(), serde_json::json!({
+        "status": 0xff_ffff,
+        "offset": -2,
+        "serial": 0x0102_0304_0506u64,
+        "drift": -(1i64 << 47),
+    }));
+}
+
+#[derive(Size, Pack, Unpack, Schema, Debug, PartialEq, Clone, Copy)]
+#[lightpack(serde)]
+#[repr(i16)]
+enum Level {
+    Low = -1,
+    High = 300,
+}
+
+#[derive(Size, Pack, Unpack, Schema, Debug, PartialEq, Clone, Copy)]
+#[lightpack(serde)]
+struct Header(u8, Reserved<2>, Level);
+
+#[derive(Size, Pack, Unpack, Schema, Debug, PartialEq, Clone, Copy)]
+#[lightpack(serde)]
+struct Telemetry {
+    header: Header,
+    _reserved: Reserved<3>,
+    #[lightpack(as = i16, scale = 0.1)]
+    temperature: f32,
+    level: LE<Level>,
+}
+
+#[test]
+fn derived() {
+    let telemetry = Telemetry { header: Header(7, Reserved, Level::High), _reserved: Reserved, temperature: -12.5, level: LE(Level::Low) };
+
+    // Binary formats see the packed representation
+    let mut packed = [0xffu8; Telemetry::SIZE];
+    telemetry.pack::<BigEndian>(&mut packed);
+    assert_eq!(packed, [7, 0, 0, 0x01, 0x2c, 0, 0, 0, 0xff, 0x83, 0xff, 0xff]);
+    let mut serialized = [0xffu8; Telemetry::SIZE];
+    assert_eq!(serde::to_slice::<BigEndian, _>(&telemetry, &mut serialized), Ok(Telemetry::SIZE));
+    assert_eq!(serialized, packed);
+    assert_eq!(serde::from_slice::<BigEndian, Telemetry>(&packed), Ok(telemetry));
+    assert_eq!(serde::from_slice::<BigEndian, Level>(&[0, 1]), Err(Error::Custom));
+
+    // Human-readable formats skip reserved fields and see logical values and variant names
+    let json = serde_json::json!({ "header": [7, "High"], "temperature": -12.5, "level": "Low" });
+    assert_eq!(serde_json::to_value(telemetry).unwrap(), json);
+    assert_eq!(serde_json::from_value::<Telemetry>(json.clone()).unwrap(), telemetry);
+    assert_eq!(serde_json::to_value(Value::of::<Telemetry>(&packed, Endianness::Big).unwrap()).unwrap(), serde_json::json!({
+        "header": [7, "High"],
+        "temperature": -12.5,
+        "level": "Low",
+    }));
+
+    let with_reserved = serde_json::json!({ "header": [7, "High"], "_reserved": [1, 2, 3], "temperature": -12.5, "level": "Low", "unknown": 1 });
+    assert_eq!(serde_json::from_value::<Telemetry>(with_reserved).unwrap(), telemetry);
+    assert!(serde_json::from_value::<Telemetry>(serde_json::json!({ "header": [7, "High"], "level": "Low" })).is_err());
+    assert!(serde_json::from_value::<Level>(serde_json::json!("Medium")).is_err());
+}
+
+#[derive(Size, Pack, Unpack, Schema, Debug, PartialEq, Clone)]
+#[lightpack(serde)]
+struct Record {
+    count: Varint<u32>,
+    delta: ZigZag<i16>,
+    sample: LE<I24>,
+    ratio: Fixed<i16, 8>,
+    half: F16,
+    code: Bcd<u16>,
+    clock: DateTime,
+    sent: NtpTimestamp,
+    at: UnixSeconds32,
+    name: FixedStr<8>,
+    readings: BoundedVec<u8, 3, u8>,
+    tags: Padded<BoundedVec<u16, 2>, u8>,
+}
+
+#[test]
+fn derived_wrappers() {
+    let record = Record {
+        count: Varint(300),
+        delta: ZigZag(-2),
+        sample: LE(I24::new(-5).unwrap()),
+        ratio: Fixed::from_bits(0x0180),
+        half: F16::from_f32(0.5),
+        code: Bcd::new(1234).unwrap(),
+        clock: DateTime { seconds: 30, minutes: 37, hours: 13, weekday: 2, day: 19, month: 10, year: 26 },
+        sent: NtpTimestamp { seconds: 1, fraction: 1 << 31 },
+        at: UnixSeconds32(1_700_000_000),
+        name: FixedStr::new("sensor").unwrap(),
+        readings: BoundedVec::from_slice(&[1, 2]).unwrap(),
+        tags: Padded::new(BoundedVec::from_slice(&[7]).unwrap()),
+    };
+
+    // Binary formats see the packed representation, including varints and odd widths
+    let mut packed = [0xffu8; Record::SIZE];
+    record.pack::<BigEndian>(&mut packed);
+    let size = record.encoded_size();
+    let mut serialized = [0xffu8; Record::SIZE];
+    assert_eq!(serde::to_slice::<BigEndian, _>(&record, &mut serialized), Ok(size));
+    assert_eq!(serialized[..size], packed[..size]);
+    assert_eq!(serialized[..5], [0xac, 0x02, 0x03, 0xfb, 0xff]);
+    assert_eq!(serde::from_slice::<BigEndian, Record>(&packed), Ok(record.clone()));
+    assert_eq!(serde::from_slice::<BigEndian, Varint<u32>>(&[0x80, 0x00]), Err(Error::InvalidVarint));
+    assert_eq!(serde::from_slice::<BigEndian, Varint<u8>>(&[0xac, 0x02]), Err(Error::Custom));
+
+    // Human-readable formats see the logical values
+    let json = serde_json::json!({
+        "count": 300,
+        "delta": -2,
+        "sample": -5,
+        "ratio": 1.5,
+        "half": 0.5,
+        "code": 1234,
+        "clock": { "seconds": 30, "minutes": 37, "hours": 13, "weekday": 2, "day": 19, "month": 10, "year": 26 },
+        "sent": { "seconds": 1, "fraction": 2147483648u32 },
+        "at": 1700000000,
+        "name": "sensor",
+        "readings": [1, 2],
+        "tags": [7],
+    });
+    assert_eq!(serde_json::to_value(&record).unwrap(), json);
+    assert_eq!(serde_json::from_value::<Record>(json).unwrap(), record);
+
+    assert!(serde_json::from_value::<Varint<u8>>(serde_json::json!(256)).is_err());
+    assert!(serde_json::from_value::<ZigZag<i8>>(serde_json::json!(-129)).is_err());
+    assert!(serde_json::from_value::<I24>(serde_json::json!(1 << 23)).is_err());
+    assert!(serde_json::from_value::<Bcd<u8>>(serde_json::json!(100)).is_err());
+    assert!(serde_json::from_value::<Fixed<i8, 4>>(serde_json::json!(8.0)).is_err());
+    assert!(serde_json::from_value::<FixedStr<4>>(serde_json::json!("sensor")).is_err());
+    assert!(serde_json::from_value::<BoundedVec<u8, 2>>(serde_json::json!([1, 2, 3])).is_err());
+}
+
+#[test]
+fn compact_collections() {
+    let tags = Compact::<BoundedVec<u16, 4>, u8>::new(BoundedVec::from_slice(&[1, 2]).unwrap());
+    let mut buffer = [0xffu8; 9];
+    assert_eq!(serde::to_slice::<LittleEndian, _>(&tags, &mut buffer), Ok(5));
+    assert_eq!(buffer[..5], [2, 1, 0, 2, 0]);
+    assert_eq!(serde::from_slice::<LittleEndian, Compact<BoundedVec<u16, 4>, u8>>(&buffer), Ok(tags));
+    assert_eq!(serde::from_slice::<LittleEndian, Compact<BoundedVec<u16, 4>, u8>>(&[5]), Err(Error::Custom));
+}
+
+#[cfg(feature = "heapless")]
+#[test]
+fn string_collections() {
+    let label = Padded::<heapless::String<4>, u8>::new(heapless::String::try_from("ab").unwrap());
+    assert_eq!(serde_json::to_value(&label).unwrap(), serde_json::json!("ab"));
+    assert_eq!(serde_json::from_value::<Padded<heapless::String<4>, u8>>(serde_json::json!("ab")).unwrap(), label);
+    assert!(serde_json::from_value::<Padded<heapless::String<4>, u8>>(serde_json::json!("abcde")).is_err());
+
+    let mut buffer = [0xffu8; 5];
+    assert_eq!(serde::to_slice::<BigEndian, _>(&label, &mut buffer), Ok(5));
+    assert_eq!(buffer, [2, b'a', b'b', 0, 0]);
+    assert_eq!(serde::from_slice::<BigEndian, Padded<heapless::String<4>, u8>>(&buffer), Ok(label));
+    assert_eq!(serde::from_slice::<BigEndian, Padded<heapless::String<4>, u8>>(&[2, 0xc3, 0x28, 0, 0]), Err(Error::Custom));
+}
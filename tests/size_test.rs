@@ -1,4 +1,4 @@
-use lightpack::Size;
+use lightpack::{Size, bounded::BoundedVec, collections::Compact, layout::FieldLayout, varint::{Varint, ZigZag}};
 
 #[test]
 fn unsigned_ints() {
@@ -84,3 +84,85 @@ fn primitive_enums() {
     assert_eq!(X::SIZE, 1);
     assert_eq!(Y::SIZE, 4);
 }
+
+#[test]
+fn field_offsets() {
+    #[derive(Size)]
+    #[allow(dead_code)]
+    struct Header {
+        kind: u8,
+        seq: u32,
+        flags: [bool; 2],
+        checksum: u16,
+    }
+
+    #[derive(Size)]
+    #[allow(dead_code)]
+    struct Tuple(u16, Header);
+
+    assert_eq!(Header::OFFSET_KIND, 0);
+    assert_eq!(Header::OFFSET_SEQ, 1);
+    assert_eq!(Header::OFFSET_FLAGS, 5);
+    assert_eq!(Header::OFFSET_CHECKSUM, 7);
+    assert_eq!(Tuple::OFFSET_0, 0);
+    assert_eq!(Tuple::OFFSET_1, 2);
+}
+
+#[test]
+fn field_layouts() {
+    #[derive(Size)]
+    #[allow(dead_code)]
+    struct Pair<L, R> {
+        left: L,
+        right: Option<R>,
+    }
+
+    #[derive(Size)]
+    struct Unit;
+
+    assert_eq!(Pair::<u8, [i16; 2]>::LAYOUT, &[
+        FieldLayout { name: "left", offset: 0, size: 1, type_name: "L" },
+        FieldLayout { name: "right", offset: 1, size: 5, type_name: "Option<R>" },
+    ]);
+    assert_eq!(Unit::LAYOUT, &[]);
+}
+
+#[test]
+#[allow(clippy::assertions_on_constants)]
+fn fixed_sizes() {
+    #[derive(Size)]
+    #[allow(dead_code)]
+    struct Fixed {
+        kind: u8,
+        values: [Option<u16>; 2],
+    }
+
+    #[derive(Size)]
+    #[allow(dead_code)]
+    struct Variable {
+        kind: u8,
+        length: Varint<u32>,
+        checksum: u16,
+    }
+
+    #[derive(Size)]
+    #[allow(dead_code)]
+    struct Wrap<T>(T);
+
+    assert!(u8::FIXED_SIZE);
+    assert!(Fixed::FIXED_SIZE);
+    assert!(BoundedVec::<Varint<u32>, 2>::FIXED_SIZE);
+    assert!(!Varint::<u32>::FIXED_SIZE);
+    assert!(!ZigZag::<i16>::FIXED_SIZE);
+    assert!(!Compact::<BoundedVec<u8, 2>, u8>::FIXED_SIZE);
+    assert!(!<(u8, Varint<u64>)>::FIXED_SIZE);
+    assert!(!Option::<Varint<u16>>::FIXED_SIZE);
+    assert!(!Variable::FIXED_SIZE);
+    assert!(Wrap::<u32>::FIXED_SIZE);
+    assert!(!Wrap::<Varint<u32>>::FIXED_SIZE);
+
+    // Offsets up to the first variable-size field are still available
+    assert_eq!(Variable::OFFSET_KIND, 0);
+    assert_eq!(Variable::OFFSET_LENGTH, 1);
+    assert_eq!(Fixed::LAYOUT.len(), 2);
+}
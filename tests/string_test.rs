@@ -0,0 +1,62 @@
+use byteorder::{BigEndian, LittleEndian};
+use lightpack::{Pack, Schema, Size, Unpack, schema::{Primitive, Type}, string::{AsciiStr, FixedStr, Space}, unpack::Error};
+
+#[test]
+fn encoding() {
+    #[derive(Size, Pack, Unpack, Debug, PartialEq)]
+    struct Identity {
+        name: FixedStr<8>,
+        serial: AsciiStr<6, Space>,
+    }
+
+    assert_eq!(Identity::SIZE, 14);
+
+    let identity = Identity { name: FixedStr::new("sensor").unwrap(), serial: AsciiStr::new("AB12").unwrap() };
+    let mut buffer = [0xffu8; Identity::SIZE];
+    identity.pack::<BigEndian>(&mut buffer);
+    assert_eq!(&buffer, b"sensor\0\0AB12  ");
+    assert_eq!(Identity::unpack::<LittleEndian>(&buffer), Ok(identity));
+}
+
+#[test]
+fn construction() {
+    let name = FixedStr::<4>::new("äb").unwrap();
+    assert_eq!(name, "äb");
+    assert_eq!(name.len(), 3);
+    assert_eq!(name.capacity(), 4);
+    assert_eq!(name.to_string(), "äb");
+    assert_eq!(format!("{:?}", name), "\"äb\"");
+    assert_eq!(FixedStr::<2>::new("äb"), None);
+    assert_eq!(AsciiStr::<4>::new("äb"), None);
+    assert!(FixedStr::<0>::default().is_empty());
+}
+
+#[test]
+fn padding() {
+    // Only trailing padding is trimmed
+    let name = FixedStr::<6>::unpack::<BigEndian>(b"a\0b\0\0\0").unwrap();
+    assert_eq!(name.as_str(), "a\0b");
+    let serial = AsciiStr::<6, Space>::unpack::<BigEndian>(b" x y  ").unwrap();
+    assert_eq!(serial.as_str(), " x y");
+    assert_eq!(FixedStr::<3>::unpack::<BigEndian>(&[0, 0, 0]).unwrap(), "");
+
+    // A full string has no padding
+    let mut buffer = [0u8; 3];
+    FixedStr::<3>::new("abc").unwrap().pack::<BigEndian>(&mut buffer);
+    assert_eq!(&buffer, b"abc");
+}
+
+#[test]
+fn validation() {
+    assert_eq!(FixedStr::<4>::unpack::<BigEndian>(&[b'a', 0xc3, 0x28, 0]), Err(Error::InvalidUtf8));
+    // A multi-byte character cut off by the capacity
+    assert_eq!(FixedStr::<2>::unpack::<BigEndian>(&[b'a', 0xc3]), Err(Error::InvalidUtf8));
+    assert_eq!(AsciiStr::<3>::unpack::<BigEndian>("äb".as_bytes()), Err(Error::InvalidAscii));
+}
+
+#[test]
+fn schema() {
+    assert_eq!(FixedStr::<8>::SCHEMA, Type::Array { element: &Type::Primitive(Primitive::U8), len: 8 });
+    assert_eq!(AsciiStr::<6, Space>::SCHEMA, <[u8; 6]>::SCHEMA);
+    assert_eq!(AsciiStr::<6, Space>::SCHEMA.size(), AsciiStr::<6, Space>::SIZE);
+}
@@ -0,0 +1,125 @@
+use core::time::Duration;
+
+use byteorder::{BigEndian, LittleEndian};
+use lightpack::{Pack, Schema, Size, Unpack, timestamp::{GpsTime, NtpTimestamp, Timestamp, UnixMillis, UnixSeconds32, UnixSeconds64}};
+
+/// 2024-01-01T00:00:00Z
+const NEW_YEAR: u64 = 1_704_067_200;
+
+#[test]
+fn encoding() {
+    #[derive(Size, Pack, Unpack, Debug, PartialEq, Clone, Copy)]
+    struct Header {
+        sent: UnixSeconds32,
+        received: UnixMillis,
+        reference: NtpTimestamp,
+        fix: GpsTime,
+    }
+
+    assert_eq!(Header::SIZE, 4 + 8 + 8 + 6);
+    assert_eq!(UnixSeconds64::SIZE, 8);
+
+    let header = Header {
+        sent: UnixSeconds32(1),
+        received: UnixMillis(2),
+        reference: NtpTimestamp { seconds: 3, fraction: 1 << 31 },
+        fix: GpsTime { week: 4, time_of_week: 5 },
+    };
+    let mut buffer = [0u8; Header::SIZE];
+    header.pack::<BigEndian>(&mut buffer);
+    assert_eq!(buffer, [
+        0, 0, 0, 1,
+        0, 0, 0, 0, 0, 0, 0, 2,
+        0, 0, 0, 3, 0x80, 0, 0, 0,
+        0, 4, 0, 0, 0, 5,
+    ]);
+    assert_eq!(Header::unpack::<BigEndian>(&buffer), Ok(header));
+
+    header.pack::<LittleEndian>(&mut buffer);
+    assert_eq!(Header::unpack::<LittleEndian>(&buffer), Ok(header));
+}
+
+#[test]
+fn schema() {
+    mod derived {
+        use lightpack::{Schema, Size};
+
+        #[derive(Size, Schema)]
+        pub struct UnixSeconds32(pub u32);
+
+        #[derive(Size, Schema)]
+        pub struct UnixMillis(pub u64);
+
+        #[derive(Size, Schema)]
+        pub struct NtpTimestamp {
+            pub seconds: u32,
+            pub fraction: u32,
+        }
+
+        #[derive(Size, Schema)]
+        pub struct GpsTime {
+            pub week: u16,
+            pub time_of_week: u32,
+        }
+    }
+
+    // Described like the derived equivalents, so generators handle them as structs
+    assert_eq!(UnixSeconds32::SCHEMA, derived::UnixSeconds32::SCHEMA);
+    assert_eq!(UnixMillis::SCHEMA, derived::UnixMillis::SCHEMA);
+    assert_eq!(NtpTimestamp::SCHEMA, derived::NtpTimestamp::SCHEMA);
+    assert_eq!(GpsTime::SCHEMA, derived::GpsTime::SCHEMA);
+    assert_eq!(UnixSeconds64::SCHEMA.size(), UnixSeconds64::SIZE);
+}
+
+#[test]
+fn durations() {
+    assert_eq!(UnixSeconds32(10).to_duration(), Duration::from_secs(10));
+    assert_eq!(UnixMillis(1_500).to_duration(), Duration::from_millis(1_500));
+    assert_eq!(NtpTimestamp { seconds: 1, fraction: 1 << 30 }.to_duration(), Duration::from_millis(1_250));
+    assert_eq!(NtpTimestamp { seconds: 1, fraction: u32::MAX }.to_duration(), Duration::from_secs(2));
+    assert_eq!(GpsTime { week: 1, time_of_week: 1_000 }.to_duration(), Duration::from_secs(604_801));
+
+    assert_eq!(UnixSeconds32::from_duration(Duration::from_millis(2_999)), Some(UnixSeconds32(2)));
+    assert_eq!(UnixSeconds32::from_duration(Duration::from_secs(1 << 32)), None);
+    assert_eq!(GpsTime::from_duration(Duration::from_millis(604_800_250)), Some(GpsTime { week: 1, time_of_week: 250 }));
+    assert_eq!(GpsTime::from_duration(Duration::from_secs(604_800 << 16)), None);
+
+    for nanos in [0, 1, 499_999_999, 999_999_999] {
+        let duration = Duration::new(7, nanos);
+        assert_eq!(NtpTimestamp::from_duration(duration).unwrap().to_duration(), duration);
+    }
+}
+
+#[test]
+fn epochs() {
+    let unix = Duration::from_secs(NEW_YEAR);
+    assert_eq!(UnixSeconds64::from_unix(unix), Some(UnixSeconds64(NEW_YEAR)));
+    assert_eq!(NtpTimestamp::from_unix(unix), Some(NtpTimestamp { seconds: 3_913_056_000, fraction: 0 }));
+    assert_eq!(GpsTime::from_unix(unix), Some(GpsTime { week: 2295, time_of_week: 86_400_000 }));
+    assert_eq!(GpsTime { week: 2295, time_of_week: 86_400_000 }.to_unix(), Some(unix));
+
+    // NTP can represent times before the Unix epoch, but GPS cannot
+    assert_eq!(NtpTimestamp { seconds: 0, fraction: 0 }.to_unix(), None);
+    assert_eq!(GpsTime::from_unix(Duration::from_secs(0)), None);
+
+    let sent = NtpTimestamp { seconds: 3_913_056_000, fraction: 1 << 31 };
+    assert_eq!(UnixMillis::from_unix(sent.to_unix().unwrap()), Some(UnixMillis(NEW_YEAR * 1_000 + 500)));
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn chrono() {
+    let date_time = chrono::DateTime::from_timestamp(NEW_YEAR as i64, 500_000_000).unwrap();
+    assert_eq!(UnixMillis::from_chrono(date_time), Some(UnixMillis(NEW_YEAR * 1_000 + 500)));
+    assert_eq!(UnixMillis(NEW_YEAR * 1_000 + 500).to_chrono(), Some(date_time));
+    assert_eq!(chrono::Datelike::year(&NtpTimestamp { seconds: 0, fraction: 0 }.to_chrono().unwrap()), 1900);
+}
+
+#[cfg(feature = "time")]
+#[test]
+fn time() {
+    let date_time = time::OffsetDateTime::from_unix_timestamp(NEW_YEAR as i64).unwrap();
+    assert_eq!(GpsTime::from_time(date_time), Some(GpsTime { week: 2295, time_of_week: 86_400_000 }));
+    assert_eq!(UnixSeconds32(NEW_YEAR as u32).to_time(), Some(date_time));
+    assert_eq!(NtpTimestamp { seconds: 0, fraction: 0 }.to_time().unwrap().year(), 1900);
+}
@@ -0,0 +1,151 @@
+use byteorder::{BigEndian, LittleEndian};
+use lightpack::{Pack, Schema, Size, Unpack, pack, schema::{Endianness, Primitive, StructFormat, Type}, unpack::Error, varint::{Varint, ZigZag}};
+
+fn encoded<T>(value: T) -> Vec<u8> where T: Pack {
+    let mut buffer = vec![0xee; T::SIZE];
+    value.pack::<BigEndian>(&mut buffer);
+    buffer.truncate(value.encoded_size());
+    buffer
+}
+
+#[test]
+fn sizes() {
+    assert_eq!(Varint::<u8>::SIZE, 2);
+    assert_eq!(Varint::<u16>::SIZE, 3);
+    assert_eq!(Varint::<u32>::SIZE, 5);
+    assert_eq!(Varint::<u64>::SIZE, 10);
+    assert_eq!(ZigZag::<i64>::SIZE, 10);
+
+    assert_eq!(Varint(0u64).encoded_size(), 1);
+    assert_eq!(Varint(127u64).encoded_size(), 1);
+    assert_eq!(Varint(128u64).encoded_size(), 2);
+    assert_eq!(Varint(u64::MAX).encoded_size(), 10);
+    assert_eq!(ZigZag(-64i32).encoded_size(), 1);
+    assert_eq!(ZigZag(64i32).encoded_size(), 2);
+    assert_eq!(ZigZag(i64::MIN).encoded_size(), 10);
+}
+
+#[test]
+fn encoding() {
+    assert_eq!(encoded(Varint(0u32)), [0]);
+    assert_eq!(encoded(Varint(1u8)), [1]);
+    assert_eq!(encoded(Varint(300u16)), [0xac, 0x02]);
+    assert_eq!(encoded(Varint(u8::MAX)), [0xff, 0x01]);
+    assert_eq!(encoded(Varint(u64::MAX)), [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01]);
+
+    assert_eq!(encoded(ZigZag(0i16)), [0]);
+    assert_eq!(encoded(ZigZag(-1i16)), [1]);
+    assert_eq!(encoded(ZigZag(1i16)), [2]);
+    assert_eq!(encoded(ZigZag(-2i16)), [3]);
+    assert_eq!(encoded(ZigZag(i8::MIN)), [0xff, 0x01]);
+    assert_eq!(encoded(ZigZag(i8::MAX)), [0xfe, 0x01]);
+    assert_eq!(encoded(ZigZag(i64::MIN)), [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01]);
+}
+
+#[test]
+fn roundtrips() {
+    let mut buffer = [0u8; 10];
+    for value in [0, 1, 127, 128, 300, 16383, 16384, u32::MAX as u64, u64::MAX - 1, u64::MAX] {
+        let size = Varint(value).encode(&mut buffer);
+        assert_eq!(Varint::<u64>::decode(&buffer), Ok((Varint(value), size)));
+    }
+    for value in [0, -1, 1, -64, 64, i32::MIN, i32::MAX] {
+        let size = ZigZag(value).encode(&mut buffer);
+        assert_eq!(ZigZag::<i32>::decode(&buffer), Ok((ZigZag(value), size)));
+    }
+    for value in i8::MIN..=i8::MAX {
+        ZigZag(value).pack::<LittleEndian>(&mut buffer);
+        assert_eq!(ZigZag::<i8>::unpack::<LittleEndian>(&buffer), Ok(ZigZag(value)));
+    }
+}
+
+#[test]
+fn back_to_back() {
+    let mut buffer = [0u8; 20];
+    let mut len = Varint(300u64).encode(&mut buffer);
+    len += ZigZag(-2i32).encode(&mut buffer[len..]);
+    assert_eq!(buffer[..len], [0xac, 0x02, 0x03]);
+
+    let (first, size) = Varint::<u64>::decode(&buffer).unwrap();
+    let (second, _) = ZigZag::<i32>::decode(&buffer[size..]).unwrap();
+    assert_eq!((first, second), (Varint(300), ZigZag(-2)));
+}
+
+#[test]
+fn structs() {
+    #[derive(Size, Pack, Unpack, Debug, PartialEq)]
+    struct Counter {
+        count: Varint<u32>,
+        delta: ZigZag<i16>,
+        flag: bool,
+    }
+
+    // The fields are packed back to back, the size is only the maximum
+    assert_eq!(Counter::SIZE, 9);
+    let counter = Counter { count: Varint(1000), delta: ZigZag(-300), flag: true };
+    assert_eq!(counter.encoded_size(), 5);
+
+    let mut buffer = [0xeeu8; Counter::SIZE];
+    counter.pack::<BigEndian>(&mut buffer);
+    assert_eq!(buffer[..5], [0xe8, 0x07, 0xd7, 0x04, 1]);
+    assert_eq!(Counter::unpack::<BigEndian>(&buffer), Ok(counter));
+
+    // Tuples and arrays
+    let mut buffer = [0u8; 6];
+    (Varint(1u16), [ZigZag(-1i8), ZigZag(64i8)], Varint(2u8)).pack::<BigEndian>(&mut buffer);
+    assert_eq!(buffer, [1, 1, 0x80, 0x01, 2, 0]);
+    assert_eq!(<(Varint<u16>, [ZigZag<i8>; 2], Varint<u8>)>::unpack_safely::<BigEndian>(&buffer[..5]), Ok((Varint(1), [ZigZag(-1), ZigZag(64)], Varint(2))));
+}
+
+#[test]
+fn short_buffers() {
+    // A complete encoding may be shorter than the maximum size
+    assert_eq!(Varint::<u32>::unpack_safely::<BigEndian>(&[0x05]), Ok(Varint(5)));
+    assert_eq!(ZigZag::<i64>::unpack_safely::<BigEndian>(&[0x03]), Ok(ZigZag(-2)));
+    assert_eq!(Varint::<u32>::unpack_safely::<BigEndian>(&[0x85]), Err(Error::BufferTooSmall { actual: 1, expected: 2 }));
+
+    let mut buffer = [0u8; 2];
+    assert_eq!(Varint(300u32).try_pack::<BigEndian>(&mut buffer), Ok(()));
+    assert_eq!(buffer, [0xac, 0x02]);
+    assert_eq!(Varint(1u32 << 14).try_pack::<BigEndian>(&mut buffer), Err(pack::Error::BufferTooSmall { actual: 2, expected: 3 }));
+
+    #[derive(Size, Pack, Unpack, Debug, PartialEq)]
+    struct Counter(Varint<u64>, #[lightpack(as = u8)] u32);
+
+    let counter = Counter(Varint(1), 2);
+    let mut buffer = [0u8; 2];
+    assert_eq!(counter.try_pack::<BigEndian>(&mut buffer), Ok(()));
+    assert_eq!(buffer, [1, 2]);
+    assert_eq!(Counter::unpack_safely::<BigEndian>(&buffer), Ok(Counter(Varint(1), 2)));
+    assert_eq!(Counter::unpack_safely::<BigEndian>(&buffer[..1]), Err(Error::BufferTooSmall { actual: 1, expected: 2 }));
+    assert_eq!(Counter(Varint(128), 2).try_pack::<BigEndian>(&mut buffer), Err(pack::Error::BufferTooSmall { actual: 2, expected: 3 }));
+}
+
+#[test]
+fn schema() {
+    assert_eq!(Varint::<u32>::SCHEMA, Type::Varint(Primitive::U32));
+    assert_eq!(ZigZag::<i64>::SCHEMA, Type::Varint(Primitive::I64));
+    assert_eq!(Type::Varint(Primitive::U64).size(), Varint::<u64>::SIZE);
+    assert_eq!(Type::Varint(Primitive::I8).size(), ZigZag::<i8>::SIZE);
+    assert_ne!(Varint::<u32>::SCHEMA_HASH, u32::SCHEMA_HASH);
+    assert_eq!(StructFormat::new(&Varint::<u8>::SCHEMA, Endianness::Big).as_str(), None);
+}
+
+#[test]
+fn errors() {
+    assert_eq!(Varint::<u64>::decode(&[]), Err(Error::BufferTooSmall { actual: 0, expected: 1 }));
+    assert_eq!(Varint::<u64>::decode(&[0x80, 0x80]), Err(Error::BufferTooSmall { actual: 2, expected: 3 }));
+
+    // Longer than necessary
+    assert_eq!(Varint::<u32>::decode(&[0x80, 0x00]), Err(Error::VarintTooLong));
+    assert_eq!(Varint::<u32>::decode(&[0x81, 0x80, 0x00]), Err(Error::VarintTooLong));
+    assert_eq!(Varint::<u8>::decode(&[0x80, 0x81, 0x01]), Err(Error::VarintTooLong));
+    assert_eq!(Varint::<u64>::decode(&[0x80; 11]), Err(Error::VarintTooLong));
+
+    // Too large for the type
+    assert_eq!(Varint::<u8>::decode(&[0x80, 0x02]), Err(Error::VarintOverflow));
+    assert_eq!(Varint::<u16>::decode(&[0xff, 0xff, 0x04]), Err(Error::VarintOverflow));
+    assert_eq!(Varint::<u64>::decode(&[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x02]), Err(Error::VarintOverflow));
+    assert_eq!(ZigZag::<i8>::decode(&[0x80, 0x02]), Err(Error::VarintOverflow));
+    assert_eq!(Varint::<u16>::unpack::<BigEndian>(&[0xff, 0xff, 0x04]), Err(Error::VarintOverflow));
+}
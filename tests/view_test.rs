@@ -0,0 +1,60 @@
+use byteorder::{BigEndian, LittleEndian};
+use lightpack::{Pack, Size, Unpack, View, extra::LE};
+
+#[derive(Size, Pack, Unpack, View, Debug, PartialEq, Eq)]
+struct Packet {
+    kind: u8,
+    seq: u32,
+    timestamp: LE<u16>,
+}
+
+#[test]
+fn read_fields() {
+    let buffer = [7, 0, 0, 1, 2, 4, 0];
+    let view = PacketView::<BigEndian>::new(&buffer);
+
+    assert_eq!(view.kind(), Ok(7));
+    assert_eq!(view.seq(), Ok(258));
+    assert_eq!(view.timestamp(), Ok(LE(4)));
+}
+
+#[test]
+fn patch_fields() {
+    let mut buffer = [0u8; Packet::SIZE];
+    Packet { kind: 1, seq: 2, timestamp: LE(3) }.pack::<BigEndian>(&mut buffer);
+
+    let mut view = PacketViewMut::<BigEndian>::new(&mut buffer);
+    view.set_seq(0x01020304);
+    view.set_timestamp(LE(0x0506));
+    assert_eq!(view.kind(), Ok(1));
+    assert_eq!(view.seq(), Ok(0x01020304));
+
+    assert_eq!(buffer, [1, 1, 2, 3, 4, 6, 5]);
+    assert_eq!(Packet::unpack::<BigEndian>(&buffer), Ok(Packet { kind: 1, seq: 0x01020304, timestamp: LE(0x0506) }));
+}
+
+#[test]
+fn patch_leaves_other_bytes_untouched() {
+    let mut buffer = [0xffu8; Packet::SIZE];
+
+    PacketViewMut::<LittleEndian>::new(&mut buffer).set_kind(0);
+
+    assert_eq!(buffer, [0, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]);
+}
+
+#[test]
+fn generic_structs() {
+    #[derive(Size, Pack, Unpack, View, Debug, PartialEq, Eq)]
+    struct Pair<L, R> {
+        left: L,
+        right: R,
+    }
+
+    let mut buffer = [0u8; Pair::<u16, i8>::SIZE];
+    let mut view = PairViewMut::<u16, i8, LittleEndian>::new(&mut buffer);
+    view.set_right(-1);
+    view.set_left(258);
+
+    assert_eq!(buffer, [2, 1, 255]);
+    assert_eq!(PairView::<u16, i8, LittleEndian>::new(&buffer).left(), Ok(258));
+}